@@ -6,14 +6,21 @@
 #![allow(clippy::needless_return)]
 
 use eventsource_stream::Eventsource;
+use futures::channel::{mpsc, oneshot};
 pub use futures::Stream;
-use futures::StreamExt;
-use parking_lot::RwLock;
+use futures::{SinkExt, StreamExt};
+use parking_lot::{Mutex, RwLock};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Method;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use thiserror::Error;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -28,10 +35,20 @@ pub enum Error {
   Jwt(#[from] jsonwebtoken::errors::Error),
   #[error("Url: {0}")]
   Url(#[from] url::ParseError),
+  #[error("WebSocket: {0}")]
+  WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+  #[error("Subscription: {0}")]
+  Subscription(String),
   #[error("Precondition: {0}")]
   Precondition(&'static str),
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+  fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+    return Error::WebSocket(Box::new(err));
+  }
+}
+
 /// Represents the currently logged-in user.
 #[derive(Clone, Debug)]
 pub struct User {
@@ -53,6 +70,7 @@ pub struct Tokens {
 pub struct Pagination {
   pub cursor: Option<String>,
   pub limit: Option<usize>,
+  pub offset: Option<usize>,
 }
 
 impl Pagination {
@@ -60,6 +78,7 @@ impl Pagination {
     return Pagination {
       limit: limit.into(),
       cursor: cursor.into(),
+      offset: None,
     };
   }
 
@@ -70,6 +89,13 @@ impl Pagination {
   pub fn with_cursor(cursor: impl Into<Option<String>>) -> Pagination {
     return Pagination::with(None, cursor);
   }
+
+  pub fn with_offset(offset: impl Into<Option<usize>>) -> Pagination {
+    return Pagination {
+      offset: offset.into(),
+      ..Default::default()
+    };
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -118,6 +144,7 @@ impl RecordId<'_> for i64 {
 pub trait ReadArgumentsTrait<'a> {
   fn serialized_id(self) -> Cow<'a, str>;
   fn expand(&self) -> Option<&'a [&'a str]>;
+  fn fields(&self) -> Option<&'a [&'a str]>;
 }
 
 impl<'a, T: RecordId<'a>> ReadArgumentsTrait<'a> for T {
@@ -128,12 +155,18 @@ impl<'a, T: RecordId<'a>> ReadArgumentsTrait<'a> for T {
   fn expand(&self) -> Option<&'a [&'a str]> {
     return None;
   }
+
+  fn fields(&self) -> Option<&'a [&'a str]> {
+    return None;
+  }
 }
 
 #[derive(Debug, Default)]
 pub struct ReadArguments<'a, T: RecordId<'a>> {
   pub id: T,
   pub expand: Option<&'a [&'a str]>,
+  /// Subset of columns to return, e.g. `["title", "author.name"]`.
+  pub fields: Option<&'a [&'a str]>,
 }
 
 impl<'a, T: RecordId<'a>> ReadArgumentsTrait<'a> for ReadArguments<'a, T> {
@@ -144,6 +177,10 @@ impl<'a, T: RecordId<'a>> ReadArgumentsTrait<'a> for ReadArguments<'a, T> {
   fn expand(&self) -> Option<&'a [&'a str]> {
     return self.expand;
   }
+
+  fn fields(&self) -> Option<&'a [&'a str]> {
+    return self.fields;
+  }
 }
 
 struct ThinClient {
@@ -209,12 +246,115 @@ pub struct RecordApi {
   name: String,
 }
 
+/// Comparison operator used by column [`Filter`]s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareOp {
+  Equal,
+  NotEqual,
+  GreaterThanEqual,
+  GreaterThan,
+  LessThanEqual,
+  LessThan,
+  Like,
+  Regexp,
+  /// Value is a comma-separated list. Commas within values are escaped as `\,`.
+  In,
+  /// Value is a comma-separated list. Commas within values are escaped as `\,`.
+  NotIn,
+  /// Value is ignored.
+  IsNull,
+  /// Value is ignored.
+  IsNotNull,
+}
+
+impl CompareOp {
+  fn format(&self) -> Option<&'static str> {
+    return match self {
+      Self::Equal => None,
+      Self::NotEqual => Some("ne"),
+      Self::GreaterThanEqual => Some("gte"),
+      Self::GreaterThan => Some("gt"),
+      Self::LessThanEqual => Some("lte"),
+      Self::LessThan => Some("lt"),
+      Self::Like => Some("like"),
+      Self::Regexp => Some("re"),
+      Self::In => Some("in"),
+      Self::NotIn => Some("nin"),
+      Self::IsNull | Self::IsNotNull => Some("is"),
+    };
+  }
+}
+
+/// Composite filter for listing records, which allows for nested AND/OR groups, e.g.:
+///
+/// ```
+/// use trailbase_client::{CompareOp, Filter};
+///
+/// let filter = Filter::Or(vec![
+///   Filter::column("status", CompareOp::Equal, "open"),
+///   Filter::column("priority", CompareOp::In, "high,urgent"),
+/// ]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+  Column {
+    column: String,
+    op: CompareOp,
+    value: String,
+  },
+  And(Vec<Filter>),
+  Or(Vec<Filter>),
+}
+
+impl Filter {
+  pub fn column(column: impl Into<String>, op: CompareOp, value: impl Into<String>) -> Filter {
+    return Filter::Column {
+      column: column.into(),
+      op,
+      value: value.into(),
+    };
+  }
+
+  fn to_query_params(&self, path: &str, params: &mut Vec<(Cow<'static, str>, Cow<'static, str>)>) {
+    match self {
+      Filter::Column { column, op, value } => {
+        let key = match op.format() {
+          Some(op) => format!("{path}[{column}][{op}]"),
+          None => format!("{path}[{column}]"),
+        };
+        let value = match op {
+          CompareOp::IsNull => "NULL".to_string(),
+          CompareOp::IsNotNull => "!NULL".to_string(),
+          _ => value.clone(),
+        };
+
+        params.push((Cow::Owned(key), Cow::Owned(value)));
+      }
+      Filter::And(filters) => {
+        for (index, filter) in filters.iter().enumerate() {
+          filter.to_query_params(&format!("{path}[$and][{index}]"), params);
+        }
+      }
+      Filter::Or(filters) => {
+        for (index, filter) in filters.iter().enumerate() {
+          filter.to_query_params(&format!("{path}[$or][{index}]"), params);
+        }
+      }
+    }
+  }
+}
+
 #[derive(Default)]
 pub struct ListArguments<'a> {
   pub pagination: Pagination,
   pub order: Option<&'a [&'a str]>,
+  /// Flat list of `column[op]=value` filters, which are AND-ed.
   pub filters: Option<&'a [&'a str]>,
+  /// Composite filter, which is AND-ed with `filters` above.
+  pub filter: Option<Filter>,
   pub expand: Option<&'a [&'a str]>,
+  /// Subset of columns to return, e.g. `["title", "author.name"]`.
+  pub fields: Option<&'a [&'a str]>,
   pub count: bool,
 }
 
@@ -232,6 +372,10 @@ impl RecordApi {
       params.push((Cow::Borrowed("limit"), Cow::Owned(limit.to_string())));
     }
 
+    if let Some(offset) = args.pagination.offset {
+      params.push((Cow::Borrowed("offset"), Cow::Owned(offset.to_string())));
+    }
+
     #[inline]
     fn to_list(slice: &[&str]) -> String {
       return slice.join(",");
@@ -249,6 +393,12 @@ impl RecordApi {
       }
     }
 
+    if let Some(fields) = args.fields {
+      if !fields.is_empty() {
+        params.push((Cow::Borrowed("fields"), Cow::Owned(to_list(fields))));
+      }
+    }
+
     if args.count {
       params.push((Cow::Borrowed("count"), Cow::Borrowed("true")));
     }
@@ -266,6 +416,10 @@ impl RecordApi {
       }
     }
 
+    if let Some(filter) = args.filter {
+      filter.to_query_params("filter", &mut params);
+    }
+
     let response = self
       .client
       .fetch(
@@ -283,9 +437,13 @@ impl RecordApi {
     &self,
     args: impl ReadArgumentsTrait<'a>,
   ) -> Result<T, Error> {
-    let expand = args
-      .expand()
-      .map(|e| vec![(Cow::Borrowed("expand"), Cow::Owned(e.join(",")))]);
+    let mut params: Vec<(Cow<'static, str>, Cow<'static, str>)> = vec![];
+    if let Some(expand) = args.expand() {
+      params.push((Cow::Borrowed("expand"), Cow::Owned(expand.join(","))));
+    }
+    if let Some(fields) = args.fields() {
+      params.push((Cow::Borrowed("fields"), Cow::Owned(fields.join(","))));
+    }
 
     let response = self
       .client
@@ -297,7 +455,7 @@ impl RecordApi {
         ),
         Method::GET,
         None::<&()>,
-        expand.as_deref(),
+        (!params.is_empty()).then_some(params.as_slice()),
       )
       .await?;
 
@@ -404,6 +562,268 @@ impl RecordApi {
         }),
     );
   }
+
+  /// Like [`RecordApi::subscribe`] but multiplexed with all other WebSocket subscriptions of this
+  /// client over a single connection.
+  pub async fn subscribe_ws<'a>(&self, id: impl RecordId<'a>) -> Result<WsSubscription, Error> {
+    let connection = self.client.ws_connection().await?;
+    return connection
+      .subscribe(&self.name, &id.serialized_id(), None)
+      .await;
+  }
+
+  /// Subscribes to all changes of the API's table matching the given filter over the client's
+  /// shared WebSocket connection.
+  pub async fn subscribe_ws_filtered(&self, filter: Filter) -> Result<WsSubscription, Error> {
+    let mut params: Vec<(Cow<'static, str>, Cow<'static, str>)> = vec![];
+    filter.to_query_params("filter", &mut params);
+
+    let query = url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(params)
+      .finish();
+
+    let connection = self.client.ws_connection().await?;
+    return connection.subscribe(&self.name, "*", Some(query)).await;
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsClientMessage {
+  Subscribe {
+    id: u64,
+    api: String,
+    record: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+  },
+  Unsubscribe {
+    id: u64,
+  },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsServerMessage {
+  Subscribed {
+    id: u64,
+  },
+  Unsubscribed {
+    id: u64,
+  },
+  Event {
+    id: u64,
+    event: DbEvent,
+  },
+  Error {
+    #[serde(default)]
+    id: Option<u64>,
+    error: String,
+  },
+}
+
+struct WsSubscriptionState {
+  events: mpsc::UnboundedSender<DbEvent>,
+  /// Resolved once the server acknowledged or rejected the subscription.
+  ack: Option<oneshot::Sender<Result<(), String>>>,
+}
+
+type WsSubscriptions = Arc<Mutex<HashMap<u64, WsSubscriptionState>>>;
+
+/// A WebSocket connection multiplexing many subscriptions.
+struct WsConnection {
+  outgoing: mpsc::UnboundedSender<Message>,
+  subscriptions: WsSubscriptions,
+  next_id: AtomicU64,
+  closed: Arc<AtomicBool>,
+  /// Authorization header the connection was established with. Subscriptions are authorized as
+  /// the respective user.
+  auth: Option<HeaderValue>,
+}
+
+impl WsConnection {
+  async fn connect(url: url::Url, headers: &HeaderMap) -> Result<Arc<WsConnection>, Error> {
+    let auth = headers.get("Authorization").cloned();
+    let mut request = url.as_str().into_client_request()?;
+    if let Some(ref auth) = auth {
+      request.headers_mut().insert("Authorization", auth.clone());
+    }
+
+    let (socket, _response) = tokio_tungstenite::connect_async(request).await?;
+    let (mut sink, mut stream) = socket.split();
+
+    let (outgoing, mut outgoing_receiver) = mpsc::unbounded::<Message>();
+    tokio::spawn(async move {
+      while let Some(message) = outgoing_receiver.next().await {
+        if sink.send(message).await.is_err() {
+          break;
+        }
+      }
+      let _ = sink.close().await;
+    });
+
+    let subscriptions: WsSubscriptions = Arc::new(Mutex::new(HashMap::new()));
+    let closed = Arc::new(AtomicBool::new(false));
+
+    {
+      let subscriptions = subscriptions.clone();
+      let closed = closed.clone();
+      tokio::spawn(async move {
+        while let Some(Ok(message)) = stream.next().await {
+          let Message::Text(text) = message else {
+            continue;
+          };
+          let Ok(message) = serde_json::from_str::<WsServerMessage>(&text) else {
+            log::warn!("Unexpected message: {text}");
+            continue;
+          };
+
+          let mut subscriptions = subscriptions.lock();
+          match message {
+            WsServerMessage::Subscribed { id } => {
+              if let Some(ack) = subscriptions.get_mut(&id).and_then(|s| s.ack.take()) {
+                let _ = ack.send(Ok(()));
+              }
+            }
+            WsServerMessage::Unsubscribed { id } => {
+              // Dropping the sender ends the subscription's stream.
+              subscriptions.remove(&id);
+            }
+            WsServerMessage::Event { id, event } => {
+              if let Some(subscription) = subscriptions.get(&id) {
+                let _ = subscription.events.unbounded_send(event);
+              }
+            }
+            WsServerMessage::Error {
+              id: Some(id),
+              error,
+            } => {
+              if let Some(subscription) = subscriptions.get_mut(&id) {
+                match subscription.ack.take() {
+                  Some(ack) => {
+                    let _ = ack.send(Err(error));
+                  }
+                  None => {
+                    let _ = subscription.events.unbounded_send(DbEvent::Error(error));
+                  }
+                }
+              }
+            }
+            WsServerMessage::Error { id: None, error } => {
+              log::warn!("Subscription error: {error}");
+            }
+          }
+        }
+
+        closed.store(true, Ordering::Release);
+        // Ends all streams and fails pending subscribes.
+        subscriptions.lock().clear();
+      });
+    }
+
+    return Ok(Arc::new(WsConnection {
+      outgoing,
+      subscriptions,
+      next_id: AtomicU64::new(1),
+      closed,
+      auth,
+    }));
+  }
+
+  fn is_closed(&self) -> bool {
+    return self.closed.load(Ordering::Acquire);
+  }
+
+  /// Closes the connection, which ends all its subscriptions' streams.
+  fn close(&self) {
+    self.closed.store(true, Ordering::Release);
+    // Ends the sending task, which then closes the socket.
+    self.outgoing.close_channel();
+  }
+
+  fn send(&self, message: &WsClientMessage) -> Result<(), Error> {
+    let text = serde_json::to_string(message)?;
+    return self
+      .outgoing
+      .unbounded_send(Message::text(text))
+      .map_err(|_| Error::Precondition("WebSocket closed"));
+  }
+
+  async fn subscribe(
+    self: &Arc<Self>,
+    api: &str,
+    record: &str,
+    filter: Option<String>,
+  ) -> Result<WsSubscription, Error> {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let (events, receiver) = mpsc::unbounded();
+    let (ack, ack_receiver) = oneshot::channel();
+    self.subscriptions.lock().insert(
+      id,
+      WsSubscriptionState {
+        events,
+        ack: Some(ack),
+      },
+    );
+
+    // Constructed early to clean up on failure.
+    let subscription = WsSubscription {
+      id,
+      connection: self.clone(),
+      events: receiver,
+    };
+
+    self.send(&WsClientMessage::Subscribe {
+      id,
+      api: api.to_string(),
+      record: record.to_string(),
+      filter,
+    })?;
+
+    return match ack_receiver.await {
+      Ok(Ok(())) => Ok(subscription),
+      Ok(Err(err)) => {
+        // Rejected subscriptions don't need unsubscribing.
+        self.subscriptions.lock().remove(&id);
+        Err(Error::Subscription(err))
+      }
+      Err(_) => Err(Error::Precondition("WebSocket closed")),
+    };
+  }
+}
+
+/// Stream of events of a subscription multiplexed over the client's shared WebSocket.
+///
+/// The stream ends when the server ends the subscription, e.g. when the record gets deleted.
+/// Dropping it unsubscribes.
+pub struct WsSubscription {
+  id: u64,
+  connection: Arc<WsConnection>,
+  events: mpsc::UnboundedReceiver<DbEvent>,
+}
+
+impl Stream for WsSubscription {
+  type Item = DbEvent;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DbEvent>> {
+    return self.events.poll_next_unpin(cx);
+  }
+}
+
+impl Drop for WsSubscription {
+  fn drop(&mut self) {
+    let active = self
+      .connection
+      .subscriptions
+      .lock()
+      .remove(&self.id)
+      .is_some();
+    if active && !self.connection.is_closed() {
+      let _ = self
+        .connection
+        .send(&WsClientMessage::Unsubscribe { id: self.id });
+    }
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -432,9 +852,24 @@ struct ClientState {
   client: ThinClient,
   site: String,
   tokens: RwLock<TokenState>,
+  /// Lazily established WebSocket shared by all subscriptions of this client.
+  ws: Mutex<Option<Arc<WsConnection>>>,
 }
 
 impl ClientState {
+  #[inline]
+  async fn headers(&self) -> Result<HeaderMap, Error> {
+    let (headers, refresh_token) = self.extract_headers_and_refresh_token_if_exp();
+    if let Some(refresh_token) = refresh_token {
+      let new_tokens = ClientState::refresh_tokens(&self.client, headers, refresh_token).await?;
+
+      let headers = new_tokens.headers.clone();
+      self.set_tokens(new_tokens);
+      return Ok(headers);
+    }
+    return Ok(headers);
+  }
+
   #[inline]
   async fn fetch<T: Serialize>(
     &self,
@@ -443,13 +878,7 @@ impl ClientState {
     body: Option<&T>,
     query_params: Option<&[(Cow<'static, str>, Cow<'static, str>)]>,
   ) -> Result<reqwest::Response, Error> {
-    let (mut headers, refresh_token) = self.extract_headers_and_refresh_token_if_exp();
-    if let Some(refresh_token) = refresh_token {
-      let new_tokens = ClientState::refresh_tokens(&self.client, headers, refresh_token).await?;
-
-      headers = new_tokens.headers.clone();
-      *self.tokens.write() = new_tokens;
-    }
+    let headers = self.headers().await?;
 
     return Ok(
       self
@@ -460,6 +889,60 @@ impl ClientState {
     );
   }
 
+  /// Replaces the tokens and closes the shared WebSocket, whose subscriptions are authorized by
+  /// the previous ones.
+  fn set_tokens(&self, tokens: TokenState) {
+    // Locked first, s.t. connections established concurrently can tell whether they're outdated.
+    let mut ws = self.ws.lock();
+    *self.tokens.write() = tokens;
+
+    if let Some(connection) = ws.take() {
+      connection.close();
+    }
+  }
+
+  async fn ws_connection(&self) -> Result<Arc<WsConnection>, Error> {
+    let headers = self.headers().await?;
+    let auth = headers.get("Authorization");
+
+    if let Some(connection) = self.ws.lock().as_ref() {
+      if !connection.is_closed() && connection.auth.as_ref() == auth {
+        return Ok(connection.clone());
+      }
+    }
+
+    let mut url = self.client.url.clone();
+    let scheme = match url.scheme() {
+      "https" => "wss",
+      _ => "ws",
+    };
+    url
+      .set_scheme(scheme)
+      .map_err(|_| Error::Precondition("Unsupported URL scheme"))?;
+    url.set_path(&format!("/{RECORD_API}/_subscribe"));
+
+    let connection = WsConnection::connect(url, &headers).await?;
+
+    let mut ws = self.ws.lock();
+    // The tokens may have changed while connecting, e.g. racing a login or logout.
+    if self.tokens.read().headers.get("Authorization") != connection.auth.as_ref() {
+      connection.close();
+      return Err(Error::Precondition("Tokens changed"));
+    }
+
+    // Another subscribe may have connected concurrently, in which case we keep theirs.
+    if let Some(existing) = ws.as_ref() {
+      if !existing.is_closed() && existing.auth == connection.auth {
+        connection.close();
+        return Ok(existing.clone());
+      }
+    }
+    if let Some(stale) = ws.replace(connection.clone()) {
+      stale.close();
+    }
+    return Ok(connection);
+  }
+
   #[inline]
   fn extract_headers_and_refresh_token_if_exp(&self) -> (HeaderMap, Option<String>) {
     #[inline]
@@ -540,6 +1023,7 @@ impl Client {
         },
         site: site.to_string(),
         tokens: RwLock::new(TokenState::build(tokens.as_ref())),
+        ws: Mutex::new(None),
       }),
     });
   }
@@ -574,7 +1058,7 @@ impl Client {
     let new_tokens =
       ClientState::refresh_tokens(&self.state.client, headers, refresh_token).await?;
 
-    *self.state.tokens.write() = new_tokens;
+    self.state.set_tokens(new_tokens);
     return Ok(());
   }
 
@@ -639,7 +1123,7 @@ impl Client {
   fn update_tokens(&self, tokens: Option<&Tokens>) -> TokenState {
     let state = TokenState::build(tokens);
 
-    *self.state.tokens.write() = state.clone();
+    self.state.set_tokens(state.clone());
     // _authChange?.call(this, state.state?.$1);
 
     if let Some(ref s) = state.state {
@@ -698,6 +1182,60 @@ const RECORD_API: &str = "api/records/v1";
 mod tests {
   use super::*;
 
+  #[test]
+  fn filter_to_query_params_test() {
+    let filter = Filter::Or(vec![
+      Filter::column("status", CompareOp::Equal, "open"),
+      Filter::And(vec![
+        Filter::column("rank", CompareOp::GreaterThan, "5"),
+        Filter::column("owner", CompareOp::IsNull, ""),
+      ]),
+    ]);
+
+    let mut params = vec![];
+    filter.to_query_params("filter", &mut params);
+
+    assert_eq!(
+      params,
+      vec![
+        (
+          Cow::Borrowed("filter[$or][0][status]"),
+          Cow::Borrowed("open")
+        ),
+        (
+          Cow::Borrowed("filter[$or][1][$and][0][rank][gt]"),
+          Cow::Borrowed("5")
+        ),
+        (
+          Cow::Borrowed("filter[$or][1][$and][1][owner][is]"),
+          Cow::Borrowed("NULL")
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn token_change_closes_ws_test() {
+    let client = Client::new("http://127.0.0.1:4000", None).unwrap();
+
+    let (outgoing, _receiver) = mpsc::unbounded::<Message>();
+    let connection = Arc::new(WsConnection {
+      outgoing,
+      subscriptions: Arc::new(Mutex::new(HashMap::new())),
+      next_id: AtomicU64::new(1),
+      closed: Arc::new(AtomicBool::new(false)),
+      auth: None,
+    });
+    *client.state.ws.lock() = Some(connection.clone());
+
+    // E.g. logging out or in as another user.
+    client.update_tokens(None);
+
+    assert!(client.state.ws.lock().is_none());
+    assert!(connection.is_closed());
+    assert!(connection.outgoing.is_closed());
+  }
+
   #[tokio::test]
   async fn is_send_test() {
     let client = Client::new("http://127.0.0.1:4000", None).unwrap();
@@ -1,7 +1,9 @@
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use trailbase_client::{Client, DbEvent, ListArguments, ListResponse, Pagination, ReadArguments};
+use trailbase_client::{
+  Client, CompareOp, DbEvent, Error, Filter, ListArguments, ListResponse, Pagination, ReadArguments,
+};
 
 struct Server {
   child: std::process::Child,
@@ -280,6 +282,7 @@ async fn expand_foreign_records_test() {
       .read(ReadArguments {
         id: 1,
         expand: Some(&["post"]),
+        ..Default::default()
       })
       .await
       .unwrap();
@@ -372,6 +375,90 @@ async fn subscription_test() {
   }
 }
 
+async fn ws_subscription_test() {
+  let client = connect().await;
+  let api = client.records("simple_strict_table");
+
+  let now = now();
+  let create_message = format!("rust client ws realtime test: {now}");
+
+  // Both subscriptions are multiplexed over the same WebSocket.
+  let table_stream = api
+    .subscribe_ws_filtered(Filter::column(
+      "text_not_null",
+      CompareOp::Equal,
+      create_message.clone(),
+    ))
+    .await
+    .unwrap();
+
+  // Not matching the filter.
+  api
+    .create(json!({"text_not_null": format!("other {now}")}))
+    .await
+    .unwrap();
+
+  let id = api
+    .create(json!({"text_not_null": create_message}))
+    .await
+    .unwrap();
+
+  let record_stream = api.subscribe_ws(&id).await.unwrap();
+
+  api.delete(&id).await.unwrap();
+
+  {
+    // The server ends record subscriptions on delete.
+    let record_events = record_stream.collect::<Vec<_>>().await;
+    assert_eq!(1, record_events.len(), "{record_events:?}");
+    match &record_events[0] {
+      DbEvent::Delete(Some(serde_json::Value::Object(obj))) => {
+        assert_eq!(obj["text_not_null"], create_message);
+      }
+      msg => panic!("Unexpected event: {msg:?}"),
+    };
+  }
+
+  {
+    let table_events = table_stream.take(2).collect::<Vec<_>>().await;
+    match &table_events[0] {
+      DbEvent::Insert(Some(serde_json::Value::Object(obj))) => {
+        assert_eq!(obj["text_not_null"], create_message);
+      }
+      msg => panic!("Unexpected event: {msg:?}"),
+    };
+    match &table_events[1] {
+      DbEvent::Delete(Some(serde_json::Value::Object(obj))) => {
+        assert_eq!(obj["text_not_null"], create_message);
+      }
+      msg => panic!("Unexpected event: {msg:?}"),
+    };
+  }
+
+  match client.records("non_existent_api").subscribe_ws("*").await {
+    Err(Error::Subscription(_)) => {}
+    Err(err) => panic!("Unexpected error: {err}"),
+    Ok(_) => panic!("Expected error"),
+  };
+
+  // Changing tokens, e.g. logging out, closes the shared WebSocket and thus ends its
+  // subscriptions, which were authorized as the previous user.
+  let stream = api.subscribe_ws("*").await.unwrap();
+  client.logout().await.unwrap();
+  assert!(stream.collect::<Vec<_>>().await.is_empty());
+
+  // Logging back in establishes a new connection.
+  client.login("admin@localhost", "secret").await.unwrap();
+  let stream = api.subscribe_ws("*").await.unwrap();
+  let id = api
+    .create(json!({"text_not_null": format!("rust client ws relogin test: {now}")}))
+    .await
+    .unwrap();
+  api.delete(&id).await.unwrap();
+  let events = stream.take(1).collect::<Vec<_>>().await;
+  assert!(matches!(events[0], DbEvent::Insert(_)), "{events:?}");
+}
+
 #[test]
 fn integration_test() {
   let _server = start_server().unwrap();
@@ -392,6 +479,9 @@ fn integration_test() {
 
   runtime.block_on(subscription_test());
   println!("Ran subscription tests");
+
+  runtime.block_on(ws_subscription_test());
+  println!("Ran WebSocket subscription tests");
 }
 
 fn now() -> u64 {
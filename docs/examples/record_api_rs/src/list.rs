@@ -8,6 +8,7 @@ pub async fn list(client: &Client) -> anyhow::Result<ListResponse<serde_json::Va
         pagination: Pagination {
           limit: Some(3),
           cursor: None,
+          offset: None,
         },
         order: Some(&["rank"]),
         filters: Some(&["watch_time[lt]=120", "description[like]=%love%"]),
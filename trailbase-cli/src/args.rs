@@ -1,7 +1,7 @@
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use trailbase::api::JsonSchemaMode;
-use trailbase::DataDir;
+use trailbase::{DataDir, SecureClientIpSource};
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
 pub enum JsonSchemaModeArg {
@@ -68,6 +68,11 @@ pub enum SubCommands {
   },
   /// Programmatically send emails.
   Email(EmailArgs),
+  /// Manage the keys used to sign and validate auth tokens.
+  Jwt {
+    #[command(subcommand)]
+    cmd: Option<JwtSubCommands>,
+  },
 }
 
 #[derive(Args, Clone, Debug)]
@@ -99,6 +104,11 @@ pub struct ServerArgs {
   #[arg(long, default_value = "*")]
   pub cors_allowed_origins: Vec<String>,
 
+  /// Trusted source of client IP addresses, e.g. "RightmostXForwardedFor" or "XRealIp" when
+  /// running behind a reverse proxy setting the respective header (Default: peer address).
+  #[arg(long, env)]
+  pub client_ip_source: Option<SecureClientIpSource>,
+
   /// Number of JavaScript isolates/workers to start (Default: #cpus).
   #[arg(long, env)]
   pub js_runtime_threads: Option<usize>,
@@ -169,3 +179,17 @@ pub enum UserSubCommands {
   /// Mint auth tokens for the given user.
   MintToken { email: String },
 }
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum JwtSubCommands {
+  /// Lists the ids of all valid keys, the current signing key first.
+  List,
+  /// Rotates the signing key. Retired keys remain valid for tokens signed before the rotation
+  /// until they expire. A running server needs to be restarted to pick up the new key.
+  Rotate {
+    /// Retired keys older than this are removed [Default: 1 week]. Should exceed the auth token
+    /// TTL.
+    #[arg(long, default_value_t = 7 * 24 * 3600)]
+    prune_after_sec: i64,
+  },
+}
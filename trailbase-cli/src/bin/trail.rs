@@ -17,7 +17,8 @@ use trailbase::{
 };
 
 use trailbase_cli::{
-  AdminSubCommands, DefaultCommandLineArgs, JsonSchemaModeArg, SubCommands, UserSubCommands,
+  AdminSubCommands, DefaultCommandLineArgs, JsonSchemaModeArg, JwtSubCommands, SubCommands,
+  UserSubCommands,
 };
 
 type BoxError = Box<dyn std::error::Error + Send + Sync>;
@@ -88,6 +89,7 @@ async fn async_main() -> Result<(), BoxError> {
         dev: cmd.dev,
         disable_auth_ui: cmd.disable_auth_ui,
         cors_allowed_origins: cmd.cors_allowed_origins,
+        client_ip_source: cmd.client_ip_source,
         js_runtime_threads: cmd.js_runtime_threads,
         tls_key: None,
         tls_cert: None,
@@ -315,6 +317,28 @@ async fn async_main() -> Result<(), BoxError> {
         }
       };
     }
+    Some(SubCommands::Jwt { cmd }) => {
+      init_logger(false);
+
+      match cmd {
+        Some(JwtSubCommands::List) => {
+          let jwt = api::JwtHelper::init_from_path(&data_dir).await?;
+          for kid in jwt.key_ids() {
+            println!("{kid}");
+          }
+        }
+        Some(JwtSubCommands::Rotate { prune_after_sec }) => {
+          let kid = api::rotate_keys(&data_dir, chrono::Duration::seconds(prune_after_sec)).await?;
+
+          println!("Rotated signing key, new key id: {kid}. Restart the server to pick it up.");
+        }
+        None => {
+          DefaultCommandLineArgs::command()
+            .find_subcommand_mut("jwt")
+            .map(|cmd| cmd.print_help());
+        }
+      };
+    }
     None => {
       let _ = DefaultCommandLineArgs::command().print_help();
     }
@@ -3,8 +3,8 @@
 mod args;
 
 pub use args::{
-  AdminSubCommands, DefaultCommandLineArgs, EmailArgs, JsonSchemaModeArg, SubCommands,
-  UserSubCommands,
+  AdminSubCommands, DefaultCommandLineArgs, EmailArgs, JsonSchemaModeArg, JwtSubCommands,
+  SubCommands, UserSubCommands,
 };
 
 #[cfg(feature = "openapi")]
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ChangeJson = { seq: bigint, created: number, table_name: string, row_id: bigint, 
+/**
+ * One of "insert", "update" or "delete".
+ */
+action: string, old_values: Object | undefined, new_values: Object | undefined, };
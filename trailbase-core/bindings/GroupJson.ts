@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type GroupJson = { name: string, 
+/**
+ * Roles granted to all members of the group.
+ */
+roles: Array<string>, members: Array<string>, };
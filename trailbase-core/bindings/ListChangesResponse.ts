@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeJson } from "./ChangeJson";
+
+export type ListChangesResponse = { 
+/**
+ * Sequence number of the last returned change to be passed as `after` to fetch the next page.
+ */
+cursor: bigint | null, entries: Array<ChangeJson>, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { WebhookDeliveryJson } from "./WebhookDeliveryJson";
+
+export type ListWebhookDeliveriesResponse = { 
+/**
+ * Id of the oldest returned delivery to be passed as `before` to fetch the next page.
+ */
+cursor: bigint | null, entries: Array<WebhookDeliveryJson>, };
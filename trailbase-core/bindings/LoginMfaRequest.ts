@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LoginMfaRequest = { mfa_token: string, 
+/**
+ * TOTP or recovery code.
+ */
+code: string, redirect_to: string | null, response_type: string | null, pkce_code_challenge: string | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Returned instead of [LoginResponse] for users with multi-factor authentication enabled. The
+ * login has to be completed by passing the `mfa_token` and a second factor to `/login/mfa`.
+ */
+export type LoginMfaResponse = { mfa_token: string, };
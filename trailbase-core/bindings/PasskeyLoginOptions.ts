@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Options for `navigator.credentials.get()`. Binary values are base64url-encoded.
+ */
+export type PasskeyLoginOptions = { challenge: string, rp_id: string, timeout_ms: bigint, };
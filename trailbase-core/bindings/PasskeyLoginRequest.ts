@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `navigator.credentials.get()`. Binary values are base64url-encoded.
+ */
+export type PasskeyLoginRequest = { credential_id: string, client_data_json: string, authenticator_data: string, signature: string, user_handle: string | null, redirect_to: string | null, response_type: string | null, pkce_code_challenge: string | null, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Options for `navigator.credentials.create()`. Binary values are base64url-encoded.
+ */
+export type PasskeyRegistrationOptions = { challenge: string, rp_id: string, rp_name: string, user_id: string, user_name: string, 
+/**
+ * Ids of already registered credentials to avoid duplicate registrations.
+ */
+exclude_credentials: Array<string>, 
+/**
+ * Supported COSE algorithm identifiers in order of preference.
+ */
+algorithms: Array<bigint>, timeout_ms: bigint, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of `navigator.credentials.create()`. Binary values are base64url-encoded.
+ */
+export type PasskeyRegistrationRequest = { client_data_json: string, attestation_object: string, 
+/**
+ * Display name to tell passkeys apart, e.g. "Laptop".
+ */
+name: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Grants or revokes a role to/from either a single user or all members of a group.
+ */
+export type RoleGrantRequest = { role: string, user: string | null, group: string | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TotpConfirmResponse = { 
+/**
+ * Single-use codes substituting for a TOTP code, e.g. when the authenticator was lost. They
+ * are only shown once.
+ */
+recovery_codes: Array<string>, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TotpEnrollResponse = { 
+/**
+ * Base32-encoded shared secret for manual entry.
+ */
+secret: string, 
+/**
+ * "otpauth://" URI to be rendered as QR code for authenticator apps.
+ */
+provisioning_uri: string, };
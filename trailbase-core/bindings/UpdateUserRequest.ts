@@ -1,3 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
 
-export type UpdateUserRequest = { id: string, email: string | null, password: string | null, verified: boolean | null, };
+export type UpdateUserRequest = { id: string, email: string | null, password: string | null, verified: boolean | null, 
+/**
+ * Replaces the user's metadata, which must be a JSON object. Changes to custom claims only take
+ * effect once the user's auth token is refreshed.
+ */
+metadata: JsonValue | null, };
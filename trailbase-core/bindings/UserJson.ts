@@ -1,3 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsonValue } from "./serde_json/JsonValue";
 
-export type UserJson = { id: string, email: string, verified: boolean, admin: boolean, provider_id: bigint, provider_user_id: string | null, email_verification_code: string, };
+export type UserJson = { id: string, email: string, verified: boolean, admin: boolean, provider_id: bigint, provider_user_id: string | null, email_verification_code: string, failed_login_attempts: bigint, 
+/**
+ * Unix timestamp until which logins are rejected, if locked.
+ */
+locked_until: bigint | null, 
+/**
+ * Admin-editable JSON metadata.
+ */
+metadata: JsonValue | null, };
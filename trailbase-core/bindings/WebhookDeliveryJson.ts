@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type WebhookDeliveryJson = { id: bigint, created: bigint, api_name: string, url: string, event: string, payload: string, 
+/**
+ * One of "pending", "delivered" or "failed".
+ */
+status: string, attempts: bigint, next_attempt: bigint, last_attempt: bigint | null, response_status: bigint | null, error: string | null, };
@@ -12,11 +12,21 @@ export const protobufPackage = "config";
 export enum OAuthProviderId {
   OAUTH_PROVIDER_ID_UNDEFINED = 0,
   CUSTOM = 1,
+  /**
+   * OIDC - / Generic OpenID Connect provider configured via discovery. Unlike other
+   * / providers, it may be configured multiple times, each instance being
+   * / identified by its key in `AuthConfig.oauth_providers`.
+   */
+  OIDC = 2,
   DISCORD = 10,
   GITLAB = 11,
   GOOGLE = 12,
   FACEBOOK = 13,
   MICROSOFT = 14,
+  GITHUB = 15,
+  APPLE = 16,
+  TWITCH = 17,
+  YAHOO = 18,
   UNRECOGNIZED = -1,
 }
 
@@ -28,6 +38,9 @@ export function oAuthProviderIdFromJSON(object: any): OAuthProviderId {
     case 1:
     case "CUSTOM":
       return OAuthProviderId.CUSTOM;
+    case 2:
+    case "OIDC":
+      return OAuthProviderId.OIDC;
     case 10:
     case "DISCORD":
       return OAuthProviderId.DISCORD;
@@ -43,6 +56,18 @@ export function oAuthProviderIdFromJSON(object: any): OAuthProviderId {
     case 14:
     case "MICROSOFT":
       return OAuthProviderId.MICROSOFT;
+    case 15:
+    case "GITHUB":
+      return OAuthProviderId.GITHUB;
+    case 16:
+    case "APPLE":
+      return OAuthProviderId.APPLE;
+    case 17:
+    case "TWITCH":
+      return OAuthProviderId.TWITCH;
+    case 18:
+    case "YAHOO":
+      return OAuthProviderId.YAHOO;
     case -1:
     case "UNRECOGNIZED":
     default:
@@ -56,6 +81,8 @@ export function oAuthProviderIdToJSON(object: OAuthProviderId): string {
       return "OAUTH_PROVIDER_ID_UNDEFINED";
     case OAuthProviderId.CUSTOM:
       return "CUSTOM";
+    case OAuthProviderId.OIDC:
+      return "OIDC";
     case OAuthProviderId.DISCORD:
       return "DISCORD";
     case OAuthProviderId.GITLAB:
@@ -66,6 +93,14 @@ export function oAuthProviderIdToJSON(object: OAuthProviderId): string {
       return "FACEBOOK";
     case OAuthProviderId.MICROSOFT:
       return "MICROSOFT";
+    case OAuthProviderId.GITHUB:
+      return "GITHUB";
+    case OAuthProviderId.APPLE:
+      return "APPLE";
+    case OAuthProviderId.TWITCH:
+      return "TWITCH";
+    case OAuthProviderId.YAHOO:
+      return "YAHOO";
     case OAuthProviderId.UNRECOGNIZED:
     default:
       return "UNRECOGNIZED";
@@ -149,7 +184,7 @@ export enum PermissionFlag {
   PERMISSION_FLAG_UNDEFINED = 0,
   /** CREATE - Database record insert. */
   CREATE = 1,
-  /** READ - Database record read/list, i.e. select. */
+  /** READ - Database record read, i.e. select by id. */
   READ = 2,
   /** UPDATE - Database record update. */
   UPDATE = 4,
@@ -157,6 +192,8 @@ export enum PermissionFlag {
   DELETE = 8,
   /** SCHEMA - / Lookup JSON schema for the given record api . */
   SCHEMA = 16,
+  /** LIST - Database record list, i.e. select with filters. */
+  LIST = 32,
   UNRECOGNIZED = -1,
 }
 
@@ -180,6 +217,9 @@ export function permissionFlagFromJSON(object: any): PermissionFlag {
     case 16:
     case "SCHEMA":
       return PermissionFlag.SCHEMA;
+    case 32:
+    case "LIST":
+      return PermissionFlag.LIST;
     case -1:
     case "UNRECOGNIZED":
     default:
@@ -201,12 +241,59 @@ export function permissionFlagToJSON(object: PermissionFlag): string {
       return "DELETE";
     case PermissionFlag.SCHEMA:
       return "SCHEMA";
+    case PermissionFlag.LIST:
+      return "LIST";
     case PermissionFlag.UNRECOGNIZED:
     default:
       return "UNRECOGNIZED";
   }
 }
 
+export enum WebhookEvent {
+  WEBHOOK_EVENT_UNDEFINED = 0,
+  WEBHOOK_EVENT_INSERT = 1,
+  WEBHOOK_EVENT_UPDATE = 2,
+  WEBHOOK_EVENT_DELETE = 3,
+  UNRECOGNIZED = -1,
+}
+
+export function webhookEventFromJSON(object: any): WebhookEvent {
+  switch (object) {
+    case 0:
+    case "WEBHOOK_EVENT_UNDEFINED":
+      return WebhookEvent.WEBHOOK_EVENT_UNDEFINED;
+    case 1:
+    case "WEBHOOK_EVENT_INSERT":
+      return WebhookEvent.WEBHOOK_EVENT_INSERT;
+    case 2:
+    case "WEBHOOK_EVENT_UPDATE":
+      return WebhookEvent.WEBHOOK_EVENT_UPDATE;
+    case 3:
+    case "WEBHOOK_EVENT_DELETE":
+      return WebhookEvent.WEBHOOK_EVENT_DELETE;
+    case -1:
+    case "UNRECOGNIZED":
+    default:
+      return WebhookEvent.UNRECOGNIZED;
+  }
+}
+
+export function webhookEventToJSON(object: WebhookEvent): string {
+  switch (object) {
+    case WebhookEvent.WEBHOOK_EVENT_UNDEFINED:
+      return "WEBHOOK_EVENT_UNDEFINED";
+    case WebhookEvent.WEBHOOK_EVENT_INSERT:
+      return "WEBHOOK_EVENT_INSERT";
+    case WebhookEvent.WEBHOOK_EVENT_UPDATE:
+      return "WEBHOOK_EVENT_UPDATE";
+    case WebhookEvent.WEBHOOK_EVENT_DELETE:
+      return "WEBHOOK_EVENT_DELETE";
+    case WebhookEvent.UNRECOGNIZED:
+    default:
+      return "UNRECOGNIZED";
+  }
+}
+
 export interface EmailTemplate {
   subject?: string | undefined;
   body?: string | undefined;
@@ -222,6 +309,7 @@ export interface EmailConfig {
   userVerificationTemplate?: EmailTemplate | undefined;
   passwordResetTemplate?: EmailTemplate | undefined;
   changeEmailTemplate?: EmailTemplate | undefined;
+  magicLinkTemplate?: EmailTemplate | undefined;
 }
 
 export interface OAuthProviderConfig {
@@ -233,12 +321,51 @@ export interface OAuthProviderConfig {
   tokenUrl?: string | undefined;
   userApiUrl?: string | undefined;
   pkce?: boolean | undefined;
+  /**
+   * / Issuer of OpenID Connect providers, e.g. "https://accounts.example.com".
+   * / Endpoints and signing keys are discovered from
+   * / `<issuer_url>/.well-known/openid-configuration`.
+   */
+  issuerUrl?: string | undefined;
+  /**
+   * / Apple's client secrets are short-lived JWTs signed with a private key
+   * / issued to a team. For Apple, `client_secret` holds the PEM encoded private
+   * / key, `key_id` its id and `team_id` the id of the owning team.
+   */
+  teamId?: string | undefined;
+  keyId?: string | undefined;
 }
 
 export interface AuthConfig {
   authTokenTtlSec?: number | undefined;
   refreshTokenTtlSec?: number | undefined;
+  /**
+   * / Time to live of passwordless login links and codes sent via email.
+   * / Default: 15 minutes.
+   */
+  magicLinkTtlSec?: number | undefined;
   oauthProviders: { [key: string]: OAuthProviderConfig };
+  /**
+   * Consecutive failed logins after which an account is temporarily locked.
+   * Setting it to 0 disables account lockout. Default: 10.
+   */
+  maxFailedLoginAttempts?: number | undefined;
+  /**
+   * Failed logins from a single IP address after which further attempts from
+   * that address are temporarily rejected. Setting it to 0 disables IP
+   * lockout. Default: 50.
+   */
+  maxFailedLoginAttemptsPerIp?: number | undefined;
+  /** Duration of temporary lockouts. Default: 15 minutes. */
+  lockoutDurationSec?:
+    | number
+    | undefined;
+  /**
+   * Custom claims embedded into auth tokens, mapping claim names to top-level
+   * keys of a user's JSON metadata, e.g. "tenant_id" -> "tenant". Claims are
+   * available to access rules as `_USER_.<claim>`.
+   */
+  customClaims: { [key: string]: string };
 }
 
 export interface AuthConfig_OauthProvidersEntry {
@@ -246,6 +373,11 @@ export interface AuthConfig_OauthProvidersEntry {
   value: OAuthProviderConfig | undefined;
 }
 
+export interface AuthConfig_CustomClaimsEntry {
+  key: string;
+  value: string;
+}
+
 export interface S3StorageConfig {
   endpoint?: string | undefined;
   region?: string | undefined;
@@ -260,6 +392,48 @@ export interface S3StorageConfig {
   secretAccessKey?: string | undefined;
 }
 
+export interface RateLimitPolicy {
+  /**
+   * / Sustained number of requests allowed per minute. Setting it to 0
+   * / disables the limit.
+   */
+  requestsPerMinute?:
+    | number
+    | undefined;
+  /**
+   * / Max number of requests that can be made in a quick burst. Default:
+   * / requests_per_minute.
+   */
+  burst?:
+    | number
+    | undefined;
+  /**
+   * / Key buckets by authenticated user rather than client IP. Anonymous
+   * / requests fall back to their client IP.
+   */
+  perUser?: boolean | undefined;
+}
+
+export interface RateLimitConfig {
+  /** / Limit for auth APIs, e.g. login, registration and password resets. */
+  auth?:
+    | RateLimitPolicy
+    | undefined;
+  /** / Default limit for all record APIs. */
+  records?:
+    | RateLimitPolicy
+    | undefined;
+  /** / Per record API overrides of `records` keyed by API name. */
+  recordApis: { [key: string]: RateLimitPolicy };
+  /** / Limit for custom routes registered by JS/TS handlers. */
+  customRoutes?: RateLimitPolicy | undefined;
+}
+
+export interface RateLimitConfig_RecordApisEntry {
+  key: string;
+  value: RateLimitPolicy | undefined;
+}
+
 export interface ServerConfig {
   /**
    * / Application name presented to users, e.g. when sending emails. Default:
@@ -292,6 +466,42 @@ export interface ServerConfig {
     | undefined;
   /** / If present will use S3 setup over local file-system based storage. */
   s3StorageConfig?: S3StorageConfig | undefined;
+  /**
+   * / Tables, whose committed inserts, updates and deletes are recorded to the
+   * / "_changes" table in "changes.db" for consumption by external systems,
+   * / e.g. to incrementally sync data into a warehouse.
+   */
+  changeCaptureTables: string[];
+  /**
+   * / Max age of captured changes that will be retained during periodic
+   * / cleanup. Setting it to 0 retains changes indefinitely. Default: 7 days.
+   */
+  changeCaptureRetentionSec?: number | undefined;
+  /**
+   * / Per route group request rate limits. Requests exceeding a limit are
+   * / rejected with "429 Too Many Requests". Default: no limits.
+   */
+  rateLimits?: RateLimitConfig | undefined;
+}
+
+export interface WebhookConfig {
+  /**
+   * / Stable identifier, unique within the record API, which queued deliveries
+   * / refer to. Assigned automatically if missing.
+   */
+  id?:
+    | string
+    | undefined;
+  /** / Endpoint, to which events are POSTed as JSON. */
+  url?: string | undefined;
+  /** / Events triggering a delivery. All events if empty. */
+  events: WebhookEvent[];
+  /**
+   * / Shared secret for signing payloads. If set, deliveries carry an
+   * / `X-TrailBase-Signature: sha256=<hex>` header with the HMAC-SHA256 of the
+   * / body.
+   */
+  secret?: string | undefined;
 }
 
 export interface RecordApiConfig {
@@ -348,7 +558,8 @@ export interface RecordApiConfig {
   readAccessRule?: string | undefined;
   updateAccessRule?: string | undefined;
   deleteAccessRule?: string | undefined;
-  schemaAccessRule?:
+  schemaAccessRule?: string | undefined;
+  listAccessRule?:
     | string
     | undefined;
   /**
@@ -356,9 +567,67 @@ export interface RecordApiConfig {
    * / foreign record will be inlined into the response.
    * /
    * / Only columns and foreign tables with names not starting with "_", i.e. are
-   * / allowed to be expanded.
+   * / allowed to be expanded. If a Record API exposes the foreign table, its
+   * / read access applies to the inlined records, see `expand_apis`.
    */
   expand: string[];
+  /**
+   * / Target Record APIs for `expand` columns, keyed by column name. The target
+   * / API's read access and hidden columns apply to the inlined records and its
+   * / `expand` to nested expansions. Columns not listed use the first Record
+   * / API in config order exposing the foreign table, if any.
+   */
+  expandApis: { [key: string]: string };
+  /**
+   * / Max offset allowed for offset-based pagination when listing records,
+   * / e.g. `?offset=100`. Large offsets are expensive, since skipped records
+   * / still need to be scanned. Prefer cursors when possible. Default: 10000.
+   */
+  maxListOffset?: number | undefined;
+  /**
+   * / Columns, which are never exposed through this API, i.e. they are excluded
+   * / from read and list responses, realtime subscriptions and JSON schemas, and
+   * / cannot be set by clients. Access rules can still reference them.
+   */
+  hiddenColumns: string[];
+  /**
+   * / Columns, which are exposed but cannot be set by clients on create or
+   * / update. They can still be filled by defaults, triggers or
+   * / `autofill_missing_user_id_columns`.
+   */
+  readOnlyColumns: string[];
+  /**
+   * / Max number of foreign key hops per expansion path, e.g.
+   * / `?expand=author.organization` has two. Hops past the first need to be
+   * / configured in `expand` of the parent expansion's target Record API.
+   * / Default: 3.
+   */
+  maxExpandDepth?: number | undefined;
+  /**
+   * / Names of Record APIs, whose records reference records of this API and can
+   * / be inlined as lists on read, e.g. `?expand=comments(limit:5,order:-created)`.
+   * / The child API's table needs exactly one foreign key referencing this
+   * / API's table and its read and list access apply.
+   */
+  expandChildren: string[];
+  /**
+   * / Text columns, which can be searched via `?q=` when listing records.
+   * / TrailBase maintains an FTS5 index for the table, kept in sync using
+   * / triggers. Results are ranked by relevance, i.e. bm25, unless ordered
+   * / explicitly.
+   */
+  searchColumns: string[];
+  /**
+   * / Webhooks notified about records being created, updated or deleted
+   * / through this API. Deliveries are queued persistently and retried with
+   * / exponential backoff.
+   */
+  webhooks: WebhookConfig[];
+}
+
+export interface RecordApiConfig_ExpandApisEntry {
+  key: string;
+  value: string;
 }
 
 export interface JsonSchemaConfig {
@@ -367,6 +636,13 @@ export interface JsonSchemaConfig {
 }
 
 export interface Config {
+  /**
+   * / Version of the config format. Older configs are migrated automatically on
+   * / startup, thus it should not be changed manually.
+   */
+  version?:
+    | number
+    | undefined;
   /**
    * NOTE: These top-level fields currently have to be `required` due to the
    * overly simple approach on how we do config merging (from env vars and
@@ -488,6 +764,9 @@ export const EmailConfig: MessageFns<EmailConfig> = {
     if (message.changeEmailTemplate !== undefined) {
       EmailTemplate.encode(message.changeEmailTemplate, writer.uint32(186).fork()).join();
     }
+    if (message.magicLinkTemplate !== undefined) {
+      EmailTemplate.encode(message.magicLinkTemplate, writer.uint32(194).fork()).join();
+    }
     return writer;
   },
 
@@ -570,6 +849,14 @@ export const EmailConfig: MessageFns<EmailConfig> = {
           message.changeEmailTemplate = EmailTemplate.decode(reader, reader.uint32());
           continue;
         }
+        case 24: {
+          if (tag !== 194) {
+            break;
+          }
+
+          message.magicLinkTemplate = EmailTemplate.decode(reader, reader.uint32());
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -596,6 +883,7 @@ export const EmailConfig: MessageFns<EmailConfig> = {
       changeEmailTemplate: isSet(object.changeEmailTemplate)
         ? EmailTemplate.fromJSON(object.changeEmailTemplate)
         : undefined,
+      magicLinkTemplate: isSet(object.magicLinkTemplate) ? EmailTemplate.fromJSON(object.magicLinkTemplate) : undefined,
     };
   },
 
@@ -628,6 +916,9 @@ export const EmailConfig: MessageFns<EmailConfig> = {
     if (message.changeEmailTemplate !== undefined) {
       obj.changeEmailTemplate = EmailTemplate.toJSON(message.changeEmailTemplate);
     }
+    if (message.magicLinkTemplate !== undefined) {
+      obj.magicLinkTemplate = EmailTemplate.toJSON(message.magicLinkTemplate);
+    }
     return obj;
   },
 
@@ -653,6 +944,9 @@ export const EmailConfig: MessageFns<EmailConfig> = {
     message.changeEmailTemplate = (object.changeEmailTemplate !== undefined && object.changeEmailTemplate !== null)
       ? EmailTemplate.fromPartial(object.changeEmailTemplate)
       : undefined;
+    message.magicLinkTemplate = (object.magicLinkTemplate !== undefined && object.magicLinkTemplate !== null)
+      ? EmailTemplate.fromPartial(object.magicLinkTemplate)
+      : undefined;
     return message;
   },
 };
@@ -687,6 +981,15 @@ export const OAuthProviderConfig: MessageFns<OAuthProviderConfig> = {
     if (message.pkce !== undefined && message.pkce !== false) {
       writer.uint32(120).bool(message.pkce);
     }
+    if (message.issuerUrl !== undefined && message.issuerUrl !== "") {
+      writer.uint32(130).string(message.issuerUrl);
+    }
+    if (message.teamId !== undefined && message.teamId !== "") {
+      writer.uint32(138).string(message.teamId);
+    }
+    if (message.keyId !== undefined && message.keyId !== "") {
+      writer.uint32(146).string(message.keyId);
+    }
     return writer;
   },
 
@@ -761,6 +1064,30 @@ export const OAuthProviderConfig: MessageFns<OAuthProviderConfig> = {
           message.pkce = reader.bool();
           continue;
         }
+        case 16: {
+          if (tag !== 130) {
+            break;
+          }
+
+          message.issuerUrl = reader.string();
+          continue;
+        }
+        case 17: {
+          if (tag !== 138) {
+            break;
+          }
+
+          message.teamId = reader.string();
+          continue;
+        }
+        case 18: {
+          if (tag !== 146) {
+            break;
+          }
+
+          message.keyId = reader.string();
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -780,6 +1107,9 @@ export const OAuthProviderConfig: MessageFns<OAuthProviderConfig> = {
       tokenUrl: isSet(object.tokenUrl) ? globalThis.String(object.tokenUrl) : undefined,
       userApiUrl: isSet(object.userApiUrl) ? globalThis.String(object.userApiUrl) : undefined,
       pkce: isSet(object.pkce) ? globalThis.Boolean(object.pkce) : undefined,
+      issuerUrl: isSet(object.issuerUrl) ? globalThis.String(object.issuerUrl) : undefined,
+      teamId: isSet(object.teamId) ? globalThis.String(object.teamId) : undefined,
+      keyId: isSet(object.keyId) ? globalThis.String(object.keyId) : undefined,
     };
   },
 
@@ -809,6 +1139,15 @@ export const OAuthProviderConfig: MessageFns<OAuthProviderConfig> = {
     if (message.pkce !== undefined && message.pkce !== false) {
       obj.pkce = message.pkce;
     }
+    if (message.issuerUrl !== undefined && message.issuerUrl !== "") {
+      obj.issuerUrl = message.issuerUrl;
+    }
+    if (message.teamId !== undefined && message.teamId !== "") {
+      obj.teamId = message.teamId;
+    }
+    if (message.keyId !== undefined && message.keyId !== "") {
+      obj.keyId = message.keyId;
+    }
     return obj;
   },
 
@@ -825,12 +1164,15 @@ export const OAuthProviderConfig: MessageFns<OAuthProviderConfig> = {
     message.tokenUrl = object.tokenUrl ?? "";
     message.userApiUrl = object.userApiUrl ?? "";
     message.pkce = object.pkce ?? false;
+    message.issuerUrl = object.issuerUrl ?? "";
+    message.teamId = object.teamId ?? "";
+    message.keyId = object.keyId ?? "";
     return message;
   },
 };
 
 function createBaseAuthConfig(): AuthConfig {
-  return { oauthProviders: {} };
+  return { oauthProviders: {}, customClaims: {} };
 }
 
 export const AuthConfig: MessageFns<AuthConfig> = {
@@ -841,9 +1183,24 @@ export const AuthConfig: MessageFns<AuthConfig> = {
     if (message.refreshTokenTtlSec !== undefined && message.refreshTokenTtlSec !== 0) {
       writer.uint32(16).int64(message.refreshTokenTtlSec);
     }
+    if (message.magicLinkTtlSec !== undefined && message.magicLinkTtlSec !== 0) {
+      writer.uint32(24).int64(message.magicLinkTtlSec);
+    }
     Object.entries(message.oauthProviders).forEach(([key, value]) => {
       AuthConfig_OauthProvidersEntry.encode({ key: key as any, value }, writer.uint32(90).fork()).join();
     });
+    if (message.maxFailedLoginAttempts !== undefined && message.maxFailedLoginAttempts !== 0) {
+      writer.uint32(96).int32(message.maxFailedLoginAttempts);
+    }
+    if (message.maxFailedLoginAttemptsPerIp !== undefined && message.maxFailedLoginAttemptsPerIp !== 0) {
+      writer.uint32(104).int32(message.maxFailedLoginAttemptsPerIp);
+    }
+    if (message.lockoutDurationSec !== undefined && message.lockoutDurationSec !== 0) {
+      writer.uint32(112).int64(message.lockoutDurationSec);
+    }
+    Object.entries(message.customClaims).forEach(([key, value]) => {
+      AuthConfig_CustomClaimsEntry.encode({ key: key as any, value }, writer.uint32(122).fork()).join();
+    });
     return writer;
   },
 
@@ -870,6 +1227,14 @@ export const AuthConfig: MessageFns<AuthConfig> = {
           message.refreshTokenTtlSec = longToNumber(reader.int64());
           continue;
         }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.magicLinkTtlSec = longToNumber(reader.int64());
+          continue;
+        }
         case 11: {
           if (tag !== 90) {
             break;
@@ -881,6 +1246,41 @@ export const AuthConfig: MessageFns<AuthConfig> = {
           }
           continue;
         }
+        case 12: {
+          if (tag !== 96) {
+            break;
+          }
+
+          message.maxFailedLoginAttempts = reader.int32();
+          continue;
+        }
+        case 13: {
+          if (tag !== 104) {
+            break;
+          }
+
+          message.maxFailedLoginAttemptsPerIp = reader.int32();
+          continue;
+        }
+        case 14: {
+          if (tag !== 112) {
+            break;
+          }
+
+          message.lockoutDurationSec = longToNumber(reader.int64());
+          continue;
+        }
+        case 15: {
+          if (tag !== 122) {
+            break;
+          }
+
+          const entry15 = AuthConfig_CustomClaimsEntry.decode(reader, reader.uint32());
+          if (entry15.value !== undefined) {
+            message.customClaims[entry15.key] = entry15.value;
+          }
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -894,12 +1294,22 @@ export const AuthConfig: MessageFns<AuthConfig> = {
     return {
       authTokenTtlSec: isSet(object.authTokenTtlSec) ? globalThis.Number(object.authTokenTtlSec) : undefined,
       refreshTokenTtlSec: isSet(object.refreshTokenTtlSec) ? globalThis.Number(object.refreshTokenTtlSec) : undefined,
+      magicLinkTtlSec: isSet(object.magicLinkTtlSec) ? globalThis.Number(object.magicLinkTtlSec) : undefined,
       oauthProviders: isObject(object.oauthProviders)
         ? Object.entries(object.oauthProviders).reduce<{ [key: string]: OAuthProviderConfig }>((acc, [key, value]) => {
           acc[key] = OAuthProviderConfig.fromJSON(value);
           return acc;
         }, {})
         : {},
+      maxFailedLoginAttempts: isSet(object.maxFailedLoginAttempts) ? globalThis.Number(object.maxFailedLoginAttempts) : undefined,
+      maxFailedLoginAttemptsPerIp: isSet(object.maxFailedLoginAttemptsPerIp) ? globalThis.Number(object.maxFailedLoginAttemptsPerIp) : undefined,
+      lockoutDurationSec: isSet(object.lockoutDurationSec) ? globalThis.Number(object.lockoutDurationSec) : undefined,
+      customClaims: isObject(object.customClaims)
+        ? Object.entries(object.customClaims).reduce<{ [key: string]: string }>((acc, [key, value]) => {
+          acc[key] = String(value);
+          return acc;
+        }, {})
+        : {},
     };
   },
 
@@ -911,6 +1321,9 @@ export const AuthConfig: MessageFns<AuthConfig> = {
     if (message.refreshTokenTtlSec !== undefined && message.refreshTokenTtlSec !== 0) {
       obj.refreshTokenTtlSec = Math.round(message.refreshTokenTtlSec);
     }
+    if (message.magicLinkTtlSec !== undefined && message.magicLinkTtlSec !== 0) {
+      obj.magicLinkTtlSec = Math.round(message.magicLinkTtlSec);
+    }
     if (message.oauthProviders) {
       const entries = Object.entries(message.oauthProviders);
       if (entries.length > 0) {
@@ -920,6 +1333,24 @@ export const AuthConfig: MessageFns<AuthConfig> = {
         });
       }
     }
+    if (message.maxFailedLoginAttempts !== undefined && message.maxFailedLoginAttempts !== 0) {
+      obj.maxFailedLoginAttempts = Math.round(message.maxFailedLoginAttempts);
+    }
+    if (message.maxFailedLoginAttemptsPerIp !== undefined && message.maxFailedLoginAttemptsPerIp !== 0) {
+      obj.maxFailedLoginAttemptsPerIp = Math.round(message.maxFailedLoginAttemptsPerIp);
+    }
+    if (message.lockoutDurationSec !== undefined && message.lockoutDurationSec !== 0) {
+      obj.lockoutDurationSec = Math.round(message.lockoutDurationSec);
+    }
+    if (message.customClaims) {
+      const entries = Object.entries(message.customClaims);
+      if (entries.length > 0) {
+        obj.customClaims = {};
+        entries.forEach(([k, v]) => {
+          obj.customClaims[k] = v;
+        });
+      }
+    }
     return obj;
   },
 
@@ -930,6 +1361,7 @@ export const AuthConfig: MessageFns<AuthConfig> = {
     const message = createBaseAuthConfig();
     message.authTokenTtlSec = object.authTokenTtlSec ?? 0;
     message.refreshTokenTtlSec = object.refreshTokenTtlSec ?? 0;
+    message.magicLinkTtlSec = object.magicLinkTtlSec ?? 0;
     message.oauthProviders = Object.entries(object.oauthProviders ?? {}).reduce<{ [key: string]: OAuthProviderConfig }>(
       (acc, [key, value]) => {
         if (value !== undefined) {
@@ -939,6 +1371,18 @@ export const AuthConfig: MessageFns<AuthConfig> = {
       },
       {},
     );
+    message.maxFailedLoginAttempts = object.maxFailedLoginAttempts ?? 0;
+    message.maxFailedLoginAttemptsPerIp = object.maxFailedLoginAttemptsPerIp ?? 0;
+    message.lockoutDurationSec = object.lockoutDurationSec ?? 0;
+    message.customClaims = Object.entries(object.customClaims ?? {}).reduce<{ [key: string]: string }>(
+      (acc, [key, value]) => {
+        if (value !== undefined) {
+          acc[key] = globalThis.String(value);
+        }
+        return acc;
+      },
+      {},
+    );
     return message;
   },
 };
@@ -1023,6 +1467,82 @@ export const AuthConfig_OauthProvidersEntry: MessageFns<AuthConfig_OauthProvider
   },
 };
 
+function createBaseAuthConfig_CustomClaimsEntry(): AuthConfig_CustomClaimsEntry {
+  return { key: "", value: "" };
+}
+
+export const AuthConfig_CustomClaimsEntry: MessageFns<AuthConfig_CustomClaimsEntry> = {
+  encode(message: AuthConfig_CustomClaimsEntry, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.key !== "") {
+      writer.uint32(10).string(message.key);
+    }
+    if (message.value !== "") {
+      writer.uint32(18).string(message.value);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): AuthConfig_CustomClaimsEntry {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseAuthConfig_CustomClaimsEntry();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.key = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.value = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): AuthConfig_CustomClaimsEntry {
+    return {
+      key: isSet(object.key) ? globalThis.String(object.key) : "",
+      value: isSet(object.value) ? globalThis.String(object.value) : "",
+    };
+  },
+
+  toJSON(message: AuthConfig_CustomClaimsEntry): unknown {
+    const obj: any = {};
+    if (message.key !== "") {
+      obj.key = message.key;
+    }
+    if (message.value !== "") {
+      obj.value = message.value;
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<AuthConfig_CustomClaimsEntry>, I>>(base?: I): AuthConfig_CustomClaimsEntry {
+    return AuthConfig_CustomClaimsEntry.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<AuthConfig_CustomClaimsEntry>, I>>(object: I): AuthConfig_CustomClaimsEntry {
+    const message = createBaseAuthConfig_CustomClaimsEntry();
+    message.key = object.key ?? "";
+    message.value = object.value ?? "";
+    return message;
+  },
+};
+
 function createBaseS3StorageConfig(): S3StorageConfig {
   return {};
 }
@@ -1147,26 +1667,343 @@ export const S3StorageConfig: MessageFns<S3StorageConfig> = {
   },
 };
 
-function createBaseServerConfig(): ServerConfig {
+function createBaseRateLimitPolicy(): RateLimitPolicy {
   return {};
 }
 
-export const ServerConfig: MessageFns<ServerConfig> = {
-  encode(message: ServerConfig, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
-    if (message.applicationName !== undefined && message.applicationName !== "") {
-      writer.uint32(10).string(message.applicationName);
-    }
-    if (message.siteUrl !== undefined && message.siteUrl !== "") {
-      writer.uint32(18).string(message.siteUrl);
-    }
-    if (message.logsRetentionSec !== undefined && message.logsRetentionSec !== 0) {
-      writer.uint32(88).int64(message.logsRetentionSec);
+export const RateLimitPolicy: MessageFns<RateLimitPolicy> = {
+  encode(message: RateLimitPolicy, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.requestsPerMinute !== undefined && message.requestsPerMinute !== 0) {
+      writer.uint32(8).uint32(message.requestsPerMinute);
     }
-    if (message.backupIntervalSec !== undefined && message.backupIntervalSec !== 0) {
-      writer.uint32(96).int64(message.backupIntervalSec);
+    if (message.burst !== undefined && message.burst !== 0) {
+      writer.uint32(16).uint32(message.burst);
     }
-    if (message.s3StorageConfig !== undefined) {
-      S3StorageConfig.encode(message.s3StorageConfig, writer.uint32(106).fork()).join();
+    if (message.perUser !== undefined && message.perUser !== false) {
+      writer.uint32(24).bool(message.perUser);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): RateLimitPolicy {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseRateLimitPolicy();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 8) {
+            break;
+          }
+
+          message.requestsPerMinute = reader.uint32();
+          continue;
+        }
+        case 2: {
+          if (tag !== 16) {
+            break;
+          }
+
+          message.burst = reader.uint32();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.perUser = reader.bool();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): RateLimitPolicy {
+    return {
+      requestsPerMinute: isSet(object.requestsPerMinute) ? globalThis.Number(object.requestsPerMinute) : undefined,
+      burst: isSet(object.burst) ? globalThis.Number(object.burst) : undefined,
+      perUser: isSet(object.perUser) ? globalThis.Boolean(object.perUser) : undefined,
+    };
+  },
+
+  toJSON(message: RateLimitPolicy): unknown {
+    const obj: any = {};
+    if (message.requestsPerMinute !== undefined && message.requestsPerMinute !== 0) {
+      obj.requestsPerMinute = Math.round(message.requestsPerMinute);
+    }
+    if (message.burst !== undefined && message.burst !== 0) {
+      obj.burst = Math.round(message.burst);
+    }
+    if (message.perUser !== undefined && message.perUser !== false) {
+      obj.perUser = message.perUser;
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<RateLimitPolicy>, I>>(base?: I): RateLimitPolicy {
+    return RateLimitPolicy.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<RateLimitPolicy>, I>>(object: I): RateLimitPolicy {
+    const message = createBaseRateLimitPolicy();
+    message.requestsPerMinute = object.requestsPerMinute ?? 0;
+    message.burst = object.burst ?? 0;
+    message.perUser = object.perUser ?? false;
+    return message;
+  },
+};
+
+function createBaseRateLimitConfig(): RateLimitConfig {
+  return { recordApis: {} };
+}
+
+export const RateLimitConfig: MessageFns<RateLimitConfig> = {
+  encode(message: RateLimitConfig, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.auth !== undefined) {
+      RateLimitPolicy.encode(message.auth, writer.uint32(10).fork()).join();
+    }
+    if (message.records !== undefined) {
+      RateLimitPolicy.encode(message.records, writer.uint32(18).fork()).join();
+    }
+    Object.entries(message.recordApis).forEach(([key, value]) => {
+      RateLimitConfig_RecordApisEntry.encode({ key: key as any, value }, writer.uint32(26).fork()).join();
+    });
+    if (message.customRoutes !== undefined) {
+      RateLimitPolicy.encode(message.customRoutes, writer.uint32(34).fork()).join();
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): RateLimitConfig {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseRateLimitConfig();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.auth = RateLimitPolicy.decode(reader, reader.uint32());
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.records = RateLimitPolicy.decode(reader, reader.uint32());
+          continue;
+        }
+        case 3: {
+          if (tag !== 26) {
+            break;
+          }
+
+          const entry3 = RateLimitConfig_RecordApisEntry.decode(reader, reader.uint32());
+          if (entry3.value !== undefined) {
+            message.recordApis[entry3.key] = entry3.value;
+          }
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.customRoutes = RateLimitPolicy.decode(reader, reader.uint32());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): RateLimitConfig {
+    return {
+      auth: isSet(object.auth) ? RateLimitPolicy.fromJSON(object.auth) : undefined,
+      records: isSet(object.records) ? RateLimitPolicy.fromJSON(object.records) : undefined,
+      recordApis: isObject(object.recordApis)
+        ? Object.entries(object.recordApis).reduce<{ [key: string]: RateLimitPolicy }>((acc, [key, value]) => {
+          acc[key] = RateLimitPolicy.fromJSON(value);
+          return acc;
+        }, {})
+        : {},
+      customRoutes: isSet(object.customRoutes) ? RateLimitPolicy.fromJSON(object.customRoutes) : undefined,
+    };
+  },
+
+  toJSON(message: RateLimitConfig): unknown {
+    const obj: any = {};
+    if (message.auth !== undefined) {
+      obj.auth = RateLimitPolicy.toJSON(message.auth);
+    }
+    if (message.records !== undefined) {
+      obj.records = RateLimitPolicy.toJSON(message.records);
+    }
+    if (message.recordApis) {
+      const entries = Object.entries(message.recordApis);
+      if (entries.length > 0) {
+        obj.recordApis = {};
+        entries.forEach(([k, v]) => {
+          obj.recordApis[k] = RateLimitPolicy.toJSON(v);
+        });
+      }
+    }
+    if (message.customRoutes !== undefined) {
+      obj.customRoutes = RateLimitPolicy.toJSON(message.customRoutes);
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<RateLimitConfig>, I>>(base?: I): RateLimitConfig {
+    return RateLimitConfig.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<RateLimitConfig>, I>>(object: I): RateLimitConfig {
+    const message = createBaseRateLimitConfig();
+    message.auth = (object.auth !== undefined && object.auth !== null)
+      ? RateLimitPolicy.fromPartial(object.auth)
+      : undefined;
+    message.records = (object.records !== undefined && object.records !== null)
+      ? RateLimitPolicy.fromPartial(object.records)
+      : undefined;
+    message.recordApis = Object.entries(object.recordApis ?? {}).reduce<{ [key: string]: RateLimitPolicy }>(
+      (acc, [key, value]) => {
+        if (value !== undefined) {
+          acc[key] = RateLimitPolicy.fromPartial(value);
+        }
+        return acc;
+      },
+      {},
+    );
+    message.customRoutes = (object.customRoutes !== undefined && object.customRoutes !== null)
+      ? RateLimitPolicy.fromPartial(object.customRoutes)
+      : undefined;
+    return message;
+  },
+};
+
+function createBaseRateLimitConfig_RecordApisEntry(): RateLimitConfig_RecordApisEntry {
+  return { key: "", value: undefined };
+}
+
+export const RateLimitConfig_RecordApisEntry: MessageFns<RateLimitConfig_RecordApisEntry> = {
+  encode(message: RateLimitConfig_RecordApisEntry, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.key !== "") {
+      writer.uint32(10).string(message.key);
+    }
+    if (message.value !== undefined) {
+      RateLimitPolicy.encode(message.value, writer.uint32(18).fork()).join();
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): RateLimitConfig_RecordApisEntry {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseRateLimitConfig_RecordApisEntry();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.key = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.value = RateLimitPolicy.decode(reader, reader.uint32());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): RateLimitConfig_RecordApisEntry {
+    return {
+      key: isSet(object.key) ? globalThis.String(object.key) : "",
+      value: isSet(object.value) ? RateLimitPolicy.fromJSON(object.value) : undefined,
+    };
+  },
+
+  toJSON(message: RateLimitConfig_RecordApisEntry): unknown {
+    const obj: any = {};
+    if (message.key !== "") {
+      obj.key = message.key;
+    }
+    if (message.value !== undefined) {
+      obj.value = RateLimitPolicy.toJSON(message.value);
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<RateLimitConfig_RecordApisEntry>, I>>(base?: I): RateLimitConfig_RecordApisEntry {
+    return RateLimitConfig_RecordApisEntry.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<RateLimitConfig_RecordApisEntry>, I>>(
+    object: I,
+  ): RateLimitConfig_RecordApisEntry {
+    const message = createBaseRateLimitConfig_RecordApisEntry();
+    message.key = object.key ?? "";
+    message.value = (object.value !== undefined && object.value !== null)
+      ? RateLimitPolicy.fromPartial(object.value)
+      : undefined;
+    return message;
+  },
+};
+
+function createBaseServerConfig(): ServerConfig {
+  return { changeCaptureTables: [] };
+}
+
+export const ServerConfig: MessageFns<ServerConfig> = {
+  encode(message: ServerConfig, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.applicationName !== undefined && message.applicationName !== "") {
+      writer.uint32(10).string(message.applicationName);
+    }
+    if (message.siteUrl !== undefined && message.siteUrl !== "") {
+      writer.uint32(18).string(message.siteUrl);
+    }
+    if (message.logsRetentionSec !== undefined && message.logsRetentionSec !== 0) {
+      writer.uint32(88).int64(message.logsRetentionSec);
+    }
+    if (message.backupIntervalSec !== undefined && message.backupIntervalSec !== 0) {
+      writer.uint32(96).int64(message.backupIntervalSec);
+    }
+    if (message.s3StorageConfig !== undefined) {
+      S3StorageConfig.encode(message.s3StorageConfig, writer.uint32(106).fork()).join();
+    }
+    for (const v of message.changeCaptureTables) {
+      writer.uint32(114).string(v!);
+    }
+    if (message.changeCaptureRetentionSec !== undefined && message.changeCaptureRetentionSec !== 0) {
+      writer.uint32(120).int64(message.changeCaptureRetentionSec);
+    }
+    if (message.rateLimits !== undefined) {
+      RateLimitConfig.encode(message.rateLimits, writer.uint32(130).fork()).join();
     }
     return writer;
   },
@@ -1218,6 +2055,30 @@ export const ServerConfig: MessageFns<ServerConfig> = {
           message.s3StorageConfig = S3StorageConfig.decode(reader, reader.uint32());
           continue;
         }
+        case 14: {
+          if (tag !== 114) {
+            break;
+          }
+
+          message.changeCaptureTables.push(reader.string());
+          continue;
+        }
+        case 15: {
+          if (tag !== 120) {
+            break;
+          }
+
+          message.changeCaptureRetentionSec = longToNumber(reader.int64());
+          continue;
+        }
+        case 16: {
+          if (tag !== 130) {
+            break;
+          }
+
+          message.rateLimits = RateLimitConfig.decode(reader, reader.uint32());
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -1234,6 +2095,9 @@ export const ServerConfig: MessageFns<ServerConfig> = {
       logsRetentionSec: isSet(object.logsRetentionSec) ? globalThis.Number(object.logsRetentionSec) : undefined,
       backupIntervalSec: isSet(object.backupIntervalSec) ? globalThis.Number(object.backupIntervalSec) : undefined,
       s3StorageConfig: isSet(object.s3StorageConfig) ? S3StorageConfig.fromJSON(object.s3StorageConfig) : undefined,
+      changeCaptureTables: globalThis.Array.isArray(object?.changeCaptureTables) ? object.changeCaptureTables.map((e: any) => globalThis.String(e)) : [],
+      changeCaptureRetentionSec: isSet(object.changeCaptureRetentionSec) ? globalThis.Number(object.changeCaptureRetentionSec) : undefined,
+      rateLimits: isSet(object.rateLimits) ? RateLimitConfig.fromJSON(object.rateLimits) : undefined,
     };
   },
 
@@ -1254,6 +2118,15 @@ export const ServerConfig: MessageFns<ServerConfig> = {
     if (message.s3StorageConfig !== undefined) {
       obj.s3StorageConfig = S3StorageConfig.toJSON(message.s3StorageConfig);
     }
+    if (message.changeCaptureTables?.length) {
+      obj.changeCaptureTables = message.changeCaptureTables;
+    }
+    if (message.changeCaptureRetentionSec !== undefined && message.changeCaptureRetentionSec !== 0) {
+      obj.changeCaptureRetentionSec = Math.round(message.changeCaptureRetentionSec);
+    }
+    if (message.rateLimits !== undefined) {
+      obj.rateLimits = RateLimitConfig.toJSON(message.rateLimits);
+    }
     return obj;
   },
 
@@ -1269,12 +2142,147 @@ export const ServerConfig: MessageFns<ServerConfig> = {
     message.s3StorageConfig = (object.s3StorageConfig !== undefined && object.s3StorageConfig !== null)
       ? S3StorageConfig.fromPartial(object.s3StorageConfig)
       : undefined;
+    message.changeCaptureTables = object.changeCaptureTables?.map((e) => e) || [];
+    message.changeCaptureRetentionSec = object.changeCaptureRetentionSec ?? 0;
+    message.rateLimits = (object.rateLimits !== undefined && object.rateLimits !== null)
+      ? RateLimitConfig.fromPartial(object.rateLimits)
+      : undefined;
+    return message;
+  },
+};
+
+function createBaseWebhookConfig(): WebhookConfig {
+  return { events: [] };
+}
+
+export const WebhookConfig: MessageFns<WebhookConfig> = {
+  encode(message: WebhookConfig, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.id !== undefined && message.id !== "") {
+      writer.uint32(34).string(message.id);
+    }
+    if (message.url !== undefined && message.url !== "") {
+      writer.uint32(10).string(message.url);
+    }
+    writer.uint32(18).fork();
+    for (const v of message.events) {
+      writer.int32(v);
+    }
+    writer.join();
+    if (message.secret !== undefined && message.secret !== "") {
+      writer.uint32(26).string(message.secret);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): WebhookConfig {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseWebhookConfig();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.id = reader.string();
+          continue;
+        }
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.url = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag === 16) {
+            message.events.push(reader.int32() as any);
+
+            continue;
+          }
+
+          if (tag === 18) {
+            const end2 = reader.uint32() + reader.pos;
+            while (reader.pos < end2) {
+              message.events.push(reader.int32() as any);
+            }
+
+            continue;
+          }
+
+          break;
+        }
+        case 3: {
+          if (tag !== 26) {
+            break;
+          }
+
+          message.secret = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): WebhookConfig {
+    return {
+      id: isSet(object.id) ? globalThis.String(object.id) : undefined,
+      url: isSet(object.url) ? globalThis.String(object.url) : undefined,
+      events: globalThis.Array.isArray(object?.events) ? object.events.map((e: any) => webhookEventFromJSON(e)) : [],
+      secret: isSet(object.secret) ? globalThis.String(object.secret) : undefined,
+    };
+  },
+
+  toJSON(message: WebhookConfig): unknown {
+    const obj: any = {};
+    if (message.id !== undefined && message.id !== "") {
+      obj.id = message.id;
+    }
+    if (message.url !== undefined && message.url !== "") {
+      obj.url = message.url;
+    }
+    if (message.events?.length) {
+      obj.events = message.events.map((e) => webhookEventToJSON(e));
+    }
+    if (message.secret !== undefined && message.secret !== "") {
+      obj.secret = message.secret;
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<WebhookConfig>, I>>(base?: I): WebhookConfig {
+    return WebhookConfig.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<WebhookConfig>, I>>(object: I): WebhookConfig {
+    const message = createBaseWebhookConfig();
+    message.id = object.id ?? "";
+    message.url = object.url ?? "";
+    message.events = object.events?.map((e) => e) || [];
+    message.secret = object.secret ?? "";
     return message;
   },
 };
 
 function createBaseRecordApiConfig(): RecordApiConfig {
-  return { aclWorld: [], aclAuthenticated: [], expand: [] };
+  return {
+    aclWorld: [],
+    aclAuthenticated: [],
+    expand: [],
+    expandApis: {},
+    hiddenColumns: [],
+    readOnlyColumns: [],
+    expandChildren: [],
+    searchColumns: [],
+    webhooks: [],
+  };
 }
 
 export const RecordApiConfig: MessageFns<RecordApiConfig> = {
@@ -1319,9 +2327,36 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
     if (message.schemaAccessRule !== undefined && message.schemaAccessRule !== "") {
       writer.uint32(122).string(message.schemaAccessRule);
     }
+    if (message.listAccessRule !== undefined && message.listAccessRule !== "") {
+      writer.uint32(130).string(message.listAccessRule);
+    }
     for (const v of message.expand) {
       writer.uint32(170).string(v!);
     }
+    Object.entries(message.expandApis).forEach(([key, value]) => {
+      RecordApiConfig_ExpandApisEntry.encode({ key: key as any, value }, writer.uint32(234).fork()).join();
+    });
+    if (message.maxListOffset !== undefined && message.maxListOffset !== 0) {
+      writer.uint32(176).uint64(message.maxListOffset);
+    }
+    for (const v of message.hiddenColumns) {
+      writer.uint32(186).string(v!);
+    }
+    for (const v of message.readOnlyColumns) {
+      writer.uint32(194).string(v!);
+    }
+    if (message.maxExpandDepth !== undefined && message.maxExpandDepth !== 0) {
+      writer.uint32(200).uint64(message.maxExpandDepth);
+    }
+    for (const v of message.expandChildren) {
+      writer.uint32(210).string(v!);
+    }
+    for (const v of message.searchColumns) {
+      writer.uint32(218).string(v!);
+    }
+    for (const v of message.webhooks) {
+      WebhookConfig.encode(v!, writer.uint32(226).fork()).join();
+    }
     return writer;
   },
 
@@ -1448,6 +2483,14 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
           message.schemaAccessRule = reader.string();
           continue;
         }
+        case 16: {
+          if (tag !== 130) {
+            break;
+          }
+
+          message.listAccessRule = reader.string();
+          continue;
+        }
         case 21: {
           if (tag !== 170) {
             break;
@@ -1456,6 +2499,73 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
           message.expand.push(reader.string());
           continue;
         }
+        case 29: {
+          if (tag !== 234) {
+            break;
+          }
+
+          const entry29 = RecordApiConfig_ExpandApisEntry.decode(reader, reader.uint32());
+          if (entry29.value !== undefined) {
+            message.expandApis[entry29.key] = entry29.value;
+          }
+          continue;
+        }
+        case 22: {
+          if (tag !== 176) {
+            break;
+          }
+
+          message.maxListOffset = longToNumber(reader.uint64());
+          continue;
+        }
+        case 23: {
+          if (tag !== 186) {
+            break;
+          }
+
+          message.hiddenColumns.push(reader.string());
+          continue;
+        }
+        case 24: {
+          if (tag !== 194) {
+            break;
+          }
+
+          message.readOnlyColumns.push(reader.string());
+          continue;
+        }
+        case 25: {
+          if (tag !== 200) {
+            break;
+          }
+
+          message.maxExpandDepth = longToNumber(reader.uint64());
+          continue;
+        }
+        case 26: {
+          if (tag !== 210) {
+            break;
+          }
+
+          message.expandChildren.push(reader.string());
+          continue;
+        }
+        case 27: {
+          if (tag !== 218) {
+            break;
+          }
+
+          message.searchColumns.push(reader.string());
+          continue;
+        }
+        case 28: {
+          if (tag !== 226) {
+            break;
+          }
+
+          message.webhooks.push(WebhookConfig.decode(reader, reader.uint32()));
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -1489,7 +2599,23 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
       updateAccessRule: isSet(object.updateAccessRule) ? globalThis.String(object.updateAccessRule) : undefined,
       deleteAccessRule: isSet(object.deleteAccessRule) ? globalThis.String(object.deleteAccessRule) : undefined,
       schemaAccessRule: isSet(object.schemaAccessRule) ? globalThis.String(object.schemaAccessRule) : undefined,
+      listAccessRule: isSet(object.listAccessRule) ? globalThis.String(object.listAccessRule) : undefined,
       expand: globalThis.Array.isArray(object?.expand) ? object.expand.map((e: any) => globalThis.String(e)) : [],
+      expandApis: isObject(object.expandApis)
+        ? Object.entries(object.expandApis).reduce<{ [key: string]: string }>((acc, [key, value]) => {
+          acc[key] = String(value);
+          return acc;
+        }, {})
+        : {},
+      maxListOffset: isSet(object.maxListOffset) ? globalThis.Number(object.maxListOffset) : undefined,
+      hiddenColumns: globalThis.Array.isArray(object?.hiddenColumns) ? object.hiddenColumns.map((e: any) => globalThis.String(e)) : [],
+      readOnlyColumns: globalThis.Array.isArray(object?.readOnlyColumns) ? object.readOnlyColumns.map((e: any) => globalThis.String(e)) : [],
+      maxExpandDepth: isSet(object.maxExpandDepth) ? globalThis.Number(object.maxExpandDepth) : undefined,
+      expandChildren: globalThis.Array.isArray(object?.expandChildren) ? object.expandChildren.map((e: any) => globalThis.String(e)) : [],
+      searchColumns: globalThis.Array.isArray(object?.searchColumns) ? object.searchColumns.map((e: any) => globalThis.String(e)) : [],
+      webhooks: globalThis.Array.isArray(object?.webhooks)
+        ? object.webhooks.map((e: any) => WebhookConfig.fromJSON(e))
+        : [],
     };
   },
 
@@ -1531,9 +2657,42 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
     if (message.schemaAccessRule !== undefined && message.schemaAccessRule !== "") {
       obj.schemaAccessRule = message.schemaAccessRule;
     }
+    if (message.listAccessRule !== undefined && message.listAccessRule !== "") {
+      obj.listAccessRule = message.listAccessRule;
+    }
     if (message.expand?.length) {
       obj.expand = message.expand;
     }
+    if (message.expandApis) {
+      const entries = Object.entries(message.expandApis);
+      if (entries.length > 0) {
+        obj.expandApis = {};
+        entries.forEach(([k, v]) => {
+          obj.expandApis[k] = v;
+        });
+      }
+    }
+    if (message.maxListOffset !== undefined && message.maxListOffset !== 0) {
+      obj.maxListOffset = Math.round(message.maxListOffset);
+    }
+    if (message.hiddenColumns?.length) {
+      obj.hiddenColumns = message.hiddenColumns;
+    }
+    if (message.readOnlyColumns?.length) {
+      obj.readOnlyColumns = message.readOnlyColumns;
+    }
+    if (message.maxExpandDepth !== undefined && message.maxExpandDepth !== 0) {
+      obj.maxExpandDepth = Math.round(message.maxExpandDepth);
+    }
+    if (message.expandChildren?.length) {
+      obj.expandChildren = message.expandChildren;
+    }
+    if (message.searchColumns?.length) {
+      obj.searchColumns = message.searchColumns;
+    }
+    if (message.webhooks?.length) {
+      obj.webhooks = message.webhooks.map((e) => WebhookConfig.toJSON(e));
+    }
     return obj;
   },
 
@@ -1554,7 +2713,100 @@ export const RecordApiConfig: MessageFns<RecordApiConfig> = {
     message.updateAccessRule = object.updateAccessRule ?? "";
     message.deleteAccessRule = object.deleteAccessRule ?? "";
     message.schemaAccessRule = object.schemaAccessRule ?? "";
+    message.listAccessRule = object.listAccessRule ?? "";
     message.expand = object.expand?.map((e) => e) || [];
+    message.expandApis = Object.entries(object.expandApis ?? {}).reduce<{ [key: string]: string }>(
+      (acc, [key, value]) => {
+        if (value !== undefined) {
+          acc[key] = globalThis.String(value);
+        }
+        return acc;
+      },
+      {},
+    );
+    message.maxListOffset = object.maxListOffset ?? 0;
+    message.hiddenColumns = object.hiddenColumns?.map((e) => e) || [];
+    message.readOnlyColumns = object.readOnlyColumns?.map((e) => e) || [];
+    message.maxExpandDepth = object.maxExpandDepth ?? 0;
+    message.expandChildren = object.expandChildren?.map((e) => e) || [];
+    message.searchColumns = object.searchColumns?.map((e) => e) || [];
+    message.webhooks = object.webhooks?.map((e) => WebhookConfig.fromPartial(e)) || [];
+    return message;
+  },
+};
+
+function createBaseRecordApiConfig_ExpandApisEntry(): RecordApiConfig_ExpandApisEntry {
+  return { key: "", value: "" };
+}
+
+export const RecordApiConfig_ExpandApisEntry: MessageFns<RecordApiConfig_ExpandApisEntry> = {
+  encode(message: RecordApiConfig_ExpandApisEntry, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.key !== "") {
+      writer.uint32(10).string(message.key);
+    }
+    if (message.value !== "") {
+      writer.uint32(18).string(message.value);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): RecordApiConfig_ExpandApisEntry {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    let end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseRecordApiConfig_ExpandApisEntry();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.key = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.value = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  fromJSON(object: any): RecordApiConfig_ExpandApisEntry {
+    return {
+      key: isSet(object.key) ? globalThis.String(object.key) : "",
+      value: isSet(object.value) ? globalThis.String(object.value) : "",
+    };
+  },
+
+  toJSON(message: RecordApiConfig_ExpandApisEntry): unknown {
+    const obj: any = {};
+    if (message.key !== "") {
+      obj.key = message.key;
+    }
+    if (message.value !== "") {
+      obj.value = message.value;
+    }
+    return obj;
+  },
+
+  create<I extends Exact<DeepPartial<RecordApiConfig_ExpandApisEntry>, I>>(base?: I): RecordApiConfig_ExpandApisEntry {
+    return RecordApiConfig_ExpandApisEntry.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<RecordApiConfig_ExpandApisEntry>, I>>(object: I): RecordApiConfig_ExpandApisEntry {
+    const message = createBaseRecordApiConfig_ExpandApisEntry();
+    message.key = object.key ?? "";
+    message.value = object.value ?? "";
     return message;
   },
 };
@@ -1641,6 +2893,9 @@ function createBaseConfig(): Config {
 
 export const Config: MessageFns<Config> = {
   encode(message: Config, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.version !== undefined && message.version !== 0) {
+      writer.uint32(8).uint32(message.version);
+    }
     if (message.email !== undefined) {
       EmailConfig.encode(message.email, writer.uint32(18).fork()).join();
     }
@@ -1666,6 +2921,14 @@ export const Config: MessageFns<Config> = {
     while (reader.pos < end) {
       const tag = reader.uint32();
       switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 8) {
+            break;
+          }
+
+          message.version = reader.uint32();
+          continue;
+        }
         case 2: {
           if (tag !== 18) {
             break;
@@ -1717,6 +2980,7 @@ export const Config: MessageFns<Config> = {
 
   fromJSON(object: any): Config {
     return {
+      version: isSet(object.version) ? globalThis.Number(object.version) : undefined,
       email: isSet(object.email) ? EmailConfig.fromJSON(object.email) : undefined,
       server: isSet(object.server) ? ServerConfig.fromJSON(object.server) : undefined,
       auth: isSet(object.auth) ? AuthConfig.fromJSON(object.auth) : undefined,
@@ -1731,6 +2995,9 @@ export const Config: MessageFns<Config> = {
 
   toJSON(message: Config): unknown {
     const obj: any = {};
+    if (message.version !== undefined && message.version !== 0) {
+      obj.version = Math.round(message.version);
+    }
     if (message.email !== undefined) {
       obj.email = EmailConfig.toJSON(message.email);
     }
@@ -1754,6 +3021,7 @@ export const Config: MessageFns<Config> = {
   },
   fromPartial<I extends Exact<DeepPartial<Config>, I>>(object: I): Config {
     const message = createBaseConfig();
+    message.version = object.version ?? 0;
     message.email = (object.email !== undefined && object.email !== null)
       ? EmailConfig.fromPartial(object.email)
       : undefined;
@@ -8,7 +8,13 @@ import {
 } from "solid-js";
 import type { Setter } from "solid-js";
 import { createForm } from "@tanstack/solid-form";
-import { TbRefresh, TbCrown, TbEdit, TbTrash } from "solid-icons/tb";
+import {
+  TbRefresh,
+  TbCrown,
+  TbEdit,
+  TbLockOpen,
+  TbTrash,
+} from "solid-icons/tb";
 import type { DialogTriggerProps } from "@kobalte/core/dialog";
 
 import {
@@ -34,6 +40,7 @@ import { IconButton } from "@/components/IconButton";
 import { Label } from "@/components/ui/label";
 import { AddUser } from "@/components/accounts/AddUser";
 import {
+  clearUserLockout,
   deleteUser,
   updateUser,
   fetchUsers,
@@ -42,12 +49,20 @@ import {
 import type { UpdateUserRequest, UserJson } from "@/lib/bindings";
 import {
   buildTextFormField,
+  buildTextAreaFormField,
   buildSecretFormField,
 } from "@/components/FormFields";
 import { SafeSheet, SheetContainer } from "@/components/SafeSheet";
 
 const columnHelper = createColumnHelper<UserJson>();
 
+function isLocked(user: UserJson): boolean {
+  const lockedUntil = user.locked_until;
+  return (
+    lockedUntil !== null && Number(lockedUntil) * 1000 > Date.now()
+  );
+}
+
 function buildColumns(
   setEditUser: Setter<UserJson | undefined>,
   userRefetch: () => void,
@@ -87,6 +102,19 @@ function buildColumns(
               <TbEdit size={20} />
             </IconButton>
 
+            <Show when={isLocked(ctx.row.original)}>
+              <IconButton
+                tooltip="Unlock user"
+                onClick={() => {
+                  clearUserLockout({ id: userId })
+                    .then(userRefetch)
+                    .catch(console.error);
+                }}
+              >
+                <TbLockOpen size={20} />
+              </IconButton>
+            </Show>
+
             <IconButton
               class="bg-destructive text-white"
               tooltip="Delete user"
@@ -109,15 +137,27 @@ function EditSheetContent(props: {
   markDirty: () => void;
   refetch: () => void;
 }) {
+  // Metadata is edited as JSON text and parsed on submit.
+  type FormValues = Omit<UpdateUserRequest, "metadata"> & {
+    metadata: string | undefined;
+  };
+
   const form = createForm(() => ({
     defaultValues: {
       id: props.user.id,
       email: props.user.email,
       password: null,
       verified: props.user.verified,
-    } as UpdateUserRequest,
+      metadata: props.user.metadata
+        ? JSON.stringify(props.user.metadata, null, 2)
+        : undefined,
+    } as FormValues,
     onSubmit: async ({ value }) => {
-      updateUser(value)
+      const metadata = value.metadata?.trim();
+      updateUser({
+        ...value,
+        metadata: metadata ? JSON.parse(metadata) : null,
+      })
         // eslint-disable-next-line solid/reactivity
         .then(() => props.close())
         .catch(console.error);
@@ -166,6 +206,32 @@ function EditSheetContent(props: {
               </div>
             )}
           </form.Field>
+
+          <form.Field
+            name="metadata"
+            validators={{
+              onChange: ({ value }: { value: string | undefined }) => {
+                if (!value?.trim()) {
+                  return undefined;
+                }
+                try {
+                  const parsed = JSON.parse(value);
+                  if (
+                    typeof parsed !== "object" ||
+                    parsed === null ||
+                    Array.isArray(parsed)
+                  ) {
+                    return "Must be a JSON object";
+                  }
+                } catch {
+                  return "Invalid JSON";
+                }
+                return undefined;
+              },
+            }}
+          >
+            {buildTextAreaFormField({ label: textLabel("Metadata") }, 4)}
+          </form.Field>
         </div>
 
         <SheetFooter>
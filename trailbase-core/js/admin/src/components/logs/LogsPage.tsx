@@ -113,7 +113,13 @@ const columns: ColumnDef<LogJson>[] = [
       );
     },
   },
-  { accessorKey: "data" },
+  {
+    accessorKey: "data",
+    cell: (ctx) => {
+      const data = ctx.row.original.data;
+      return data ? JSON.stringify(data) : null;
+    },
+  },
 ];
 
 type GetLogsProps = {
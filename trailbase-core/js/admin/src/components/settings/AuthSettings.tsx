@@ -33,24 +33,37 @@ import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader } from "@/components/ui/card";
 
 import type { OAuthProviderResponse, OAuthProviderEntry } from "@/lib/bindings";
-import { AuthConfig, Config, OAuthProviderConfig } from "@proto/config";
+import {
+  AuthConfig,
+  Config,
+  OAuthProviderConfig,
+  OAuthProviderId,
+} from "@proto/config";
 import { createConfigQuery, setConfig } from "@/lib/config";
 import { adminFetch } from "@/lib/fetch";
 import { showSaveFileDialog } from "@/lib/utils";
 
 // OAuth2 provider assets.
+import apple from "@shared/assets/oauth2/apple.svg";
 import discord from "@shared/assets/oauth2/discord.svg";
 import facebook from "@shared/assets/oauth2/facebook.svg";
+import github from "@shared/assets/oauth2/github.svg";
 import gitlab from "@shared/assets/oauth2/gitlab.svg";
 import google from "@shared/assets/oauth2/google.svg";
 import microsoft from "@shared/assets/oauth2/microsoft.svg";
+import twitch from "@shared/assets/oauth2/twitch.svg";
+import yahoo from "@shared/assets/oauth2/yahoo.svg";
 
 const assets: Record<string, string> = {
+  apple: apple,
   discord: discord,
   facebook: facebook,
+  github: github,
   gitlab: gitlab,
   google: google,
   microsoft: microsoft,
+  twitch: twitch,
+  yahoo: yahoo,
 } as const;
 
 // Using a proxy struct since tanstack only deals with arrays and not maps.
@@ -59,6 +72,9 @@ const assets: Record<string, string> = {
 type State = {
   clientId?: string;
   clientSecret?: string;
+  // Only used by Apple.
+  teamId?: string;
+  keyId?: string;
 };
 type NamedOAuthProvider = {
   provider: OAuthProviderEntry;
@@ -66,8 +82,15 @@ type NamedOAuthProvider = {
 };
 type AuthConfigProxy = Omit<AuthConfig, "oauthProviders"> & {
   namedOauthProviders: NamedOAuthProvider[];
+  // Generic OpenID Connect providers can be configured multiple times under
+  // arbitrary names and are thus passed through as is.
+  oidcProviders: { [key: string]: OAuthProviderConfig };
 };
 
+function isOidc(providerId: number | undefined): boolean {
+  return providerId === OAuthProviderId.OIDC;
+}
+
 function nonEmpty(v: string | undefined): string | undefined {
   return v && v !== "" ? v : undefined;
 }
@@ -117,6 +140,11 @@ function configToProxy(
 
   return {
     ...config,
+    oidcProviders: Object.fromEntries(
+      Object.entries(config.oauthProviders).filter(([_, c]) =>
+        isOidc(c.providerId),
+      ),
+    ),
     namedOauthProviders: providers.map((p): NamedOAuthProvider => {
       const config = idToConfig.get(p.id);
       const clientId = config?.clientId;
@@ -128,6 +156,8 @@ function configToProxy(
               clientId: clientId,
               // NOTE: This is basically undefined since the config doesn't contain the striped secret.
               clientSecret: config?.clientSecret,
+              teamId: config?.teamId,
+              keyId: config?.keyId,
             }
           : undefined,
       };
@@ -137,9 +167,12 @@ function configToProxy(
 
 function proxyToConfig(proxy: AuthConfigProxy): AuthConfig {
   const config = AuthConfig.fromPartial({
-    ...(proxy as Omit<AuthConfigProxy, "namedOauthProviders">),
+    ...(proxy as Omit<
+      AuthConfigProxy,
+      "namedOauthProviders" | "oidcProviders"
+    >),
   });
-  config.oauthProviders = {};
+  config.oauthProviders = { ...proxy.oidcProviders };
 
   for (const entry of proxy.namedOauthProviders) {
     const p = entry.provider;
@@ -152,6 +185,8 @@ function proxyToConfig(proxy: AuthConfigProxy): AuthConfig {
         displayName: p.display_name,
         clientId,
         clientSecret,
+        teamId: nonEmpty(entry.state?.teamId),
+        keyId: nonEmpty(entry.state?.keyId),
       };
     } else {
       console.debug("Skipping: ", entry);
@@ -182,12 +217,18 @@ function ProviderSettingsSubForm(props: {
   );
 
   const dirty = () => {
-    const id = nonEmpty(current()?.clientId) !== nonEmpty(original()?.clientId);
-    const secret =
-      nonEmpty(current()?.clientSecret) !== nonEmpty(original()?.clientSecret);
-    return id || secret;
+    const changed = (key: keyof State) =>
+      nonEmpty(current()?.[key]) !== nonEmpty(original()?.[key]);
+    return (
+      changed("clientId") ||
+      changed("clientSecret") ||
+      changed("teamId") ||
+      changed("keyId")
+    );
   };
 
+  const isApple = () => props.provider.id === OAuthProviderId.APPLE;
+
   const icon = () => {
     if (dirty()) {
       return <TbCirclePlus color="orange" />;
@@ -237,8 +278,27 @@ function ProviderSettingsSubForm(props: {
               },
             }}
           >
-            {buildSecretFormField({ label: () => "Client Secret" })}
+            {buildSecretFormField({
+              label: () =>
+                isApple() ? "Private Key (PEM)" : "Client Secret",
+            })}
           </props.form.Field>
+
+          {isApple() && (
+            <>
+              <props.form.Field
+                name={`namedOauthProviders[${props.index}].state.teamId`}
+              >
+                {buildOptionalTextFormField({ label: () => "Team Id" })}
+              </props.form.Field>
+
+              <props.form.Field
+                name={`namedOauthProviders[${props.index}].state.keyId`}
+              >
+                {buildOptionalTextFormField({ label: () => "Key Id" })}
+              </props.form.Field>
+            </>
+          )}
         </div>
 
         <div class="mr-4 flex items-center justify-end gap-2">
@@ -278,11 +338,13 @@ function AuthSettingsForm(props: {
   markDirty: () => void;
   postSubmit: () => void;
 }) {
+  // OIDC providers are configured in the config file, since they require more
+  // than credentials and may be configured multiple times.
+  const providers = () =>
+    props.providers.providers.filter((p) => !isOidc(p.id));
+
   const values = createMemo(() =>
-    configToProxy(
-      props.providers.providers,
-      props.config.auth ?? AuthConfig.create(),
-    ),
+    configToProxy(providers(), props.config.auth ?? AuthConfig.create()),
   );
 
   const form = createForm(() => ({
@@ -345,6 +407,72 @@ function AuthSettingsForm(props: {
                   ),
                 })}
               </form.Field>
+
+              <form.Field name="magicLinkTtlSec">
+                {buildOptionalNumberFormField({
+                  integer: true,
+                  label: () => (
+                    <div class={labelWidth}>Magic Link TTL [sec]</div>
+                  ),
+                  info: (
+                    <p>
+                      Validity of passwordless login links and one-time codes
+                      sent via email.
+                    </p>
+                  ),
+                })}
+              </form.Field>
+            </div>
+          </CardContent>
+        </Card>
+
+        <Card>
+          <CardHeader>
+            <h2>Brute-Force Protection</h2>
+          </CardHeader>
+
+          <CardContent>
+            <div class="flex flex-col gap-4">
+              <form.Field name="maxFailedLoginAttempts">
+                {buildOptionalNumberFormField({
+                  integer: true,
+                  label: () => (
+                    <div class={labelWidth}>Max Failed Logins</div>
+                  ),
+                  info: (
+                    <p>
+                      Consecutive failed logins after which an account is
+                      temporarily locked. Zero disables account lockout.
+                    </p>
+                  ),
+                })}
+              </form.Field>
+
+              <form.Field name="maxFailedLoginAttemptsPerIp">
+                {buildOptionalNumberFormField({
+                  integer: true,
+                  label: () => (
+                    <div class={labelWidth}>Max Failed Logins per IP</div>
+                  ),
+                  info: (
+                    <p>
+                      Failed logins from a single IP address after which
+                      further attempts are temporarily rejected. Zero disables
+                      IP lockout.
+                    </p>
+                  ),
+                })}
+              </form.Field>
+
+              <form.Field name="lockoutDurationSec">
+                {buildOptionalNumberFormField({
+                  integer: true,
+                  label: () => (
+                    <div class={labelWidth}>Lockout Duration [sec]</div>
+                  ),
+                  info: <p>Duration of temporary lockouts.</p>,
+                })}
+              </form.Field>
             </div>
           </CardContent>
         </Card>
@@ -407,7 +535,7 @@ function AuthSettingsForm(props: {
               {(_field) => {
                 return (
                   <Accordion multiple={false} collapsible class="w-full">
-                    <For each={props.providers.providers ?? []}>
+                    <For each={providers()}>
                       {(provider, index) => {
                         return (
                           <ProviderSettingsSubForm
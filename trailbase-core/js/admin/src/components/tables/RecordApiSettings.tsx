@@ -40,6 +40,7 @@ import { tableType, getForeignKey } from "@/lib/schema";
 const tablePermissions = {
   Create: PermissionFlag.CREATE,
   Read: PermissionFlag.READ,
+  List: PermissionFlag.LIST,
   Update: PermissionFlag.UPDATE,
   Delete: PermissionFlag.DELETE,
   Schema: PermissionFlag.SCHEMA,
@@ -47,6 +48,7 @@ const tablePermissions = {
 
 const viewPermissions = {
   Read: PermissionFlag.READ,
+  List: PermissionFlag.LIST,
   Schema: PermissionFlag.SCHEMA,
 } as const;
 
@@ -70,7 +72,7 @@ function AclForm(props: {
     <div class="flex">
       <div
         class="grid w-[300px] items-end gap-2"
-        style={{ "grid-template-columns": "auto 1fr 1fr 1fr 1fr 1fr" }}
+        style={{ "grid-template-columns": "auto 1fr 1fr 1fr 1fr 1fr 1fr" }}
       >
         {props.showHeader && (
           <For
@@ -132,6 +134,12 @@ const tableAccessRules: AccessRule[] = [
     description:
       'Row- and request-level read access (_user_, _row_, _req_): If the table has an "owner"\'s column containing binary user ids, access could be rstricted to the owner by setting \'_row_.owner = _user_\' here. Or if the table as a foreign key to a "group" and a relationship defined in a "membership" table: \'(SELECT 1 FROM membership WHERE group = _row_.group AND user = _user_)\'',
   },
+  {
+    field: "listAccessRule",
+    label: "List Access:",
+    description:
+      "Row- and request-level list access (_USER_, _ROW_): Applied as an additional filter on top of the read access rule when listing records.",
+  },
   {
     field: "createAccessRule",
     label: "Create Access:",
@@ -164,6 +172,12 @@ const viewAccessRules: AccessRule[] = [
     description:
       'Row- and request-level read access (_user_, _row_, _req_): If the table has an "owner"\'s column containing binary user ids, access could be rstricted to the owner by setting \'_row_.owner = _user_\' here. Or if the table as a foreign key to a "group" and a relationship defined in a "membership" table: \'(SELECT 1 FROM membership WHERE group = _row_.group AND user = _user_)\'',
   },
+  {
+    field: "listAccessRule",
+    label: "List Access:",
+    description:
+      "Row- and request-level list access (_USER_, _ROW_): Applied as an additional filter on top of the read access rule when listing records.",
+  },
   {
     field: "schemaAccessRule",
     label: "Schema Access",
@@ -1,5 +1,6 @@
 export type * from "@bindings/AlterIndexRequest";
 export type * from "@bindings/AlterTableRequest";
+export type * from "@bindings/ClearUserLockoutRequest";
 export type * from "@bindings/Column";
 export type * from "@bindings/ColumnDataType";
 export type * from "@bindings/ColumnOption";
@@ -16,9 +17,13 @@ export type * from "@bindings/DropIndexRequest";
 export type * from "@bindings/DropTableRequest";
 export type * from "@bindings/ForeignKey";
 export type * from "@bindings/GeneratedExpressionMode";
+export type * from "@bindings/GroupJson";
+export type * from "@bindings/GroupMemberRequest";
+export type * from "@bindings/GroupRequest";
 export type * from "@bindings/JsonSchema";
 export type * from "@bindings/ListJsonSchemasResponse";
 export type * from "@bindings/ListLogsResponse";
+export type * from "@bindings/ListRolesResponse";
 export type * from "@bindings/ListRowsResponse";
 export type * from "@bindings/ListSchemasResponse";
 export type * from "@bindings/ListUsersResponse";
@@ -34,6 +39,9 @@ export type * from "@bindings/QueryRequest";
 export type * from "@bindings/QueryResponse";
 export type * from "@bindings/ReadFilesRequest";
 export type * from "@bindings/ReferentialAction";
+export type * from "@bindings/RoleGrantRequest";
+export type * from "@bindings/RoleJson";
+export type * from "@bindings/RoleRequest";
 export type * from "@bindings/Stats";
 export type * from "@bindings/Table";
 export type * from "@bindings/TableIndex";
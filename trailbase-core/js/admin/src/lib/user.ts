@@ -1,4 +1,5 @@
 import type {
+  ClearUserLockoutRequest,
   UpdateUserRequest,
   CreateUserRequest,
   ListUsersResponse,
@@ -29,6 +30,13 @@ export async function updateUser(request: UpdateUserRequest) {
   });
 }
 
+export async function clearUserLockout(request: ClearUserLockoutRequest) {
+  await adminFetch("/user/lockout", {
+    method: "DELETE",
+    body: JSON.stringify(request),
+  });
+}
+
 export type FetchUsersArgs = {
   filter: string | undefined;
   pageSize: number;
@@ -6,18 +6,26 @@ import type { ConfiguredOAuthProvidersResponse } from "@bindings/ConfiguredOAuth
 import { AUTH_API } from "@/lib/constants";
 
 // OAuth2 provider assets.
+import apple from "@shared/assets/oauth2/apple.svg";
 import discord from "@shared/assets/oauth2/discord.svg";
 import facebook from "@shared/assets/oauth2/facebook.svg";
+import github from "@shared/assets/oauth2/github.svg";
 import gitlab from "@shared/assets/oauth2/gitlab.svg";
 import google from "@shared/assets/oauth2/google.svg";
 import microsoft from "@shared/assets/oauth2/microsoft.svg";
+import twitch from "@shared/assets/oauth2/twitch.svg";
+import yahoo from "@shared/assets/oauth2/yahoo.svg";
 
 const assets: Record<string, string> = {
+  apple: apple.src,
   discord: discord.src,
   facebook: facebook.src,
+  github: github.src,
   gitlab: gitlab.src,
   google: google.src,
   microsoft: microsoft.src,
+  twitch: twitch.src,
+  yahoo: yahoo.src,
 
   fake: facebook.src,
 } as const;
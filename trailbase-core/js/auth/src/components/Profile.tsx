@@ -3,6 +3,7 @@ import { TbUser, TbLogout, TbTrash } from "solid-icons/tb";
 import { Client, type User } from "trailbase";
 
 import { HOST, RECORD_API } from "@/lib/constants";
+import { passkeysSupported, registerPasskey } from "@/lib/passkey";
 import { Button, buttonVariants } from "@/components/ui/button";
 import { Card } from "@/components/ui/card";
 import { ErrorBoundary } from "@/components/ErrorBoundary";
@@ -55,6 +56,35 @@ function DeleteAccountButton(props: { client: Client }) {
   );
 }
 
+function AddPasskeyButton(props: { client: Client }) {
+  const [status, setStatus] = createSignal<string | undefined>();
+
+  return (
+    <div class="flex flex-col gap-1">
+      <Button
+        variant="outline"
+        onClick={() =>
+          (async () => {
+            const name = window.prompt("Passkey name", "Passkey");
+            if (name === null) {
+              return;
+            }
+            await registerPasskey(props.client, name);
+            setStatus("Passkey added");
+          })().catch((err) => {
+            console.error(err);
+            setStatus(`Failed to add passkey: ${err}`);
+          })
+        }
+      >
+        Add Passkey
+      </Button>
+
+      {status() && <span class="text-sm">{status()}</span>}
+    </div>
+  );
+}
+
 function Avatar(props: { avatarUrl?: () => string | undefined }) {
   const url = () => props.avatarUrl?.();
 
@@ -168,6 +198,8 @@ function ProfileTable(props: {
         >
           Change Password
         </a>
+
+        {passkeysSupported() && <AddPasskeyButton client={props.client} />}
       </div>
 
       {import.meta.env.DEV && (
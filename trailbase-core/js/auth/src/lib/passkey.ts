@@ -0,0 +1,139 @@
+// Helpers for the WebAuthn browser API, which deals in ArrayBuffers, while the
+// passkey endpoints exchange base64url-encoded strings.
+
+import type { Client } from "trailbase";
+
+import { AUTH_API } from "@/lib/constants";
+
+export function base64UrlEncode(buffer: ArrayBuffer): string {
+  const bytes = new Uint8Array(buffer);
+  let binary = "";
+  for (const b of bytes) {
+    binary += String.fromCharCode(b);
+  }
+  return btoa(binary)
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+}
+
+export function base64UrlDecode(value: string): Uint8Array {
+  const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+  const binary = atob(base64.padEnd(Math.ceil(base64.length / 4) * 4, "="));
+  return Uint8Array.from(binary, (c) => c.charCodeAt(0));
+}
+
+export function passkeysSupported(): boolean {
+  return window.PublicKeyCredential !== undefined;
+}
+
+type PasskeyLoginOptions = {
+  challenge: string;
+  rp_id: string;
+  timeout_ms: number;
+};
+
+export type PasskeyAssertion = {
+  credential_id: string;
+  client_data_json: string;
+  authenticator_data: string;
+  signature: string;
+  user_handle: string;
+};
+
+// Runs the browser side of a passkey login, i.e. lets the user pick a passkey
+// and sign the server's challenge.
+export async function getPasskeyAssertion(): Promise<PasskeyAssertion> {
+  const response = await fetch(`${AUTH_API}/passkey/login/start`, {
+    method: "POST",
+  });
+  if (!response.ok) {
+    throw Error(await response.text());
+  }
+  const options = (await response.json()) as PasskeyLoginOptions;
+
+  const credential = (await navigator.credentials.get({
+    publicKey: {
+      challenge: base64UrlDecode(options.challenge),
+      rpId: options.rp_id,
+      timeout: options.timeout_ms,
+      userVerification: "preferred",
+    },
+  })) as PublicKeyCredential | null;
+  if (!credential) {
+    throw Error("No passkey selected");
+  }
+
+  const assertion = credential.response as AuthenticatorAssertionResponse;
+  return {
+    credential_id: base64UrlEncode(credential.rawId),
+    client_data_json: base64UrlEncode(assertion.clientDataJSON),
+    authenticator_data: base64UrlEncode(assertion.authenticatorData),
+    signature: base64UrlEncode(assertion.signature),
+    user_handle: assertion.userHandle
+      ? base64UrlEncode(assertion.userHandle)
+      : "",
+  };
+}
+
+type PasskeyRegistrationOptions = {
+  challenge: string;
+  rp_id: string;
+  rp_name: string;
+  user_id: string;
+  user_name: string;
+  exclude_credentials: string[];
+  algorithms: number[];
+  timeout_ms: number;
+};
+
+// Registers a new passkey for the currently logged in user.
+export async function registerPasskey(
+  client: Client,
+  name: string,
+): Promise<void> {
+  const response = await client.fetch("api/auth/v1/passkey/register/start", {
+    method: "POST",
+  });
+  const options = (await response.json()) as PasskeyRegistrationOptions;
+
+  const credential = (await navigator.credentials.create({
+    publicKey: {
+      challenge: base64UrlDecode(options.challenge),
+      rp: { id: options.rp_id, name: options.rp_name },
+      user: {
+        id: base64UrlDecode(options.user_id),
+        name: options.user_name,
+        displayName: options.user_name,
+      },
+      pubKeyCredParams: options.algorithms.map((alg) => ({
+        type: "public-key",
+        alg,
+      })),
+      excludeCredentials: options.exclude_credentials.map((id) => ({
+        type: "public-key",
+        id: base64UrlDecode(id),
+      })),
+      authenticatorSelection: {
+        residentKey: "required",
+        userVerification: "preferred",
+      },
+      attestation: "none",
+      timeout: options.timeout_ms,
+    },
+  })) as PublicKeyCredential | null;
+  if (!credential) {
+    throw Error("Passkey creation aborted");
+  }
+
+  const attestation = credential.response as AuthenticatorAttestationResponse;
+  await client.fetch("api/auth/v1/passkey/register/finish", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({
+      client_data_json: base64UrlEncode(attestation.clientDataJSON),
+      attestation_object: base64UrlEncode(attestation.attestationObject),
+      name,
+    }),
+  });
+}
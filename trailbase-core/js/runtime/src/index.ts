@@ -835,6 +835,10 @@ export type UserType = {
   email: string;
   /// The user's CSRF token.
   csrf: string;
+  /// The user's effective roles.
+  roles: string[];
+  /// Custom claims derived from the user's metadata.
+  claims: { [key: string]: unknown };
 };
 export type RequestType = {
   uri: string;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS _changes (
+  -- Sequence number for consumers to incrementally page through changes.
+  -- AUTOINCREMENT guarantees numbers aren't reused after pruning old changes.
+  seq                          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  -- Timestamp in seconds with fractional millisecond resolution.
+  created                      REAL DEFAULT (UNIXEPOCH('subsec')) NOT NULL,
+
+  table_name                   TEXT NOT NULL,
+  row_id                       INTEGER NOT NULL,
+  -- One of 'insert', 'update' or 'delete'.
+  action                       TEXT NOT NULL,
+
+  -- JSON-encoded records before and after the change. Missing for inserts and
+  -- deletes, respectively.
+  old_values                   TEXT,
+  new_values                   TEXT
+) strict;
+
+CREATE INDEX IF NOT EXISTS __changes__created_index ON _changes (created);
+CREATE INDEX IF NOT EXISTS __changes__table_name_index ON _changes (table_name);
@@ -0,0 +1,26 @@
+--
+-- Outgoing webhook delivery queue and log.
+--
+CREATE TABLE _webhook_deliveries (
+  id                           INTEGER PRIMARY KEY NOT NULL,
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL,
+
+  -- Record API and webhook endpoint the delivery was enqueued for.
+  api_name                     TEXT NOT NULL,
+  url                          TEXT NOT NULL,
+  -- One of 'insert', 'update' or 'delete'.
+  event                        TEXT NOT NULL,
+  -- JSON-encoded request body.
+  payload                      TEXT NOT NULL,
+
+  -- One of 'pending', 'delivered' or 'failed'.
+  status                       TEXT DEFAULT 'pending' NOT NULL,
+  attempts                     INTEGER DEFAULT 0 NOT NULL,
+  next_attempt                 INTEGER DEFAULT (UNIXEPOCH()) NOT NULL,
+  last_attempt                 INTEGER,
+  -- HTTP status and error of the most recent attempt.
+  response_status              INTEGER,
+  error                        TEXT
+) STRICT;
+
+CREATE INDEX __webhook_deliveries__pending_index ON _webhook_deliveries (next_attempt) WHERE status = 'pending';
@@ -0,0 +1,33 @@
+--
+-- TOTP second factor for multi-factor authentication.
+--
+CREATE TABLE _user_mfa (
+  user                         BLOB PRIMARY KEY NOT NULL REFERENCES _user(id) ON DELETE CASCADE,
+  -- Base32-encoded TOTP shared secret.
+  totp_secret                  TEXT NOT NULL,
+  -- Only set once the user confirmed the enrollment with a valid code.
+  enabled                      INTEGER DEFAULT FALSE NOT NULL,
+  -- Time step of the most recently accepted code to prevent replays.
+  last_used_step               INTEGER DEFAULT 0 NOT NULL,
+
+  -- Pending second step of a login, i.e. the password has already been validated.
+  challenge_token              TEXT,
+  challenge_sent_at            INTEGER,
+  challenge_attempts           INTEGER DEFAULT 0 NOT NULL,
+
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL
+) STRICT;
+
+CREATE UNIQUE INDEX __user_mfa__challenge_token_index ON _user_mfa (challenge_token);
+
+--
+-- Single-use MFA recovery codes.
+--
+CREATE TABLE _user_mfa_recovery_code (
+  id                           INTEGER PRIMARY KEY NOT NULL,
+  user                         BLOB NOT NULL REFERENCES _user(id) ON DELETE CASCADE,
+  -- Hex-encoded SHA-256 hash of the recovery code.
+  code_hash                    TEXT NOT NULL
+) STRICT;
+
+CREATE INDEX __user_mfa_recovery_code__user_index ON _user_mfa_recovery_code (user);
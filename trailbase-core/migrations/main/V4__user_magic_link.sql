@@ -0,0 +1,11 @@
+--
+-- Passwordless login via email magic links and one-time codes.
+--
+ALTER TABLE _user ADD COLUMN magic_link_code TEXT;
+-- Short numeric alternative to the link for manual entry, e.g. on mobile.
+ALTER TABLE _user ADD COLUMN magic_link_otp TEXT;
+ALTER TABLE _user ADD COLUMN magic_link_sent_at INTEGER;
+-- Failed one-time code attempts to prevent brute-forcing.
+ALTER TABLE _user ADD COLUMN magic_link_attempts INTEGER DEFAULT 0 NOT NULL;
+
+CREATE UNIQUE INDEX __user__magic_link_code_index ON _user (magic_link_code);
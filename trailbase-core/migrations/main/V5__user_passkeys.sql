@@ -0,0 +1,38 @@
+--
+-- WebAuthn credentials, i.e. passkeys, for passwordless login.
+--
+CREATE TABLE _user_passkey (
+  id                           INTEGER PRIMARY KEY NOT NULL,
+  user                         BLOB NOT NULL REFERENCES _user(id) ON DELETE CASCADE,
+  -- Base64url-encoded credential id as assigned by the authenticator.
+  credential_id                TEXT NOT NULL,
+  -- COSE algorithm identifier, i.e. -7 (ES256) or -8 (EdDSA).
+  algorithm                    INTEGER NOT NULL,
+  -- SEC1-encoded point (ES256) or raw public key (EdDSA).
+  public_key                   BLOB NOT NULL,
+  -- Signature counter to detect cloned authenticators.
+  sign_count                   INTEGER DEFAULT 0 NOT NULL,
+  -- User-provided name to tell passkeys apart.
+  name                         TEXT NOT NULL,
+
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL,
+  last_used                    INTEGER
+) STRICT;
+
+CREATE UNIQUE INDEX __user_passkey__credential_id_index ON _user_passkey (credential_id);
+CREATE INDEX __user_passkey__user_index ON _user_passkey (user);
+
+--
+-- Pending WebAuthn ceremonies.
+--
+CREATE TABLE _webauthn_challenge (
+  -- Base64url-encoded random challenge.
+  challenge                    TEXT PRIMARY KEY NOT NULL,
+  -- Set for registrations, NULL for discoverable logins.
+  user                         BLOB REFERENCES _user(id) ON DELETE CASCADE,
+  kind                         TEXT NOT NULL CHECK(kind IN ('registration', 'authentication')),
+
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL
+) STRICT;
+
+CREATE INDEX __webauthn_challenge__created_index ON _webauthn_challenge (created);
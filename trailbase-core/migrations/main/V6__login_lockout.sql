@@ -0,0 +1,18 @@
+--
+-- Failed login tracking for brute-force protection.
+--
+-- Consecutive failed logins, reset on success or once older than the lockout duration.
+ALTER TABLE _user ADD COLUMN failed_login_attempts INTEGER DEFAULT 0 NOT NULL;
+ALTER TABLE _user ADD COLUMN last_failed_login INTEGER;
+-- Logins are rejected until then.
+ALTER TABLE _user ADD COLUMN locked_until INTEGER;
+
+CREATE TABLE _login_failure (
+  -- Client IP address.
+  ip                           TEXT PRIMARY KEY NOT NULL,
+  failed_attempts              INTEGER DEFAULT 0 NOT NULL,
+  last_failed                  INTEGER NOT NULL,
+  locked_until                 INTEGER
+) STRICT;
+
+CREATE INDEX __login_failure__last_failed_index ON _login_failure (last_failed);
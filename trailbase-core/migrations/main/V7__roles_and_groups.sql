@@ -0,0 +1,40 @@
+--
+-- Roles and groups. A user's effective roles are the roles granted to them
+-- directly plus the ones granted to any of their groups.
+--
+CREATE TABLE _role (
+  name                         TEXT PRIMARY KEY NOT NULL CHECK(name REGEXP '^[\w-]+$'),
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL
+) STRICT;
+
+CREATE TABLE _group (
+  name                         TEXT PRIMARY KEY NOT NULL CHECK(name REGEXP '^[\w-]+$'),
+  created                      INTEGER DEFAULT (UNIXEPOCH()) NOT NULL
+) STRICT;
+
+CREATE TABLE _user_role (
+  user                         BLOB NOT NULL REFERENCES _user(id) ON DELETE CASCADE,
+  role                         TEXT NOT NULL REFERENCES _role(name) ON DELETE CASCADE,
+
+  PRIMARY KEY (user, role)
+) STRICT;
+
+CREATE INDEX __user_role__role_index ON _user_role (role);
+
+CREATE TABLE _group_member (
+  group_name                   TEXT NOT NULL REFERENCES _group(name) ON DELETE CASCADE,
+  user                         BLOB NOT NULL REFERENCES _user(id) ON DELETE CASCADE,
+
+  PRIMARY KEY (group_name, user)
+) STRICT;
+
+CREATE INDEX __group_member__user_index ON _group_member (user);
+
+CREATE TABLE _group_role (
+  group_name                   TEXT NOT NULL REFERENCES _group(name) ON DELETE CASCADE,
+  role                         TEXT NOT NULL REFERENCES _role(name) ON DELETE CASCADE,
+
+  PRIMARY KEY (group_name, role)
+) STRICT;
+
+CREATE INDEX __group_role__role_index ON _group_role (role);
@@ -0,0 +1,5 @@
+--
+-- Admin-editable, per-user JSON metadata. Selected top-level keys can be
+-- embedded into auth tokens as custom claims, see `auth.custom_claims`.
+--
+ALTER TABLE _user ADD COLUMN metadata TEXT CHECK(is_json(metadata));
@@ -0,0 +1,7 @@
+--
+-- Refer to webhooks by their stable config id rather than their URL.
+--
+ALTER TABLE _webhook_deliveries ADD COLUMN webhook_id TEXT;
+
+-- Used for pruning completed deliveries.
+CREATE INDEX __webhook_deliveries__created_index ON _webhook_deliveries (created) WHERE status != 'pending';
@@ -0,0 +1,269 @@
+use axum::{
+  extract::{Query, State},
+  Json,
+};
+use serde::{Deserialize, Serialize};
+use trailbase_sqlite::params;
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::CHANGES_TABLE;
+use crate::listing::limit_or_default;
+
+#[derive(Debug, Serialize, TS)]
+pub struct ChangeJson {
+  pub seq: i64,
+  pub created: f64,
+
+  pub table_name: String,
+  pub row_id: i64,
+  /// One of "insert", "update" or "delete".
+  pub action: String,
+
+  #[ts(type = "Object | undefined")]
+  pub old_values: Option<serde_json::Value>,
+  #[ts(type = "Object | undefined")]
+  pub new_values: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangeQuery {
+  seq: i64,
+  created: f64,
+  table_name: String,
+  row_id: i64,
+  action: String,
+  old_values: Option<String>,
+  new_values: Option<String>,
+}
+
+impl TryFrom<ChangeQuery> for ChangeJson {
+  type Error = serde_json::Error;
+
+  fn try_from(value: ChangeQuery) -> Result<Self, Self::Error> {
+    return Ok(ChangeJson {
+      seq: value.seq,
+      created: value.created,
+      table_name: value.table_name,
+      row_id: value.row_id,
+      action: value.action,
+      old_values: value
+        .old_values
+        .map(|v| serde_json::from_str(&v))
+        .transpose()?,
+      new_values: value
+        .new_values
+        .map(|v| serde_json::from_str(&v))
+        .transpose()?,
+    });
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListChangesQuery {
+  /// Only return changes with a sequence number larger than `after`, i.e. the previous cursor.
+  after: Option<i64>,
+  limit: Option<usize>,
+  /// Only return changes of the given table.
+  table: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct ListChangesResponse {
+  /// Sequence number of the last returned change to be passed as `after` to fetch the next page.
+  cursor: Option<i64>,
+  entries: Vec<ChangeJson>,
+}
+
+/// Pages through the captured changes in ascending sequence order.
+pub async fn list_changes_handler(
+  State(state): State<AppState>,
+  Query(query): Query<ListChangesQuery>,
+) -> Result<Json<ListChangesResponse>, Error> {
+  let limit = limit_or_default(query.limit);
+
+  let changes = state
+    .changes_conn()
+    .query_values::<ChangeQuery>(
+      &format!(
+        r#"
+          SELECT seq, created, table_name, row_id, action, old_values, new_values
+          FROM {CHANGES_TABLE}
+          WHERE seq > $1 AND ($2 IS NULL OR table_name = $2)
+          ORDER BY seq ASC
+          LIMIT $3
+        "#
+      ),
+      params!(query.after.unwrap_or(0), query.table, limit as i64),
+    )
+    .await?;
+
+  let entries = changes
+    .into_iter()
+    .map(ChangeJson::try_from)
+    .collect::<Result<Vec<_>, _>>()?;
+
+  return Ok(Json(ListChangesResponse {
+    cursor: entries.last().map(|e| e.seq).or(query.after),
+    entries,
+  }));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::app_state::test_state;
+
+  #[tokio::test]
+  async fn test_change_capture() {
+    let state = test_state(None).await.unwrap();
+    let conn = state.conn();
+
+    conn
+      .execute(
+        "CREATE TABLE captured (id INTEGER PRIMARY KEY, text TEXT NOT NULL) STRICT",
+        (),
+      )
+      .await
+      .unwrap();
+    conn
+      .execute(
+        "CREATE TABLE uncaptured (id INTEGER PRIMARY KEY, text TEXT NOT NULL) STRICT",
+        (),
+      )
+      .await
+      .unwrap();
+    state.refresh_table_cache().await.unwrap();
+
+    let mut config = state.get_config();
+    config
+      .server
+      .change_capture_tables
+      .push("captured".to_string());
+    state
+      .validate_and_update_config(config, None)
+      .await
+      .unwrap();
+
+    conn
+      .execute("INSERT INTO captured (id, text) VALUES (1, 'a')", ())
+      .await
+      .unwrap();
+    conn
+      .execute("INSERT INTO uncaptured (id, text) VALUES (1, 'a')", ())
+      .await
+      .unwrap();
+    conn
+      .execute("UPDATE captured SET text = 'b' WHERE id = 1", ())
+      .await
+      .unwrap();
+
+    // Rolled-back changes must not be recorded.
+    conn
+      .call(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute("INSERT INTO captured (id, text) VALUES (2, 'x')", ())?;
+        tx.rollback()?;
+        return Ok(());
+      })
+      .await
+      .unwrap();
+
+    // Neither must changes rolled back to a savepoint of a committed transaction.
+    conn
+      .call(|conn| {
+        let mut tx = conn.transaction()?;
+        {
+          let mut sp = tx.savepoint()?;
+          sp.execute("INSERT INTO captured (id, text) VALUES (3, 'y')", ())?;
+          sp.rollback()?;
+        }
+        tx.commit()?;
+        return Ok(());
+      })
+      .await
+      .unwrap();
+
+    conn
+      .execute("DELETE FROM captured WHERE id = 1", ())
+      .await
+      .unwrap();
+
+    let Json(response) =
+      list_changes_handler(State(state.clone()), Query(ListChangesQuery::default()))
+        .await
+        .unwrap();
+
+    let entries = &response.entries;
+    assert_eq!(3, entries.len(), "{entries:?}");
+    assert!(entries
+      .iter()
+      .all(|e| e.table_name == "captured" && e.row_id == 1));
+    assert_eq!(
+      vec!["insert", "update", "delete"],
+      entries
+        .iter()
+        .map(|e| e.action.as_str())
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(None, entries[0].old_values);
+    assert_eq!(
+      Some(serde_json::json!({"id": 1, "text": "a"})),
+      entries[0].new_values
+    );
+    assert_eq!(
+      Some(serde_json::json!({"id": 1, "text": "a"})),
+      entries[1].old_values
+    );
+    assert_eq!(
+      Some(serde_json::json!({"id": 1, "text": "b"})),
+      entries[1].new_values
+    );
+    assert_eq!(
+      Some(serde_json::json!({"id": 1, "text": "b"})),
+      entries[2].old_values
+    );
+    assert_eq!(None, entries[2].new_values);
+    assert_eq!(Some(entries[2].seq), response.cursor);
+
+    // Page through the log.
+    let Json(first_page) = list_changes_handler(
+      State(state.clone()),
+      Query(ListChangesQuery {
+        limit: Some(2),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(2, first_page.entries.len());
+
+    let Json(second_page) = list_changes_handler(
+      State(state.clone()),
+      Query(ListChangesQuery {
+        after: first_page.cursor,
+        limit: Some(2),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(1, second_page.entries.len());
+    assert_eq!("delete", second_page.entries[0].action);
+    assert_eq!(response.cursor, second_page.cursor);
+
+    let Json(filtered) = list_changes_handler(
+      State(state.clone()),
+      Query(ListChangesQuery {
+        table: Some("uncaptured".to_string()),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert!(filtered.entries.is_empty());
+  }
+}
@@ -18,7 +18,7 @@ use crate::listing::{
   build_filter_where_clause, limit_or_default, parse_query, Order, QueryParseResult, WhereClause,
 };
 use crate::table_metadata::{lookup_and_parse_table_schema, TableMetadata};
-use crate::util::id_to_b64;
+use crate::util::{b64_to_id, id_to_b64};
 
 #[derive(Debug, Serialize, TS)]
 pub struct LogJson {
@@ -61,7 +61,8 @@ struct LogQuery {
   referer: String,
   user_agent: String,
 
-  data: Option<serde_json::Value>,
+  // JSON encoded.
+  data: Option<Vec<u8>>,
 }
 
 impl From<LogQuery> for LogJson {
@@ -79,7 +80,9 @@ impl From<LogQuery> for LogJson {
       client_cc: value.client_cc,
       referer: value.referer,
       user_agent: value.user_agent,
-      data: value.data,
+      data: value
+        .data
+        .and_then(|data| serde_json::from_slice(&data).ok()),
     };
   }
 }
@@ -104,18 +107,20 @@ pub async fn list_logs_handler(
   // falling back to defaults.
   let QueryParseResult {
     params: filter_params,
+    filter,
     cursor,
     limit,
     order,
     ..
   } = parse_query(raw_url_query.as_deref())
     .map_err(|err| Error::Precondition(format!("Invalid query '{err}': {raw_url_query:?}")))?;
+  let cursor = cursor.and_then(|cursor| b64_to_id(&cursor).ok());
 
   // NOTE: We cannot use state.table_metadata() here, since we're working on the logs database.
   // We could cache, however this is just the admin logs handler.
   let table = lookup_and_parse_table_schema(conn, LOGS_TABLE_NAME).await?;
   let table_metadata = TableMetadata::new(table.clone(), &[table]);
-  let filter_where_clause = build_filter_where_clause(&table_metadata, filter_params)?;
+  let filter_where_clause = build_filter_where_clause(&table_metadata, filter_params, filter)?;
 
   let total_row_count = {
     let row = crate::util::query_one_row(
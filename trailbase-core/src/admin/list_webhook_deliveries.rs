@@ -0,0 +1,155 @@
+use axum::{
+  extract::{Query, State},
+  Json,
+};
+use serde::{Deserialize, Serialize};
+use trailbase_sqlite::params;
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::WEBHOOK_DELIVERIES_TABLE;
+use crate::listing::limit_or_default;
+
+#[derive(Debug, Deserialize, Serialize, TS)]
+pub struct WebhookDeliveryJson {
+  pub id: i64,
+  pub created: i64,
+
+  pub api_name: String,
+  pub url: String,
+  pub event: String,
+  pub payload: String,
+
+  /// One of "pending", "delivered" or "failed".
+  pub status: String,
+  pub attempts: i64,
+  pub next_attempt: i64,
+  pub last_attempt: Option<i64>,
+  pub response_status: Option<i64>,
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListWebhookDeliveriesQuery {
+  /// Only return deliveries older than `before`, i.e. the previous cursor.
+  before: Option<i64>,
+  limit: Option<usize>,
+  status: Option<String>,
+  api_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct ListWebhookDeliveriesResponse {
+  /// Id of the oldest returned delivery to be passed as `before` to fetch the next page.
+  cursor: Option<i64>,
+  entries: Vec<WebhookDeliveryJson>,
+}
+
+/// Lists webhook deliveries, most recent first.
+pub async fn list_webhook_deliveries_handler(
+  State(state): State<AppState>,
+  Query(query): Query<ListWebhookDeliveriesQuery>,
+) -> Result<Json<ListWebhookDeliveriesResponse>, Error> {
+  let limit = limit_or_default(query.limit);
+
+  let entries = state
+    .conn()
+    .query_values::<WebhookDeliveryJson>(
+      &format!(
+        r#"
+          SELECT
+            id, created, api_name, url, event, payload, status, attempts, next_attempt,
+            last_attempt, response_status, error
+          FROM {WEBHOOK_DELIVERIES_TABLE}
+          WHERE
+            ($1 IS NULL OR id < $1)
+            AND ($2 IS NULL OR status = $2)
+            AND ($3 IS NULL OR api_name = $3)
+          ORDER BY id DESC
+          LIMIT $4
+        "#
+      ),
+      params!(query.before, query.status, query.api_name, limit as i64),
+    )
+    .await?;
+
+  return Ok(Json(ListWebhookDeliveriesResponse {
+    cursor: entries.last().map(|e| e.id),
+    entries,
+  }));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::app_state::test_state;
+
+  #[tokio::test]
+  async fn test_list_webhook_deliveries() {
+    let state = test_state(None).await.unwrap();
+
+    for (api_name, status) in [
+      ("api0", "delivered"),
+      ("api0", "failed"),
+      ("api1", "pending"),
+    ] {
+      state
+        .conn()
+        .execute(
+          &format!(
+            "INSERT INTO {WEBHOOK_DELIVERIES_TABLE} (api_name, url, event, payload, status) VALUES ($1, 'http://localhost/hook', 'insert', '{{}}', $2)"
+          ),
+          params!(api_name, status),
+        )
+        .await
+        .unwrap();
+    }
+
+    let Json(first_page) = list_webhook_deliveries_handler(
+      State(state.clone()),
+      Query(ListWebhookDeliveriesQuery {
+        limit: Some(2),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+      vec!["pending", "failed"],
+      first_page
+        .entries
+        .iter()
+        .map(|e| e.status.as_str())
+        .collect::<Vec<_>>()
+    );
+
+    let Json(second_page) = list_webhook_deliveries_handler(
+      State(state.clone()),
+      Query(ListWebhookDeliveriesQuery {
+        before: first_page.cursor,
+        limit: Some(2),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(1, second_page.entries.len());
+    assert_eq!("delivered", second_page.entries[0].status);
+
+    let Json(filtered) = list_webhook_deliveries_handler(
+      State(state.clone()),
+      Query(ListWebhookDeliveriesQuery {
+        api_name: Some("api0".to_string()),
+        status: Some("failed".to_string()),
+        ..Default::default()
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(1, filtered.entries.len());
+    assert_eq!("api0", filtered.entries[0].api_name);
+  }
+}
@@ -2,10 +2,13 @@ mod config;
 mod error;
 mod info;
 mod jwt;
+mod list_changes;
 mod list_logs;
+mod list_webhook_deliveries;
 mod oauth_providers;
 mod parse;
 mod query;
+mod role;
 pub(crate) mod rows;
 mod schema;
 mod table;
@@ -52,11 +55,28 @@ pub fn router() -> Router<AppState> {
     .route("/user", get(user::list_users_handler))
     .route("/user", post(user::create_user_handler))
     .route("/user", patch(user::update_user_handler))
+    .route("/user/mfa", delete(user::reset_user_mfa_handler))
+    .route("/user/lockout", delete(user::clear_user_lockout_handler))
+    // Role & group actions
+    .route("/role", get(role::list_roles_handler))
+    .route("/role", post(role::create_role_handler))
+    .route("/role", delete(role::delete_role_handler))
+    .route("/role/grant", post(role::grant_role_handler))
+    .route("/role/grant", delete(role::revoke_role_handler))
+    .route("/group", post(role::create_group_handler))
+    .route("/group", delete(role::delete_group_handler))
+    .route("/group/member", post(role::add_group_member_handler))
+    .route("/group/member", delete(role::remove_group_member_handler))
     // Schema actions
     .route("/schema", get(schema::list_schemas_handler))
     .route("/schema", post(schema::update_schema_handler))
     // Logs
     .route("/logs", get(list_logs::list_logs_handler))
+    .route("/changes", get(list_changes::list_changes_handler))
+    .route(
+      "/webhook_deliveries",
+      get(list_webhook_deliveries::list_webhook_deliveries_handler),
+    )
     // Query execution handler for the UI editor
     .route("/query", post(query::query_handler))
     // Parse handler for UI validation.
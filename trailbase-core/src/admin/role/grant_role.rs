@@ -0,0 +1,126 @@
+use axum::{
+  extract::State,
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use trailbase_sqlite::params;
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::{GROUP_ROLE_TABLE, USER_ROLE_TABLE};
+
+/// Grants or revokes a role to/from either a single user or all members of a group.
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RoleGrantRequest {
+  pub role: String,
+  pub user: Option<uuid::Uuid>,
+  pub group: Option<String>,
+}
+
+pub async fn grant_role_handler(
+  State(state): State<AppState>,
+  Json(request): Json<RoleGrantRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref USER_QUERY: String = format!(
+      r#"INSERT INTO "{USER_ROLE_TABLE}" (user, role) VALUES ($1, $2) ON CONFLICT DO NOTHING"#
+    );
+    static ref GROUP_QUERY: String = format!(
+      r#"INSERT INTO "{GROUP_ROLE_TABLE}" (group_name, role) VALUES ($1, $2) ON CONFLICT DO NOTHING"#
+    );
+  }
+
+  let conn = state.user_conn();
+  let grantee = match request {
+    RoleGrantRequest {
+      ref role,
+      user: Some(user),
+      group: None,
+    } => {
+      conn
+        .execute(&USER_QUERY, params!(user.into_bytes(), role.clone()))
+        .await?;
+      user.to_string()
+    }
+    RoleGrantRequest {
+      ref role,
+      user: None,
+      group: Some(ref group),
+    } => {
+      conn
+        .execute(&GROUP_QUERY, params!(group.clone(), role.clone()))
+        .await?;
+      format!("group {group}")
+    }
+    _ => {
+      return Err(Error::BadRequest("Expected either user or group".into()));
+    }
+  };
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Granted role {} to {grantee}", request.role),
+    )
+      .into_response(),
+  );
+}
+
+pub async fn revoke_role_handler(
+  State(state): State<AppState>,
+  Json(request): Json<RoleGrantRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref USER_QUERY: String =
+      format!(r#"DELETE FROM "{USER_ROLE_TABLE}" WHERE user = $1 AND role = $2"#);
+    static ref GROUP_QUERY: String =
+      format!(r#"DELETE FROM "{GROUP_ROLE_TABLE}" WHERE group_name = $1 AND role = $2"#);
+  }
+
+  let conn = state.user_conn();
+  let (grantee, rows_affected) = match request {
+    RoleGrantRequest {
+      ref role,
+      user: Some(user),
+      group: None,
+    } => (
+      user.to_string(),
+      conn
+        .execute(&USER_QUERY, params!(user.into_bytes(), role.clone()))
+        .await?,
+    ),
+    RoleGrantRequest {
+      ref role,
+      user: None,
+      group: Some(ref group),
+    } => (
+      format!("group {group}"),
+      conn
+        .execute(&GROUP_QUERY, params!(group.clone(), role.clone()))
+        .await?,
+    ),
+    _ => {
+      return Err(Error::BadRequest("Expected either user or group".into()));
+    }
+  };
+
+  if rows_affected == 0 {
+    return Err(Error::Precondition(format!(
+      "Role {} not granted to {grantee}",
+      request.role
+    )));
+  }
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Revoked role {} from {grantee}", request.role),
+    )
+      .into_response(),
+  );
+}
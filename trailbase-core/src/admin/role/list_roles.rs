@@ -0,0 +1,103 @@
+use axum::{extract::State, Json};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::{
+  GROUP_MEMBER_TABLE, GROUP_ROLE_TABLE, GROUP_TABLE, ROLE_TABLE, USER_ROLE_TABLE,
+};
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct RoleJson {
+  pub name: String,
+  /// Users the role was granted to directly.
+  pub users: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct GroupJson {
+  pub name: String,
+  /// Roles granted to all members of the group.
+  pub roles: Vec<String>,
+  pub members: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export)]
+pub struct ListRolesResponse {
+  pub roles: Vec<RoleJson>,
+  pub groups: Vec<GroupJson>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Assignment {
+  name: String,
+  user: Option<[u8; 16]>,
+  role: Option<String>,
+}
+
+pub async fn list_roles_handler(
+  State(state): State<AppState>,
+) -> Result<Json<ListRolesResponse>, Error> {
+  lazy_static! {
+    static ref ROLES_QUERY: String = format!(
+      r#"
+        SELECT r.name, ur.user, NULL AS role
+        FROM "{ROLE_TABLE}" AS r LEFT JOIN "{USER_ROLE_TABLE}" AS ur ON ur.role = r.name
+        ORDER BY r.name
+      "#
+    );
+    static ref GROUPS_QUERY: String = format!(
+      r#"
+        SELECT g.name, gm.user, NULL AS role
+        FROM "{GROUP_TABLE}" AS g LEFT JOIN "{GROUP_MEMBER_TABLE}" AS gm ON gm.group_name = g.name
+        UNION ALL
+        SELECT group_name AS name, NULL AS user, role FROM "{GROUP_ROLE_TABLE}"
+        ORDER BY name
+      "#
+    );
+  }
+
+  let conn = state.user_conn();
+
+  let mut roles = BTreeMap::<String, RoleJson>::new();
+  for assignment in conn.query_values::<Assignment>(&ROLES_QUERY, ()).await? {
+    let role = roles
+      .entry(assignment.name.clone())
+      .or_insert_with(|| RoleJson {
+        name: assignment.name,
+        users: vec![],
+      });
+    if let Some(user) = assignment.user {
+      role.users.push(Uuid::from_bytes(user));
+    }
+  }
+
+  let mut groups = BTreeMap::<String, GroupJson>::new();
+  for assignment in conn.query_values::<Assignment>(&GROUPS_QUERY, ()).await? {
+    let group = groups
+      .entry(assignment.name.clone())
+      .or_insert_with(|| GroupJson {
+        name: assignment.name,
+        roles: vec![],
+        members: vec![],
+      });
+    if let Some(user) = assignment.user {
+      group.members.push(Uuid::from_bytes(user));
+    }
+    if let Some(role) = assignment.role {
+      group.roles.push(role);
+    }
+  }
+
+  return Ok(Json(ListRolesResponse {
+    roles: roles.into_values().collect(),
+    groups: groups.into_values().collect(),
+  }));
+}
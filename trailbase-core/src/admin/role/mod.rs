@@ -0,0 +1,174 @@
+mod grant_role;
+mod list_roles;
+mod update_group;
+mod update_role;
+
+pub(super) use grant_role::{grant_role_handler, revoke_role_handler};
+pub(super) use list_roles::list_roles_handler;
+pub(super) use update_group::{
+  add_group_member_handler, create_group_handler, delete_group_handler, remove_group_member_handler,
+};
+pub(super) use update_role::{create_role_handler, delete_role_handler};
+
+#[cfg(test)]
+mod tests {
+  use axum::{extract::State, Json};
+
+  use crate::admin::user::create_user_for_test;
+  use crate::app_state::test_state;
+  use crate::auth::api::login::login_with_password;
+  use crate::auth::util::user_roles;
+  use crate::auth::User;
+
+  use super::grant_role::*;
+  use super::list_roles::*;
+  use super::update_group::*;
+  use super::update_role::*;
+
+  fn role(name: &str) -> Json<RoleRequest> {
+    return Json(RoleRequest {
+      name: name.to_string(),
+    });
+  }
+
+  #[tokio::test]
+  async fn test_roles_and_groups() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "editor@bar.org";
+    let password = "Secret!1!!";
+    let user_id = create_user_for_test(&state, email, password).await.unwrap();
+    let other_id = create_user_for_test(&state, "other@bar.org", password)
+      .await
+      .unwrap();
+
+    create_role_handler(State(state.clone()), role("editor"))
+      .await
+      .unwrap();
+    create_role_handler(State(state.clone()), role("viewer"))
+      .await
+      .unwrap();
+    assert!(create_role_handler(State(state.clone()), role("editor"))
+      .await
+      .is_err());
+    // Invalid names are rejected.
+    assert!(create_role_handler(State(state.clone()), role("no spaces"))
+      .await
+      .is_err());
+
+    create_group_handler(
+      State(state.clone()),
+      Json(GroupRequest {
+        name: "staff".to_string(),
+      }),
+    )
+    .await
+    .unwrap();
+
+    // Grant "editor" directly and "viewer" via group membership.
+    grant_role_handler(
+      State(state.clone()),
+      Json(RoleGrantRequest {
+        role: "editor".to_string(),
+        user: Some(user_id),
+        group: None,
+      }),
+    )
+    .await
+    .unwrap();
+    grant_role_handler(
+      State(state.clone()),
+      Json(RoleGrantRequest {
+        role: "viewer".to_string(),
+        user: None,
+        group: Some("staff".to_string()),
+      }),
+    )
+    .await
+    .unwrap();
+    for member in [user_id, other_id] {
+      add_group_member_handler(
+        State(state.clone()),
+        Json(GroupMemberRequest {
+          group: "staff".to_string(),
+          user: member,
+        }),
+      )
+      .await
+      .unwrap();
+    }
+
+    // Granting unknown roles fails.
+    assert!(grant_role_handler(
+      State(state.clone()),
+      Json(RoleGrantRequest {
+        role: "unknown".to_string(),
+        user: Some(user_id),
+        group: None,
+      }),
+    )
+    .await
+    .is_err());
+
+    assert_eq!(
+      vec!["editor".to_string(), "viewer".to_string()],
+      user_roles(&state, &user_id).await.unwrap()
+    );
+    assert_eq!(
+      vec!["viewer".to_string()],
+      user_roles(&state, &other_id).await.unwrap()
+    );
+
+    let Json(response) = list_roles_handler(State(state.clone())).await.unwrap();
+    assert_eq!(2, response.roles.len());
+    assert_eq!(vec![user_id], response.roles[0].users);
+    assert_eq!(1, response.groups.len());
+    assert_eq!(vec!["viewer".to_string()], response.groups[0].roles);
+    assert_eq!(2, response.groups[0].members.len());
+
+    // Roles are embedded into the auth token claims.
+    let tokens = login_with_password(&state, email, password).await.unwrap();
+    let user = User::from_auth_token(&state, &tokens.auth_token).unwrap();
+    assert_eq!(vec!["editor".to_string(), "viewer".to_string()], user.roles);
+
+    // Revocations and deletions.
+    remove_group_member_handler(
+      State(state.clone()),
+      Json(GroupMemberRequest {
+        group: "staff".to_string(),
+        user: other_id,
+      }),
+    )
+    .await
+    .unwrap();
+    assert!(user_roles(&state, &other_id).await.unwrap().is_empty());
+
+    revoke_role_handler(
+      State(state.clone()),
+      Json(RoleGrantRequest {
+        role: "editor".to_string(),
+        user: Some(user_id),
+        group: None,
+      }),
+    )
+    .await
+    .unwrap();
+
+    delete_group_handler(
+      State(state.clone()),
+      Json(GroupRequest {
+        name: "staff".to_string(),
+      }),
+    )
+    .await
+    .unwrap();
+    assert!(user_roles(&state, &user_id).await.unwrap().is_empty());
+
+    delete_role_handler(State(state.clone()), role("viewer"))
+      .await
+      .unwrap();
+    assert!(delete_role_handler(State(state.clone()), role("viewer"))
+      .await
+      .is_err());
+  }
+}
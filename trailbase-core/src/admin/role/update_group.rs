@@ -0,0 +1,132 @@
+use axum::{
+  extract::State,
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use trailbase_sqlite::params;
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::{GROUP_MEMBER_TABLE, GROUP_TABLE};
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GroupRequest {
+  pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct GroupMemberRequest {
+  pub group: String,
+  pub user: uuid::Uuid,
+}
+
+pub async fn create_group_handler(
+  State(state): State<AppState>,
+  Json(request): Json<GroupRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String =
+      format!(r#"INSERT INTO "{GROUP_TABLE}" (name) VALUES ($1) ON CONFLICT DO NOTHING"#);
+  }
+
+  if state
+    .user_conn()
+    .execute(&QUERY, params!(request.name.clone()))
+    .await?
+    == 0
+  {
+    return Err(Error::AlreadyExists("group"));
+  }
+
+  return Ok((StatusCode::OK, format!("Created group: {}", request.name)).into_response());
+}
+
+/// Deletes a group including its memberships and role grants.
+pub async fn delete_group_handler(
+  State(state): State<AppState>,
+  Json(request): Json<GroupRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String = format!(r#"DELETE FROM "{GROUP_TABLE}" WHERE name = $1"#);
+  }
+
+  if state
+    .user_conn()
+    .execute(&QUERY, params!(request.name.clone()))
+    .await?
+    == 0
+  {
+    return Err(Error::Precondition(format!(
+      "Group not found: {}",
+      request.name
+    )));
+  }
+
+  return Ok((StatusCode::OK, format!("Deleted group: {}", request.name)).into_response());
+}
+
+pub async fn add_group_member_handler(
+  State(state): State<AppState>,
+  Json(request): Json<GroupMemberRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"INSERT INTO "{GROUP_MEMBER_TABLE}" (group_name, user) VALUES ($1, $2) ON CONFLICT DO NOTHING"#
+    );
+  }
+
+  state
+    .user_conn()
+    .execute(
+      &QUERY,
+      params!(request.group.clone(), request.user.into_bytes()),
+    )
+    .await?;
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Added {} to group: {}", request.user, request.group),
+    )
+      .into_response(),
+  );
+}
+
+pub async fn remove_group_member_handler(
+  State(state): State<AppState>,
+  Json(request): Json<GroupMemberRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String =
+      format!(r#"DELETE FROM "{GROUP_MEMBER_TABLE}" WHERE group_name = $1 AND user = $2"#);
+  }
+
+  if state
+    .user_conn()
+    .execute(
+      &QUERY,
+      params!(request.group.clone(), request.user.into_bytes()),
+    )
+    .await?
+    == 0
+  {
+    return Err(Error::Precondition(format!(
+      "{} is not a member of group: {}",
+      request.user, request.group
+    )));
+  }
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Removed {} from group: {}", request.user, request.group),
+    )
+      .into_response(),
+  );
+}
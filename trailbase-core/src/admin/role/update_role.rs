@@ -0,0 +1,65 @@
+use axum::{
+  extract::State,
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use trailbase_sqlite::params;
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::constants::ROLE_TABLE;
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RoleRequest {
+  pub name: String,
+}
+
+pub async fn create_role_handler(
+  State(state): State<AppState>,
+  Json(request): Json<RoleRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String =
+      format!(r#"INSERT INTO "{ROLE_TABLE}" (name) VALUES ($1) ON CONFLICT DO NOTHING"#);
+  }
+
+  if state
+    .user_conn()
+    .execute(&QUERY, params!(request.name.clone()))
+    .await?
+    == 0
+  {
+    return Err(Error::AlreadyExists("role"));
+  }
+
+  return Ok((StatusCode::OK, format!("Created role: {}", request.name)).into_response());
+}
+
+/// Deletes a role including all its grants to users and groups.
+pub async fn delete_role_handler(
+  State(state): State<AppState>,
+  Json(request): Json<RoleRequest>,
+) -> Result<Response, Error> {
+  lazy_static! {
+    static ref QUERY: String = format!(r#"DELETE FROM "{ROLE_TABLE}" WHERE name = $1"#);
+  }
+
+  if state
+    .user_conn()
+    .execute(&QUERY, params!(request.name.clone()))
+    .await?
+    == 0
+  {
+    return Err(Error::Precondition(format!(
+      "Role not found: {}",
+      request.name
+    )));
+  }
+
+  return Ok((StatusCode::OK, format!("Deleted role: {}", request.name)).into_response());
+}
@@ -61,6 +61,7 @@ async fn delete_row(
     &table_metadata,
     pk_col,
     simple_json_value_to_param(column.data_type, value)?,
+    None,
   )
   .await?;
 
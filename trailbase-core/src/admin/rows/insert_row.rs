@@ -39,6 +39,7 @@ pub(crate) async fn insert_row(
     None,
     Some("*"),
     |row| Ok(trailbase_sqlite::Row::from_row(row, None)?),
+    None,
   )
   .await?;
 
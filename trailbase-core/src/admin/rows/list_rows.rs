@@ -13,6 +13,7 @@ use crate::listing::{
 use crate::records::sql_to_json::rows_to_json_arrays;
 use crate::schema::Column;
 use crate::table_metadata::{TableMetadata, TableOrViewMetadata};
+use crate::util::b64_to_id;
 
 #[derive(Debug, Serialize, TS)]
 #[ts(export)]
@@ -35,6 +36,7 @@ pub async fn list_rows_handler(
 ) -> Result<Json<ListRowsResponse>, Error> {
   let QueryParseResult {
     params: filter_params,
+    filter,
     cursor,
     limit,
     order,
@@ -42,6 +44,7 @@ pub async fn list_rows_handler(
     ..
   } = parse_query(raw_url_query.as_deref())
     .map_err(|err| Error::Precondition(format!("Invalid query '{err}': {raw_url_query:?}")))?;
+  let cursor = cursor.and_then(|cursor| b64_to_id(&cursor).ok());
 
   let (table_metadata, table_or_view_metadata): (
     Option<Arc<TableMetadata>>,
@@ -60,7 +63,8 @@ pub async fn list_rows_handler(
 
   // Where clause contains column filters and cursor depending on what's present in the url query
   // string.
-  let filter_where_clause = build_filter_where_clause(&*table_or_view_metadata, filter_params)?;
+  let filter_where_clause =
+    build_filter_where_clause(&*table_or_view_metadata, filter_params, filter)?;
 
   let total_row_count = {
     let where_clause = &filter_where_clause.clause;
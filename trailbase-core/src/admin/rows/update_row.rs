@@ -48,6 +48,7 @@ pub async fn update_row_handler(
     Params::from(&table_metadata, request.row, None)?,
     &column.name,
     simple_json_value_to_param(column.data_type, request.primary_key_value)?,
+    None,
   )
   .await?;
 
@@ -125,7 +125,7 @@ pub async fn alter_table_handler(
     debug!("Migration report: {report:?}");
   }
 
-  state.table_metadata().invalidate_all().await?;
+  state.refresh_table_cache().await?;
 
   return Ok((StatusCode::OK, "altered table").into_response());
 }
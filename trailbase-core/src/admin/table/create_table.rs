@@ -57,7 +57,7 @@ pub async fn create_table_handler(
       let _report = writer.write(conn).await?;
     }
 
-    state.table_metadata().invalidate_all().await?;
+    state.refresh_table_cache().await?;
   }
 
   return Ok(Json(CreateTableResponse {
@@ -77,7 +77,7 @@ pub async fn drop_table_handler(
     let _report = writer.write(conn).await?;
   }
 
-  state.table_metadata().invalidate_all().await?;
+  state.refresh_table_cache().await?;
 
   return Ok((StatusCode::OK, "").into_response());
 }
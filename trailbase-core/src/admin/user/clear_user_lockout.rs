@@ -0,0 +1,39 @@
+use axum::{
+  extract::State,
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::auth::lockout::clear_lockout;
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ClearUserLockoutRequest {
+  id: uuid::Uuid,
+}
+
+/// Unlocks a user's account and resets their failed login attempts.
+pub async fn clear_user_lockout_handler(
+  State(state): State<AppState>,
+  Json(request): Json<ClearUserLockoutRequest>,
+) -> Result<Response, Error> {
+  if !clear_lockout(state.user_conn(), &request.id).await? {
+    return Err(Error::Precondition(format!(
+      "User not found: {}",
+      request.id
+    )));
+  }
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Cleared lockout for user: {}", request.id),
+    )
+      .into_response(),
+  );
+}
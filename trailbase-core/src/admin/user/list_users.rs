@@ -16,7 +16,7 @@ use crate::constants::{USER_TABLE, USER_TABLE_ID_COLUMN};
 use crate::listing::{
   build_filter_where_clause, limit_or_default, parse_query, Order, QueryParseResult, WhereClause,
 };
-use crate::util::id_to_b64;
+use crate::util::{b64_to_id, id_to_b64};
 
 #[derive(Debug, Serialize, TS)]
 pub struct UserJson {
@@ -30,6 +30,14 @@ pub struct UserJson {
   pub provider_user_id: Option<String>,
 
   pub email_verification_code: String,
+
+  // Brute-force protection.
+  pub failed_login_attempts: i64,
+  /// Unix timestamp until which logins are rejected, if locked.
+  pub locked_until: Option<i64>,
+
+  /// Admin-editable JSON metadata.
+  pub metadata: Option<serde_json::Value>,
 }
 
 impl From<DbUser> for UserJson {
@@ -42,6 +50,11 @@ impl From<DbUser> for UserJson {
       provider_id: value.provider_id,
       provider_user_id: value.provider_user_id,
       email_verification_code: value.email_verification_code.unwrap_or_default(),
+      failed_login_attempts: value.failed_login_attempts,
+      locked_until: value.locked_until,
+      metadata: value
+        .metadata
+        .and_then(|metadata| serde_json::from_str(&metadata).ok()),
     }
   }
 }
@@ -65,19 +78,21 @@ pub async fn list_users_handler(
   // falling back to defaults.
   let QueryParseResult {
     params: filter_params,
+    filter,
     cursor,
     limit,
     order,
     ..
   } = parse_query(raw_url_query.as_deref())
     .map_err(|err| Error::Precondition(format!("Invalid query '{err}': {raw_url_query:?}")))?;
+  let cursor = cursor.and_then(|cursor| b64_to_id(&cursor).ok());
 
   let Some(table_metadata) = state.table_metadata().get(USER_TABLE) else {
     return Err(Error::Precondition(format!("Table {USER_TABLE} not found")));
   };
   // Where clause contains column filters and cursor depending on what's present in the url query
   // string.
-  let filter_where_clause = build_filter_where_clause(&*table_metadata, filter_params)?;
+  let filter_where_clause = build_filter_where_clause(&*table_metadata, filter_params, filter)?;
 
   let total_row_count = {
     let where_clause = &filter_where_clause.clause;
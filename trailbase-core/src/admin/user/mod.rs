@@ -1,9 +1,13 @@
+mod clear_user_lockout;
 mod create_user;
 mod list_users;
+mod reset_user_mfa;
 mod update_user;
 
+pub(super) use clear_user_lockout::clear_user_lockout_handler;
 pub use create_user::{create_user_handler, CreateUserRequest};
 pub(super) use list_users::list_users_handler;
+pub(super) use reset_user_mfa::reset_user_mfa_handler;
 pub(super) use update_user::update_user_handler;
 
 #[cfg(test)]
@@ -16,11 +20,14 @@ mod tests {
   use uuid::Uuid;
 
   use crate::app_state::{test_state, TestStateOptions};
+  use crate::auth::api::mfa::mfa_enabled;
   use crate::auth::util::user_by_email;
-  use crate::constants::USER_TABLE;
+  use crate::constants::{USER_MFA_TABLE, USER_TABLE};
   use crate::email::{testing::TestAsyncSmtpTransport, Mailer};
 
+  use super::clear_user_lockout::*;
   use super::create_user::*;
+  use super::reset_user_mfa::*;
 
   #[tokio::test]
   async fn test_user_creation_and_deletion() {
@@ -64,4 +71,81 @@ mod tests {
 
     assert!(user_by_email(&state, email).await.is_err());
   }
+
+  #[tokio::test]
+  async fn test_reset_user_mfa() {
+    let state = test_state(None).await.unwrap();
+
+    let user_id = create_user_for_test(&state, "mfa@bar.org", "Secret!1!!")
+      .await
+      .unwrap();
+
+    state
+      .user_conn()
+      .execute(
+        &format!(
+          "INSERT INTO '{USER_MFA_TABLE}' (user, totp_secret, enabled) VALUES ($1, 'SECRET', TRUE)"
+        ),
+        (user_id.as_bytes().to_vec(),),
+      )
+      .await
+      .unwrap();
+    assert!(mfa_enabled(&state, &user_id).await.unwrap());
+
+    reset_user_mfa_handler(
+      State(state.clone()),
+      Json(serde_json::from_value(serde_json::json!({ "id": user_id })).unwrap()),
+    )
+    .await
+    .unwrap();
+    assert!(!mfa_enabled(&state, &user_id).await.unwrap());
+
+    // Resetting again fails, since there's nothing to reset.
+    assert!(reset_user_mfa_handler(
+      State(state.clone()),
+      Json(serde_json::from_value(serde_json::json!({ "id": user_id })).unwrap()),
+    )
+    .await
+    .is_err());
+  }
+
+  #[tokio::test]
+  async fn test_clear_user_lockout() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "locked@bar.org";
+    let user_id = create_user_for_test(&state, email, "Secret!1!!")
+      .await
+      .unwrap();
+
+    state
+      .user_conn()
+      .execute(
+        &format!(
+          "UPDATE '{USER_TABLE}' SET failed_login_attempts = 10, locked_until = UNIXEPOCH() + 60 WHERE id = $1"
+        ),
+        (user_id.as_bytes().to_vec(),),
+      )
+      .await
+      .unwrap();
+
+    clear_user_lockout_handler(
+      State(state.clone()),
+      Json(serde_json::from_value(serde_json::json!({ "id": user_id })).unwrap()),
+    )
+    .await
+    .unwrap();
+
+    let user = user_by_email(&state, email).await.unwrap();
+    assert_eq!(0, user.failed_login_attempts);
+    assert_eq!(None, user.locked_until);
+
+    // Unknown users cannot be unlocked.
+    assert!(clear_user_lockout_handler(
+      State(state.clone()),
+      Json(serde_json::from_value(serde_json::json!({ "id": Uuid::now_v7() })).unwrap()),
+    )
+    .await
+    .is_err());
+  }
 }
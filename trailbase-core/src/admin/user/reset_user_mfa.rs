@@ -0,0 +1,39 @@
+use axum::{
+  extract::State,
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::admin::AdminError as Error;
+use crate::app_state::AppState;
+use crate::auth::api::mfa::delete_mfa;
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ResetUserMfaRequest {
+  id: uuid::Uuid,
+}
+
+/// Removes a user's second factor and recovery codes, e.g. after they lost their authenticator.
+pub async fn reset_user_mfa_handler(
+  State(state): State<AppState>,
+  Json(request): Json<ResetUserMfaRequest>,
+) -> Result<Response, Error> {
+  if !delete_mfa(state.user_conn(), &request.id).await? {
+    return Err(Error::Precondition(format!(
+      "MFA not set up for user: {}",
+      request.id
+    )));
+  }
+
+  return Ok(
+    (
+      StatusCode::OK,
+      format!("Reset MFA for user: {}", request.id),
+    )
+      .into_response(),
+  );
+}
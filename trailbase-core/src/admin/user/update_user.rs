@@ -22,6 +22,10 @@ pub struct UpdateUserRequest {
   email: Option<String>,
   password: Option<String>,
   verified: Option<bool>,
+
+  /// Replaces the user's metadata, which must be a JSON object. Changes to custom claims only take
+  /// effect once the user's auth token is refreshed.
+  metadata: Option<serde_json::Value>,
 }
 
 pub async fn update_user_handler(
@@ -36,6 +40,14 @@ pub async fn update_user_handler(
     None => None,
   };
 
+  let metadata = match &request.metadata {
+    Some(metadata @ serde_json::Value::Object(_)) => Some(metadata.to_string()),
+    Some(_) => {
+      return Err(Error::BadRequest("Metadata must be a JSON object".into()));
+    }
+    None => None,
+  };
+
   // TODO: Rather than using a transaction below we could build combined update queries:
   //   UPDATE <table> SET x = :x, y = :y WHERE id = :id.
   fn update_query(property: &str) -> String {
@@ -46,6 +58,7 @@ pub async fn update_user_handler(
     static ref UPDATE_EMAIL_QUERY: String = update_query("email");
     static ref UPDATE_PW_HASH_QUERY: String = update_query("password_hash");
     static ref UPDATE_VERIFIED_QUERY: String = update_query("verified");
+    static ref UPDATE_METADATA_QUERY: String = update_query("metadata");
   }
 
   let email = request.email.clone();
@@ -63,6 +76,9 @@ pub async fn update_user_handler(
       if let Some(verified) = verified {
         tx.execute(&UPDATE_VERIFIED_QUERY, params!(verified, user_id_bytes))?;
       }
+      if let Some(metadata) = metadata {
+        tx.execute(&UPDATE_METADATA_QUERY, params!(metadata, user_id_bytes))?;
+      }
 
       tx.commit()?;
 
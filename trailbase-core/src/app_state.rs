@@ -11,10 +11,13 @@ use crate::constants::SITE_URL_DEFAULT;
 use crate::data_dir::DataDir;
 use crate::email::Mailer;
 use crate::js::RuntimeHandle;
+use crate::rate_limit::RateLimiter;
+use crate::records::change_capture::ChangeCapture;
 use crate::records::subscribe::SubscriptionManager;
-use crate::records::RecordApi;
+use crate::records::{sync_search_indexes, validate_search_indexes, RecordApi};
 use crate::table_metadata::TableMetadataCache;
 use crate::value_notifier::{Computed, ValueNotifier};
+use crate::webhooks::Webhooks;
 
 /// The app's internal state. AppState needs to be clonable which puts unnecessary constraints on
 /// the internals. Thus rather arc once than many times.
@@ -22,13 +25,17 @@ struct InternalState {
   data_dir: DataDir,
   public_dir: Option<PathBuf>,
   dev: bool,
+  cors_allowed_origins: Vec<String>,
 
   oauth: Computed<ConfiguredOAuthProviders, Config>,
   mailer: Computed<Mailer, Config>,
+  webhooks: Webhooks,
+  rate_limiter: RateLimiter,
   record_apis: Computed<Vec<(String, RecordApi)>, Config>,
   config: ValueNotifier<Config>,
 
   logs_conn: trailbase_sqlite::Connection,
+  changes_conn: trailbase_sqlite::Connection,
   conn: trailbase_sqlite::Connection,
 
   jwt: JwtHelper,
@@ -48,10 +55,12 @@ pub(crate) struct AppStateArgs {
   pub data_dir: DataDir,
   pub public_dir: Option<PathBuf>,
   pub dev: bool,
+  pub cors_allowed_origins: Vec<String>,
   pub table_metadata: TableMetadataCache,
   pub config: Config,
   pub conn: trailbase_sqlite::Connection,
   pub logs_conn: trailbase_sqlite::Connection,
+  pub changes_conn: trailbase_sqlite::Connection,
   pub jwt: JwtHelper,
   pub object_store: Box<dyn ObjectStore + Send + Sync>,
   pub js_runtime_threads: Option<usize>,
@@ -90,11 +99,20 @@ impl AppState {
       .map_or_else(RuntimeHandle::new, RuntimeHandle::new_with_threads);
     runtime.set_connection(args.conn.clone());
 
+    let subscription_manager = SubscriptionManager::new(
+      args.conn.clone(),
+      args.table_metadata.clone(),
+      record_apis.clone(),
+      ChangeCapture::new(&config),
+      &config,
+    );
+
     AppState {
       state: Arc::new(InternalState {
         data_dir: args.data_dir,
         public_dir: args.public_dir,
         dev: args.dev,
+        cors_allowed_origins: args.cors_allowed_origins,
         oauth: Computed::new(&config, |c| {
           match ConfiguredOAuthProviders::from_config(c.auth.clone()) {
             Ok(providers) => providers,
@@ -105,13 +123,16 @@ impl AppState {
           }
         }),
         mailer: build_mailer(&config, None),
+        webhooks: Webhooks::default(),
+        rate_limiter: RateLimiter::default(),
         record_apis: record_apis.clone(),
         config,
         conn: args.conn.clone(),
         logs_conn: args.logs_conn,
+        changes_conn: args.changes_conn,
         jwt: args.jwt,
         table_metadata: args.table_metadata.clone(),
-        subscription_manager: SubscriptionManager::new(args.conn, args.table_metadata, record_apis),
+        subscription_manager,
         object_store: args.object_store,
         runtime,
         #[cfg(test)]
@@ -134,6 +155,11 @@ impl AppState {
     return self.state.dev;
   }
 
+  /// Origins, besides the site's own, allowed to make cross-origin requests.
+  pub(crate) fn cors_allowed_origins(&self) -> &[String] {
+    return &self.state.cors_allowed_origins;
+  }
+
   pub fn conn(&self) -> &trailbase_sqlite::Connection {
     return &self.state.conn;
   }
@@ -146,6 +172,10 @@ impl AppState {
     return &self.state.logs_conn;
   }
 
+  pub fn changes_conn(&self) -> &trailbase_sqlite::Connection {
+    return &self.state.changes_conn;
+  }
+
   pub fn version(&self) -> rustc_tools_util::VersionInfo {
     return rustc_tools_util::get_version_info!();
   }
@@ -158,8 +188,11 @@ impl AppState {
     return &self.state.subscription_manager;
   }
 
+  /// Rebuilds the table metadata cache and dependent state after schema changes.
   pub async fn refresh_table_cache(&self) -> Result<(), crate::table_metadata::TableLookupError> {
-    self.table_metadata().invalidate_all().await
+    self.table_metadata().invalidate_all().await?;
+    self.subscription_manager().sync_change_capture().await?;
+    return Ok(());
   }
 
   pub(crate) fn objectstore(&self) -> &(dyn ObjectStore + Send + Sync) {
@@ -191,6 +224,14 @@ impl AppState {
     return self.state.mailer.load().clone();
   }
 
+  pub(crate) fn webhooks(&self) -> &Webhooks {
+    return &self.state.webhooks;
+  }
+
+  pub(crate) fn rate_limiter(&self) -> &RateLimiter {
+    return &self.state.rate_limiter;
+  }
+
   pub(crate) fn jwt(&self) -> &JwtHelper {
     return &self.state.jwt;
   }
@@ -204,6 +245,16 @@ impl AppState {
     return None;
   }
 
+  /// Returns the first Record API in config order exposing the given table, if any.
+  pub(crate) fn lookup_record_api_for_table(&self, table_name: &str) -> Option<RecordApi> {
+    for (_record_api_name, record_api) in self.state.record_apis.load().iter() {
+      if record_api.table_name() == table_name {
+        return Some(record_api.clone());
+      }
+    }
+    return None;
+  }
+
   pub fn get_config(&self) -> Config {
     return (*self.state.config.load_full()).clone();
   }
@@ -217,19 +268,25 @@ impl AppState {
 
   pub async fn validate_and_update_config(
     &self,
-    config: Config,
+    mut config: Config,
     hash: Option<u64>,
   ) -> Result<(), crate::config::ConfigError> {
+    crate::config::assign_webhook_ids(&mut config);
     validate_config(self.table_metadata(), &config)?;
 
-    match hash {
+    // Only check whether search indexes can be created. Changes are applied only once the
+    // config was swapped successfully.
+    validate_search_indexes(self.conn(), &config.record_apis).await?;
+
+    let new_config = Arc::new(config);
+    let old_config = match hash {
       Some(hash) => {
-        let old_config = self.state.config.load();
+        let old_config = self.state.config.load_full();
         if old_config.hash() == hash {
           let success = self
             .state
             .config
-            .compare_and_swap(old_config, Arc::new(config));
+            .compare_and_swap(&old_config, new_config.clone());
 
           if !success {
             return Err(crate::config::ConfigError::Update(
@@ -241,10 +298,56 @@ impl AppState {
             "Safe config update failed: mismatching hash".to_string(),
           ));
         }
+        old_config
       }
-      None => self.state.config.store(config.clone()),
+      None => self.state.config.swap(new_config.clone()),
     };
 
+    // Create or drop search indexes now that the new config is in place. Searches racing the
+    // creation of a new index fail rather than the schema changing for a rejected config.
+    let search_indexes_changed = match sync_search_indexes(
+      self.conn(),
+      self.data_dir().migrations_path(),
+      &new_config.record_apis,
+    )
+    .await
+    {
+      Ok(changed) => changed,
+      Err(err) => {
+        // Restore the previous config and its search indexes, in case the migration was applied
+        // before failing, to keep memory, disk and schema consistent. Unless the config has been
+        // replaced concurrently in the meantime.
+        if self
+          .state
+          .config
+          .compare_and_swap(&new_config, old_config.clone())
+        {
+          match sync_search_indexes(
+            self.conn(),
+            self.data_dir().migrations_path(),
+            &old_config.record_apis,
+          )
+          .await
+          {
+            Ok(true) => {
+              if let Err(err) = self.refresh_table_cache().await {
+                error!("Failed to refresh table cache: {err}");
+              }
+            }
+            Ok(false) => {}
+            Err(err) => error!("Failed to restore search indexes: {err}"),
+          }
+        }
+        return Err(crate::config::ConfigError::Update(err.to_string()));
+      }
+    };
+    if search_indexes_changed {
+      self
+        .refresh_table_cache()
+        .await
+        .map_err(|err| crate::config::ConfigError::Update(err.to_string()))?;
+    }
+
     // Write new config to the file system.
     return write_config_and_vault_textproto(
       self.data_dir(),
@@ -286,7 +389,10 @@ pub async fn test_state(options: Option<TestStateOptions>) -> anyhow::Result<App
   use crate::auth::oauth::providers::test::TestOAuthProvider;
   use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
   use crate::config::validate_config;
-  use crate::migrations::{apply_logs_migrations, apply_main_migrations, apply_user_migrations};
+  use crate::migrations::{
+    apply_changes_migrations, apply_logs_migrations, apply_main_migrations, apply_user_migrations,
+  };
+  use crate::records::change_capture::attach_changes_db;
 
   let _ = env_logger::try_init_from_env(env_logger::Env::new().default_filter_or(
     "info,refinery_core=warn,trailbase_refinery_core=warn,tracing::span=warn,swc_ecma_codegen=off",
@@ -295,10 +401,20 @@ pub async fn test_state(options: Option<TestStateOptions>) -> anyhow::Result<App
   let temp_dir = temp_dir::TempDir::new()?;
   tokio::fs::create_dir_all(temp_dir.child("uploads")).await?;
 
+  // NOTE: The changes DB is file-backed, since it's attached to the main DB.
+  let changes_db_path = temp_dir.child("changes.db");
+  let changes_conn = {
+    let mut conn = trailbase_sqlite::connect_sqlite(Some(changes_db_path.clone()), None)?;
+    apply_changes_migrations(&mut conn)?;
+
+    trailbase_sqlite::Connection::from_conn(conn)?
+  };
+
   let conn = {
     let mut conn = trailbase_sqlite::connect_sqlite(None, None)?;
     apply_user_migrations(&mut conn)?;
     let _new_db = apply_main_migrations(&mut conn, None)?;
+    attach_changes_db(&conn, &changes_db_path)?;
 
     trailbase_sqlite::Connection::from_conn(conn)?
   };
@@ -397,22 +513,34 @@ pub async fn test_state(options: Option<TestStateOptions>) -> anyhow::Result<App
   let runtime = RuntimeHandle::new();
   runtime.set_connection(conn.clone());
 
+  let subscription_manager = SubscriptionManager::new(
+    conn.clone(),
+    table_metadata.clone(),
+    record_apis.clone(),
+    ChangeCapture::new(&config),
+    &config,
+  );
+
   return Ok(AppState {
     state: Arc::new(InternalState {
       data_dir,
       public_dir: None,
       dev: true,
+      cors_allowed_origins: vec![],
       oauth: Computed::new(&config, |c| {
         ConfiguredOAuthProviders::from_config(c.auth.clone()).unwrap()
       }),
       mailer: build_mailer(&config, options.and_then(|o| o.mailer)),
+      webhooks: Webhooks::default(),
+      rate_limiter: RateLimiter::default(),
       record_apis: record_apis.clone(),
       config,
       conn: conn.clone(),
       logs_conn,
+      changes_conn,
       jwt: jwt::test_jwt_helper(),
       table_metadata: table_metadata.clone(),
-      subscription_manager: SubscriptionManager::new(conn, table_metadata, record_apis),
+      subscription_manager,
       object_store,
       runtime,
       cleanup: vec![Box::new(temp_dir)],
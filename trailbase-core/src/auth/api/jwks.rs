@@ -0,0 +1,47 @@
+use axum::extract::{Json, State};
+
+use crate::auth::jwt::Jwks;
+use crate::AppState;
+
+/// Lists the public keys for validating auth tokens as JSON Web Key Set (JWKS).
+///
+/// This allows other services to verify auth tokens minted by TrailBase, including tokens signed
+/// by recently rotated keys.
+pub async fn jwks_handler(State(state): State<AppState>) -> Json<Jwks> {
+  return Json(state.jwt().jwks());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::app_state::test_state;
+
+  #[tokio::test]
+  async fn test_jwks() {
+    let state = test_state(None).await.unwrap();
+
+    let Json(jwks) = jwks_handler(State(state.clone())).await;
+    assert_eq!(1, jwks.keys.len());
+
+    let key = &jwks.keys[0];
+    assert_eq!("OKP", key.kty);
+    assert_eq!("Ed25519", key.crv);
+    assert_eq!(state.jwt().key_ids(), vec![key.kid.clone()]);
+
+    let decoding_key = jsonwebtoken::DecodingKey::from_ed_components(&key.x).unwrap();
+    let token = state
+      .jwt()
+      .encode(&crate::auth::TokenClaims::new(
+        true,
+        uuid::Uuid::now_v7(),
+        "foo@bar.com".to_string(),
+        crate::constants::DEFAULT_AUTH_TOKEN_TTL,
+      ))
+      .unwrap();
+    let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::EdDSA);
+    assert!(
+      jsonwebtoken::decode::<crate::auth::TokenClaims>(&token, &decoding_key, &validation).is_ok()
+    );
+  }
+}
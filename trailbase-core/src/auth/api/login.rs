@@ -6,13 +6,16 @@ use axum::{
 };
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use tower_cookies::Cookies;
 use trailbase_sqlite::named_params;
 use ts_rs::TS;
 use utoipa::{IntoParams, ToSchema};
 
 use crate::app_state::AppState;
+use crate::auth::api::mfa::{begin_mfa_challenge, login_mfa_ui_url, mfa_enabled};
 use crate::auth::api::register::validate_and_normalize_email_address;
+use crate::auth::lockout;
 use crate::auth::tokens::{mint_new_tokens, Tokens};
 use crate::auth::user::DbUser;
 use crate::auth::util::{new_cookie, user_by_email, validate_redirects};
@@ -20,7 +23,7 @@ use crate::auth::AuthError;
 use crate::constants::{
   COOKIE_AUTH_TOKEN, COOKIE_REFRESH_TOKEN, USER_TABLE, VERIFICATION_CODE_LENGTH,
 };
-use crate::extract::Either;
+use crate::extract::{ClientIp, Either};
 use crate::rand::generate_random_string;
 
 #[derive(Debug, Default, Deserialize, IntoParams)]
@@ -47,6 +50,14 @@ pub struct LoginResponse {
   pub csrf_token: String,
 }
 
+/// Returned instead of [LoginResponse] for users with multi-factor authentication enabled. The
+/// login has to be completed by passing the `mfa_token` and a second factor to `/login/mfa`.
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct LoginMfaResponse {
+  pub mfa_token: String,
+}
+
 /// Logs in user by email and password.
 #[utoipa::path(
   post,
@@ -54,12 +65,13 @@ pub struct LoginResponse {
   params(LoginQuery),
   request_body = LoginRequest,
   responses(
-    (status = 200, description = "Auth & refresh tokens.", body = LoginResponse)
+    (status = 200, description = "Auth & refresh tokens or MFA challenge.", body = LoginResponse)
   )
 )]
 pub(crate) async fn login_handler(
   State(state): State<AppState>,
   Query(query): Query<LoginQuery>,
+  ClientIp(ip): ClientIp,
   cookies: Cookies,
   either_request: Either<LoginRequest>,
 ) -> Result<Response, AuthError> {
@@ -69,38 +81,82 @@ pub(crate) async fn login_handler(
     Either::Multipart(req, _) => (req, false),
   };
 
-  let email = request.email.clone();
-  let redirect = validate_redirects(&state, &query.redirect_to, &request.redirect_to)?;
-  let code_response = request.response_type.as_ref().is_some_and(|t| t == "code");
-  let pkce_code_challenge = request.pkce_code_challenge.clone();
+  let options = LoginResponseOptions {
+    json,
+    redirect: validate_redirects(&state, &query.redirect_to, &request.redirect_to)?,
+    response_type: request.response_type,
+    pkce_code_challenge: request.pkce_code_challenge,
+  };
+
+  let db_user =
+    match check_password_with_lockout(&state, &request.email, &request.password, ip).await {
+      Ok(db_user) => db_user,
+      Err(err) if !json => {
+        return Ok(form_error_response(err, "/_/auth/login".to_string()));
+      }
+      Err(err) => return Err(err),
+    };
 
-  let response_or = login_handler_impl(&state, request).await;
+  return respond_with_login_or_mfa_challenge(&state, &cookies, db_user, options).await;
+}
 
-  if json {
-    return Ok(Json(response_or?).into_response());
+/// Completes a login after the first factor succeeded, unless the user has MFA enabled, in which
+/// case a challenge for the second factor is issued.
+pub(crate) async fn respond_with_login_or_mfa_challenge(
+  state: &AppState,
+  cookies: &Cookies,
+  db_user: DbUser,
+  options: LoginResponseOptions,
+) -> Result<Response, AuthError> {
+  if let Some(mfa_token) = begin_mfa_challenge(state, &db_user.uuid()).await? {
+    if options.json {
+      return Ok(Json(LoginMfaResponse { mfa_token }).into_response());
+    }
+    return Ok(Redirect::to(&login_mfa_ui_url(&mfa_token, &options)).into_response());
+  }
+
+  return respond_with_login(state, cookies, db_user, options).await;
+}
+
+/// Determines how a successful login is responded to.
+pub(crate) struct LoginResponseOptions {
+  pub json: bool,
+  pub redirect: Option<String>,
+  pub response_type: Option<String>,
+  pub pkce_code_challenge: Option<String>,
+}
+
+/// Completes a login for an already authenticated user, i.e. either responds with tokens,
+/// redirects with an authorization code or sets the auth cookies.
+pub(crate) async fn respond_with_login(
+  state: &AppState,
+  cookies: &Cookies,
+  db_user: DbUser,
+  options: LoginResponseOptions,
+) -> Result<Response, AuthError> {
+  if options.json {
+    let NewTokens {
+      auth_token,
+      refresh_token,
+      csrf_token,
+      ..
+    } = mint_tokens_for_user(state, db_user).await?;
+
+    return Ok(
+      Json(LoginResponse {
+        auth_token,
+        refresh_token,
+        csrf_token,
+      })
+      .into_response(),
+    );
   }
 
   // Cookie and redirect handling for the non-json case. The assumption is that json login is used
   // by SPAs or mobile applications, which should handle credential passing explicitly. No cookies
   // also removes the risk for any CSRF.
-  let response = match response_or {
-    Ok(response) => response,
-    Err(err) => {
-      let err_str = err.to_string();
-      let err_response: Response = err.into_response();
-      if err_response.status().is_client_error() {
-        let err_msg = crate::util::urlencode(&format!(
-          "Login Failed [{}]: {err_str}",
-          err_response.status()
-        ));
-        return Ok(Redirect::to(&format!("/_/auth/login?alert={err_msg}")).into_response());
-      }
-      return Ok(err_response);
-    }
-  };
-
-  if code_response {
-    let Some(redirect) = redirect else {
+  if options.response_type.as_ref().is_some_and(|t| t == "code") {
+    let Some(redirect) = options.redirect else {
       return Err(AuthError::BadRequest("missing 'redirect_to'"));
     };
 
@@ -117,7 +173,7 @@ pub(crate) async fn login_handler(
           authorization_code_sent_at = UNIXEPOCH(),
           pkce_code_challenge = :pkce_code_challenge
         WHERE
-          email = :email
+          id = :user_id
       "#
       );
     }
@@ -128,8 +184,8 @@ pub(crate) async fn login_handler(
         &QUERY,
         named_params! {
           ":authorization_code": authorization_code.clone(),
-          ":pkce_code_challenge": pkce_code_challenge,
-          ":email": email,
+          ":pkce_code_challenge": options.pkce_code_challenge,
+          ":user_id": db_user.id,
         },
       )
       .await?;
@@ -147,22 +203,24 @@ pub(crate) async fn login_handler(
     };
   }
 
+  let tokens = mint_tokens_for_user(state, db_user).await?;
+
   let (auth_token_ttl, refresh_token_ttl) = state.access_config(|c| c.auth.token_ttls());
   cookies.add(new_cookie(
     COOKIE_AUTH_TOKEN,
-    response.auth_token,
+    tokens.auth_token,
     auth_token_ttl,
     state.dev_mode(),
   ));
   cookies.add(new_cookie(
     COOKIE_REFRESH_TOKEN,
-    response.refresh_token,
+    tokens.refresh_token,
     refresh_token_ttl,
     state.dev_mode(),
   ));
 
   return Ok(
-    Redirect::to(redirect.as_deref().unwrap_or_else(|| {
+    Redirect::to(options.redirect.as_deref().unwrap_or_else(|| {
       if state.public_dir().is_some() {
         "/"
       } else {
@@ -173,28 +231,19 @@ pub(crate) async fn login_handler(
   );
 }
 
-async fn login_handler_impl(
-  state: &AppState,
-  request: LoginRequest,
-) -> Result<LoginResponse, AuthError> {
-  let email = if validate_and_normalize_email_address(&request.email).is_ok() {
-    request.email
-  } else {
-    return Err(AuthError::BadRequest("invalid e-mail"));
-  };
-
-  let NewTokens {
-    auth_token,
-    refresh_token,
-    csrf_token,
-    ..
-  } = login_with_password(state, &email, &request.password).await?;
-
-  return Ok(LoginResponse {
-    auth_token,
-    refresh_token,
-    csrf_token,
-  });
+/// Redirects client errors of form-based flows back to the given auth UI page with an alert.
+pub(crate) fn form_error_response(err: AuthError, page: String) -> Response {
+  let err_str = err.to_string();
+  let err_response: Response = err.into_response();
+  if err_response.status().is_client_error() {
+    let err_msg = crate::util::urlencode(&format!(
+      "Login Failed [{}]: {err_str}",
+      err_response.status()
+    ));
+    let separator = if page.contains('?') { '&' } else { '?' };
+    return Redirect::to(&format!("{page}{separator}alert={err_msg}")).into_response();
+  }
+  return err_response;
 }
 
 #[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
@@ -249,11 +298,48 @@ pub struct NewTokens {
   pub csrf_token: String,
 }
 
+/// Logs in user by email and password.
+///
+/// NOTE: Users with multi-factor authentication enabled are rejected, since a password alone is
+/// insufficient.
 pub async fn login_with_password(
   state: &AppState,
   email: &str,
   password: &str,
 ) -> Result<NewTokens, AuthError> {
+  let db_user = check_password(state, email, password).await?;
+  if mfa_enabled(state, &db_user.uuid()).await? {
+    return Err(AuthError::UnauthorizedExt("second factor required".into()));
+  }
+
+  return mint_tokens_for_user(state, db_user).await;
+}
+
+/// Like [check_password] but rejects attempts for locked accounts and IP addresses, and counts
+/// failed attempts towards their lockout.
+async fn check_password_with_lockout(
+  state: &AppState,
+  email: &str,
+  password: &str,
+  ip: Option<IpAddr>,
+) -> Result<DbUser, AuthError> {
+  let normalized_email = validate_and_normalize_email_address(email)?;
+  lockout::check_lockout(state, Some(&normalized_email), ip).await?;
+  // Count the attempt upfront, since concurrent attempts would otherwise all pass the check
+  // above while the password hashes are computed.
+  lockout::count_login_attempt(state, Some(&normalized_email), ip).await?;
+
+  let db_user = check_password(state, &normalized_email, password).await?;
+  lockout::reset_failed_logins(state, &db_user.uuid(), ip).await?;
+  return Ok(db_user);
+}
+
+/// Looks up a verified user by email and validates their password.
+pub(crate) async fn check_password(
+  state: &AppState,
+  email: &str,
+  password: &str,
+) -> Result<DbUser, AuthError> {
   let normalized_email = validate_and_normalize_email_address(email)?;
   let db_user: DbUser = user_by_email(state, &normalized_email).await?;
 
@@ -268,6 +354,10 @@ pub async fn login_with_password(
     .verify_password(password.as_bytes(), &parsed_hash)
     .map_err(|_err| AuthError::Unauthorized)?;
 
+  return Ok(db_user);
+}
+
+async fn mint_tokens_for_user(state: &AppState, db_user: DbUser) -> Result<NewTokens, AuthError> {
   let (auth_token_ttl, _refresh_token_ttl) = state.access_config(|c| c.auth.token_ttls());
   let user_id = db_user.uuid();
 
@@ -0,0 +1,459 @@
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use rand::{rngs::OsRng, Rng};
+use serde::Deserialize;
+use tower_cookies::Cookies;
+use trailbase_sqlite::params;
+use ts_rs::TS;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::app_state::AppState;
+use crate::auth::api::login::{
+  respond_with_login_or_mfa_challenge, LoginQuery, LoginResponse, LoginResponseOptions,
+};
+use crate::auth::api::register::validate_and_normalize_email_address;
+use crate::auth::user::DbUser;
+use crate::auth::util::{user_by_email, validate_redirects};
+use crate::auth::AuthError;
+use crate::constants::{USER_TABLE, VERIFICATION_CODE_LENGTH};
+use crate::email::Email;
+use crate::extract::Either;
+use crate::rand::generate_random_string;
+
+/// Minimum interval between two magic links for the same address.
+const RATE_LIMIT_SEC: i64 = 60;
+/// Number of attempts to enter the one-time code before a new one has to be requested.
+const MAX_OTP_ATTEMPTS: i64 = 5;
+
+#[derive(Debug, Default, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct MagicLinkRequest {
+  pub email: String,
+  pub redirect_to: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct MagicLinkConfirmRequest {
+  pub email: String,
+  /// One-time code included in the magic link email.
+  pub code: String,
+
+  pub redirect_to: Option<String>,
+  pub response_type: Option<String>,
+  pub pkce_code_challenge: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub(crate) struct MagicLinkConfirmQuery {
+  pub redirect_to: Option<String>,
+}
+
+/// Request an email with a link and one-time code to log in without a password.
+#[utoipa::path(
+  post,
+  path = "/login/magic_link/request",
+  request_body = MagicLinkRequest,
+  responses(
+    (status = 200, description = "Magic link sent.")
+  )
+)]
+pub async fn magic_link_request_handler(
+  State(state): State<AppState>,
+  either_request: Either<MagicLinkRequest>,
+) -> Result<Response, AuthError> {
+  let request = match either_request {
+    Either::Json(req) => req,
+    Either::Multipart(req, _) => req,
+    Either::Form(req) => req,
+  };
+
+  let redirect = validate_redirects(&state, &request.redirect_to, &None)?;
+  let normalized_email = validate_and_normalize_email_address(&request.email)?;
+
+  let user = user_by_email(&state, &normalized_email).await?;
+
+  if let Some(last_sent) = user.magic_link_sent_at {
+    let Some(timestamp) = chrono::DateTime::from_timestamp(last_sent, 0) else {
+      return Err(AuthError::Internal("Invalid timestamp".into()));
+    };
+
+    let age: chrono::Duration = chrono::Utc::now() - timestamp;
+    if age < chrono::Duration::seconds(RATE_LIMIT_SEC) {
+      return Err(AuthError::BadRequest("Magic link sent already"));
+    }
+  }
+
+  let magic_link_code = generate_random_string(VERIFICATION_CODE_LENGTH);
+  let one_time_code = format!("{:06}", OsRng.gen_range(0..1_000_000));
+
+  lazy_static! {
+    static ref UPDATE_CODE_QUERY: String = format!(
+      r#"
+        UPDATE
+          '{USER_TABLE}'
+        SET
+          magic_link_code = $1,
+          magic_link_otp = $2,
+          magic_link_sent_at = UNIXEPOCH(),
+          magic_link_attempts = 0
+        WHERE
+          id = $3
+      "#
+    );
+  }
+
+  let rows_affected = state
+    .user_conn()
+    .execute(
+      &UPDATE_CODE_QUERY,
+      params!(magic_link_code.clone(), one_time_code.clone(), user.id),
+    )
+    .await?;
+
+  return match rows_affected {
+    0 => Err(AuthError::Conflict),
+    1 => {
+      let email = Email::magic_link_email(
+        &state,
+        &user,
+        &magic_link_code,
+        &one_time_code,
+        redirect.as_deref(),
+      )
+      .map_err(|err| AuthError::Internal(err.into()))?;
+      email
+        .send()
+        .await
+        .map_err(|err| AuthError::Internal(err.into()))?;
+
+      Ok((StatusCode::OK, "Magic link sent").into_response())
+    }
+    _ => {
+      panic!("Magic link update affected multiple users: {rows_affected}");
+    }
+  };
+}
+
+/// Log in by following the magic link sent via email.
+#[utoipa::path(
+  get,
+  path = "/login/magic_link/confirm/:magic_link_code",
+  params(MagicLinkConfirmQuery),
+  responses(
+    (status = 303, description = "Logged in and redirected.")
+  )
+)]
+pub async fn magic_link_confirm_handler(
+  State(state): State<AppState>,
+  Path(magic_link_code): Path<String>,
+  Query(query): Query<MagicLinkConfirmQuery>,
+  cookies: Cookies,
+) -> Result<Response, AuthError> {
+  let redirect = validate_redirects(&state, &query.redirect_to, &None)?;
+
+  lazy_static! {
+    static ref CONSUME_CODE_QUERY: String = format!(
+      r#"
+        UPDATE '{USER_TABLE}'
+        SET
+          verified = TRUE,
+          magic_link_code = NULL,
+          magic_link_otp = NULL,
+          magic_link_sent_at = NULL,
+          magic_link_attempts = 0
+        WHERE
+          magic_link_code = $1 AND magic_link_sent_at > (UNIXEPOCH() - $2)
+        RETURNING *
+      "#
+    );
+  }
+
+  let ttl = state.access_config(|c| c.auth.magic_link_ttl());
+  let Some(db_user) = state
+    .user_conn()
+    .query_value::<DbUser>(
+      &CONSUME_CODE_QUERY,
+      params!(magic_link_code, ttl.num_seconds()),
+    )
+    .await?
+  else {
+    return Err(AuthError::BadRequest("Invalid or expired magic link"));
+  };
+
+  return respond_with_login_or_mfa_challenge(
+    &state,
+    &cookies,
+    db_user,
+    LoginResponseOptions {
+      json: false,
+      redirect,
+      response_type: None,
+      pkce_code_challenge: None,
+    },
+  )
+  .await;
+}
+
+/// Log in using the one-time code sent via email.
+#[utoipa::path(
+  post,
+  path = "/login/magic_link/confirm",
+  params(LoginQuery),
+  request_body = MagicLinkConfirmRequest,
+  responses(
+    (status = 200, description = "Auth & refresh tokens or MFA challenge.", body = LoginResponse)
+  )
+)]
+pub(crate) async fn magic_link_code_handler(
+  State(state): State<AppState>,
+  Query(query): Query<LoginQuery>,
+  cookies: Cookies,
+  either_request: Either<MagicLinkConfirmRequest>,
+) -> Result<Response, AuthError> {
+  let (request, json) = match either_request {
+    Either::Json(req) => (req, true),
+    Either::Form(req) => (req, false),
+    Either::Multipart(req, _) => (req, false),
+  };
+
+  let options = LoginResponseOptions {
+    json,
+    redirect: validate_redirects(&state, &query.redirect_to, &request.redirect_to)?,
+    response_type: request.response_type,
+    pkce_code_challenge: request.pkce_code_challenge,
+  };
+
+  let normalized_email = validate_and_normalize_email_address(&request.email)?;
+
+  lazy_static! {
+    static ref ATTEMPT_QUERY: String = format!(
+      r#"
+        UPDATE '{USER_TABLE}'
+        SET
+          magic_link_attempts = magic_link_attempts + 1
+        WHERE
+          email = $1 AND magic_link_otp IS NOT NULL
+            AND magic_link_sent_at > (UNIXEPOCH() - $2) AND magic_link_attempts < {MAX_OTP_ATTEMPTS}
+        RETURNING magic_link_otp
+      "#
+    );
+    static ref CONSUME_CODE_QUERY: String = format!(
+      r#"
+        UPDATE '{USER_TABLE}'
+        SET
+          verified = TRUE,
+          magic_link_code = NULL,
+          magic_link_otp = NULL,
+          magic_link_sent_at = NULL,
+          magic_link_attempts = 0
+        WHERE
+          email = $1 AND magic_link_otp = $2
+        RETURNING *
+      "#
+    );
+  }
+
+  // Count every attempt up-front to bound brute-forcing of the short code.
+  let ttl = state.access_config(|c| c.auth.magic_link_ttl());
+  let Some(row) = state
+    .user_conn()
+    .query_row(
+      &ATTEMPT_QUERY,
+      params!(normalized_email.clone(), ttl.num_seconds()),
+    )
+    .await?
+  else {
+    return Err(AuthError::Unauthorized);
+  };
+  let expected_code: String = row.get(0).map_err(|err| AuthError::Internal(err.into()))?;
+
+  let code = request.code.trim().to_string();
+  if code != expected_code {
+    return Err(AuthError::Unauthorized);
+  }
+
+  let Some(db_user) = state
+    .user_conn()
+    .query_value::<DbUser>(&CONSUME_CODE_QUERY, params!(normalized_email, code))
+    .await?
+  else {
+    return Err(AuthError::Unauthorized);
+  };
+
+  return respond_with_login_or_mfa_challenge(&state, &cookies, db_user, options).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::http::header::{LOCATION, SET_COOKIE};
+  use std::sync::Arc;
+
+  use super::*;
+  use crate::admin::user::create_user_for_test;
+  use crate::app_state::{test_state, TestStateOptions};
+  use crate::email::{testing::TestAsyncSmtpTransport, Mailer};
+
+  /// Steals the magic link code and one-time code from the DB.
+  async fn magic_link_codes(state: &AppState, email: &str) -> (String, String) {
+    let db_user = user_by_email(state, email).await.unwrap();
+    return (
+      db_user.magic_link_code.unwrap(),
+      db_user.magic_link_otp.unwrap(),
+    );
+  }
+
+  async fn request_magic_link(state: &AppState, email: &str) -> Result<Response, AuthError> {
+    return magic_link_request_handler(
+      State(state.clone()),
+      Either::Json(MagicLinkRequest {
+        email: email.to_string(),
+        redirect_to: Some("/target".to_string()),
+      }),
+    )
+    .await;
+  }
+
+  #[tokio::test]
+  async fn test_magic_link_login() {
+    let mailer = TestAsyncSmtpTransport::new();
+    let state = test_state(Some(TestStateOptions {
+      mailer: Some(Mailer::Smtp(Arc::new(mailer.clone()))),
+      ..Default::default()
+    }))
+    .await
+    .unwrap();
+
+    let email = "magic@test.org";
+    create_user_for_test(&state, email, "Secret!1!!")
+      .await
+      .unwrap();
+
+    request_magic_link(&state, email).await.unwrap();
+    // Rate limited.
+    assert!(request_magic_link(&state, email).await.is_err());
+
+    assert_eq!(1, mailer.get_logs().len());
+    let (magic_link_code, _) = magic_link_codes(&state, email).await;
+    let body = String::from_utf8_lossy(
+      &quoted_printable::decode(
+        mailer.get_logs()[0].1.as_bytes(),
+        quoted_printable::ParseMode::Robust,
+      )
+      .unwrap(),
+    )
+    .to_string();
+    assert!(
+      body.contains(&format!(
+        "/login/magic_link/confirm/{magic_link_code}?redirect_to=%2Ftarget"
+      )),
+      "{body}"
+    );
+
+    // Follow the link.
+    let response = magic_link_confirm_handler(
+      State(state.clone()),
+      Path(magic_link_code.clone()),
+      Query(MagicLinkConfirmQuery {
+        redirect_to: Some("/target".to_string()),
+      }),
+      Cookies::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::SEE_OTHER, response.status());
+    assert_eq!("/target", response.headers().get(LOCATION).unwrap());
+
+    // Links are single use.
+    assert!(magic_link_confirm_handler(
+      State(state.clone()),
+      Path(magic_link_code),
+      Query(MagicLinkConfirmQuery::default()),
+      Cookies::default(),
+    )
+    .await
+    .is_err());
+
+    // Enter the one-time code instead. Consuming the link also lifted the rate limit.
+    request_magic_link(&state, email).await.unwrap();
+
+    assert_eq!(2, mailer.get_logs().len());
+    let (_, one_time_code) = magic_link_codes(&state, email).await;
+
+    let confirm = |code: &str| {
+      magic_link_code_handler(
+        State(state.clone()),
+        Query(LoginQuery::default()),
+        Cookies::default(),
+        Either::Json(MagicLinkConfirmRequest {
+          email: email.to_string(),
+          code: code.to_string(),
+          ..Default::default()
+        }),
+      )
+    };
+
+    assert!(confirm("invalid").await.is_err());
+
+    let response = confirm(&one_time_code).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    assert!(response.headers().get(SET_COOKIE).is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let _tokens: LoginResponse = serde_json::from_slice(&body).unwrap();
+
+    assert!(confirm(&one_time_code).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_magic_link_code_attempts() {
+    let mailer = TestAsyncSmtpTransport::new();
+    let state = test_state(Some(TestStateOptions {
+      mailer: Some(Mailer::Smtp(Arc::new(mailer.clone()))),
+      ..Default::default()
+    }))
+    .await
+    .unwrap();
+
+    let email = "attempts@test.org";
+    create_user_for_test(&state, email, "Secret!1!!")
+      .await
+      .unwrap();
+    request_magic_link(&state, email).await.unwrap();
+    let (_, one_time_code) = magic_link_codes(&state, email).await;
+
+    for _ in 0..MAX_OTP_ATTEMPTS {
+      assert!(magic_link_code_handler(
+        State(state.clone()),
+        Query(LoginQuery::default()),
+        Cookies::default(),
+        Either::Json(MagicLinkConfirmRequest {
+          email: email.to_string(),
+          code: "invalid".to_string(),
+          ..Default::default()
+        }),
+      )
+      .await
+      .is_err());
+    }
+
+    // Even the correct code is rejected once all attempts are used up.
+    assert!(magic_link_code_handler(
+      State(state.clone()),
+      Query(LoginQuery::default()),
+      Cookies::default(),
+      Either::Json(MagicLinkConfirmRequest {
+        email: email.to_string(),
+        code: one_time_code,
+        ..Default::default()
+      }),
+    )
+    .await
+    .is_err());
+  }
+}
@@ -0,0 +1,671 @@
+use axum::{
+  extract::{Query, State},
+  response::Response,
+  Json,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tower_cookies::Cookies;
+use trailbase_sqlite::params;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::auth::api::login::{
+  form_error_response, respond_with_login, LoginQuery, LoginResponse, LoginResponseOptions,
+};
+use crate::auth::lockout;
+use crate::auth::totp;
+use crate::auth::user::{DbUser, User};
+use crate::auth::util::{user_by_id, validate_redirects};
+use crate::auth::AuthError;
+use crate::constants::{
+  MFA_CHALLENGE_MAX_ATTEMPTS, MFA_CHALLENGE_TTL, MFA_RECOVERY_CODE_COUNT,
+  USER_MFA_RECOVERY_CODE_TABLE, USER_MFA_TABLE, VERIFICATION_CODE_LENGTH,
+};
+use crate::extract::{ClientIp, Either};
+use crate::rand::generate_random_string;
+use crate::util::urlencode;
+
+const RECOVERY_CODE_LENGTH: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct TotpEnrollResponse {
+  /// Base32-encoded shared secret for manual entry.
+  pub secret: String,
+  /// "otpauth://" URI to be rendered as QR code for authenticator apps.
+  pub provisioning_uri: String,
+}
+
+#[derive(Debug, Default, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct TotpCodeRequest {
+  pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct TotpConfirmResponse {
+  /// Single-use codes substituting for a TOTP code, e.g. when the authenticator was lost. They
+  /// are only shown once.
+  pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct LoginMfaRequest {
+  pub mfa_token: String,
+  /// TOTP or recovery code.
+  pub code: String,
+
+  pub redirect_to: Option<String>,
+  pub response_type: Option<String>,
+  pub pkce_code_challenge: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MfaRow {
+  totp_secret: String,
+  enabled: bool,
+  last_used_step: i64,
+}
+
+/// Starts TOTP enrollment for the current user.
+///
+/// The second factor only becomes active once confirmed with a valid code.
+#[utoipa::path(
+  post,
+  path = "/mfa/totp/enroll",
+  responses(
+    (status = 200, description = "TOTP secret and provisioning URI.", body = TotpEnrollResponse)
+  )
+)]
+pub(crate) async fn totp_enroll_handler(
+  State(state): State<AppState>,
+  user: User,
+) -> Result<Json<TotpEnrollResponse>, AuthError> {
+  let secret = totp::generate_secret();
+
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        INSERT INTO "{USER_MFA_TABLE}" (user, totp_secret) VALUES ($1, $2)
+        ON CONFLICT (user) DO UPDATE SET totp_secret = excluded.totp_secret, last_used_step = 0
+        WHERE enabled = FALSE
+      "#
+    );
+  }
+
+  let rows_affected = state
+    .user_conn()
+    .execute(&QUERY, params!(user.uuid.into_bytes(), secret.clone()))
+    .await?;
+  if rows_affected == 0 {
+    // MFA is already enabled and has to be disabled first.
+    return Err(AuthError::Conflict);
+  }
+
+  let issuer = state
+    .access_config(|c| c.server.application_name.clone())
+    .unwrap_or_else(|| "TrailBase".to_string());
+
+  return Ok(Json(TotpEnrollResponse {
+    provisioning_uri: totp::provisioning_uri(&secret, &issuer, &user.email),
+    secret,
+  }));
+}
+
+/// Confirms TOTP enrollment with a valid code, which enables MFA for the current user.
+#[utoipa::path(
+  post,
+  path = "/mfa/totp/confirm",
+  request_body = TotpCodeRequest,
+  responses(
+    (status = 200, description = "Recovery codes.", body = TotpConfirmResponse)
+  )
+)]
+pub(crate) async fn totp_confirm_handler(
+  State(state): State<AppState>,
+  user: User,
+  Json(request): Json<TotpCodeRequest>,
+) -> Result<Json<TotpConfirmResponse>, AuthError> {
+  let Some(mfa) = mfa_row(&state, &user.uuid).await? else {
+    return Err(AuthError::BadRequest("TOTP enrollment not started"));
+  };
+  if mfa.enabled {
+    return Err(AuthError::Conflict);
+  }
+
+  let Some(step) = totp::verify(
+    &mfa.totp_secret,
+    &request.code,
+    chrono::Utc::now().timestamp(),
+    mfa.last_used_step,
+  ) else {
+    return Err(AuthError::Unauthorized);
+  };
+
+  let recovery_codes: Vec<String> = (0..MFA_RECOVERY_CODE_COUNT)
+    .map(|_| generate_random_string(RECOVERY_CODE_LENGTH))
+    .collect();
+  let code_hashes: Vec<String> = recovery_codes
+    .iter()
+    .map(|code| hash_recovery_code(code))
+    .collect();
+
+  lazy_static! {
+    static ref ENABLE_QUERY: String = format!(
+      r#"
+        UPDATE "{USER_MFA_TABLE}" SET enabled = TRUE, last_used_step = $1
+        WHERE user = $2 AND totp_secret = $3 AND enabled = FALSE
+      "#
+    );
+    static ref DELETE_CODES_QUERY: String =
+      format!(r#"DELETE FROM "{USER_MFA_RECOVERY_CODE_TABLE}" WHERE user = $1"#);
+    static ref INSERT_CODE_QUERY: String =
+      format!(r#"INSERT INTO "{USER_MFA_RECOVERY_CODE_TABLE}" (user, code_hash) VALUES ($1, $2)"#);
+  }
+
+  let user_id_bytes = user.uuid.into_bytes();
+  let totp_secret = mfa.totp_secret;
+  let enabled = state
+    .user_conn()
+    .call(move |conn| {
+      let tx = conn.transaction()?;
+
+      // Guards against concurrent re-enrollment.
+      if tx.execute(
+        &ENABLE_QUERY,
+        rusqlite::params!(step, user_id_bytes, totp_secret),
+      )? != 1
+      {
+        return Ok(false);
+      }
+
+      tx.execute(&DELETE_CODES_QUERY, rusqlite::params!(user_id_bytes))?;
+      for code_hash in code_hashes {
+        tx.execute(
+          &INSERT_CODE_QUERY,
+          rusqlite::params!(user_id_bytes, code_hash),
+        )?;
+      }
+
+      tx.commit()?;
+      return Ok(true);
+    })
+    .await?;
+
+  if !enabled {
+    return Err(AuthError::Conflict);
+  }
+
+  return Ok(Json(TotpConfirmResponse { recovery_codes }));
+}
+
+/// Disables MFA for the current user given a valid TOTP or recovery code.
+///
+/// Failed attempts count towards the account's login lockout, since codes could otherwise be
+/// brute-forced.
+#[utoipa::path(
+  post,
+  path = "/mfa/totp/disable",
+  request_body = TotpCodeRequest,
+  responses(
+    (status = 200, description = "Success.")
+  )
+)]
+pub(crate) async fn totp_disable_handler(
+  State(state): State<AppState>,
+  ClientIp(ip): ClientIp,
+  user: User,
+  Json(request): Json<TotpCodeRequest>,
+) -> Result<(), AuthError> {
+  let Some(mfa) = mfa_row(&state, &user.uuid).await? else {
+    return Err(AuthError::BadRequest("MFA not enabled"));
+  };
+  if !mfa.enabled {
+    return Err(AuthError::BadRequest("MFA not enabled"));
+  }
+
+  lockout::check_lockout(&state, Some(&user.email), ip).await?;
+  lockout::count_login_attempt(&state, Some(&user.email), ip).await?;
+
+  if !verify_second_factor(&state, &user.uuid, &mfa, &request.code).await? {
+    return Err(AuthError::Unauthorized);
+  }
+  lockout::reset_failed_logins(&state, &user.uuid, ip).await?;
+
+  delete_mfa(state.user_conn(), &user.uuid).await?;
+
+  return Ok(());
+}
+
+/// Completes a login for users with MFA enabled given the challenge token returned by `/login`
+/// and a TOTP or recovery code.
+#[utoipa::path(
+  post,
+  path = "/login/mfa",
+  params(LoginQuery),
+  request_body = LoginMfaRequest,
+  responses(
+    (status = 200, description = "Auth & refresh tokens.", body = LoginResponse)
+  )
+)]
+pub(crate) async fn login_mfa_handler(
+  State(state): State<AppState>,
+  Query(query): Query<LoginQuery>,
+  cookies: Cookies,
+  either_request: Either<LoginMfaRequest>,
+) -> Result<Response, AuthError> {
+  let (request, json) = match either_request {
+    Either::Json(req) => (req, true),
+    Either::Form(req) => (req, false),
+    Either::Multipart(req, _) => (req, false),
+  };
+
+  let options = LoginResponseOptions {
+    json,
+    redirect: validate_redirects(&state, &query.redirect_to, &request.redirect_to)?,
+    response_type: request.response_type,
+    pkce_code_challenge: request.pkce_code_challenge,
+  };
+
+  return match complete_mfa_challenge(&state, &request.mfa_token, &request.code).await {
+    Ok(db_user) => respond_with_login(&state, &cookies, db_user, options).await,
+    Err(err) if !json => Ok(form_error_response(
+      err,
+      login_mfa_ui_url(&request.mfa_token, &options),
+    )),
+    Err(err) => Err(err),
+  };
+}
+
+pub(crate) async fn mfa_enabled(state: &AppState, user_id: &Uuid) -> Result<bool, AuthError> {
+  return Ok(
+    mfa_row(state, user_id)
+      .await?
+      .is_some_and(|mfa| mfa.enabled),
+  );
+}
+
+/// Issues a new MFA challenge token if the user has MFA enabled. Any previously issued challenge
+/// is invalidated.
+pub(crate) async fn begin_mfa_challenge(
+  state: &AppState,
+  user_id: &Uuid,
+) -> Result<Option<String>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        UPDATE "{USER_MFA_TABLE}"
+        SET challenge_token = $1, challenge_sent_at = UNIXEPOCH(), challenge_attempts = 0
+        WHERE user = $2 AND enabled = TRUE
+      "#
+    );
+  }
+
+  let mfa_token = generate_random_string(VERIFICATION_CODE_LENGTH);
+  let rows_affected = state
+    .user_conn()
+    .execute(&QUERY, params!(mfa_token.clone(), user_id.into_bytes()))
+    .await?;
+
+  return Ok(match rows_affected {
+    0 => None,
+    _ => Some(mfa_token),
+  });
+}
+
+/// Auth UI page for entering the second factor, carrying over the state of the first login step.
+pub(crate) fn login_mfa_ui_url(mfa_token: &str, options: &LoginResponseOptions) -> String {
+  let mut url = format!("/_/auth/login_mfa?mfa_token={}", urlencode(mfa_token));
+  for (key, value) in [
+    ("redirect_to", &options.redirect),
+    ("response_type", &options.response_type),
+    ("pkce_code_challenge", &options.pkce_code_challenge),
+  ] {
+    if let Some(value) = value {
+      url.push_str(&format!("&{key}={}", urlencode(value)));
+    }
+  }
+  return url;
+}
+
+/// Removes the second factor and recovery codes of the given user.
+pub(crate) async fn delete_mfa(
+  user_conn: &trailbase_sqlite::Connection,
+  user_id: &Uuid,
+) -> Result<bool, trailbase_sqlite::Error> {
+  lazy_static! {
+    static ref DELETE_MFA_QUERY: String =
+      format!(r#"DELETE FROM "{USER_MFA_TABLE}" WHERE user = $1"#);
+    static ref DELETE_CODES_QUERY: String =
+      format!(r#"DELETE FROM "{USER_MFA_RECOVERY_CODE_TABLE}" WHERE user = $1"#);
+  }
+
+  let user_id_bytes = user_id.into_bytes();
+  return user_conn
+    .call(move |conn| {
+      let tx = conn.transaction()?;
+      let rows_affected = tx.execute(&DELETE_MFA_QUERY, rusqlite::params!(user_id_bytes))?;
+      tx.execute(&DELETE_CODES_QUERY, rusqlite::params!(user_id_bytes))?;
+      tx.commit()?;
+
+      return Ok(rows_affected > 0);
+    })
+    .await;
+}
+
+async fn mfa_row(state: &AppState, user_id: &Uuid) -> Result<Option<MfaRow>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"SELECT totp_secret, enabled, last_used_step FROM "{USER_MFA_TABLE}" WHERE user = $1"#
+    );
+  }
+
+  return Ok(
+    state
+      .user_conn()
+      .query_value::<MfaRow>(&QUERY, params!(user_id.into_bytes()))
+      .await?,
+  );
+}
+
+async fn complete_mfa_challenge(
+  state: &AppState,
+  mfa_token: &str,
+  code: &str,
+) -> Result<DbUser, AuthError> {
+  #[derive(Debug, Deserialize)]
+  struct Challenge {
+    user: [u8; 16],
+    totp_secret: String,
+    last_used_step: i64,
+    challenge_attempts: i64,
+  }
+
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        UPDATE "{USER_MFA_TABLE}" SET challenge_attempts = challenge_attempts + 1
+        WHERE challenge_token = $1 AND enabled = TRUE AND challenge_sent_at > (UNIXEPOCH() - $2)
+        RETURNING user, totp_secret, last_used_step, challenge_attempts
+      "#
+    );
+    static ref CLEAR_QUERY: String = format!(
+      r#"UPDATE "{USER_MFA_TABLE}" SET challenge_token = NULL, challenge_sent_at = NULL WHERE user = $1"#
+    );
+  }
+
+  let Some(challenge) = state
+    .user_conn()
+    .query_value::<Challenge>(
+      &QUERY,
+      params!(mfa_token.to_string(), MFA_CHALLENGE_TTL.num_seconds()),
+    )
+    .await?
+  else {
+    return Err(AuthError::Unauthorized);
+  };
+
+  let user_id = Uuid::from_bytes(challenge.user);
+  if challenge.challenge_attempts > MFA_CHALLENGE_MAX_ATTEMPTS {
+    state
+      .user_conn()
+      .execute(&CLEAR_QUERY, params!(challenge.user))
+      .await?;
+    return Err(AuthError::Unauthorized);
+  }
+
+  let mfa = MfaRow {
+    totp_secret: challenge.totp_secret,
+    enabled: true,
+    last_used_step: challenge.last_used_step,
+  };
+  if !verify_second_factor(state, &user_id, &mfa, code).await? {
+    return Err(AuthError::Unauthorized);
+  }
+
+  state
+    .user_conn()
+    .execute(&CLEAR_QUERY, params!(challenge.user))
+    .await?;
+
+  let db_user = user_by_id(state, &user_id).await?;
+  if !db_user.verified {
+    return Err(AuthError::Unauthorized);
+  }
+  return Ok(db_user);
+}
+
+/// Validates either a TOTP code or a recovery code, which is consumed on success.
+async fn verify_second_factor(
+  state: &AppState,
+  user_id: &Uuid,
+  mfa: &MfaRow,
+  code: &str,
+) -> Result<bool, AuthError> {
+  lazy_static! {
+    static ref UPDATE_STEP_QUERY: String = format!(
+      r#"UPDATE "{USER_MFA_TABLE}" SET last_used_step = $1 WHERE user = $2 AND last_used_step < $1"#
+    );
+    static ref CONSUME_CODE_QUERY: String =
+      format!(r#"DELETE FROM "{USER_MFA_RECOVERY_CODE_TABLE}" WHERE user = $1 AND code_hash = $2"#);
+  }
+
+  let conn = state.user_conn();
+  if let Some(step) = totp::verify(
+    &mfa.totp_secret,
+    code,
+    chrono::Utc::now().timestamp(),
+    mfa.last_used_step,
+  ) {
+    // Conditional update to accept every code only once even for concurrent requests.
+    let rows_affected = conn
+      .execute(&UPDATE_STEP_QUERY, params!(step, user_id.into_bytes()))
+      .await?;
+    return Ok(rows_affected > 0);
+  }
+
+  let rows_affected = conn
+    .execute(
+      &CONSUME_CODE_QUERY,
+      params!(user_id.into_bytes(), hash_recovery_code(code.trim())),
+    )
+    .await?;
+  return Ok(rows_affected > 0);
+}
+
+fn hash_recovery_code(code: &str) -> String {
+  return Sha256::digest(code.as_bytes())
+    .iter()
+    .map(|b| format!("{b:02x}"))
+    .collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::http::StatusCode;
+  use axum::response::IntoResponse;
+
+  use super::*;
+  use crate::admin::user::create_user_for_test;
+  use crate::app_state::test_state;
+  use crate::auth::api::login::{
+    login_handler, login_with_password, LoginMfaResponse, LoginRequest,
+  };
+  use crate::auth::util::user_by_email;
+  use crate::constants::DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS;
+
+  fn current_code(secret: &str) -> String {
+    return totp::generate_code(secret, chrono::Utc::now().timestamp()).unwrap();
+  }
+
+  async fn login_json(state: &AppState, email: &str, password: &str) -> Response {
+    return login_handler(
+      State(state.clone()),
+      Query(LoginQuery::default()),
+      ClientIp::default(),
+      Cookies::default(),
+      Either::Json(LoginRequest {
+        email: email.to_string(),
+        password: password.to_string(),
+        redirect_to: None,
+        response_type: None,
+        pkce_code_challenge: None,
+      }),
+    )
+    .await
+    .unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_totp_mfa_login() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "mfa@test.org";
+    let password = "Secret!1!!";
+    create_user_for_test(&state, email, password).await.unwrap();
+    let tokens = login_with_password(&state, email, password).await.unwrap();
+    let user = User::from_auth_token(&state, &tokens.auth_token).unwrap();
+
+    // Enroll and confirm.
+    let Json(enrollment) = totp_enroll_handler(State(state.clone()), user.clone())
+      .await
+      .unwrap();
+    assert!(enrollment.provisioning_uri.starts_with("otpauth://totp/"));
+
+    assert!(totp_confirm_handler(
+      State(state.clone()),
+      user.clone(),
+      Json(TotpCodeRequest {
+        code: "000000".to_string(),
+      }),
+    )
+    .await
+    .is_err());
+
+    let confirmation_code = current_code(&enrollment.secret);
+    let Json(confirmation) = totp_confirm_handler(
+      State(state.clone()),
+      user.clone(),
+      Json(TotpCodeRequest {
+        code: confirmation_code.clone(),
+      }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(MFA_RECOVERY_CODE_COUNT, confirmation.recovery_codes.len());
+
+    // Re-enrolling requires disabling first.
+    assert!(matches!(
+      totp_enroll_handler(State(state.clone()), user.clone()).await,
+      Err(AuthError::Conflict)
+    ));
+
+    // Passwords alone are no longer sufficient.
+    assert!(login_with_password(&state, email, password).await.is_err());
+
+    let response = login_json(&state, email, password).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let LoginMfaResponse { mfa_token } = serde_json::from_slice(&body).unwrap();
+
+    let login_mfa = |mfa_token: String, code: String| {
+      login_mfa_handler(
+        State(state.clone()),
+        Query(LoginQuery::default()),
+        Cookies::default(),
+        Either::Json(LoginMfaRequest {
+          mfa_token,
+          code,
+          redirect_to: None,
+          response_type: None,
+          pkce_code_challenge: None,
+        }),
+      )
+    };
+
+    // The TOTP code was already used during confirmation and must not be replayed.
+    assert!(login_mfa(mfa_token.clone(), confirmation_code)
+      .await
+      .is_err());
+
+    let recovery_code = confirmation.recovery_codes[0].clone();
+    let response = login_mfa(mfa_token.clone(), recovery_code.clone())
+      .await
+      .unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    // Challenge tokens and recovery codes are single use.
+    assert!(login_mfa(mfa_token, recovery_code.clone()).await.is_err());
+    let response = login_json(&state, email, password).await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let LoginMfaResponse { mfa_token } = serde_json::from_slice(&body).unwrap();
+    assert!(login_mfa(mfa_token.clone(), recovery_code).await.is_err());
+
+    // Too many attempts invalidate the challenge.
+    for _ in 0..MFA_CHALLENGE_MAX_ATTEMPTS {
+      let _ = login_mfa(mfa_token.clone(), "invalid".to_string()).await;
+    }
+    let err = login_mfa(mfa_token, confirmation.recovery_codes[1].clone())
+      .await
+      .unwrap_err();
+    assert_eq!(StatusCode::UNAUTHORIZED, err.into_response().status());
+
+    // Guessing codes to disable MFA runs into the account's lockout, i.e. the progressive delay
+    // after half the attempts.
+    for _ in 0..DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS / 2 {
+      assert!(matches!(
+        totp_disable_handler(
+          State(state.clone()),
+          ClientIp::default(),
+          user.clone(),
+          Json(TotpCodeRequest {
+            code: "000000".to_string(),
+          }),
+        )
+        .await,
+        Err(AuthError::Unauthorized)
+      ));
+    }
+    assert!(matches!(
+      totp_disable_handler(
+        State(state.clone()),
+        ClientIp::default(),
+        user.clone(),
+        Json(TotpCodeRequest {
+          code: confirmation.recovery_codes[2].clone(),
+        }),
+      )
+      .await,
+      Err(AuthError::TooManyRequests(_))
+    ));
+    crate::auth::lockout::clear_lockout(state.user_conn(), &user.uuid)
+      .await
+      .unwrap();
+
+    // Disable using a recovery code.
+    totp_disable_handler(
+      State(state.clone()),
+      ClientIp::default(),
+      user.clone(),
+      Json(TotpCodeRequest {
+        code: confirmation.recovery_codes[2].clone(),
+      }),
+    )
+    .await
+    .unwrap();
+
+    let db_user = user_by_email(&state, email).await.unwrap();
+    assert!(!mfa_enabled(&state, &db_user.uuid()).await.unwrap());
+    login_with_password(&state, email, password).await.unwrap();
+  }
+}
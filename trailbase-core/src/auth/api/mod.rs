@@ -1,12 +1,16 @@
 pub mod login;
 
+pub(crate) mod mfa;
 pub(crate) mod register;
 
 pub(super) mod avatar;
 pub(super) mod change_email;
 pub(super) mod change_password;
 pub(super) mod delete;
+pub(super) mod jwks;
 pub(super) mod logout;
+pub(super) mod magic_link;
+pub(super) mod passkey;
 pub(super) mod refresh;
 pub(super) mod reset_password;
 pub(super) mod token;
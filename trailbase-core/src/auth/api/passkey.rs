@@ -0,0 +1,590 @@
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::{IntoResponse, Response},
+  Json,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tower_cookies::Cookies;
+use trailbase_sqlite::params;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::auth::api::login::{
+  respond_with_login, respond_with_login_or_mfa_challenge, LoginQuery, LoginResponse,
+  LoginResponseOptions,
+};
+use crate::auth::user::User;
+use crate::auth::util::{user_by_id, validate_redirects};
+use crate::auth::webauthn::{self, CredentialKey, RelyingParty};
+use crate::auth::AuthError;
+use crate::constants::{USER_PASSKEY_TABLE, WEBAUTHN_CHALLENGE_TABLE, WEBAUTHN_CHALLENGE_TTL};
+use crate::extract::Either;
+
+const KIND_REGISTRATION: &str = "registration";
+const KIND_AUTHENTICATION: &str = "authentication";
+
+/// Options for `navigator.credentials.create()`. Binary values are base64url-encoded.
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PasskeyRegistrationOptions {
+  pub challenge: String,
+  pub rp_id: String,
+  pub rp_name: String,
+  pub user_id: String,
+  pub user_name: String,
+  /// Ids of already registered credentials to avoid duplicate registrations.
+  pub exclude_credentials: Vec<String>,
+  /// Supported COSE algorithm identifiers in order of preference.
+  pub algorithms: Vec<i64>,
+  pub timeout_ms: i64,
+}
+
+/// Result of `navigator.credentials.create()`. Binary values are base64url-encoded.
+#[derive(Debug, Default, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PasskeyRegistrationRequest {
+  pub client_data_json: String,
+  pub attestation_object: String,
+  /// Display name to tell passkeys apart, e.g. "Laptop".
+  pub name: Option<String>,
+}
+
+/// Options for `navigator.credentials.get()`. Binary values are base64url-encoded.
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PasskeyLoginOptions {
+  pub challenge: String,
+  pub rp_id: String,
+  pub timeout_ms: i64,
+}
+
+/// Result of `navigator.credentials.get()`. Binary values are base64url-encoded.
+#[derive(Debug, Default, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PasskeyLoginRequest {
+  pub credential_id: String,
+  pub client_data_json: String,
+  pub authenticator_data: String,
+  pub signature: String,
+  pub user_handle: Option<String>,
+
+  pub redirect_to: Option<String>,
+  pub response_type: Option<String>,
+  pub pkce_code_challenge: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct Passkey {
+  pub id: i64,
+  pub name: String,
+  pub created: i64,
+  pub last_used: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PasskeyRow {
+  user: [u8; 16],
+  algorithm: i64,
+  public_key: Vec<u8>,
+  sign_count: i64,
+}
+
+/// Starts the registration of a new passkey for the current user.
+#[utoipa::path(
+  post,
+  path = "/passkey/register/start",
+  responses(
+    (status = 200, description = "Credential creation options.", body = PasskeyRegistrationOptions)
+  )
+)]
+pub(crate) async fn passkey_register_start_handler(
+  State(state): State<AppState>,
+  user: User,
+) -> Result<Json<PasskeyRegistrationOptions>, AuthError> {
+  let rp = relying_party(&state)?;
+  let challenge = new_challenge(&state, KIND_REGISTRATION, Some(&user.uuid)).await?;
+
+  lazy_static! {
+    static ref QUERY: String =
+      format!(r#"SELECT credential_id FROM "{USER_PASSKEY_TABLE}" WHERE user = $1"#);
+  }
+
+  let rows = state
+    .user_conn()
+    .query(&QUERY, params!(user.uuid.into_bytes()))
+    .await?;
+  let exclude_credentials = rows
+    .iter()
+    .map(|row| row.get::<String>(0))
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| AuthError::Internal(err.into()))?;
+
+  let rp_name = state
+    .access_config(|c| c.server.application_name.clone())
+    .unwrap_or_else(|| "TrailBase".to_string());
+
+  return Ok(Json(PasskeyRegistrationOptions {
+    challenge,
+    rp_id: rp.id,
+    rp_name,
+    user_id: webauthn::encode_b64(user.uuid.as_bytes()),
+    user_name: user.email,
+    exclude_credentials,
+    algorithms: vec![webauthn::COSE_ALG_ES256, webauthn::COSE_ALG_EDDSA],
+    timeout_ms: WEBAUTHN_CHALLENGE_TTL.num_milliseconds(),
+  }));
+}
+
+/// Completes the registration of a new passkey for the current user.
+#[utoipa::path(
+  post,
+  path = "/passkey/register/finish",
+  request_body = PasskeyRegistrationRequest,
+  responses(
+    (status = 200, description = "Passkey registered.")
+  )
+)]
+pub(crate) async fn passkey_register_finish_handler(
+  State(state): State<AppState>,
+  user: User,
+  Json(request): Json<PasskeyRegistrationRequest>,
+) -> Result<Response, AuthError> {
+  let rp = relying_party(&state)?;
+  let client_data_json = decode(&request.client_data_json)?;
+  let attestation_object = decode(&request.attestation_object)?;
+
+  let challenge = webauthn::client_data_challenge(&client_data_json)
+    .map_err(|_| AuthError::BadRequest("invalid client data"))?;
+  if !consume_challenge(&state, &challenge, KIND_REGISTRATION, Some(&user.uuid)).await? {
+    return Err(AuthError::BadRequest("invalid or expired challenge"));
+  }
+
+  let credential =
+    webauthn::verify_registration(&rp, &challenge, &client_data_json, &attestation_object)
+      .map_err(|err| {
+        log::debug!("Passkey registration failed: {err}");
+        return AuthError::BadRequest("invalid passkey registration");
+      })?;
+
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        INSERT INTO "{USER_PASSKEY_TABLE}" (user, credential_id, algorithm, public_key, sign_count, name)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT DO NOTHING
+      "#
+    );
+  }
+
+  let name = request
+    .name
+    .filter(|name| !name.trim().is_empty())
+    .unwrap_or_else(|| "Passkey".to_string());
+  let rows_affected = state
+    .user_conn()
+    .execute(
+      &QUERY,
+      params!(
+        user.uuid.into_bytes(),
+        webauthn::encode_b64(&credential.credential_id),
+        credential.algorithm,
+        credential.public_key,
+        credential.sign_count as i64,
+        name,
+      ),
+    )
+    .await?;
+  if rows_affected == 0 {
+    // Credential already registered.
+    return Err(AuthError::Conflict);
+  }
+
+  return Ok((StatusCode::OK, "Passkey registered").into_response());
+}
+
+/// Starts a passkey login.
+///
+/// No user needs to be specified, since passkeys are discoverable credentials.
+#[utoipa::path(
+  post,
+  path = "/passkey/login/start",
+  responses(
+    (status = 200, description = "Credential request options.", body = PasskeyLoginOptions)
+  )
+)]
+pub(crate) async fn passkey_login_start_handler(
+  State(state): State<AppState>,
+) -> Result<Json<PasskeyLoginOptions>, AuthError> {
+  let rp = relying_party(&state)?;
+  let challenge = new_challenge(&state, KIND_AUTHENTICATION, None).await?;
+
+  return Ok(Json(PasskeyLoginOptions {
+    challenge,
+    rp_id: rp.id,
+    timeout_ms: WEBAUTHN_CHALLENGE_TTL.num_milliseconds(),
+  }));
+}
+
+/// Completes a passkey login.
+///
+/// Passkeys verifying the user, e.g. by biometrics or PIN, count as multiple factors. Otherwise,
+/// users with MFA enabled are challenged for their second factor.
+#[utoipa::path(
+  post,
+  path = "/passkey/login/finish",
+  params(LoginQuery),
+  request_body = PasskeyLoginRequest,
+  responses(
+    (status = 200, description = "Auth & refresh tokens or MFA challenge.", body = LoginResponse)
+  )
+)]
+pub(crate) async fn passkey_login_finish_handler(
+  State(state): State<AppState>,
+  Query(query): Query<LoginQuery>,
+  cookies: Cookies,
+  either_request: Either<PasskeyLoginRequest>,
+) -> Result<Response, AuthError> {
+  let (request, json) = match either_request {
+    Either::Json(req) => (req, true),
+    Either::Form(req) => (req, false),
+    Either::Multipart(req, _) => (req, false),
+  };
+
+  let options = LoginResponseOptions {
+    json,
+    redirect: validate_redirects(&state, &query.redirect_to, &request.redirect_to)?,
+    response_type: request.response_type,
+    pkce_code_challenge: request.pkce_code_challenge,
+  };
+
+  let rp = relying_party(&state)?;
+  let client_data_json = decode(&request.client_data_json)?;
+  let authenticator_data = decode(&request.authenticator_data)?;
+  let signature = decode(&request.signature)?;
+  let credential_id = webauthn::encode_b64(&decode(&request.credential_id)?);
+
+  let challenge = webauthn::client_data_challenge(&client_data_json)
+    .map_err(|_| AuthError::BadRequest("invalid client data"))?;
+  if !consume_challenge(&state, &challenge, KIND_AUTHENTICATION, None).await? {
+    return Err(AuthError::BadRequest("invalid or expired challenge"));
+  }
+
+  lazy_static! {
+    static ref SELECT_QUERY: String = format!(
+      r#"
+        SELECT user, algorithm, public_key, sign_count FROM "{USER_PASSKEY_TABLE}"
+        WHERE credential_id = $1
+      "#
+    );
+    static ref UPDATE_QUERY: String = format!(
+      r#"
+        UPDATE "{USER_PASSKEY_TABLE}" SET sign_count = $1, last_used = UNIXEPOCH()
+        WHERE credential_id = $2 AND sign_count = $3
+      "#
+    );
+  }
+
+  let Some(passkey) = state
+    .user_conn()
+    .query_value::<PasskeyRow>(&SELECT_QUERY, params!(credential_id.clone()))
+    .await?
+  else {
+    return Err(AuthError::Unauthorized);
+  };
+
+  if let Some(user_handle) = request.user_handle.filter(|h| !h.is_empty()) {
+    if decode(&user_handle)? != passkey.user {
+      return Err(AuthError::Unauthorized);
+    }
+  }
+
+  let assertion = webauthn::verify_assertion(
+    &rp,
+    &challenge,
+    &CredentialKey {
+      algorithm: passkey.algorithm,
+      public_key: passkey.public_key,
+      sign_count: passkey.sign_count as u32,
+    },
+    &client_data_json,
+    &authenticator_data,
+    &signature,
+  )
+  .map_err(|err| {
+    log::debug!("Passkey assertion failed: {err}");
+    return AuthError::Unauthorized;
+  })?;
+
+  // Guards against concurrent logins with the same counter value.
+  let rows_affected = state
+    .user_conn()
+    .execute(
+      &UPDATE_QUERY,
+      params!(
+        assertion.sign_count as i64,
+        credential_id,
+        passkey.sign_count
+      ),
+    )
+    .await?;
+  if rows_affected != 1 {
+    return Err(AuthError::Unauthorized);
+  }
+
+  let db_user = user_by_id(&state, &Uuid::from_bytes(passkey.user)).await?;
+  if !db_user.verified {
+    return Err(AuthError::Unauthorized);
+  }
+
+  if assertion.user_verified {
+    return respond_with_login(&state, &cookies, db_user, options).await;
+  }
+  return respond_with_login_or_mfa_challenge(&state, &cookies, db_user, options).await;
+}
+
+/// Lists the current user's passkeys.
+#[utoipa::path(
+  get,
+  path = "/passkeys",
+  responses(
+    (status = 200, description = "Registered passkeys.", body = Vec<Passkey>)
+  )
+)]
+pub(crate) async fn list_passkeys_handler(
+  State(state): State<AppState>,
+  user: User,
+) -> Result<Json<Vec<Passkey>>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        SELECT id, name, created, last_used FROM "{USER_PASSKEY_TABLE}"
+        WHERE user = $1 ORDER BY id
+      "#
+    );
+  }
+
+  let passkeys = state
+    .user_conn()
+    .query_values::<Passkey>(&QUERY, params!(user.uuid.into_bytes()))
+    .await?;
+
+  return Ok(Json(passkeys));
+}
+
+/// Deletes one of the current user's passkeys.
+#[utoipa::path(
+  delete,
+  path = "/passkeys/:id",
+  responses(
+    (status = 200, description = "Passkey deleted.")
+  )
+)]
+pub(crate) async fn delete_passkey_handler(
+  State(state): State<AppState>,
+  Path(id): Path<i64>,
+  user: User,
+) -> Result<Response, AuthError> {
+  lazy_static! {
+    static ref QUERY: String =
+      format!(r#"DELETE FROM "{USER_PASSKEY_TABLE}" WHERE id = $1 AND user = $2"#);
+  }
+
+  let rows_affected = state
+    .user_conn()
+    .execute(&QUERY, params!(id, user.uuid.into_bytes()))
+    .await?;
+  if rows_affected == 0 {
+    return Err(AuthError::NotFound);
+  }
+
+  return Ok((StatusCode::OK, "Passkey deleted").into_response());
+}
+
+fn relying_party(state: &AppState) -> Result<RelyingParty, AuthError> {
+  return RelyingParty::from_site_url(&state.site_url(), state.dev_mode())
+    .map_err(|err| AuthError::Internal(err.into()));
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AuthError> {
+  return webauthn::decode_b64(value).map_err(|_| AuthError::BadRequest("invalid base64"));
+}
+
+/// Stores a new challenge and returns it. Expired challenges are cleaned up along the way.
+async fn new_challenge(
+  state: &AppState,
+  kind: &'static str,
+  user: Option<&Uuid>,
+) -> Result<String, AuthError> {
+  lazy_static! {
+    static ref DELETE_EXPIRED_QUERY: String =
+      format!(r#"DELETE FROM "{WEBAUTHN_CHALLENGE_TABLE}" WHERE created < (UNIXEPOCH() - $1)"#);
+    static ref INSERT_QUERY: String = format!(
+      r#"INSERT INTO "{WEBAUTHN_CHALLENGE_TABLE}" (challenge, user, kind) VALUES ($1, $2, $3)"#
+    );
+  }
+
+  let conn = state.user_conn();
+  conn
+    .execute(
+      &DELETE_EXPIRED_QUERY,
+      params!(WEBAUTHN_CHALLENGE_TTL.num_seconds()),
+    )
+    .await?;
+
+  let challenge = webauthn::generate_challenge();
+  conn
+    .execute(
+      &INSERT_QUERY,
+      params!(
+        challenge.clone(),
+        user.map(|id| id.into_bytes()),
+        kind.to_string()
+      ),
+    )
+    .await?;
+
+  return Ok(challenge);
+}
+
+/// Consumes a pending challenge. Returns false if it doesn't exist or has expired.
+async fn consume_challenge(
+  state: &AppState,
+  challenge: &str,
+  kind: &'static str,
+  user: Option<&Uuid>,
+) -> Result<bool, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        DELETE FROM "{WEBAUTHN_CHALLENGE_TABLE}"
+        WHERE challenge = $1 AND kind = $2 AND user IS $3 AND created > (UNIXEPOCH() - $4)
+      "#
+    );
+  }
+
+  let rows_affected = state
+    .user_conn()
+    .execute(
+      &QUERY,
+      params!(
+        challenge.to_string(),
+        kind.to_string(),
+        user.map(|id| id.into_bytes()),
+        WEBAUTHN_CHALLENGE_TTL.num_seconds()
+      ),
+    )
+    .await?;
+
+  return Ok(rows_affected > 0);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::admin::user::create_user_for_test;
+  use crate::app_state::test_state;
+  use crate::auth::api::login::login_with_password;
+  use crate::auth::webauthn::testing::SoftAuthenticator;
+
+  #[tokio::test]
+  async fn test_passkey_registration_and_login() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "passkey@test.org";
+    let password = "Secret!1!!";
+    create_user_for_test(&state, email, password).await.unwrap();
+    let tokens = login_with_password(&state, email, password).await.unwrap();
+    let user = User::from_auth_token(&state, &tokens.auth_token).unwrap();
+
+    let rp = relying_party(&state).unwrap();
+    let mut authenticator = SoftAuthenticator::new();
+
+    // Register.
+    let Json(options) = passkey_register_start_handler(State(state.clone()), user.clone())
+      .await
+      .unwrap();
+    assert_eq!(rp.id, options.rp_id);
+    assert!(options.exclude_credentials.is_empty());
+
+    let (client_data_json, attestation_object) = authenticator.register(&rp, &options.challenge);
+    let register = || {
+      passkey_register_finish_handler(
+        State(state.clone()),
+        user.clone(),
+        Json(PasskeyRegistrationRequest {
+          client_data_json: webauthn::encode_b64(&client_data_json),
+          attestation_object: webauthn::encode_b64(&attestation_object),
+          name: Some("Laptop".to_string()),
+        }),
+      )
+    };
+    register().await.unwrap();
+    // Challenges are single use.
+    assert!(register().await.is_err());
+
+    let Json(passkeys) = list_passkeys_handler(State(state.clone()), user.clone())
+      .await
+      .unwrap();
+    assert_eq!(1, passkeys.len());
+    assert_eq!("Laptop", passkeys[0].name);
+
+    // Log in.
+    let login = |authenticator: &mut SoftAuthenticator, challenge: &str| {
+      let (client_data_json, authenticator_data, signature) = authenticator.assert(&rp, challenge);
+      return passkey_login_finish_handler(
+        State(state.clone()),
+        Query(LoginQuery::default()),
+        Cookies::default(),
+        Either::Json(PasskeyLoginRequest {
+          credential_id: webauthn::encode_b64(&authenticator.credential_id),
+          client_data_json: webauthn::encode_b64(&client_data_json),
+          authenticator_data: webauthn::encode_b64(&authenticator_data),
+          signature: webauthn::encode_b64(&signature),
+          user_handle: Some(webauthn::encode_b64(user.uuid.as_bytes())),
+          ..Default::default()
+        }),
+      );
+    };
+
+    let Json(options) = passkey_login_start_handler(State(state.clone()))
+      .await
+      .unwrap();
+    let response = login(&mut authenticator, &options.challenge).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    let tokens: LoginResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+      user.uuid,
+      User::from_auth_token(&state, &tokens.auth_token)
+        .unwrap()
+        .uuid
+    );
+
+    // Replaying the challenge fails.
+    assert!(login(&mut authenticator, &options.challenge).await.is_err());
+
+    // Unknown credentials fail.
+    let Json(options) = passkey_login_start_handler(State(state.clone()))
+      .await
+      .unwrap();
+    assert!(login(&mut SoftAuthenticator::new(), &options.challenge)
+      .await
+      .is_err());
+
+    // Deleted passkeys can no longer be used.
+    delete_passkey_handler(State(state.clone()), Path(passkeys[0].id), user.clone())
+      .await
+      .unwrap();
+    let Json(options) = passkey_login_start_handler(State(state.clone()))
+      .await
+      .unwrap();
+    assert!(login(&mut authenticator, &options.challenge).await.is_err());
+  }
+}
@@ -13,10 +13,11 @@ use uuid::Uuid;
 use crate::app_state::AppState;
 use crate::constants::{PASSWORD_OPTIONS, USER_TABLE};
 use crate::email::Email;
-use crate::extract::Either;
+use crate::extract::{ClientIp, Either};
 use crate::rand::generate_random_string;
 
 use crate::auth::api::register::validate_and_normalize_email_address;
+use crate::auth::lockout;
 use crate::auth::password::{hash_password, validate_passwords};
 use crate::auth::util::user_by_email;
 use crate::auth::AuthError;
@@ -40,6 +41,7 @@ pub struct ResetPasswordRequest {
 )]
 pub async fn reset_password_request_handler(
   State(state): State<AppState>,
+  ClientIp(ip): ClientIp,
   either_request: Either<ResetPasswordRequest>,
 ) -> Result<Response, AuthError> {
   let request = match either_request {
@@ -50,7 +52,15 @@ pub async fn reset_password_request_handler(
 
   let normalized_email = validate_and_normalize_email_address(&request.email)?;
 
-  let user = user_by_email(&state, &normalized_email).await?;
+  // Probing for registered addresses counts towards the IP's lockout.
+  lockout::check_lockout(&state, None, ip).await?;
+  let user = match user_by_email(&state, &normalized_email).await {
+    Ok(user) => user,
+    Err(err) => {
+      lockout::count_login_attempt(&state, None, ip).await?;
+      return Err(err);
+    }
+  };
 
   if let Some(last_reset) = user.password_reset_code_sent_at {
     let Some(timestamp) = chrono::DateTime::from_timestamp(last_reset, 0) else {
@@ -23,7 +23,7 @@ use crate::auth::api::verify_email::{verify_email_handler, VerifyEmailQuery};
 use crate::auth::user::{DbUser, User};
 use crate::constants::*;
 use crate::email::{testing::TestAsyncSmtpTransport, Mailer};
-use crate::extract::Either;
+use crate::extract::{ClientIp, Either};
 use crate::util::query_one_row;
 
 #[tokio::test]
@@ -190,6 +190,7 @@ async fn test_auth_registration_reset_and_change_email() {
     // Reset (forgotten) password flow.
     reset_password_request_handler(
       State(state.clone()),
+      ClientIp::default(),
       Either::Form(ResetPasswordRequest {
         email: email.clone(),
       }),
@@ -203,6 +204,7 @@ async fn test_auth_registration_reset_and_change_email() {
     // Test rate limiting.
     assert!(reset_password_request_handler(
       State(state.clone()),
+      ClientIp::default(),
       Either::Json(ResetPasswordRequest {
         email: email.clone()
       }),
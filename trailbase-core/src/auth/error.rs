@@ -1,5 +1,8 @@
 use axum::body::Body;
-use axum::http::{header::CONTENT_TYPE, StatusCode};
+use axum::http::{
+  header::{CONTENT_TYPE, RETRY_AFTER},
+  StatusCode,
+};
 use axum::response::{IntoResponse, Response};
 use log::*;
 use thiserror::Error;
@@ -20,6 +23,9 @@ pub enum AuthError {
   OAuthProviderNotFound,
   #[error("Bad request: {0}")]
   BadRequest(&'static str),
+  /// Too many failed attempts or requests. Holds the number of seconds after which to retry.
+  #[error("Too many requests")]
+  TooManyRequests(i64),
   #[error("Failed dependency: {0}")]
   FailedDependency(Box<dyn std::error::Error + Send + Sync>),
   #[error("Internal: {0}")]
@@ -75,6 +81,13 @@ impl IntoResponse for AuthError {
       Self::NotFound => (StatusCode::NOT_FOUND, None),
       Self::OAuthProviderNotFound => (StatusCode::METHOD_NOT_ALLOWED, None),
       Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, Some(msg.to_string())),
+      Self::TooManyRequests(retry_after) => {
+        return Response::builder()
+          .status(StatusCode::TOO_MANY_REQUESTS)
+          .header(RETRY_AFTER, retry_after.max(1).to_string())
+          .body(Body::empty())
+          .unwrap();
+      }
       Self::FailedDependency(msg) => (StatusCode::FAILED_DEPENDENCY, Some(msg.to_string())),
       Self::Internal(err) if cfg!(debug_assertions) => {
         (StatusCode::INTERNAL_SERVER_ERROR, Some(err.to_string()))
@@ -1,11 +1,14 @@
 use crate::rand::generate_random_string;
 use crate::util::uuid_to_b64;
+use base64::prelude::*;
 use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
-use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
 use ed25519_dalek::{SigningKey, VerifyingKey};
-use jsonwebtoken::{errors::Error as JwtError, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind as JwtErrorKind};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 use rand::rngs::OsRng;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tokio::{
@@ -42,6 +45,14 @@ pub struct TokenClaims {
   /// CSRF random token. Requiring that the client echos this random token back on a non-cookie,
   /// non-auto-attach channel can be used to protect from CSRF.
   pub csrf_token: String,
+
+  /// Effective roles of [sub] at the time the token was minted.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub roles: Vec<String>,
+
+  /// Custom claims derived from [sub]'s metadata according to the configured claims mapping.
+  #[serde(flatten)]
+  pub custom_claims: serde_json::Map<String, serde_json::Value>,
 }
 
 impl TokenClaims {
@@ -60,33 +71,87 @@ impl TokenClaims {
       iat: now.timestamp(),
       email,
       csrf_token: generate_random_string(20),
+      roles: vec![],
+      custom_claims: serde_json::Map::new(),
     };
   }
 }
 
+/// Public key in JSON Web Key format, see RFC 8037.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Jwk {
+  pub kty: String,
+  pub crv: String,
+  pub alg: String,
+  #[serde(rename = "use")]
+  pub use_: String,
+  pub kid: String,
+  /// Url-safe Base64 encoded public key.
+  pub x: String,
+}
+
+/// JSON Web Key Set, i.e. the public keys other parties can use to verify tokens.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Jwks {
+  pub keys: Vec<Jwk>,
+}
+
+struct VerificationKey {
+  kid: String,
+  verifying_key: VerifyingKey,
+  decoding_key: DecodingKey,
+}
+
+impl VerificationKey {
+  fn from_pem(public_key: &[u8]) -> Result<Self, JwtHelperError> {
+    let verifying_key = VerifyingKey::from_public_key_pem(&String::from_utf8_lossy(public_key))?;
+    return Ok(VerificationKey {
+      kid: key_id(&verifying_key),
+      verifying_key,
+      decoding_key: DecodingKey::from_ed_pem(public_key)?,
+    });
+  }
+}
+
 pub struct JwtHelper {
+  // Carries the id of the signing key.
   header: Header,
   validation: Validation,
 
   // The private key used for minting new JWTs.
   encoding_key: EncodingKey,
 
-  // The public key used for validating provided JWTs.
-  decoding_key: DecodingKey,
+  // The public keys used for validating provided JWTs. The first key belongs to the current
+  // signing key followed by retired keys, which remain valid until tokens they signed expire.
+  verification_keys: Vec<VerificationKey>,
   public_key: String,
 }
 
 impl JwtHelper {
   pub fn new(private_key: Vec<u8>, public_key: Vec<u8>) -> Result<Self, JwtHelperError> {
+    let verification_key = VerificationKey::from_pem(&public_key)?;
+
+    let mut header = Header::new(jsonwebtoken::Algorithm::EdDSA);
+    header.kid = Some(verification_key.kid.clone());
+
     return Ok(JwtHelper {
-      header: Header::new(jsonwebtoken::Algorithm::EdDSA),
+      header,
       validation: Validation::new(jsonwebtoken::Algorithm::EdDSA),
       encoding_key: EncodingKey::from_ed_pem(&private_key)?,
-      decoding_key: DecodingKey::from_ed_pem(&public_key)?,
+      verification_keys: vec![verification_key],
       public_key: String::from_utf8_lossy(&public_key).to_string(),
     });
   }
 
+  /// Adds a retired public key, i.e. one that is only used to validate previously minted tokens.
+  fn add_retired_key(&mut self, public_key: &[u8]) -> Result<(), JwtHelperError> {
+    let key = VerificationKey::from_pem(public_key)?;
+    if !self.verification_keys.iter().any(|k| k.kid == key.kid) {
+      self.verification_keys.push(key);
+    }
+    return Ok(());
+  }
+
   pub async fn init_from_path(data_dir: &DataDir) -> Result<Self, JwtHelperError> {
     let key_path = data_dir.key_path();
 
@@ -98,10 +163,22 @@ impl JwtHelper {
     }
 
     let (private_key, public_key) = match open_key_files(&key_path).await {
-      Ok((priv_key_file, pub_key_file)) => (
-        read_file(priv_key_file).await?,
-        read_file(pub_key_file).await?,
-      ),
+      Ok((priv_key_file, pub_key_file)) => {
+        let private_key = read_file(priv_key_file).await?;
+        let mut public_key = read_file(pub_key_file).await?;
+
+        // Repair key pairs torn by an interrupted rotation.
+        let derived_public_key = public_key_from_private_key(&private_key)?;
+        if VerificationKey::from_pem(&public_key)?.kid
+          != VerificationKey::from_pem(&derived_public_key)?.kid
+        {
+          log::warn!("Public key doesn't match private key. Re-deriving public key.");
+          write_new_file(key_path.join(PUBLIC_KEY_FILE), &derived_public_key).await?;
+          public_key = derived_public_key;
+        }
+
+        (private_key, public_key)
+      }
       Err(err) => match err.kind() {
         std::io::ErrorKind::NotFound => write_new_pem_keys(&key_path).await?,
         _ => {
@@ -110,17 +187,64 @@ impl JwtHelper {
       },
     };
 
-    return Self::new(private_key, public_key);
+    let mut helper = Self::new(private_key, public_key)?;
+    for (_retired_at, path) in list_retired_keys(&key_path).await? {
+      helper.add_retired_key(&fs::read(path).await?)?;
+    }
+
+    return Ok(helper);
   }
 
+  /// The PEM encoded public key of the current signing key.
   pub fn public_key(&self) -> String {
     return self.public_key.clone();
   }
 
+  /// Ids of all keys valid for validation, the current signing key first.
+  pub fn key_ids(&self) -> Vec<String> {
+    return self
+      .verification_keys
+      .iter()
+      .map(|k| k.kid.clone())
+      .collect();
+  }
+
+  pub fn jwks(&self) -> Jwks {
+    return Jwks {
+      keys: self
+        .verification_keys
+        .iter()
+        .map(|k| Jwk {
+          kty: "OKP".to_string(),
+          crv: "Ed25519".to_string(),
+          alg: "EdDSA".to_string(),
+          use_: "sig".to_string(),
+          kid: k.kid.clone(),
+          x: BASE64_URL_SAFE_NO_PAD.encode(k.verifying_key.as_bytes()),
+        })
+        .collect(),
+    };
+  }
+
   pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, JwtError> {
-    // Note: we don't need to expose the token headers.
-    return jsonwebtoken::decode::<T>(token, &self.decoding_key, &self.validation)
-      .map(|data| data.claims);
+    let kid = jsonwebtoken::decode_header(token)?.kid;
+
+    // Tokens minted before the introduction of key ids carry none, in which case we try all keys.
+    let mut result = Err(JwtError::from(JwtErrorKind::InvalidSignature));
+    for key in &self.verification_keys {
+      if kid.as_ref().is_some_and(|kid| *kid != key.kid) {
+        continue;
+      }
+
+      // Note: we don't need to expose the token headers.
+      result = jsonwebtoken::decode::<T>(token, &key.decoding_key, &self.validation)
+        .map(|data| data.claims);
+      if result.is_ok() {
+        break;
+      }
+    }
+
+    return result;
   }
 
   pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
@@ -128,6 +252,96 @@ impl JwtHelper {
   }
 }
 
+/// Rotates the signing key on disk.
+///
+/// The previous public key is retained to validate tokens signed before the rotation until they
+/// expire. Retired keys older than `prune_after` are removed. Returns the id of the new key.
+///
+/// NOTE: Keys are only loaded on startup, i.e. a running server keeps signing with the previous
+/// key until restarted.
+pub async fn rotate_keys(
+  data_dir: &DataDir,
+  prune_after: chrono::Duration,
+) -> Result<String, JwtHelperError> {
+  let key_path = data_dir.key_path();
+  let now = chrono::Utc::now().timestamp();
+
+  // Write the new pair next to the current one first, s.t. a failure leaves the current key in
+  // place.
+  let (private_key, public_key) = new_pem_keys()?;
+  let tmp_suffix = format!(".{}.tmp", generate_random_string(8));
+  let tmp_private_key_path = key_path.join(format!("{PRIVATE_KEY_FILE}{tmp_suffix}"));
+  let tmp_public_key_path = key_path.join(format!("{PUBLIC_KEY_FILE}{tmp_suffix}"));
+  write_new_file(tmp_private_key_path.clone(), &private_key).await?;
+  write_new_file(tmp_public_key_path.clone(), &public_key).await?;
+
+  // Retain the current public key. Copying rather than moving it means there's always a key pair
+  // in place.
+  let public_key_path = key_path.join(PUBLIC_KEY_FILE);
+  if fs::try_exists(&public_key_path).await? {
+    fs::copy(&public_key_path, key_path.join(retired_key_file(now))).await?;
+  }
+
+  // NOTE: The two renames aren't atomic together. Should we fail in between, the public key is
+  // re-derived from the private key on startup, see `init_from_path`.
+  fs::rename(&tmp_private_key_path, key_path.join(PRIVATE_KEY_FILE)).await?;
+  fs::rename(&tmp_public_key_path, &public_key_path).await?;
+
+  for (retired_at, path) in list_retired_keys(&key_path).await? {
+    if retired_at < now - prune_after.num_seconds() {
+      fs::remove_file(path).await?;
+    }
+  }
+
+  return Ok(VerificationKey::from_pem(&public_key)?.kid);
+}
+
+/// Key id, i.e. the JWK thumbprint of the public key as defined in RFC 7638.
+fn key_id(verifying_key: &VerifyingKey) -> String {
+  // Members in lexicographic order w/o whitespace.
+  let canonical_jwk = format!(
+    r#"{{"crv":"Ed25519","kty":"OKP","x":"{x}"}}"#,
+    x = BASE64_URL_SAFE_NO_PAD.encode(verifying_key.as_bytes())
+  );
+  return BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(canonical_jwk.as_bytes()));
+}
+
+/// Name of a retired key file. The random suffix keeps rotations within the same second apart.
+fn retired_key_file(retired_at: i64) -> String {
+  return format!(
+    "{RETIRED_PUBLIC_KEY_PREFIX}{retired_at}.{suffix}.pem",
+    suffix = generate_random_string(8)
+  );
+}
+
+/// Lists retired public keys and when they were retired, most recent first.
+async fn list_retired_keys(key_path: &Path) -> std::io::Result<Vec<(i64, PathBuf)>> {
+  let mut keys = vec![];
+
+  let mut entries = fs::read_dir(key_path).await?;
+  while let Some(entry) = entries.next_entry().await? {
+    let file_name = entry.file_name();
+    let Some(retired_at) = file_name
+      .to_str()
+      .and_then(|name| name.strip_prefix(RETIRED_PUBLIC_KEY_PREFIX))
+      .and_then(|name| name.strip_suffix(".pem"))
+      .and_then(|name| {
+        let timestamp = name
+          .split_once('.')
+          .map_or(name, |(timestamp, _suffix)| timestamp);
+        return timestamp.parse::<i64>().ok();
+      })
+    else {
+      continue;
+    };
+
+    keys.push((retired_at, entry.path()));
+  }
+
+  keys.sort_by(|a, b| b.0.cmp(&a.0));
+  return Ok(keys);
+}
+
 fn generate_new_key_pair() -> (SigningKey, VerifyingKey) {
   let mut csprng = OsRng {};
   let signing_key = SigningKey::generate(&mut csprng);
@@ -136,13 +350,30 @@ fn generate_new_key_pair() -> (SigningKey, VerifyingKey) {
   return (signing_key, verifying_key);
 }
 
-async fn write_new_pem_keys(key_path: &Path) -> Result<(Vec<u8>, Vec<u8>), JwtHelperError> {
+fn new_pem_keys() -> Result<(Vec<u8>, Vec<u8>), JwtHelperError> {
   let (signing_key, verifying_key) = generate_new_key_pair();
 
   let le = LineEnding::default();
   let priv_key = signing_key.to_pkcs8_pem(le)?.as_bytes().to_vec();
   let pub_key = verifying_key.to_public_key_pem(le)?.into_bytes();
 
+  return Ok((priv_key, pub_key));
+}
+
+/// PEM encoded public key belonging to the given PEM encoded private key.
+fn public_key_from_private_key(private_key: &[u8]) -> Result<Vec<u8>, JwtHelperError> {
+  let signing_key = SigningKey::from_pkcs8_pem(&String::from_utf8_lossy(private_key))?;
+  return Ok(
+    signing_key
+      .verifying_key()
+      .to_public_key_pem(LineEnding::default())?
+      .into_bytes(),
+  );
+}
+
+async fn write_new_pem_keys(key_path: &Path) -> Result<(Vec<u8>, Vec<u8>), JwtHelperError> {
+  let (priv_key, pub_key) = new_pem_keys()?;
+
   write_new_file(key_path.join(PRIVATE_KEY_FILE), &priv_key).await?;
   write_new_file(key_path.join(PUBLIC_KEY_FILE), &pub_key).await?;
 
@@ -197,7 +428,111 @@ mod tests {
 
     assert_eq!(claims, jwt.decode(&token).unwrap());
   }
+
+  #[tokio::test]
+  async fn test_key_rotation() {
+    let temp_dir = temp_dir::TempDir::new().unwrap();
+    let data_dir = DataDir(temp_dir.path().to_path_buf());
+    fs::create_dir_all(data_dir.key_path()).await.unwrap();
+
+    let claims = TokenClaims::new(
+      true,
+      uuid::Uuid::now_v7(),
+      "foo@bar.com".to_string(),
+      crate::constants::DEFAULT_AUTH_TOKEN_TTL,
+    );
+
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    let old_kid = jwt.key_ids()[0].clone();
+    let old_token = jwt.encode(&claims).unwrap();
+    assert_eq!(
+      Some(old_kid.clone()),
+      jsonwebtoken::decode_header(&old_token).unwrap().kid
+    );
+
+    let new_kid = rotate_keys(&data_dir, chrono::Duration::days(1))
+      .await
+      .unwrap();
+    assert_ne!(old_kid, new_kid);
+
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    assert_eq!(vec![new_kid.clone(), old_kid.clone()], jwt.key_ids());
+
+    // Previously minted tokens remain valid, while new ones are signed with the new key.
+    assert_eq!(claims, jwt.decode::<TokenClaims>(&old_token).unwrap());
+    let new_token = jwt.encode(&claims).unwrap();
+    assert_eq!(
+      Some(new_kid.clone()),
+      jsonwebtoken::decode_header(&new_token).unwrap().kid
+    );
+    assert_eq!(claims, jwt.decode::<TokenClaims>(&new_token).unwrap());
+
+    let jwks = jwt.jwks();
+    assert_eq!(2, jwks.keys.len());
+    assert_eq!(new_kid, jwks.keys[0].kid);
+
+    // A negative retention prunes all retired keys including the one just retired.
+    let newest_kid = rotate_keys(&data_dir, chrono::Duration::seconds(-1))
+      .await
+      .unwrap();
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    assert_eq!(vec![newest_kid.clone()], jwt.key_ids());
+    assert!(jwt.decode::<TokenClaims>(&old_token).is_err());
+    assert!(jwt.decode::<TokenClaims>(&new_token).is_err());
+
+    // Rotations within the same second retain all keys.
+    let kids = [
+      rotate_keys(&data_dir, chrono::Duration::days(1))
+        .await
+        .unwrap(),
+      rotate_keys(&data_dir, chrono::Duration::days(1))
+        .await
+        .unwrap(),
+    ];
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    assert_eq!(3, jwt.key_ids().len());
+    assert_eq!(kids[1], jwt.key_ids()[0]);
+    assert!(jwt.key_ids().contains(&kids[0]));
+    assert!(jwt.key_ids().contains(&newest_kid));
+  }
+
+  #[tokio::test]
+  async fn test_torn_key_pair_repair() {
+    let temp_dir = temp_dir::TempDir::new().unwrap();
+    let data_dir = DataDir(temp_dir.path().to_path_buf());
+    let key_path = data_dir.key_path();
+    fs::create_dir_all(&key_path).await.unwrap();
+
+    let kid = JwtHelper::init_from_path(&data_dir)
+      .await
+      .unwrap()
+      .key_ids()[0]
+      .clone();
+
+    // Simulate a rotation interrupted after replacing the private but not the public key.
+    let (private_key, _public_key) = new_pem_keys().unwrap();
+    fs::write(key_path.join(PRIVATE_KEY_FILE), &private_key)
+      .await
+      .unwrap();
+
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    assert_ne!(kid, jwt.key_ids()[0]);
+
+    let claims = TokenClaims::new(
+      true,
+      uuid::Uuid::now_v7(),
+      "foo@bar.com".to_string(),
+      crate::constants::DEFAULT_AUTH_TOKEN_TTL,
+    );
+    let token = jwt.encode(&claims).unwrap();
+    assert_eq!(claims, jwt.decode::<TokenClaims>(&token).unwrap());
+
+    // The repaired public key was persisted.
+    let jwt = JwtHelper::init_from_path(&data_dir).await.unwrap();
+    assert_eq!(claims, jwt.decode::<TokenClaims>(&token).unwrap());
+  }
 }
 
 const PRIVATE_KEY_FILE: &str = "private_key.pem";
 const PUBLIC_KEY_FILE: &str = "public_key.pem";
+const RETIRED_PUBLIC_KEY_PREFIX: &str = "public_key.retired.";
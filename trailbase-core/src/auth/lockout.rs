@@ -0,0 +1,533 @@
+//! Brute-force protection for logins.
+//!
+//! Failed logins are counted per account and per client IP. Once half of the respective limit is
+//! reached, further attempts are subject to an exponentially growing delay, and once the limit is
+//! reached, attempts are rejected altogether until the lockout expires.
+
+use lazy_static::lazy_static;
+use std::net::IpAddr;
+use trailbase_sqlite::params;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::auth::AuthError;
+use crate::constants::{LOGIN_FAILURE_TABLE, USER_TABLE};
+
+/// Upper bound for the progressive delay between attempts.
+const MAX_DELAY_SEC: i64 = 60;
+
+/// Failed login state of either an account or an IP address.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Failures {
+  attempts: i64,
+  last_failed: Option<i64>,
+  locked_until: Option<i64>,
+}
+
+impl Failures {
+  /// Returns the seconds until the next attempt is permitted or `None` if permitted right away.
+  fn retry_after(&self, now: i64, max_attempts: i64, lockout_duration: i64) -> Option<i64> {
+    if let Some(locked_until) = self.locked_until {
+      if locked_until > now {
+        return Some(locked_until - now);
+      }
+    }
+
+    let Some(last_failed) = self.last_failed else {
+      return None;
+    };
+    if max_attempts <= 0 || last_failed <= now - lockout_duration {
+      return None;
+    }
+
+    let delay = progressive_delay(self.attempts, max_attempts);
+    if last_failed + delay > now {
+      return Some(last_failed + delay - now);
+    }
+    return None;
+  }
+}
+
+/// Delay in seconds required after `attempts` consecutive failures.
+fn progressive_delay(attempts: i64, max_attempts: i64) -> i64 {
+  let threshold = (max_attempts / 2).max(1);
+  if attempts < threshold {
+    return 0;
+  }
+  return 1_i64
+    .checked_shl((attempts - threshold) as u32)
+    .unwrap_or(MAX_DELAY_SEC)
+    .min(MAX_DELAY_SEC);
+}
+
+/// Rejects login attempts for locked accounts or IP addresses.
+pub(crate) async fn check_lockout(
+  state: &AppState,
+  normalized_email: Option<&str>,
+  ip: Option<IpAddr>,
+) -> Result<(), AuthError> {
+  let (max_attempts, max_attempts_per_ip, lockout_duration) =
+    state.access_config(|c| c.auth.lockout_options());
+  let now = chrono::Utc::now().timestamp();
+
+  if let Some(email) = normalized_email {
+    let failures = user_failures(state, email).await?.unwrap_or_default();
+    if let Some(retry_after) =
+      failures.retry_after(now, max_attempts, lockout_duration.num_seconds())
+    {
+      return Err(AuthError::TooManyRequests(retry_after));
+    }
+  }
+
+  if let Some(ip) = ip {
+    let failures = ip_failures(state, &ip).await?.unwrap_or_default();
+    if let Some(retry_after) =
+      failures.retry_after(now, max_attempts_per_ip, lockout_duration.num_seconds())
+    {
+      return Err(AuthError::TooManyRequests(retry_after));
+    }
+  }
+
+  return Ok(());
+}
+
+/// Counts a login attempt as failed against the account, if it exists, and the IP address.
+///
+/// Attempts are counted atomically and *before* credentials are checked, thus concurrent attempts
+/// cannot slip past the limit. Attempts exceeding either limit are rejected. Successful logins
+/// are expected to undo the count via [reset_failed_logins].
+pub(crate) async fn count_login_attempt(
+  state: &AppState,
+  normalized_email: Option<&str>,
+  ip: Option<IpAddr>,
+) -> Result<(), AuthError> {
+  let (max_attempts, max_attempts_per_ip, lockout_duration) =
+    state.access_config(|c| c.auth.lockout_options());
+  let lockout_duration = lockout_duration.num_seconds();
+  let now = chrono::Utc::now().timestamp();
+
+  if let Some(email) = normalized_email.filter(|_| max_attempts > 0) {
+    lazy_static! {
+      // NOTE: Failures expire after the lockout duration. Assignments are evaluated against the
+      // old row, hence the repetition.
+      static ref UPDATE_QUERY: String = format!(
+        r#"
+          UPDATE "{USER_TABLE}" SET
+            failed_login_attempts = CASE
+              WHEN last_failed_login > $1 - $2 THEN failed_login_attempts + 1 ELSE 1 END,
+            last_failed_login = $1,
+            locked_until = CASE
+              WHEN (CASE WHEN last_failed_login > $1 - $2 THEN failed_login_attempts + 1 ELSE 1 END) >= $3
+              THEN $1 + $2 ELSE locked_until END
+          WHERE email = $4
+          RETURNING failed_login_attempts, last_failed_login, locked_until
+        "#
+      );
+    }
+
+    if let Some(failures) = failures(
+      state,
+      &UPDATE_QUERY,
+      params!(now, lockout_duration, max_attempts, email.to_string()),
+    )
+    .await?
+    {
+      if failures.attempts == max_attempts {
+        log::info!("Locked account after {max_attempts} failed logins: {email}");
+      }
+      reject_excess_attempt(&failures, now, max_attempts)?;
+    }
+  }
+
+  if let Some(ip) = ip.filter(|_| max_attempts_per_ip > 0) {
+    lazy_static! {
+      static ref DELETE_EXPIRED_QUERY: String = format!(
+        r#"
+          DELETE FROM "{LOGIN_FAILURE_TABLE}"
+          WHERE last_failed < $1 AND (locked_until IS NULL OR locked_until < $1)
+        "#
+      );
+      // NOTE: Parameters are numbered in order of their first occurrence.
+      static ref UPSERT_QUERY: String = format!(
+        r#"
+          INSERT INTO "{LOGIN_FAILURE_TABLE}" (ip, failed_attempts, last_failed, locked_until)
+          VALUES ($1, 1, $2, CASE WHEN $3 <= 1 THEN $2 + $4 ELSE NULL END)
+          ON CONFLICT (ip) DO UPDATE SET
+            failed_attempts = CASE
+              WHEN last_failed > $2 - $4 THEN failed_attempts + 1 ELSE 1 END,
+            last_failed = $2,
+            locked_until = CASE
+              WHEN (CASE WHEN last_failed > $2 - $4 THEN failed_attempts + 1 ELSE 1 END) >= $3
+              THEN $2 + $4 ELSE locked_until END
+          RETURNING failed_attempts, last_failed, locked_until
+        "#
+      );
+    }
+
+    state
+      .user_conn()
+      .execute(&DELETE_EXPIRED_QUERY, params!(now - lockout_duration))
+      .await?;
+
+    if let Some(failures) = failures(
+      state,
+      &UPSERT_QUERY,
+      params!(ip.to_string(), now, max_attempts_per_ip, lockout_duration),
+    )
+    .await?
+    {
+      reject_excess_attempt(&failures, now, max_attempts_per_ip)?;
+    }
+  }
+
+  return Ok(());
+}
+
+/// Rejects attempts beyond the limit, i.e. ones that raced past [check_lockout].
+fn reject_excess_attempt(
+  failures: &Failures,
+  now: i64,
+  max_attempts: i64,
+) -> Result<(), AuthError> {
+  if failures.attempts <= max_attempts {
+    return Ok(());
+  }
+  let retry_after = failures
+    .locked_until
+    .map_or(MAX_DELAY_SEC, |locked_until| (locked_until - now).max(1));
+  return Err(AuthError::TooManyRequests(retry_after));
+}
+
+/// Undoes the count of a successful login attempt, see [count_login_attempt], and resets the
+/// failed login counter of the account.
+///
+/// NOTE: Other failures counted against the IP are deliberately left alone, since otherwise
+/// attackers could reset them by interleaving logins to an account they control.
+pub(crate) async fn reset_failed_logins(
+  state: &AppState,
+  user_id: &Uuid,
+  ip: Option<IpAddr>,
+) -> Result<(), AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        UPDATE "{USER_TABLE}"
+        SET failed_login_attempts = 0, last_failed_login = NULL, locked_until = NULL
+        WHERE id = $1 AND failed_login_attempts > 0
+      "#
+    );
+    // Only lift a lockout caused by the successful attempt itself.
+    static ref IP_QUERY: String = format!(
+      r#"
+        UPDATE "{LOGIN_FAILURE_TABLE}" SET
+          failed_attempts = failed_attempts - 1,
+          locked_until = CASE WHEN failed_attempts - 1 < $2 THEN NULL ELSE locked_until END
+        WHERE ip = $1 AND failed_attempts > 0
+      "#
+    );
+  }
+
+  let (_, max_attempts_per_ip, _) = state.access_config(|c| c.auth.lockout_options());
+  let conn = state.user_conn();
+  conn.execute(&QUERY, params!(user_id.into_bytes())).await?;
+
+  if let Some(ip) = ip.filter(|_| max_attempts_per_ip > 0) {
+    conn
+      .execute(&IP_QUERY, params!(ip.to_string(), max_attempts_per_ip))
+      .await?;
+  }
+  return Ok(());
+}
+
+/// Clears failed logins and any lockout of the given account. Returns false if no such user
+/// exists.
+pub(crate) async fn clear_lockout(
+  user_conn: &trailbase_sqlite::Connection,
+  user_id: &Uuid,
+) -> Result<bool, trailbase_sqlite::Error> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        UPDATE "{USER_TABLE}"
+        SET failed_login_attempts = 0, last_failed_login = NULL, locked_until = NULL
+        WHERE id = $1
+      "#
+    );
+  }
+
+  let rows_affected = user_conn
+    .execute(&QUERY, params!(user_id.into_bytes()))
+    .await?;
+  return Ok(rows_affected > 0);
+}
+
+async fn user_failures(state: &AppState, email: &str) -> Result<Option<Failures>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        SELECT failed_login_attempts, last_failed_login, locked_until FROM "{USER_TABLE}"
+        WHERE email = $1
+      "#
+    );
+  }
+
+  return failures(state, &QUERY, params!(email.to_string())).await;
+}
+
+async fn ip_failures(state: &AppState, ip: &IpAddr) -> Result<Option<Failures>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        SELECT failed_attempts, last_failed, locked_until FROM "{LOGIN_FAILURE_TABLE}"
+        WHERE ip = $1
+      "#
+    );
+  }
+
+  return failures(state, &QUERY, params!(ip.to_string())).await;
+}
+
+async fn failures(
+  state: &AppState,
+  query: &str,
+  params: impl trailbase_sqlite::Params + Send + 'static,
+) -> Result<Option<Failures>, AuthError> {
+  let Some(row) = state.user_conn().query_row(query, params).await? else {
+    return Ok(None);
+  };
+
+  let get = |idx: usize| -> Result<Option<i64>, AuthError> {
+    return row
+      .get::<Option<i64>>(idx)
+      .map_err(|err| AuthError::Internal(err.into()));
+  };
+
+  return Ok(Some(Failures {
+    attempts: get(0)?.unwrap_or(0),
+    last_failed: get(1)?,
+    locked_until: get(2)?,
+  }));
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::extract::{Query, State};
+  use axum::http::{header::RETRY_AFTER, StatusCode};
+  use axum::response::IntoResponse;
+  use tower_cookies::Cookies;
+
+  use super::*;
+  use crate::admin::user::create_user_for_test;
+  use crate::app_state::test_state;
+  use crate::auth::api::login::{login_handler, LoginQuery, LoginRequest};
+  use crate::auth::util::user_by_email;
+  use crate::constants::{
+    DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS, DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP,
+  };
+  use crate::extract::{ClientIp, Either};
+
+  #[test]
+  fn test_progressive_delay_and_lockout() {
+    const MAX: i64 = 10;
+    const DURATION: i64 = 900;
+
+    assert_eq!(0, progressive_delay(1, MAX));
+    assert_eq!(0, progressive_delay(4, MAX));
+    assert_eq!(1, progressive_delay(5, MAX));
+    assert_eq!(8, progressive_delay(8, MAX));
+    assert_eq!(MAX_DELAY_SEC, progressive_delay(100, MAX));
+
+    let now = 1_000_000;
+    let failures = |attempts: i64, locked_until: Option<i64>| Failures {
+      attempts,
+      last_failed: Some(now),
+      locked_until,
+    };
+    assert_eq!(None, failures(4, None).retry_after(now, MAX, DURATION));
+
+    assert_eq!(Some(1), failures(5, None).retry_after(now, MAX, DURATION));
+    assert_eq!(None, failures(5, None).retry_after(now + 1, MAX, DURATION));
+    // Disabled.
+    assert_eq!(None, failures(5, None).retry_after(now, 0, DURATION));
+
+    let locked = failures(MAX, Some(now + DURATION));
+    assert_eq!(Some(DURATION), locked.retry_after(now, MAX, DURATION));
+    assert_eq!(None, locked.retry_after(now + DURATION, MAX, DURATION));
+
+    assert!(reject_excess_attempt(&locked, now, MAX).is_ok());
+    assert!(matches!(
+      reject_excess_attempt(&failures(MAX + 1, Some(now + DURATION)), now, MAX),
+      Err(AuthError::TooManyRequests(DURATION))
+    ));
+  }
+
+  /// Backdates failures to not trip over the progressive delay.
+  async fn backdate(state: &AppState) {
+    let conn = state.user_conn();
+    conn
+      .execute(
+        &format!("UPDATE '{USER_TABLE}' SET last_failed_login = last_failed_login - 120"),
+        (),
+      )
+      .await
+      .unwrap();
+    conn
+      .execute(
+        &format!("UPDATE '{LOGIN_FAILURE_TABLE}' SET last_failed = last_failed - 120"),
+        (),
+      )
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_account_and_ip_lockout() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "lockout@test.org";
+    let user_id = create_user_for_test(&state, email, "Secret!1!!")
+      .await
+      .unwrap();
+
+    let ip: IpAddr = "192.168.0.1".parse().unwrap();
+    let other_ip: IpAddr = "192.168.0.2".parse().unwrap();
+
+    for _ in 0..9 {
+      check_lockout(&state, Some(email), Some(ip)).await.unwrap();
+      count_login_attempt(&state, Some(email), Some(ip))
+        .await
+        .unwrap();
+      backdate(&state).await;
+    }
+    assert_eq!(
+      9,
+      user_by_email(&state, email)
+        .await
+        .unwrap()
+        .failed_login_attempts
+    );
+
+    count_login_attempt(&state, Some(email), Some(ip))
+      .await
+      .unwrap();
+    assert!(user_by_email(&state, email)
+      .await
+      .unwrap()
+      .locked_until
+      .is_some());
+    // Attempts racing past the lockout check are rejected.
+    assert!(matches!(
+      count_login_attempt(&state, Some(email), Some(other_ip)).await,
+      Err(AuthError::TooManyRequests(_))
+    ));
+
+    // Locked regardless of the IP address.
+    assert!(matches!(
+      check_lockout(&state, Some(email), Some(other_ip)).await,
+      Err(AuthError::TooManyRequests(_))
+    ));
+    // While the IP address isn't locked yet.
+    check_lockout(&state, Some("other@test.org"), Some(ip))
+      .await
+      .unwrap();
+
+    assert!(clear_lockout(state.user_conn(), &user_id).await.unwrap());
+    check_lockout(&state, Some(email), Some(other_ip))
+      .await
+      .unwrap();
+    assert_eq!(
+      0,
+      user_by_email(&state, email)
+        .await
+        .unwrap()
+        .failed_login_attempts
+    );
+
+    // Lock the IP address by failing logins for unknown users.
+    for _ in 0..DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP {
+      count_login_attempt(&state, None, Some(other_ip))
+        .await
+        .unwrap();
+      backdate(&state).await;
+    }
+    assert!(matches!(
+      check_lockout(&state, Some(email), Some(other_ip)).await,
+      Err(AuthError::TooManyRequests(_))
+    ));
+    check_lockout(&state, Some(email), Some(ip)).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_concurrent_attempts() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "concurrent@test.org";
+    create_user_for_test(&state, email, "Secret!1!!")
+      .await
+      .unwrap();
+
+    let max = DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS as i64;
+    let results = futures_util::future::join_all(
+      (0..2 * max).map(|_| count_login_attempt(&state, Some(email), None)),
+    )
+    .await;
+
+    // No increments are lost and only attempts up to the limit are admitted.
+    assert_eq!(max as usize, results.iter().filter(|r| r.is_ok()).count());
+    assert_eq!(
+      2 * max,
+      user_by_email(&state, email)
+        .await
+        .unwrap()
+        .failed_login_attempts
+    );
+  }
+
+  #[tokio::test]
+  async fn test_login_lockout() {
+    let state = test_state(None).await.unwrap();
+
+    let email = "login_lockout@test.org";
+    let password = "Secret!1!!";
+    let user_id = create_user_for_test(&state, email, password).await.unwrap();
+
+    let login = |password: &str| {
+      login_handler(
+        State(state.clone()),
+        Query(LoginQuery::default()),
+        ClientIp("10.0.0.1".parse().ok()),
+        Cookies::default(),
+        Either::Json(LoginRequest {
+          email: email.to_string(),
+          password: password.to_string(),
+          redirect_to: None,
+          response_type: None,
+          pkce_code_challenge: None,
+        }),
+      )
+    };
+
+    for _ in 0..DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS {
+      assert!(matches!(login("wrong").await, Err(AuthError::Unauthorized)));
+      backdate(&state).await;
+    }
+
+    // Even the correct password is rejected now.
+    let response = login(password).await.unwrap_err().into_response();
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    assert!(response.headers().get(RETRY_AFTER).is_some());
+
+    assert!(clear_lockout(state.user_conn(), &user_id).await.unwrap());
+    assert_eq!(StatusCode::OK, login(password).await.unwrap().status());
+
+    // Successful logins don't count towards the IP's limit.
+    let ip_failures = ip_failures(&state, &"10.0.0.1".parse().unwrap())
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(
+      DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS as i64,
+      ip_failures.attempts
+    );
+  }
+}
@@ -8,10 +8,13 @@ pub mod jwt;
 pub mod user;
 
 pub(crate) mod api;
+pub(crate) mod lockout;
 pub(crate) mod oauth;
 pub(crate) mod password;
 pub(crate) mod tokens;
+pub(crate) mod totp;
 pub(crate) mod util;
+pub(crate) mod webauthn;
 
 mod error;
 mod ui;
@@ -29,6 +32,19 @@ use crate::constants::AUTH_API_PATH;
   paths(
     api::login::login_handler,
     api::login::login_status_handler,
+    api::magic_link::magic_link_request_handler,
+    api::magic_link::magic_link_confirm_handler,
+    api::magic_link::magic_link_code_handler,
+    api::mfa::login_mfa_handler,
+    api::mfa::totp_enroll_handler,
+    api::mfa::totp_confirm_handler,
+    api::mfa::totp_disable_handler,
+    api::passkey::passkey_register_start_handler,
+    api::passkey::passkey_register_finish_handler,
+    api::passkey::passkey_login_start_handler,
+    api::passkey::passkey_login_finish_handler,
+    api::passkey::list_passkeys_handler,
+    api::passkey::delete_passkey_handler,
     api::token::auth_code_to_token_handler,
     api::logout::logout_handler,
     api::refresh::refresh_handler,
@@ -46,7 +62,19 @@ use crate::constants::AUTH_API_PATH;
   components(schemas(
     api::login::LoginRequest,
     api::login::LoginResponse,
+    api::login::LoginMfaResponse,
     api::login::LoginStatusResponse,
+    api::magic_link::MagicLinkRequest,
+    api::magic_link::MagicLinkConfirmRequest,
+    api::mfa::LoginMfaRequest,
+    api::mfa::TotpEnrollResponse,
+    api::mfa::TotpCodeRequest,
+    api::mfa::TotpConfirmResponse,
+    api::passkey::PasskeyRegistrationOptions,
+    api::passkey::PasskeyRegistrationRequest,
+    api::passkey::PasskeyLoginOptions,
+    api::passkey::PasskeyLoginRequest,
+    api::passkey::Passkey,
     api::token::TokenResponse,
     api::token::AuthCodeToTokenRequest,
     api::refresh::RefreshRequest,
@@ -65,15 +93,21 @@ pub(super) struct AuthAPI;
 pub(super) fn router() -> Router<crate::AppState> {
   // We support the following authentication flows:
   //
-  //  * unauthed: register, login, get-avatar-url
+  //  * unauthed: register, login, login-mfa (requires challenge token), passkey login
+  //    (requires challenge), get-avatar-url, jwks
   //  * unauthed + rate limited:
-  //    * reset-password
+  //    * login (failed attempts lock out accounts and IPs, see `lockout`)
+  //    * reset-password (unknown addresses count towards the IP's lockout)
+  //    * magic-link login (+one-time code)
   //    * verify-email (+retrigger)
   //  * authed:
   //    * get-login-status (no CSRF, no side-effect)
   //    * refresh-token (no CSRF, safe side-effect)
   //    * logout (no CSRF, safe side-effect)
   //    * change-password (no CSRF: requires old pass),
+  //    * mfa enroll/confirm/disable (no CSRF: confirm & disable require a code)
+  //    * passkey register/list (no CSRF: registration requires a challenge)
+  //    * passkey delete (technically CSRF: however, DELETE method)
   //    * change-email (TODO: CSRF: requires old email so only targeted),
   //    * delete-user (technically CSRF: however, currently DELETE method)
   //
@@ -129,6 +163,62 @@ pub(super) fn router() -> Router<crate::AppState> {
       &format!("/{AUTH_API_PATH}/login"),
       post(api::login::login_handler),
     )
+    // Passwordless login via email.
+    .route(
+      &format!("/{AUTH_API_PATH}/login/magic_link/request"),
+      post(api::magic_link::magic_link_request_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/login/magic_link/confirm/{{magic_link_code}}"),
+      get(api::magic_link::magic_link_confirm_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/login/magic_link/confirm"),
+      post(api::magic_link::magic_link_code_handler),
+    )
+    // Second login step for users with MFA enabled.
+    .route(
+      &format!("/{AUTH_API_PATH}/login/mfa"),
+      post(api::mfa::login_mfa_handler),
+    )
+    // TOTP second-factor life-cycle.
+    .route(
+      &format!("/{AUTH_API_PATH}/mfa/totp/enroll"),
+      post(api::mfa::totp_enroll_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/mfa/totp/confirm"),
+      post(api::mfa::totp_confirm_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/mfa/totp/disable"),
+      post(api::mfa::totp_disable_handler),
+    )
+    // Passkey (WebAuthn) registration and login.
+    .route(
+      &format!("/{AUTH_API_PATH}/passkey/register/start"),
+      post(api::passkey::passkey_register_start_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/passkey/register/finish"),
+      post(api::passkey::passkey_register_finish_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/passkey/login/start"),
+      post(api::passkey::passkey_login_start_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/passkey/login/finish"),
+      post(api::passkey::passkey_login_finish_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/passkeys"),
+      get(api::passkey::list_passkeys_handler),
+    )
+    .route(
+      &format!("/{AUTH_API_PATH}/passkeys/{{id}}"),
+      delete(api::passkey::delete_passkey_handler),
+    )
     // Converts auth code (+pkce code verifier) to auth tokens
     .route(
       &format!("/{AUTH_API_PATH}/token"),
@@ -159,6 +249,8 @@ pub(super) fn router() -> Router<crate::AppState> {
       &format!("/{AUTH_API_PATH}/delete"),
       delete(api::delete::delete_handler),
     )
+    // Public keys for validating auth tokens, e.g. by other services.
+    .route("/.well-known/jwks.json", get(api::jwks::jwks_handler))
     // OAuth flows: list providers, login+callback
     .nest(&format!("/{AUTH_API_PATH}/oauth"), oauth::oauth_router());
 }
@@ -171,6 +263,10 @@ pub(super) fn admin_auth_router() -> Router<crate::AppState> {
       &format!("/{AUTH_API_PATH}/login"),
       post(api::login::login_handler),
     )
+    .route(
+      &format!("/{AUTH_API_PATH}/login/mfa"),
+      post(api::mfa::login_mfa_handler),
+    )
     .route(
       &format!("/{AUTH_API_PATH}/status"),
       get(api::login::login_status_handler),
@@ -1,17 +1,20 @@
 use axum::{
-  extract::{Path, Query, State},
+  extract::{Form, Path, Query, State},
   response::Redirect,
 };
 use chrono::Duration;
 use lazy_static::lazy_static;
 use oauth2::PkceCodeVerifier;
 use oauth2::{AsyncHttpClient, HttpClientError, HttpRequest, HttpResponse};
-use oauth2::{AuthorizationCode, StandardTokenResponse, TokenResponse};
+use oauth2::{AuthorizationCode, TokenResponse};
 use serde::Deserialize;
 use thiserror::Error;
 use tower_cookies::Cookies;
 use trailbase_sqlite::{named_params, params};
 
+use crate::auth::api::login::LoginResponseOptions;
+use crate::auth::api::mfa::{begin_mfa_challenge, login_mfa_ui_url};
+use crate::auth::oauth::provider::OAuthTokenResponse;
 use crate::auth::oauth::state::{OAuthState, ResponseType};
 use crate::auth::oauth::OAuthUser;
 use crate::auth::tokens::{mint_new_tokens, FreshTokens};
@@ -77,6 +80,58 @@ impl<'c> AsyncHttpClient<'c> for WrappedReqwest {
   }
 }
 
+/// HTTP client for token requests, which normalizes non-standard token responses.
+struct TokenHttpClient(reqwest::Client);
+
+impl<'c> AsyncHttpClient<'c> for TokenHttpClient {
+  type Error = HttpClientError<reqwest::Error>;
+
+  type Future = std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<HttpResponse, Self::Error>> + Send + Sync + 'c>,
+  >;
+
+  fn call(&'c self, request: HttpRequest) -> Self::Future {
+    Box::pin(async move {
+      let mut response = self.0.call(request).await?;
+      normalize_scopes(response.body_mut());
+      Ok(response)
+    })
+  }
+}
+
+/// Some providers, e.g. Twitch, return granted scopes as an array rather than as a
+/// space-delimited string (RFC 6749, Section 5.1), which would fail to parse.
+fn normalize_scopes(body: &mut Vec<u8>) {
+  let Ok(serde_json::Value::Object(mut json)) = serde_json::from_slice(body) else {
+    return;
+  };
+  let Some(serde_json::Value::Array(scopes)) = json.get("scope") else {
+    return;
+  };
+
+  let scopes = scopes
+    .iter()
+    .filter_map(|scope| scope.as_str())
+    .collect::<Vec<_>>()
+    .join(" ");
+  json.insert("scope".to_string(), serde_json::Value::String(scopes));
+
+  if let Ok(normalized) = serde_json::to_vec(&json) {
+    *body = normalized;
+  }
+}
+
+/// Providers using `response_mode=form_post`, e.g. Apple, POST the authorization response rather
+/// than redirecting with query parameters.
+pub(crate) async fn callback_from_external_auth_provider_with_form_post(
+  state: State<AppState>,
+  provider: Path<String>,
+  cookies: Cookies,
+  Form(request): Form<AuthRequest>,
+) -> Result<Redirect, AuthError> {
+  return callback_from_external_auth_provider(state, provider, Query(request), cookies).await;
+}
+
 // This handler receives the ?code=<>&state=<>, uses it to get an external oauth token, gets the
 // user's information, creates a new local user if needed, and finally mints our own tokens.
 
@@ -104,16 +159,18 @@ pub(crate) async fn callback_from_external_auth_provider(
     return Err(AuthError::BadRequest("invalid state"));
   }
 
-  let http_client = reqwest::ClientBuilder::new()
-    // Following redirects opens the client up to SSRF vulnerabilities.
-    .redirect(reqwest::redirect::Policy::none())
-    .build()
-    .map_err(|err| AuthError::Internal(err.into()))?;
+  let http_client = TokenHttpClient(
+    reqwest::ClientBuilder::new()
+      // Following redirects opens the client up to SSRF vulnerabilities.
+      .redirect(reqwest::redirect::Policy::none())
+      .build()
+      .map_err(|err| AuthError::Internal(err.into()))?,
+  );
 
-  let client = provider.oauth_client(&state)?;
+  let client = provider.oauth_client(&state).await?;
 
   // Exchange code for token.
-  let token_response: StandardTokenResponse<_, oauth2::basic::BasicTokenType> = client
+  let token_response: OAuthTokenResponse = client
     .exchange_code(AuthorizationCode::new(query.code))
     .set_pkce_verifier(PkceCodeVerifier::new(oauth_state.pkce_code_verifier))
     .request_async(&http_client)
@@ -126,9 +183,7 @@ pub(crate) async fn callback_from_external_auth_provider(
     ));
   }
 
-  let oauth_user = provider
-    .get_user(token_response.access_token().secret().clone())
-    .await?;
+  let oauth_user = provider.get_user_from_tokens(&token_response).await?;
 
   if !oauth_user.verified {
     return Err(AuthError::BadRequest("remote oauth user not verified"));
@@ -158,6 +213,24 @@ pub(crate) async fn callback_from_external_auth_provider(
     }
   };
 
+  // External providers only substitute for the password, thus users with MFA enabled still need
+  // to provide their second factor.
+  if let Some(mfa_token) = begin_mfa_challenge(&state, &db_user.uuid()).await? {
+    remove_cookie(&cookies, COOKIE_OAUTH_STATE);
+
+    return Ok(Redirect::to(&login_mfa_ui_url(
+      &mfa_token,
+      &LoginResponseOptions {
+        json: false,
+        redirect,
+        response_type: oauth_state
+          .response_type
+          .map(|ResponseType::Code| "code".to_string()),
+        pkce_code_challenge: oauth_state.user_pkce_code_challenge,
+      },
+    )));
+  }
+
   // Mint user token.
   let (auth_token_ttl, refresh_token_ttl) = state.access_config(|c| c.auth.token_ttls());
   let expires_in = token_response.expires_in().map_or(auth_token_ttl, |exp| {
@@ -5,7 +5,7 @@ use axum::{
 use chrono::Duration;
 use oauth2::{CsrfToken, PkceCodeChallenge, Scope};
 use serde::Deserialize;
-use tower_cookies::Cookies;
+use tower_cookies::{cookie::SameSite, Cookies};
 use utoipa::IntoParams;
 
 use crate::auth::oauth::state::{OAuthState, ResponseType};
@@ -33,11 +33,11 @@ pub(crate) async fn login_with_external_auth_provider(
   let redirect = validate_redirects(&state, &query.redirect_to, &None)?;
   let code_response = query.response_type.is_some_and(|r| r == "code");
 
-  let client = provider.oauth_client(&state)?;
+  let client = provider.oauth_client(&state).await?;
 
   let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
 
-  let (authorize_url, csrf_state) = client
+  let mut request = client
     .authorize_url(CsrfToken::new_random)
     .add_scopes(
       provider
@@ -45,8 +45,14 @@ pub(crate) async fn login_with_external_auth_provider(
         .into_iter()
         .map(|s| Scope::new(s.to_string())),
     )
-    .set_pkce_challenge(pkce_code_challenge)
-    .url();
+    .set_pkce_challenge(pkce_code_challenge);
+
+  let form_post = provider.form_post_response();
+  if form_post {
+    request = request.add_extra_param("response_mode", "form_post");
+  }
+
+  let (authorize_url, csrf_state) = request.url();
 
   // Set short-lived CSRF and PkceCodeVerifier cookies for the callback.
   let oauth_state = OAuthState {
@@ -62,7 +68,7 @@ pub(crate) async fn login_with_external_auth_provider(
     redirect_to: redirect,
   };
 
-  cookies.add(new_cookie_opts(
+  let mut cookie = new_cookie_opts(
     COOKIE_OAUTH_STATE,
     // Encoding as JWT token for tamper proofing. This doesn't encrypt anything but merely adds a
     // signature. None of the state handed to the user needs to be hidden from the user.
@@ -75,7 +81,14 @@ pub(crate) async fn login_with_external_auth_provider(
     // We need to include cookies on redirect back from oauth provider.
     /* same_site: */
     false,
-  ));
+  );
+  if form_post {
+    // Browsers only include cookies in cross-site POST requests if they're marked "SameSite=None",
+    // which in turn requires "Secure".
+    cookie.set_same_site(SameSite::None);
+    cookie.set_secure(true);
+  }
+  cookies.add(cookie);
 
   Ok(Redirect::to(authorize_url.as_str()))
 }
@@ -28,6 +28,7 @@ pub fn oauth_router() -> Router<AppState> {
     )
     .route(
       "/{provider}/callback",
-      get(callback::callback_from_external_auth_provider),
+      get(callback::callback_from_external_auth_provider)
+        .post(callback::callback_from_external_auth_provider_with_form_post),
     )
 }
@@ -3,16 +3,24 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Redirect};
 use axum::routing::{get, post, Router};
 use axum_test::{TestServer, TestServerConfig};
+use oauth2::basic::BasicTokenType;
+use oauth2::AccessToken;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
 use tower_cookies::Cookies;
 
 use crate::app_state::{test_state, TestStateOptions};
+use crate::auth::jwt::test_jwt_helper;
+use crate::auth::oauth::provider::{IdTokenFields, OAuthTokenResponse};
 use crate::auth::oauth::providers::test::{TestOAuthProvider, TestUser};
 use crate::auth::oauth::state::OAuthState;
 use crate::auth::oauth::{callback, list_providers, login};
 use crate::auth::util::derive_pkce_code_challenge;
+use crate::auth::AuthError;
 use crate::config::proto::{Config, OAuthProviderConfig, OAuthProviderId};
-use crate::constants::{AUTH_API_PATH, COOKIE_OAUTH_STATE, USER_TABLE};
+use crate::constants::{
+  AUTH_API_PATH, COOKIE_AUTH_TOKEN, COOKIE_OAUTH_STATE, USER_MFA_TABLE, USER_TABLE,
+};
 
 fn unpack_redirect(redirect: Redirect) -> String {
   let response = redirect.into_response();
@@ -193,4 +201,244 @@ async fn test_oauth() {
     .unwrap();
 
   assert_eq!(row.get::<String>(0).unwrap(), external_user_email);
+
+  // Users with MFA enabled still need to provide their second factor.
+  state
+    .user_conn()
+    .execute(
+      &format!(
+        r#"
+          INSERT INTO "{USER_MFA_TABLE}" (user, totp_secret, enabled)
+          SELECT id, 'JBSWY3DPEHPK3PXP', TRUE FROM "{USER_TABLE}" WHERE provider_user_id = $1
+        "#
+      ),
+      (external_user_id,),
+    )
+    .await
+    .unwrap();
+
+  let cookies = Cookies::default();
+  login::login_with_external_auth_provider(
+    State(state.clone()),
+    Path(name.clone()),
+    Query(login::LoginQuery {
+      redirect_to: None,
+      response_type: None,
+      pkce_code_challenge: None,
+    }),
+    cookies.clone(),
+  )
+  .await
+  .unwrap();
+  let oauth_state: OAuthState = state
+    .jwt()
+    .decode(cookies.get(COOKIE_OAUTH_STATE).unwrap().value())
+    .unwrap();
+
+  let internal_redirect = callback::callback_from_external_auth_provider(
+    State(state.clone()),
+    Path(name.clone()),
+    Query(callback::AuthRequest {
+      state: oauth_state.csrf_secret.clone(),
+      code: "code".to_string(),
+    }),
+    cookies.clone(),
+  )
+  .await
+  .unwrap();
+
+  assert!(unpack_redirect(internal_redirect).starts_with("/_/auth/login_mfa?mfa_token="));
+  assert!(cookies.get(COOKIE_AUTH_TOKEN).is_none());
+}
+
+#[tokio::test]
+async fn test_oidc() {
+  let name = "my_idp".to_string();
+  let external_user_id = "ExternalSubject";
+  let external_user_email = "oidc@bar.com";
+
+  // Mock identity provider. The issuer is only known once the server is listening.
+  let issuer = Arc::new(OnceLock::<String>::new());
+  let idp_jwt = Arc::new(test_jwt_helper());
+
+  let id_token = {
+    let issuer = issuer.clone();
+    let idp_jwt = idp_jwt.clone();
+    move |audience: &str| {
+      idp_jwt
+        .encode(&serde_json::json!({
+          "iss": issuer.get().unwrap(),
+          "sub": external_user_id,
+          "aud": audience,
+          "exp": (chrono::Utc::now() + chrono::Duration::minutes(5)).timestamp(),
+          "email": external_user_email,
+          "email_verified": true,
+        }))
+        .unwrap()
+    }
+  };
+
+  let app = Router::new()
+    .route(
+      "/.well-known/openid-configuration",
+      get({
+        let issuer = issuer.clone();
+        move || async move {
+          let issuer = issuer.get().unwrap();
+          Json(serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/auth"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+          }))
+        }
+      }),
+    )
+    .route(
+      "/jwks",
+      get({
+        let idp_jwt = idp_jwt.clone();
+        move || async move { Json(idp_jwt.jwks()) }
+      }),
+    )
+    .route(
+      "/auth",
+      get(|Query(query): Query<AuthQuery>| async { Json(query) }),
+    )
+    .route(
+      "/token",
+      post({
+        let id_token = id_token.clone();
+        move |Form(_req): Form<TokenRequest>| async move {
+          Json(serde_json::json!({
+            "access_token": "opaque_token",
+            "token_type": "Bearer",
+            "id_token": id_token("oidc_client_id"),
+          }))
+        }
+      }),
+    );
+
+  let server = TestServer::new_with_config(
+    app,
+    TestServerConfig {
+      transport: Some(axum_test::Transport::HttpRandomPort),
+      ..Default::default()
+    },
+  )
+  .unwrap();
+
+  let issuer_url = server
+    .server_url("/")
+    .unwrap()
+    .to_string()
+    .trim_end_matches('/')
+    .to_string();
+  issuer.set(issuer_url.clone()).unwrap();
+
+  let mut config = Config::new_with_custom_defaults();
+  config.auth.oauth_providers.insert(
+    name.clone(),
+    OAuthProviderConfig {
+      client_id: Some("oidc_client_id".to_string()),
+      client_secret: Some("oidc_client_secret".to_string()),
+      provider_id: Some(OAuthProviderId::Oidc as i32),
+      display_name: Some("My IdP".to_string()),
+      issuer_url: Some(issuer_url.clone()),
+      ..Default::default()
+    },
+  );
+
+  let state = test_state(Some(TestStateOptions {
+    config: Some(config),
+    ..Default::default()
+  }))
+  .await
+  .unwrap();
+
+  // OIDC providers are identified by their config key.
+  assert_eq!(
+    state.get_oauth_providers(),
+    vec![(name.clone(), "My IdP".to_string())]
+  );
+
+  let cookies = Cookies::default();
+  let external_redirect: Redirect = login::login_with_external_auth_provider(
+    State(state.clone()),
+    Path(name.clone()),
+    Query(login::LoginQuery::default()),
+    cookies.clone(),
+  )
+  .await
+  .unwrap();
+
+  // Redirects to the discovered authorization endpoint.
+  let external_redirect = unpack_redirect(external_redirect);
+  assert!(external_redirect.starts_with(&format!("{issuer_url}/auth?")));
+
+  let response = reqwest::get(&external_redirect).await.unwrap();
+  assert_eq!(response.status(), StatusCode::OK);
+  let auth_query: AuthQuery = response.json().await.unwrap();
+  assert_eq!(auth_query.client_id, "oidc_client_id");
+  assert_eq!(auth_query.scope, "openid email profile");
+
+  let internal_redirect = callback::callback_from_external_auth_provider(
+    State(state.clone()),
+    Path(name.clone()),
+    Query(callback::AuthRequest {
+      state: auth_query.state.clone(),
+      code: "code".to_string(),
+    }),
+    cookies.clone(),
+  )
+  .await
+  .unwrap();
+  assert_eq!(unpack_redirect(internal_redirect), "/_/auth/profile");
+
+  let row = state
+    .user_conn()
+    .query_row(
+      &format!(r#"SELECT email, provider_id FROM "{USER_TABLE}" WHERE provider_user_id = $1"#),
+      (format!("{issuer_url}#{external_user_id}"),),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(row.get::<String>(0).unwrap(), external_user_email);
+  assert_eq!(row.get::<i64>(1).unwrap(), OAuthProviderId::Oidc as i64);
+
+  // ID tokens issued for other clients are rejected.
+  let provider = state.get_oauth_provider(&name).unwrap();
+  let tokens = OAuthTokenResponse::new(
+    AccessToken::new("opaque_token".to_string()),
+    BasicTokenType::Bearer,
+    IdTokenFields {
+      id_token: Some(id_token("other_client_id")),
+    },
+  );
+  assert!(matches!(
+    provider.get_user_from_tokens(&tokens).await,
+    Err(AuthError::UnauthorizedExt(_))
+  ));
+
+  // As are ID tokens signed by other keys.
+  let tokens = OAuthTokenResponse::new(
+    AccessToken::new("opaque_token".to_string()),
+    BasicTokenType::Bearer,
+    IdTokenFields {
+      id_token: Some(
+        test_jwt_helper()
+          .encode(&serde_json::json!({
+            "iss": issuer_url,
+            "sub": external_user_id,
+            "aud": "oidc_client_id",
+            "exp": (chrono::Utc::now() + chrono::Duration::minutes(5)).timestamp(),
+            "email": external_user_email,
+            "email_verified": true,
+          }))
+          .unwrap(),
+      ),
+    },
+  );
+  assert!(provider.get_user_from_tokens(&tokens).await.is_err());
 }
@@ -1,11 +1,10 @@
 use async_trait::async_trait;
 use oauth2::basic::{
-  BasicClient, BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse,
-  BasicTokenResponse,
+  BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse, BasicTokenType,
 };
 use oauth2::{
-  AuthUrl, ClientId, ClientSecret, EndpointNotSet, EndpointSet, RedirectUrl,
-  StandardRevocableToken, TokenUrl,
+  AuthType, AuthUrl, ClientId, ClientSecret, EndpointNotSet, EndpointSet, ExtraTokenFields,
+  RedirectUrl, StandardRevocableToken, StandardTokenResponse, TokenResponse, TokenUrl,
 };
 use serde::{Deserialize, Serialize};
 use url::Url;
@@ -15,6 +14,18 @@ use crate::auth::AuthError;
 use crate::config::proto::OAuthProviderId;
 use crate::constants::AUTH_API_PATH;
 
+/// Extra fields of token responses. OpenID Connect providers return an ID token alongside the
+/// access token.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IdTokenFields {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub id_token: Option<String>,
+}
+
+impl ExtraTokenFields for IdTokenFields {}
+
+pub type OAuthTokenResponse = StandardTokenResponse<IdTokenFields, BasicTokenType>;
+
 pub type OAuthClient<
   HasAuthUrl = EndpointSet,
   HasDeviceAuthUrl = EndpointNotSet,
@@ -23,7 +34,7 @@ pub type OAuthClient<
   HasTokenUrl = EndpointSet,
 > = oauth2::Client<
   BasicErrorResponse,
-  BasicTokenResponse,
+  OAuthTokenResponse,
   BasicTokenIntrospectionResponse,
   StandardRevocableToken,
   BasicRevocationErrorResponse,
@@ -57,15 +68,15 @@ pub trait OAuthProvider {
   #[allow(unused)]
   fn provider(&self) -> OAuthProviderId;
 
-  fn name(&self) -> &'static str;
+  fn name(&self) -> &str;
 
-  fn display_name(&self) -> &'static str {
+  fn display_name(&self) -> &str {
     self.name()
   }
 
-  fn settings(&self) -> Result<OAuthClientSettings, AuthError>;
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError>;
 
-  fn oauth_client(&self, state: &AppState) -> Result<OAuthClient, AuthError> {
+  async fn oauth_client(&self, state: &AppState) -> Result<OAuthClient, AuthError> {
     let redirect_url: Url = Url::parse(&format!(
       "{site}/{AUTH_API_PATH}/oauth/{name}/callback",
       site = state.site_url(),
@@ -73,7 +84,7 @@ pub trait OAuthProvider {
     ))
     .unwrap();
 
-    let settings = self.settings()?;
+    let settings = self.settings().await?;
     if settings.client_id.is_empty() {
       return Err(AuthError::Internal(
         format!("Missing client id for {}", self.name()).into(),
@@ -85,10 +96,11 @@ pub trait OAuthProvider {
       ));
     }
 
-    let client = BasicClient::new(ClientId::new(settings.client_id))
+    let client: OAuthClient = oauth2::Client::new(ClientId::new(settings.client_id))
       .set_client_secret(ClientSecret::new(settings.client_secret))
       .set_auth_uri(AuthUrl::from_url(settings.auth_url))
       .set_token_uri(TokenUrl::from_url(settings.token_url))
+      .set_auth_type(self.oauth_auth_type())
       .set_redirect_uri(RedirectUrl::from_url(redirect_url));
 
     return Ok(client);
@@ -96,5 +108,25 @@ pub trait OAuthProvider {
 
   fn oauth_scopes(&self) -> Vec<&'static str>;
 
+  /// How client credentials are passed to the token endpoint. Defaults to HTTP basic auth.
+  fn oauth_auth_type(&self) -> AuthType {
+    AuthType::BasicAuth
+  }
+
+  /// Whether the provider should POST the authorization response to the callback, i.e.
+  /// `response_mode=form_post`, rather than redirecting with query parameters.
+  fn form_post_response(&self) -> bool {
+    false
+  }
+
   async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError>;
+
+  /// Retrieves the user given the provider's token response. Defaults to looking the user up
+  /// using the access token.
+  async fn get_user_from_tokens(
+    &self,
+    tokens: &OAuthTokenResponse,
+  ) -> Result<OAuthUser, AuthError> {
+    return self.get_user(tokens.access_token().secret().clone()).await;
+  }
 }
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use oauth2::AuthType;
+use serde::Serialize;
+
+use crate::auth::oauth::provider::OAuthTokenResponse;
+use crate::auth::oauth::providers::oidc::OidcOAuthProvider;
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
+use crate::auth::AuthError;
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+#[derive(Debug, Serialize)]
+struct ClientSecretClaims<'a> {
+  iss: &'a str,
+  sub: &'a str,
+  aud: &'a str,
+  iat: i64,
+  exp: i64,
+}
+
+/// Sign in with Apple implements OpenID Connect with two peculiarities: the client secret is a
+/// JWT signed with the team's private key and the authorization response is POSTed to the
+/// callback.
+pub(crate) struct AppleOAuthProvider {
+  oidc: OidcOAuthProvider,
+
+  client_id: String,
+  team_id: String,
+  key_id: String,
+  private_key: EncodingKey,
+}
+
+impl AppleOAuthProvider {
+  const NAME: &'static str = "apple";
+  const DISPLAY_NAME: &'static str = "Apple";
+
+  const ISSUER_URL: &'static str = "https://appleid.apple.com";
+  // Client secrets are minted for every token request, thus they can be short-lived.
+  const CLIENT_SECRET_TTL_SEC: i64 = 5 * 60;
+
+  fn new(config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing("Apple client id".to_string()));
+    };
+    let Some(private_key) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing(
+        "Apple client secret, i.e. private key".to_string(),
+      ));
+    };
+    let Some(team_id) = config.team_id.clone() else {
+      return Err(OAuthProviderError::Missing("Apple team id".to_string()));
+    };
+    let Some(key_id) = config.key_id.clone() else {
+      return Err(OAuthProviderError::Missing("Apple key id".to_string()));
+    };
+
+    let private_key = EncodingKey::from_ec_pem(private_key.as_bytes())
+      .map_err(|err| OAuthProviderError::Invalid(format!("Apple private key: {err}")))?;
+
+    return Ok(Self {
+      oidc: OidcOAuthProvider::for_issuer(
+        OAuthProviderId::Apple,
+        Self::NAME,
+        Self::DISPLAY_NAME,
+        Self::ISSUER_URL,
+        client_id.clone(),
+        // Minted on demand.
+        String::new(),
+      ),
+      client_id,
+      team_id,
+      key_id,
+      private_key,
+    });
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Apple,
+      name: Self::NAME,
+      display_name: Self::DISPLAY_NAME,
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
+    }
+  }
+
+  fn client_secret(&self) -> Result<String, AuthError> {
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(self.key_id.clone());
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = ClientSecretClaims {
+      iss: &self.team_id,
+      sub: &self.client_id,
+      aud: Self::ISSUER_URL,
+      iat: now,
+      exp: now + Self::CLIENT_SECRET_TTL_SEC,
+    };
+
+    return jsonwebtoken::encode(&header, &claims, &self.private_key)
+      .map_err(|err| AuthError::Internal(err.into()));
+  }
+}
+
+#[async_trait]
+impl OAuthProvider for AppleOAuthProvider {
+  fn name(&self) -> &str {
+    Self::NAME
+  }
+  fn provider(&self) -> OAuthProviderId {
+    OAuthProviderId::Apple
+  }
+  fn display_name(&self) -> &str {
+    Self::DISPLAY_NAME
+  }
+
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+    let mut settings = self.oidc.settings().await?;
+    settings.client_secret = self.client_secret()?;
+    return Ok(settings);
+  }
+
+  fn oauth_scopes(&self) -> Vec<&'static str> {
+    return vec!["name", "email"];
+  }
+
+  fn oauth_auth_type(&self) -> AuthType {
+    AuthType::RequestBody
+  }
+
+  fn form_post_response(&self) -> bool {
+    // Required when requesting the "name" or "email" scopes.
+    true
+  }
+
+  async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError> {
+    return self.oidc.get_user(access_token).await;
+  }
+
+  async fn get_user_from_tokens(
+    &self,
+    tokens: &OAuthTokenResponse,
+  ) -> Result<OAuthUser, AuthError> {
+    return self.oidc.get_user_from_tokens(tokens).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use jsonwebtoken::{DecodingKey, Validation};
+  use p256::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+  use rand::rngs::OsRng;
+  use serde::Deserialize;
+
+  #[test]
+  fn test_client_secret() {
+    let secret_key = p256::SecretKey::random(&mut OsRng);
+    let private_key = secret_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+    let public_key = secret_key
+      .public_key()
+      .to_public_key_pem(LineEnding::LF)
+      .unwrap();
+
+    let provider = AppleOAuthProvider::new(&OAuthProviderConfig {
+      client_id: Some("com.example.app".to_string()),
+      client_secret: Some(private_key.to_string()),
+      provider_id: Some(OAuthProviderId::Apple as i32),
+      team_id: Some("TEAMID".to_string()),
+      key_id: Some("KEYID".to_string()),
+      ..Default::default()
+    })
+    .unwrap();
+
+    let client_secret = provider.client_secret().unwrap();
+
+    let header = jsonwebtoken::decode_header(&client_secret).unwrap();
+    assert_eq!(header.alg, Algorithm::ES256);
+    assert_eq!(header.kid.as_deref(), Some("KEYID"));
+
+    #[derive(Deserialize)]
+    struct Claims {
+      iss: String,
+      sub: String,
+    }
+
+    let mut validation = Validation::new(Algorithm::ES256);
+    validation.set_audience(&["https://appleid.apple.com"]);
+    let claims = jsonwebtoken::decode::<Claims>(
+      &client_secret,
+      &DecodingKey::from_ec_pem(public_key.as_bytes()).unwrap(),
+      &validation,
+    )
+    .unwrap()
+    .claims;
+    assert_eq!(claims.iss, "TEAMID");
+    assert_eq!(claims.sub, "com.example.app");
+
+    // Invalid keys are rejected upfront.
+    assert!(AppleOAuthProvider::new(&OAuthProviderConfig {
+      client_id: Some("com.example.app".to_string()),
+      client_secret: Some("not a key".to_string()),
+      team_id: Some("TEAMID".to_string()),
+      key_id: Some("KEYID".to_string()),
+      ..Default::default()
+    })
+    .is_err());
+  }
+}
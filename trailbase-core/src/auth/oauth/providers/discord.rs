@@ -42,24 +42,26 @@ impl DiscordOAuthProvider {
       id: OAuthProviderId::Discord,
       name: Self::NAME,
       display_name: Self::DISPLAY_NAME,
-      factory: Box::new(|config: &OAuthProviderConfig| Ok(Box::new(Self::new(config)?))),
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
     }
   }
 }
 
 #[async_trait]
 impl OAuthProvider for DiscordOAuthProvider {
-  fn name(&self) -> &'static str {
+  fn name(&self) -> &str {
     Self::NAME
   }
   fn provider(&self) -> OAuthProviderId {
     OAuthProviderId::Discord
   }
-  fn display_name(&self) -> &'static str {
+  fn display_name(&self) -> &str {
     Self::DISPLAY_NAME
   }
 
-  fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
     lazy_static! {
       static ref AUTH_URL: Url = Url::parse(DiscordOAuthProvider::AUTH_URL).unwrap();
       static ref TOKEN_URL: Url = Url::parse(DiscordOAuthProvider::TOKEN_URL).unwrap();
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use url::Url;
+
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
+use crate::auth::AuthError;
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+#[derive(Default, Deserialize, Debug)]
+struct GithubUser {
+  id: i64,
+  avatar_url: Option<String>,
+}
+
+#[derive(Default, Deserialize, Debug)]
+struct GithubEmail {
+  email: String,
+  primary: bool,
+  verified: bool,
+}
+
+pub(crate) struct GithubOAuthProvider {
+  client_id: String,
+  client_secret: String,
+}
+
+impl GithubOAuthProvider {
+  const NAME: &'static str = "github";
+  const DISPLAY_NAME: &'static str = "GitHub";
+
+  const AUTH_URL: &'static str = "https://github.com/login/oauth/authorize";
+  const TOKEN_URL: &'static str = "https://github.com/login/oauth/access_token";
+  const USER_API_URL: &'static str = "https://api.github.com/user";
+  // The user's public profile email may be unset or unverified.
+  const EMAILS_API_URL: &'static str = "https://api.github.com/user/emails";
+
+  fn new(config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing("GitHub client id".to_string()));
+    };
+    let Some(client_secret) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing(
+        "GitHub client secret".to_string(),
+      ));
+    };
+
+    return Ok(Self {
+      client_id,
+      client_secret,
+    });
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Github,
+      name: Self::NAME,
+      display_name: Self::DISPLAY_NAME,
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
+    }
+  }
+
+  async fn get<T: serde::de::DeserializeOwned>(
+    url: &str,
+    access_token: &str,
+  ) -> Result<T, AuthError> {
+    let response = reqwest::Client::new()
+      .get(url)
+      .bearer_auth(access_token)
+      // GitHub's API rejects requests w/o user agent.
+      .header(USER_AGENT, "TrailBase")
+      .send()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    return response
+      .json::<T>()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()));
+  }
+}
+
+#[async_trait]
+impl OAuthProvider for GithubOAuthProvider {
+  fn name(&self) -> &str {
+    Self::NAME
+  }
+  fn provider(&self) -> OAuthProviderId {
+    OAuthProviderId::Github
+  }
+  fn display_name(&self) -> &str {
+    Self::DISPLAY_NAME
+  }
+
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+    lazy_static! {
+      static ref AUTH_URL: Url = Url::parse(GithubOAuthProvider::AUTH_URL).unwrap();
+      static ref TOKEN_URL: Url = Url::parse(GithubOAuthProvider::TOKEN_URL).unwrap();
+    }
+
+    return Ok(OAuthClientSettings {
+      auth_url: AUTH_URL.clone(),
+      token_url: TOKEN_URL.clone(),
+      client_id: self.client_id.clone(),
+      client_secret: self.client_secret.clone(),
+    });
+  }
+
+  fn oauth_scopes(&self) -> Vec<&'static str> {
+    return vec!["read:user", "user:email"];
+  }
+
+  async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError> {
+    let user = Self::get::<GithubUser>(Self::USER_API_URL, &access_token).await?;
+    let emails = Self::get::<Vec<GithubEmail>>(Self::EMAILS_API_URL, &access_token).await?;
+
+    let Some(email) = primary_verified_email(emails) else {
+      return Err(AuthError::Unauthorized);
+    };
+
+    return Ok(OAuthUser {
+      provider_user_id: user.id.to_string(),
+      provider_id: OAuthProviderId::Github,
+      email,
+      verified: true,
+      avatar: user.avatar_url,
+    });
+  }
+}
+
+fn primary_verified_email(emails: Vec<GithubEmail>) -> Option<String> {
+  return emails
+    .into_iter()
+    .find(|e| e.primary && e.verified)
+    .map(|e| e.email);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_primary_verified_email() {
+    let emails: Vec<GithubEmail> = serde_json::from_str(
+      r#"[
+        {"email": "other@bar.com", "primary": false, "verified": true, "visibility": null},
+        {"email": "foo@bar.com", "primary": true, "verified": true, "visibility": "public"}
+      ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+      primary_verified_email(emails),
+      Some("foo@bar.com".to_string())
+    );
+
+    let unverified: Vec<GithubEmail> = serde_json::from_str(
+      r#"[{"email": "foo@bar.com", "primary": true, "verified": false, "visibility": null}]"#,
+    )
+    .unwrap();
+    assert_eq!(primary_verified_email(unverified), None);
+  }
+}
@@ -42,24 +42,26 @@ impl GitlabOAuthProvider {
       id: OAuthProviderId::Gitlab,
       name: Self::NAME,
       display_name: Self::DISPLAY_NAME,
-      factory: Box::new(|config: &OAuthProviderConfig| Ok(Box::new(Self::new(config)?))),
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
     }
   }
 }
 
 #[async_trait]
 impl OAuthProvider for GitlabOAuthProvider {
-  fn name(&self) -> &'static str {
+  fn name(&self) -> &str {
     Self::NAME
   }
   fn provider(&self) -> OAuthProviderId {
     OAuthProviderId::Gitlab
   }
-  fn display_name(&self) -> &'static str {
+  fn display_name(&self) -> &str {
     Self::DISPLAY_NAME
   }
 
-  fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
     lazy_static! {
       static ref AUTH_URL: Url = Url::parse(GitlabOAuthProvider::AUTH_URL).unwrap();
       static ref TOKEN_URL: Url = Url::parse(GitlabOAuthProvider::TOKEN_URL).unwrap();
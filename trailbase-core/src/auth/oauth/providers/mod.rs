@@ -1,8 +1,13 @@
+mod apple;
 mod discord;
 mod facebook;
+mod github;
 mod gitlab;
 mod google;
 mod microsoft;
+mod oidc;
+mod twitch;
+mod yahoo;
 
 #[cfg(test)]
 pub(crate) mod test;
@@ -21,11 +26,16 @@ use crate::config::proto::{AuthConfig, OAuthProviderConfig, OAuthProviderId};
 pub enum OAuthProviderError {
   #[error("Missing error: {0}")]
   Missing(String),
+  #[error("Invalid: {0}")]
+  Invalid(String),
+  #[error("Conflict: {0}")]
+  Conflict(String),
 }
 
 pub type OAuthProviderType = Box<dyn OAuthProvider + Send + Sync>;
+/// Constructs a provider given its key in `AuthConfig.oauth_providers` and its config.
 type OAuthFactoryType =
-  dyn Fn(&OAuthProviderConfig) -> Result<OAuthProviderType, OAuthProviderError> + Send + Sync;
+  dyn Fn(&str, &OAuthProviderConfig) -> Result<OAuthProviderType, OAuthProviderError> + Send + Sync;
 
 pub(crate) struct OAuthProviderFactory {
   pub id: OAuthProviderId,
@@ -58,6 +68,11 @@ lazy_static! {
     google::GoogleOAuthProvider::factory(),
     facebook::FacebookOAuthProvider::factory(),
     microsoft::MicrosoftOAuthProvider::factory(),
+    github::GithubOAuthProvider::factory(),
+    apple::AppleOAuthProvider::factory(),
+    twitch::TwitchOAuthProvider::factory(),
+    yahoo::YahooOAuthProvider::factory(),
+    oidc::OidcOAuthProvider::factory(),
     #[cfg(test)]
     test::TestOAuthProvider::factory(),
   ];
@@ -83,7 +98,15 @@ impl ConfiguredOAuthProviders {
         )));
       };
 
-      providers.insert(entry.name.to_string(), (entry.factory)(&config)?.into());
+      let provider: Arc<OAuthProviderType> = (entry.factory)(&key, &config)?.into();
+      // Most providers are identified by their well-known name, whereas generic OIDC providers can
+      // be configured multiple times and are thus identified by their config key.
+      let name = provider.name().to_string();
+      if providers.insert(name.clone(), provider).is_some() {
+        return Err(OAuthProviderError::Conflict(format!(
+          "Multiple oauth providers named: {name}"
+        )));
+      }
     }
 
     return Ok(ConfiguredOAuthProviders { providers });
@@ -96,7 +119,7 @@ impl ConfiguredOAuthProviders {
     return None;
   }
 
-  pub fn list(&self) -> Vec<(&'static str, &'static str)> {
+  pub fn list(&self) -> Vec<(&str, &str)> {
     return self
       .providers
       .values()
@@ -0,0 +1,337 @@
+use async_trait::async_trait;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use oauth2::TokenResponse;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::auth::oauth::provider::OAuthTokenResponse;
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
+use crate::auth::AuthError;
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+/// Minimum time between re-fetching the provider's keys when encountering unknown key ids.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Subset of the provider metadata, see OpenID Connect Discovery 1.0, Section 3.
+#[derive(Debug, Deserialize)]
+struct Discovery {
+  issuer: String,
+  authorization_endpoint: Url,
+  token_endpoint: Url,
+  userinfo_endpoint: Option<String>,
+  jwks_uri: String,
+}
+
+/// Some providers encode booleans as strings.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Bool {
+  Bool(bool),
+  String(String),
+}
+
+/// Standard claims shared by ID tokens and userinfo responses, see OpenID Connect Core 1.0,
+/// Section 5.1.
+#[derive(Debug, Deserialize)]
+struct StandardClaims {
+  sub: String,
+  email: Option<String>,
+  email_verified: Option<Bool>,
+  picture: Option<String>,
+}
+
+/// Generic OpenID Connect provider, which discovers its endpoints and validates ID tokens against
+/// the issuer's published keys. Also backs built-in providers implementing OpenID Connect.
+pub(crate) struct OidcOAuthProvider {
+  provider_id: OAuthProviderId,
+  name: String,
+  display_name: String,
+  issuer_url: String,
+
+  client_id: String,
+  client_secret: String,
+
+  discovery: Mutex<Option<Arc<Discovery>>>,
+  jwks: Mutex<Option<(Instant, Arc<JwkSet>)>>,
+}
+
+impl OidcOAuthProvider {
+  const NAME: &'static str = "oidc";
+  const DISPLAY_NAME: &'static str = "OpenID Connect";
+
+  fn new(name: &str, config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing(format!("{name} client id")));
+    };
+    let Some(client_secret) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing(format!("{name} client secret")));
+    };
+    let Some(issuer_url) = config.issuer_url.clone() else {
+      return Err(OAuthProviderError::Missing(format!("{name} issuer url")));
+    };
+
+    let display_name = config
+      .display_name
+      .clone()
+      .unwrap_or_else(|| name.to_string());
+
+    return Ok(Self::for_issuer(
+      OAuthProviderId::Oidc,
+      name,
+      &display_name,
+      &issuer_url,
+      client_id,
+      client_secret,
+    ));
+  }
+
+  /// Constructs a provider for a well-known issuer.
+  pub(super) fn for_issuer(
+    provider_id: OAuthProviderId,
+    name: &str,
+    display_name: &str,
+    issuer_url: &str,
+    client_id: String,
+    client_secret: String,
+  ) -> Self {
+    return Self {
+      provider_id,
+      name: name.to_string(),
+      display_name: display_name.to_string(),
+      issuer_url: issuer_url.to_string(),
+      client_id,
+      client_secret,
+      discovery: Mutex::new(None),
+      jwks: Mutex::new(None),
+    };
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Oidc,
+      name: Self::NAME,
+      display_name: Self::DISPLAY_NAME,
+      factory: Box::new(|name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(name, config)?))
+      }),
+    }
+  }
+
+  async fn discovery(&self) -> Result<Arc<Discovery>, AuthError> {
+    let cached = self.discovery.lock().clone();
+    if let Some(discovery) = cached {
+      return Ok(discovery);
+    }
+
+    let issuer_url = self.issuer_url.trim_end_matches('/');
+    let discovery: Discovery =
+      fetch_json(&format!("{issuer_url}/.well-known/openid-configuration")).await?;
+
+    // See OpenID Connect Discovery 1.0, Section 4.3.
+    if discovery.issuer.trim_end_matches('/') != issuer_url {
+      return Err(AuthError::FailedDependency(
+        format!("Issuer mismatch: {}", discovery.issuer).into(),
+      ));
+    }
+
+    let discovery = Arc::new(discovery);
+    *self.discovery.lock() = Some(discovery.clone());
+    return Ok(discovery);
+  }
+
+  /// Looks up the key an ID token was signed with. Unknown keys trigger a re-fetch, since the
+  /// provider may have rotated its keys.
+  async fn find_key(&self, jwks_uri: &str, kid: Option<&str>) -> Result<Jwk, AuthError> {
+    let cached = self.jwks.lock().clone();
+    if let Some((fetched_at, ref jwks)) = cached {
+      if let Some(jwk) = select_key(jwks, kid) {
+        return Ok(jwk.clone());
+      }
+
+      if fetched_at.elapsed() < JWKS_REFRESH_INTERVAL {
+        return Err(AuthError::Unauthorized);
+      }
+    }
+
+    let jwks: Arc<JwkSet> = Arc::new(fetch_json(jwks_uri).await?);
+    *self.jwks.lock() = Some((Instant::now(), jwks.clone()));
+
+    return select_key(&jwks, kid)
+      .cloned()
+      .ok_or(AuthError::Unauthorized);
+  }
+
+  async fn validate_id_token(
+    &self,
+    discovery: &Discovery,
+    id_token: &str,
+  ) -> Result<StandardClaims, AuthError> {
+    let header = jsonwebtoken::decode_header(id_token)
+      .map_err(|err| AuthError::UnauthorizedExt(err.into()))?;
+
+    // We only accept tokens signed by the provider's published keys.
+    if matches!(
+      header.alg,
+      Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    ) {
+      return Err(AuthError::Unauthorized);
+    }
+
+    let jwk = self
+      .find_key(&discovery.jwks_uri, header.kid.as_deref())
+      .await?;
+    let key = DecodingKey::from_jwk(&jwk).map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&self.client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    return jsonwebtoken::decode::<StandardClaims>(id_token, &key, &validation)
+      .map(|data| data.claims)
+      .map_err(|err| AuthError::UnauthorizedExt(err.into()));
+  }
+
+  async fn user_info(
+    &self,
+    discovery: &Discovery,
+    access_token: &str,
+  ) -> Result<StandardClaims, AuthError> {
+    let Some(ref userinfo_endpoint) = discovery.userinfo_endpoint else {
+      return Err(AuthError::FailedDependency(
+        "Missing userinfo endpoint".into(),
+      ));
+    };
+
+    let response = http_client()?
+      .get(userinfo_endpoint)
+      .bearer_auth(access_token)
+      .send()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    return response
+      .json::<StandardClaims>()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()));
+  }
+
+  fn to_user(&self, discovery: &Discovery, claims: StandardClaims) -> Result<OAuthUser, AuthError> {
+    let Some(email) = claims.email else {
+      return Err(AuthError::FailedDependency("Missing email claim".into()));
+    };
+
+    return Ok(OAuthUser {
+      provider_user_id: match self.provider_id {
+        // Subjects are only unique per issuer and there may be multiple OIDC providers.
+        OAuthProviderId::Oidc => format!("{}#{}", discovery.issuer, claims.sub),
+        _ => claims.sub,
+      },
+      provider_id: self.provider_id,
+      email,
+      verified: match claims.email_verified {
+        Some(Bool::Bool(verified)) => verified,
+        Some(Bool::String(verified)) => verified == "true",
+        None => false,
+      },
+      avatar: claims.picture,
+    });
+  }
+}
+
+#[async_trait]
+impl OAuthProvider for OidcOAuthProvider {
+  fn name(&self) -> &str {
+    &self.name
+  }
+  fn provider(&self) -> OAuthProviderId {
+    self.provider_id
+  }
+  fn display_name(&self) -> &str {
+    &self.display_name
+  }
+
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+    let discovery = self.discovery().await?;
+
+    return Ok(OAuthClientSettings {
+      auth_url: discovery.authorization_endpoint.clone(),
+      token_url: discovery.token_endpoint.clone(),
+      client_id: self.client_id.clone(),
+      client_secret: self.client_secret.clone(),
+    });
+  }
+
+  fn oauth_scopes(&self) -> Vec<&'static str> {
+    return vec!["openid", "email", "profile"];
+  }
+
+  async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError> {
+    let discovery = self.discovery().await?;
+    let claims = self.user_info(&discovery, &access_token).await?;
+    return self.to_user(&discovery, claims);
+  }
+
+  async fn get_user_from_tokens(
+    &self,
+    tokens: &OAuthTokenResponse,
+  ) -> Result<OAuthUser, AuthError> {
+    let Some(ref id_token) = tokens.extra_fields().id_token else {
+      return Err(AuthError::FailedDependency("Missing ID token".into()));
+    };
+
+    let discovery = self.discovery().await?;
+    let mut claims = self.validate_id_token(&discovery, id_token).await?;
+
+    // Providers may omit claims from ID tokens, which are then available from the userinfo
+    // endpoint.
+    if claims.email.is_none() {
+      let user_info = self
+        .user_info(&discovery, tokens.access_token().secret())
+        .await?;
+      if user_info.sub != claims.sub {
+        return Err(AuthError::Unauthorized);
+      }
+      claims = user_info;
+    }
+
+    return self.to_user(&discovery, claims);
+  }
+}
+
+fn select_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+  return match kid {
+    Some(kid) => jwks.find(kid),
+    // Tokens w/o key id are only acceptable if there is no ambiguity.
+    None if jwks.keys.len() == 1 => jwks.keys.first(),
+    None => None,
+  };
+}
+
+fn http_client() -> Result<reqwest::Client, AuthError> {
+  return reqwest::ClientBuilder::new()
+    // Following redirects opens the client up to SSRF vulnerabilities.
+    .redirect(reqwest::redirect::Policy::none())
+    .build()
+    .map_err(|err| AuthError::Internal(err.into()));
+}
+
+async fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T, AuthError> {
+  let response = http_client()?
+    .get(url)
+    .send()
+    .await
+    .and_then(|response| response.error_for_status())
+    .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+  return response
+    .json::<T>()
+    .await
+    .map_err(|err| AuthError::FailedDependency(err.into()));
+}
@@ -26,7 +26,7 @@ impl TestOAuthProvider {
       id: OAuthProviderId::Custom,
       name: Self::NAME,
       display_name: Self::DISPLAY_NAME,
-      factory: Box::new(|config: &OAuthProviderConfig| {
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
         Ok(Box::new(TestOAuthProvider {
           client_id: config.client_id.clone().unwrap(),
           client_secret: config.client_secret.clone().unwrap(),
@@ -48,17 +48,17 @@ pub struct TestUser {
 
 #[async_trait]
 impl OAuthProvider for TestOAuthProvider {
-  fn name(&self) -> &'static str {
+  fn name(&self) -> &str {
     Self::NAME
   }
   fn provider(&self) -> OAuthProviderId {
     OAuthProviderId::Custom
   }
-  fn display_name(&self) -> &'static str {
+  fn display_name(&self) -> &str {
     Self::DISPLAY_NAME
   }
 
-  fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
     return Ok(OAuthClientSettings {
       auth_url: Url::parse(&self.auth_url).unwrap(),
       token_url: Url::parse(&self.token_url).unwrap(),
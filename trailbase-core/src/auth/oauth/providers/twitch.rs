@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use oauth2::AuthType;
+use serde::Deserialize;
+use url::Url;
+
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::auth::oauth::{OAuthClientSettings, OAuthProvider, OAuthUser};
+use crate::auth::AuthError;
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+#[derive(Default, Deserialize, Debug)]
+struct TwitchUser {
+  id: String,
+  // Only present with the "user:read:email" scope and always verified.
+  email: Option<String>,
+  profile_image_url: Option<String>,
+}
+
+#[derive(Default, Deserialize, Debug)]
+struct TwitchUsers {
+  data: Vec<TwitchUser>,
+}
+
+pub(crate) struct TwitchOAuthProvider {
+  client_id: String,
+  client_secret: String,
+}
+
+impl TwitchOAuthProvider {
+  const NAME: &'static str = "twitch";
+  const DISPLAY_NAME: &'static str = "Twitch";
+
+  const AUTH_URL: &'static str = "https://id.twitch.tv/oauth2/authorize";
+  const TOKEN_URL: &'static str = "https://id.twitch.tv/oauth2/token";
+  const USER_API_URL: &'static str = "https://api.twitch.tv/helix/users";
+
+  fn new(config: &OAuthProviderConfig) -> Result<Self, OAuthProviderError> {
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing("Twitch client id".to_string()));
+    };
+    let Some(client_secret) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing(
+        "Twitch client secret".to_string(),
+      ));
+    };
+
+    return Ok(Self {
+      client_id,
+      client_secret,
+    });
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Twitch,
+      name: Self::NAME,
+      display_name: Self::DISPLAY_NAME,
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
+    }
+  }
+}
+
+#[async_trait]
+impl OAuthProvider for TwitchOAuthProvider {
+  fn name(&self) -> &str {
+    Self::NAME
+  }
+  fn provider(&self) -> OAuthProviderId {
+    OAuthProviderId::Twitch
+  }
+  fn display_name(&self) -> &str {
+    Self::DISPLAY_NAME
+  }
+
+  async fn settings(&self) -> Result<OAuthClientSettings, AuthError> {
+    lazy_static! {
+      static ref AUTH_URL: Url = Url::parse(TwitchOAuthProvider::AUTH_URL).unwrap();
+      static ref TOKEN_URL: Url = Url::parse(TwitchOAuthProvider::TOKEN_URL).unwrap();
+    }
+
+    return Ok(OAuthClientSettings {
+      auth_url: AUTH_URL.clone(),
+      token_url: TOKEN_URL.clone(),
+      client_id: self.client_id.clone(),
+      client_secret: self.client_secret.clone(),
+    });
+  }
+
+  fn oauth_scopes(&self) -> Vec<&'static str> {
+    return vec!["user:read:email"];
+  }
+
+  fn oauth_auth_type(&self) -> AuthType {
+    // Twitch expects client credentials in the request body.
+    AuthType::RequestBody
+  }
+
+  async fn get_user(&self, access_token: String) -> Result<OAuthUser, AuthError> {
+    let response = reqwest::Client::new()
+      .get(Self::USER_API_URL)
+      .bearer_auth(access_token)
+      .header("Client-Id", &self.client_id)
+      .send()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    let users = response
+      .json::<TwitchUsers>()
+      .await
+      .map_err(|err| AuthError::FailedDependency(err.into()))?;
+
+    let Some(TwitchUser {
+      id,
+      email: Some(email),
+      profile_image_url,
+    }) = users.data.into_iter().next()
+    else {
+      return Err(AuthError::Unauthorized);
+    };
+
+    return Ok(OAuthUser {
+      provider_user_id: id,
+      provider_id: OAuthProviderId::Twitch,
+      email,
+      verified: true,
+      avatar: profile_image_url,
+    });
+  }
+}
@@ -0,0 +1,44 @@
+use crate::auth::oauth::providers::oidc::OidcOAuthProvider;
+use crate::auth::oauth::providers::{OAuthProviderError, OAuthProviderFactory};
+use crate::config::proto::{OAuthProviderConfig, OAuthProviderId};
+
+/// Yahoo implements OpenID Connect, thus endpoints are discovered and ID tokens validated.
+pub(crate) struct YahooOAuthProvider;
+
+impl YahooOAuthProvider {
+  const NAME: &'static str = "yahoo";
+  const DISPLAY_NAME: &'static str = "Yahoo";
+
+  const ISSUER_URL: &'static str = "https://api.login.yahoo.com";
+
+  fn new(config: &OAuthProviderConfig) -> Result<OidcOAuthProvider, OAuthProviderError> {
+    let Some(client_id) = config.client_id.clone() else {
+      return Err(OAuthProviderError::Missing("Yahoo client id".to_string()));
+    };
+    let Some(client_secret) = config.client_secret.clone() else {
+      return Err(OAuthProviderError::Missing(
+        "Yahoo client secret".to_string(),
+      ));
+    };
+
+    return Ok(OidcOAuthProvider::for_issuer(
+      OAuthProviderId::Yahoo,
+      Self::NAME,
+      Self::DISPLAY_NAME,
+      Self::ISSUER_URL,
+      client_id,
+      client_secret,
+    ));
+  }
+
+  pub fn factory() -> OAuthProviderFactory {
+    OAuthProviderFactory {
+      id: OAuthProviderId::Yahoo,
+      name: Self::NAME,
+      display_name: Self::DISPLAY_NAME,
+      factory: Box::new(|_name: &str, config: &OAuthProviderConfig| {
+        Ok(Box::new(Self::new(config)?))
+      }),
+    }
+  }
+}
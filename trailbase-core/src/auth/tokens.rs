@@ -10,7 +10,7 @@ use trailbase_sqlite::params;
 use crate::app_state::AppState;
 use crate::auth::jwt::TokenClaims;
 use crate::auth::user::DbUser;
-use crate::auth::util::{extract_cookies_from_parts, new_cookie};
+use crate::auth::util::{extract_cookies_from_parts, new_cookie, user_custom_claims, user_roles};
 use crate::auth::AuthError;
 use crate::constants::{
   COOKIE_AUTH_TOKEN, COOKIE_REFRESH_TOKEN, HEADER_REFRESH_TOKEN, REFRESH_TOKEN_LENGTH,
@@ -174,7 +174,9 @@ pub(crate) async fn mint_new_tokens(
     ));
   }
 
-  let claims = TokenClaims::new(verified, user_id, user_email, expires_in);
+  let mut claims = TokenClaims::new(verified, user_id, user_email, expires_in);
+  claims.roles = user_roles(state, &user_id).await?;
+  claims.custom_claims = user_custom_claims(state, &user_id).await?;
 
   // Unlike JWT auth tokens, refresh tokens are opaque.
   let refresh_token = generate_random_string(REFRESH_TOKEN_LENGTH);
@@ -240,10 +242,11 @@ pub(crate) async fn reauth_with_refresh_token(
     "unverified user, should have been caught by above query"
   );
 
-  return Ok(TokenClaims::new(
-    db_user.verified,
-    db_user.uuid(),
-    db_user.email,
-    auth_token_ttl,
-  ));
+  let user_id = db_user.uuid();
+  let mut claims = TokenClaims::new(db_user.verified, user_id, db_user.email, auth_token_ttl);
+  claims.roles = user_roles(state, &user_id).await?;
+  // NOTE: Same as when minting, s.t. refreshed tokens carry identical claims.
+  claims.custom_claims = user_custom_claims(state, &user_id).await?;
+
+  return Ok(claims);
 }
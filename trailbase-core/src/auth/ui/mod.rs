@@ -19,6 +19,7 @@ fn build_env() -> Environment<'static> {
 
   lazy_static! {
     static ref login_template: String = get("login/index.html");
+    static ref login_mfa_template: String = get("login_mfa/index.html");
     static ref register_template: String = get("register/index.html");
     static ref reset_password_request_template: String = get("reset_password/request/index.html");
     static ref reset_password_update_template: String = get("reset_password/update/index.html");
@@ -29,6 +30,7 @@ fn build_env() -> Environment<'static> {
   let mut env = Environment::new();
 
   env.add_template("login", &login_template).unwrap();
+  env.add_template("login_mfa", &login_mfa_template).unwrap();
   env.add_template("register", &register_template).unwrap();
   env
     .add_template("reset_password_request", &reset_password_request_template)
@@ -94,6 +96,44 @@ async fn ui_login_handler(Query(query): Query<LoginQuery>, user: Option<User>) -
   };
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct LoginMfaQuery {
+  mfa_token: String,
+  redirect_to: Option<String>,
+  response_type: Option<String>,
+  pkce_code_challenge: Option<String>,
+  alert: Option<String>,
+}
+
+async fn ui_login_mfa_handler(Query(query): Query<LoginMfaQuery>) -> Response {
+  let form_state = indoc::formatdoc!(
+    r#"
+    {mfa_token}
+    {redirect_to}
+    {response_type}
+    {pkce_code_challenge}
+  "#,
+    mfa_token = hidden_input("mfa_token", Some(&query.mfa_token)),
+    redirect_to = hidden_input("redirect_to", query.redirect_to.as_ref()),
+    response_type = hidden_input("response_type", query.response_type.as_ref()),
+    pkce_code_challenge = hidden_input("pkce_code_challenge", query.pkce_code_challenge.as_ref()),
+  );
+
+  let ctx = context! {
+    alert => query.alert.as_deref().unwrap_or(""),
+    state => form_state,
+  };
+
+  return match templates().get_template("login_mfa").unwrap().render(ctx) {
+    Ok(output) => Html(output).into_response(),
+    Err(err) => (
+      StatusCode::INTERNAL_SERVER_ERROR,
+      format!("failed to render template: {err}"),
+    )
+      .into_response(),
+  };
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct RegisterQuery {
   redirect_to: Option<String>,
@@ -247,6 +287,7 @@ pub(crate) fn auth_ui_router() -> Router<crate::AppState> {
 
   return Router::new()
     .route("/_/auth/login", get(ui_login_handler))
+    .route("/_/auth/login_mfa", get(ui_login_mfa_handler))
     .route("/_/auth/logout", get(ui_logout_handler))
     .route("/_/auth/register", get(ui_register_handler))
     .route(
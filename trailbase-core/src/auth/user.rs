@@ -39,6 +39,20 @@ pub(crate) struct DbUser {
   pub provider_id: i64,
   pub provider_user_id: Option<String>,
   pub provider_avatar_url: Option<String>,
+
+  // Passwordless login flow.
+  pub magic_link_code: Option<String>,
+  pub magic_link_otp: Option<String>,
+  pub magic_link_sent_at: Option<i64>,
+  pub magic_link_attempts: i64,
+
+  // Brute-force protection.
+  pub failed_login_attempts: i64,
+  pub last_failed_login: Option<i64>,
+  pub locked_until: Option<i64>,
+
+  /// Admin-editable JSON metadata, which may be embedded into auth tokens as custom claims.
+  pub metadata: Option<String>,
 }
 
 impl DbUser {
@@ -68,6 +82,12 @@ pub struct User {
 
   /// The "expected" CSRF token as included in the auth token claims [User] was constructed from.
   pub csrf_token: String,
+
+  /// Effective roles as included in the auth token claims.
+  pub roles: Vec<String>,
+
+  /// Custom claims as included in the auth token claims.
+  pub custom_claims: serde_json::Map<String, serde_json::Value>,
 }
 
 impl PartialEq for User {
@@ -90,6 +110,8 @@ impl User {
       email: claims.email,
       uuid,
       csrf_token: claims.csrf_token,
+      roles: claims.roles,
+      custom_claims: claims.custom_claims,
     });
   }
 
@@ -105,6 +127,8 @@ impl User {
       email: email.to_string(),
       uuid: user_id,
       csrf_token: crate::rand::generate_random_string(20),
+      roles: vec![],
+      custom_claims: serde_json::Map::new(),
     };
   }
 }
@@ -3,6 +3,7 @@ use base64::prelude::*;
 use chrono::Duration;
 use lazy_static::lazy_static;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use tower_cookies::{
   cookie::{self, SameSite},
   Cookie, Cookies,
@@ -12,7 +13,8 @@ use trailbase_sqlite::params;
 use crate::auth::user::{DbUser, User};
 use crate::auth::AuthError;
 use crate::constants::{
-  COOKIE_AUTH_TOKEN, COOKIE_OAUTH_STATE, COOKIE_REFRESH_TOKEN, SESSION_TABLE, USER_TABLE,
+  COOKIE_AUTH_TOKEN, COOKIE_OAUTH_STATE, COOKIE_REFRESH_TOKEN, GROUP_MEMBER_TABLE,
+  GROUP_ROLE_TABLE, SESSION_TABLE, USER_ROLE_TABLE, USER_TABLE,
 };
 use crate::AppState;
 
@@ -198,6 +200,81 @@ pub(crate) async fn is_admin(state: &AppState, user: &User) -> bool {
   return row.get::<bool>(0).unwrap_or(false);
 }
 
+/// Returns the user's effective roles, i.e. roles granted directly or via any of their groups.
+pub(crate) async fn user_roles(
+  state: &AppState,
+  user_id: &uuid::Uuid,
+) -> Result<Vec<String>, AuthError> {
+  lazy_static! {
+    static ref QUERY: String = format!(
+      r#"
+        SELECT role FROM "{USER_ROLE_TABLE}" WHERE user = $1
+        UNION
+        SELECT gr.role
+        FROM
+          "{GROUP_ROLE_TABLE}" AS gr
+          INNER JOIN "{GROUP_MEMBER_TABLE}" AS gm ON gm.group_name = gr.group_name
+        WHERE
+          gm.user = $1
+        ORDER BY role
+      "#
+    );
+  };
+
+  return Ok(
+    state
+      .user_conn()
+      .query_values::<String>(&QUERY, params!(user_id.into_bytes()))
+      .await?,
+  );
+}
+
+/// Returns the custom claims for the user's current metadata according to the configured mapping.
+pub(crate) async fn user_custom_claims(
+  state: &AppState,
+  user_id: &uuid::Uuid,
+) -> Result<serde_json::Map<String, serde_json::Value>, AuthError> {
+  let mapping = state.access_config(|c| c.auth.custom_claims.clone());
+  if mapping.is_empty() {
+    return Ok(serde_json::Map::new());
+  }
+
+  lazy_static! {
+    static ref QUERY: String = format!(r#"SELECT metadata FROM "{USER_TABLE}" WHERE id = $1"#);
+  };
+
+  let metadata = state
+    .user_conn()
+    .query_row(&QUERY, params!(user_id.into_bytes()))
+    .await?
+    .and_then(|row| row.get::<Option<String>>(0).ok().flatten());
+
+  return Ok(custom_claims(&mapping, metadata.as_deref()));
+}
+
+/// Picks custom claims from the top-level keys of a user's JSON metadata.
+///
+/// `mapping` maps claim names to metadata keys. Keys missing from the metadata are skipped.
+fn custom_claims(
+  mapping: &HashMap<String, String>,
+  metadata: Option<&str>,
+) -> serde_json::Map<String, serde_json::Value> {
+  if mapping.is_empty() {
+    return serde_json::Map::new();
+  }
+
+  let Some(serde_json::Value::Object(metadata)) =
+    metadata.and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+  else {
+    return serde_json::Map::new();
+  };
+
+  return mapping
+    .iter()
+    .filter_map(|(claim, key)| Some((claim.clone(), metadata.get(key)?.clone())))
+    .collect();
+}
+
 pub(crate) async fn delete_all_sessions_for_user(
   state: &AppState,
   user_id: uuid::Uuid,
@@ -0,0 +1,585 @@
+//! Minimal WebAuthn relying party for passkey registration and authentication.
+//!
+//! We request "none" attestation and thus don't verify attestation statements. Supported
+//! credential algorithms are ES256 and EdDSA (Ed25519), which covers all mainstream passkey
+//! providers.
+
+use base64::prelude::*;
+use ciborium::value::Value;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// COSE algorithm identifier for ECDSA w/ SHA-256 on P-256.
+pub(crate) const COSE_ALG_ES256: i64 = -7;
+/// COSE algorithm identifier for EdDSA, i.e. Ed25519 for passkeys.
+pub(crate) const COSE_ALG_EDDSA: i64 = -8;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Debug, Error)]
+pub enum WebAuthnError {
+  #[error("Base64: {0}")]
+  Base64(#[from] base64::DecodeError),
+  #[error("Client data: {0}")]
+  ClientData(#[from] serde_json::Error),
+  #[error("CBOR: {0}")]
+  Cbor(String),
+  #[error("Invalid: {0}")]
+  Invalid(&'static str),
+  #[error("Unsupported algorithm: {0}")]
+  UnsupportedAlgorithm(i64),
+  #[error("Signature verification failed")]
+  Signature,
+}
+
+/// The relying party, i.e. us, as seen by authenticators.
+#[derive(Debug, Clone)]
+pub(crate) struct RelyingParty {
+  /// Effective domain, e.g. "example.com".
+  pub id: String,
+  /// Expected origin of client data, e.g. "https://example.com".
+  pub origin: String,
+  /// Also accept "http://localhost:<port>" origins, e.g. for dev servers.
+  pub allow_localhost: bool,
+}
+
+impl RelyingParty {
+  pub(crate) fn from_site_url(site_url: &str, dev: bool) -> Result<Self, WebAuthnError> {
+    let url = url::Url::parse(site_url).map_err(|_| WebAuthnError::Invalid("site url"))?;
+    let Some(host) = url.host_str() else {
+      return Err(WebAuthnError::Invalid("site url"));
+    };
+
+    return Ok(RelyingParty {
+      id: host.to_string(),
+      origin: url.origin().ascii_serialization(),
+      allow_localhost: dev,
+    });
+  }
+
+  fn valid_origin(&self, origin: &str) -> bool {
+    if origin == self.origin {
+      return true;
+    }
+    if !self.allow_localhost {
+      return false;
+    }
+
+    // Compare the parsed host rather than a prefix, which would also match e.g.
+    // "http://localhost.evil.com".
+    let Ok(url) = url::Url::parse(origin) else {
+      return false;
+    };
+    return url.scheme() == "http"
+      && url.host() == Some(url::Host::Domain("localhost"))
+      && url.origin().ascii_serialization() == origin;
+  }
+}
+
+/// A newly registered credential.
+#[derive(Debug, Clone)]
+pub(crate) struct NewCredential {
+  pub credential_id: Vec<u8>,
+  /// COSE algorithm identifier.
+  pub algorithm: i64,
+  /// SEC1-encoded point for ES256 or raw 32-byte key for EdDSA.
+  pub public_key: Vec<u8>,
+  pub sign_count: u32,
+}
+
+/// A previously registered credential's public key.
+#[derive(Debug, Clone)]
+pub(crate) struct CredentialKey {
+  pub algorithm: i64,
+  pub public_key: Vec<u8>,
+  pub sign_count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Assertion {
+  pub sign_count: u32,
+  pub user_verified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientData {
+  #[serde(rename = "type")]
+  type_: String,
+  challenge: String,
+  origin: String,
+  #[serde(rename = "crossOrigin")]
+  cross_origin: Option<bool>,
+}
+
+struct AuthenticatorData<'a> {
+  rp_id_hash: &'a [u8],
+  flags: u8,
+  sign_count: u32,
+  /// Attested credential data and extensions, if any.
+  rest: &'a [u8],
+}
+
+/// Generates a new random challenge, base64url-encoded.
+pub(crate) fn generate_challenge() -> String {
+  use rand::{rngs::OsRng, RngCore};
+
+  let mut challenge = [0u8; 32];
+  OsRng.fill_bytes(&mut challenge);
+  return BASE64_URL_SAFE_NO_PAD.encode(challenge);
+}
+
+pub(crate) fn decode_b64(s: &str) -> Result<Vec<u8>, WebAuthnError> {
+  return Ok(BASE64_URL_SAFE_NO_PAD.decode(s.trim_end_matches('='))?);
+}
+
+pub(crate) fn encode_b64(bytes: &[u8]) -> String {
+  return BASE64_URL_SAFE_NO_PAD.encode(bytes);
+}
+
+/// Extracts the challenge from client data without verifying it, e.g. to look up the pending
+/// challenge.
+pub(crate) fn client_data_challenge(client_data_json: &[u8]) -> Result<String, WebAuthnError> {
+  let client_data: ClientData = serde_json::from_slice(client_data_json)?;
+  return Ok(client_data.challenge);
+}
+
+/// Verifies a registration response, i.e. the result of `navigator.credentials.create()`.
+pub(crate) fn verify_registration(
+  rp: &RelyingParty,
+  challenge: &str,
+  client_data_json: &[u8],
+  attestation_object: &[u8],
+) -> Result<NewCredential, WebAuthnError> {
+  verify_client_data(rp, "webauthn.create", challenge, client_data_json)?;
+
+  let attestation: Value = ciborium::de::from_reader(attestation_object)
+    .map_err(|err| WebAuthnError::Cbor(err.to_string()))?;
+  let Some(auth_data) =
+    map_get(&attestation, |k| k.as_text() == Some("authData")).and_then(|v| v.as_bytes())
+  else {
+    return Err(WebAuthnError::Invalid("missing authData"));
+  };
+
+  let auth_data = parse_authenticator_data(rp, auth_data)?;
+  if auth_data.flags & FLAG_ATTESTED_CREDENTIAL_DATA == 0 {
+    return Err(WebAuthnError::Invalid("missing attested credential data"));
+  }
+
+  // Attested credential data: AAGUID (16) | credential id length (2) | credential id | COSE key.
+  let rest = auth_data.rest;
+  if rest.len() < 18 {
+    return Err(WebAuthnError::Invalid("attested credential data"));
+  }
+  let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+  if rest.len() < 18 + id_len {
+    return Err(WebAuthnError::Invalid("credential id"));
+  }
+  let credential_id = rest[18..18 + id_len].to_vec();
+
+  let cose_key: Value = ciborium::de::from_reader(&rest[18 + id_len..])
+    .map_err(|err| WebAuthnError::Cbor(err.to_string()))?;
+  let (algorithm, public_key) = parse_cose_key(&cose_key)?;
+
+  return Ok(NewCredential {
+    credential_id,
+    algorithm,
+    public_key,
+    sign_count: auth_data.sign_count,
+  });
+}
+
+/// Verifies an authentication response, i.e. the result of `navigator.credentials.get()`.
+pub(crate) fn verify_assertion(
+  rp: &RelyingParty,
+  challenge: &str,
+  credential: &CredentialKey,
+  client_data_json: &[u8],
+  authenticator_data: &[u8],
+  signature: &[u8],
+) -> Result<Assertion, WebAuthnError> {
+  verify_client_data(rp, "webauthn.get", challenge, client_data_json)?;
+  let auth_data = parse_authenticator_data(rp, authenticator_data)?;
+
+  let mut signed = authenticator_data.to_vec();
+  signed.extend_from_slice(&Sha256::digest(client_data_json));
+  verify_signature(
+    credential.algorithm,
+    &credential.public_key,
+    &signed,
+    signature,
+  )?;
+
+  // Authenticators, which don't support counters, always report zero. Otherwise, the counter has
+  // to increase to detect cloned authenticators.
+  if (auth_data.sign_count != 0 || credential.sign_count != 0)
+    && auth_data.sign_count <= credential.sign_count
+  {
+    return Err(WebAuthnError::Invalid("signature counter"));
+  }
+
+  return Ok(Assertion {
+    sign_count: auth_data.sign_count,
+    user_verified: auth_data.flags & FLAG_USER_VERIFIED != 0,
+  });
+}
+
+fn verify_client_data(
+  rp: &RelyingParty,
+  expected_type: &str,
+  challenge: &str,
+  client_data_json: &[u8],
+) -> Result<(), WebAuthnError> {
+  let client_data: ClientData = serde_json::from_slice(client_data_json)?;
+
+  if client_data.type_ != expected_type {
+    return Err(WebAuthnError::Invalid("client data type"));
+  }
+  if client_data.challenge.trim_end_matches('=') != challenge {
+    return Err(WebAuthnError::Invalid("challenge"));
+  }
+  if !rp.valid_origin(&client_data.origin) {
+    return Err(WebAuthnError::Invalid("origin"));
+  }
+  if client_data.cross_origin == Some(true) {
+    return Err(WebAuthnError::Invalid("cross origin"));
+  }
+
+  return Ok(());
+}
+
+fn parse_authenticator_data<'a>(
+  rp: &RelyingParty,
+  data: &'a [u8],
+) -> Result<AuthenticatorData<'a>, WebAuthnError> {
+  // RP id hash (32) | flags (1) | signature counter (4) | ... .
+  if data.len() < 37 {
+    return Err(WebAuthnError::Invalid("authenticator data"));
+  }
+
+  let auth_data = AuthenticatorData {
+    rp_id_hash: &data[0..32],
+    flags: data[32],
+    sign_count: u32::from_be_bytes([data[33], data[34], data[35], data[36]]),
+    rest: &data[37..],
+  };
+
+  if auth_data.rp_id_hash != Sha256::digest(rp.id.as_bytes()).as_slice() {
+    return Err(WebAuthnError::Invalid("rp id"));
+  }
+  if auth_data.flags & FLAG_USER_PRESENT == 0 {
+    return Err(WebAuthnError::Invalid("user not present"));
+  }
+
+  return Ok(auth_data);
+}
+
+fn map_get(map: &Value, pred: impl Fn(&Value) -> bool) -> Option<&Value> {
+  return map
+    .as_map()?
+    .iter()
+    .find_map(|(k, v)| if pred(k) { Some(v) } else { None });
+}
+
+fn cose_get(key: &Value, label: i64) -> Option<&Value> {
+  return map_get(key, |k| {
+    k.as_integer().map(i128::from) == Some(label as i128)
+  });
+}
+
+fn cose_int(key: &Value, label: i64) -> Option<i64> {
+  return cose_get(key, label)
+    .and_then(|v| v.as_integer())
+    .and_then(|i| i64::try_from(i).ok());
+}
+
+/// Parses a COSE_Key into its algorithm and raw public key.
+fn parse_cose_key(key: &Value) -> Result<(i64, Vec<u8>), WebAuthnError> {
+  const KTY: i64 = 1;
+  const ALG: i64 = 3;
+  const CRV: i64 = -1;
+  const X: i64 = -2;
+  const Y: i64 = -3;
+
+  let Some(alg) = cose_int(key, ALG) else {
+    return Err(WebAuthnError::Invalid("COSE algorithm"));
+  };
+  let coordinate = |label: i64| -> Result<&[u8], WebAuthnError> {
+    return match cose_get(key, label).and_then(|v| v.as_bytes()) {
+      Some(bytes) if bytes.len() == 32 => Ok(bytes),
+      _ => Err(WebAuthnError::Invalid("COSE key coordinate")),
+    };
+  };
+
+  return match alg {
+    COSE_ALG_ES256 => {
+      // EC2 key type on curve P-256.
+      if cose_int(key, KTY) != Some(2) || cose_int(key, CRV) != Some(1) {
+        return Err(WebAuthnError::Invalid("COSE key type"));
+      }
+      // Uncompressed SEC1 point.
+      let mut point = vec![0x04];
+      point.extend_from_slice(coordinate(X)?);
+      point.extend_from_slice(coordinate(Y)?);
+      Ok((alg, point))
+    }
+    COSE_ALG_EDDSA => {
+      // OKP key type on curve Ed25519.
+      if cose_int(key, KTY) != Some(1) || cose_int(key, CRV) != Some(6) {
+        return Err(WebAuthnError::Invalid("COSE key type"));
+      }
+      Ok((alg, coordinate(X)?.to_vec()))
+    }
+    alg => Err(WebAuthnError::UnsupportedAlgorithm(alg)),
+  };
+}
+
+fn verify_signature(
+  algorithm: i64,
+  public_key: &[u8],
+  message: &[u8],
+  signature: &[u8],
+) -> Result<(), WebAuthnError> {
+  match algorithm {
+    COSE_ALG_ES256 => {
+      use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+      let key = VerifyingKey::from_sec1_bytes(public_key).map_err(|_| WebAuthnError::Signature)?;
+      let signature = Signature::from_der(signature).map_err(|_| WebAuthnError::Signature)?;
+      key
+        .verify(message, &signature)
+        .map_err(|_| WebAuthnError::Signature)?;
+    }
+    COSE_ALG_EDDSA => {
+      use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+      let Ok(public_key) = <[u8; 32]>::try_from(public_key) else {
+        return Err(WebAuthnError::Signature);
+      };
+      let key = VerifyingKey::from_bytes(&public_key).map_err(|_| WebAuthnError::Signature)?;
+      let signature = Signature::from_slice(signature).map_err(|_| WebAuthnError::Signature)?;
+      key
+        .verify(message, &signature)
+        .map_err(|_| WebAuthnError::Signature)?;
+    }
+    alg => {
+      return Err(WebAuthnError::UnsupportedAlgorithm(alg));
+    }
+  };
+
+  return Ok(());
+}
+
+/// A software authenticator emulating a platform authenticator with an ES256 passkey.
+#[cfg(test)]
+pub(crate) mod testing {
+  use ciborium::value::Value;
+  use p256::ecdsa::{signature::Signer, DerSignature, SigningKey};
+  use rand::rngs::OsRng;
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+
+  pub(crate) struct SoftAuthenticator {
+    pub credential_id: Vec<u8>,
+    pub sign_count: u32,
+    signing_key: SigningKey,
+  }
+
+  impl SoftAuthenticator {
+    pub(crate) fn new() -> Self {
+      return Self {
+        credential_id: generate_challenge().into_bytes(),
+        sign_count: 0,
+        signing_key: SigningKey::random(&mut OsRng),
+      };
+    }
+
+    pub(crate) fn client_data(typ: &str, challenge: &str, origin: &str) -> Vec<u8> {
+      return serde_json::json!({
+        "type": typ,
+        "challenge": challenge,
+        "origin": origin,
+        "crossOrigin": false,
+      })
+      .to_string()
+      .into_bytes();
+    }
+
+    fn authenticator_data(&self, rp_id: &str, flags: u8) -> Vec<u8> {
+      let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+      data.push(flags);
+      data.extend_from_slice(&self.sign_count.to_be_bytes());
+      return data;
+    }
+
+    /// Returns client data JSON and attestation object.
+    pub(crate) fn register(&mut self, rp: &RelyingParty, challenge: &str) -> (Vec<u8>, Vec<u8>) {
+      let point = self.signing_key.verifying_key().to_encoded_point(false);
+      let cose_key = Value::Map(vec![
+        (Value::from(1), Value::from(2)),
+        (Value::from(3), Value::from(COSE_ALG_ES256)),
+        (Value::from(-1), Value::from(1)),
+        (Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+        (Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+      ]);
+
+      let mut auth_data = self.authenticator_data(
+        &rp.id,
+        FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA,
+      );
+      auth_data.extend_from_slice(&[0u8; 16]);
+      auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+      auth_data.extend_from_slice(&self.credential_id);
+      ciborium::ser::into_writer(&cose_key, &mut auth_data).unwrap();
+
+      let attestation = Value::Map(vec![
+        (Value::from("fmt"), Value::from("none")),
+        (Value::from("attStmt"), Value::Map(vec![])),
+        (Value::from("authData"), Value::Bytes(auth_data)),
+      ]);
+      let mut attestation_object = vec![];
+      ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+
+      return (
+        Self::client_data("webauthn.create", challenge, &rp.origin),
+        attestation_object,
+      );
+    }
+
+    /// Returns client data JSON, authenticator data and signature.
+    pub(crate) fn assert(
+      &mut self,
+      rp: &RelyingParty,
+      challenge: &str,
+    ) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+      self.sign_count += 1;
+
+      let client_data_json = Self::client_data("webauthn.get", challenge, &rp.origin);
+      let auth_data = self.authenticator_data(&rp.id, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+
+      let mut signed = auth_data.clone();
+      signed.extend_from_slice(&Sha256::digest(&client_data_json));
+      let signature: DerSignature = self.signing_key.sign(&signed);
+
+      return (client_data_json, auth_data, signature.as_bytes().to_vec());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::testing::SoftAuthenticator;
+  use super::*;
+
+  #[test]
+  fn test_valid_origin() {
+    let rp = RelyingParty::from_site_url("https://example.com", false).unwrap();
+    assert!(rp.valid_origin("https://example.com"));
+    assert!(!rp.valid_origin("http://localhost:3000"));
+
+    let rp = RelyingParty::from_site_url("https://example.com", true).unwrap();
+    assert!(rp.valid_origin("https://example.com"));
+    assert!(rp.valid_origin("http://localhost"));
+    assert!(rp.valid_origin("http://localhost:3000"));
+
+    for origin in [
+      "http://localhost.evil.com",
+      "http://localhost.evil.com:3000",
+      "http://localhost@evil.com",
+      "http://localhost:3000/path",
+      "https://localhost:3000",
+      "http://127.0.0.1:3000",
+      "localhost",
+    ] {
+      assert!(!rp.valid_origin(origin), "{origin}");
+    }
+  }
+
+  #[test]
+  fn test_webauthn_registration_and_assertion() {
+    let rp = RelyingParty::from_site_url("https://example.com:8080/some/path", false).unwrap();
+    assert_eq!("example.com", rp.id);
+    assert_eq!("https://example.com:8080", rp.origin);
+
+    let mut authenticator = SoftAuthenticator::new();
+
+    let challenge = generate_challenge();
+    let (client_data_json, attestation_object) = authenticator.register(&rp, &challenge);
+    assert!(verify_registration(
+      &rp,
+      &generate_challenge(),
+      &client_data_json,
+      &attestation_object
+    )
+    .is_err());
+
+    let credential =
+      verify_registration(&rp, &challenge, &client_data_json, &attestation_object).unwrap();
+    assert_eq!(authenticator.credential_id, credential.credential_id);
+    assert_eq!(COSE_ALG_ES256, credential.algorithm);
+
+    let key = CredentialKey {
+      algorithm: credential.algorithm,
+      public_key: credential.public_key,
+      sign_count: credential.sign_count,
+    };
+
+    let challenge = generate_challenge();
+    let (client_data_json, auth_data, signature) = authenticator.assert(&rp, &challenge);
+    let assertion = verify_assertion(
+      &rp,
+      &challenge,
+      &key,
+      &client_data_json,
+      &auth_data,
+      &signature,
+    )
+    .unwrap();
+    assert_eq!(1, assertion.sign_count);
+    assert!(assertion.user_verified);
+
+    // Tampered authenticator data.
+    let mut tampered = auth_data.clone();
+    tampered[36] += 1;
+    assert!(verify_assertion(
+      &rp,
+      &challenge,
+      &key,
+      &client_data_json,
+      &tampered,
+      &signature
+    )
+    .is_err());
+
+    // Replayed counter.
+    let key = CredentialKey {
+      sign_count: assertion.sign_count,
+      ..key
+    };
+    assert!(verify_assertion(
+      &rp,
+      &challenge,
+      &key,
+      &client_data_json,
+      &auth_data,
+      &signature
+    )
+    .is_err());
+
+    // Foreign origin.
+    let other_rp = RelyingParty::from_site_url("https://evil.com", false).unwrap();
+    let (client_data_json, auth_data, signature) = authenticator.assert(&other_rp, &challenge);
+    assert!(verify_assertion(
+      &rp,
+      &challenge,
+      &key,
+      &client_data_json,
+      &auth_data,
+      &signature
+    )
+    .is_err());
+  }
+}
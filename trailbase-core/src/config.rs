@@ -11,7 +11,7 @@ use tokio::fs;
 use validator::{ValidateEmail, ValidateUrl};
 
 use crate::data_dir::DataDir;
-use crate::records::validate_record_api_config;
+use crate::records::{validate_record_api_children, validate_record_api_config};
 use crate::table_metadata::TableMetadataCache;
 use crate::DESCRIPTOR_POOL;
 
@@ -95,8 +95,9 @@ pub mod proto {
 
   use crate::config::ConfigError;
   use crate::constants::{
-    AVATAR_TABLE, DEFAULT_AUTH_TOKEN_TTL, DEFAULT_REFRESH_TOKEN_TTL, LOGS_RETENTION_DEFAULT,
-    SITE_URL_DEFAULT,
+    AVATAR_TABLE, DEFAULT_AUTH_TOKEN_TTL, DEFAULT_LOCKOUT_DURATION, DEFAULT_MAGIC_LINK_TTL,
+    DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS, DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP,
+    DEFAULT_REFRESH_TOKEN_TTL, LOGS_RETENTION_DEFAULT, SITE_URL_DEFAULT,
   };
   use crate::email;
   use crate::DESCRIPTOR_POOL;
@@ -135,6 +136,7 @@ pub mod proto {
       // however it lets us tie into the set update-config Admin UI flow to let users change the
       // templates.
       let mut config = Config {
+        version: Some(CONFIG_VERSION),
         server: ServerConfig {
           application_name: Some("TrailBase".to_string()),
           site_url: Some(SITE_URL_DEFAULT.to_string()),
@@ -145,11 +147,16 @@ pub mod proto {
           user_verification_template: Some(email::defaults::email_validation_email()),
           password_reset_template: Some(email::defaults::password_reset_email()),
           change_email_template: Some(email::defaults::change_email_address_email()),
+          magic_link_template: Some(email::defaults::magic_link_email()),
           ..Default::default()
         },
         auth: AuthConfig {
           auth_token_ttl_sec: Some(DEFAULT_AUTH_TOKEN_TTL.num_seconds()),
           refresh_token_ttl_sec: Some(DEFAULT_REFRESH_TOKEN_TTL.num_seconds()),
+          magic_link_ttl_sec: Some(DEFAULT_MAGIC_LINK_TTL.num_seconds()),
+          max_failed_login_attempts: Some(DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS),
+          max_failed_login_attempts_per_ip: Some(DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP),
+          lockout_duration_sec: Some(DEFAULT_LOCKOUT_DURATION.num_seconds()),
           ..Default::default()
         },
         ..Default::default()
@@ -173,7 +180,15 @@ pub mod proto {
         update_access_rule: Some("_ROW_.user = _USER_.id".to_string()),
         delete_access_rule: Some("_ROW_.user = _USER_.id".to_string()),
         schema_access_rule: None,
+        list_access_rule: None,
         expand: vec![],
+        max_list_offset: None,
+        hidden_columns: vec![],
+        read_only_columns: vec![],
+        max_expand_depth: None,
+        expand_children: vec![],
+        search_columns: vec![],
+        webhooks: vec![],
       }];
 
       return config;
@@ -213,6 +228,28 @@ pub mod proto {
           .map_or(DEFAULT_REFRESH_TOKEN_TTL, Duration::seconds),
       );
     }
+
+    pub fn magic_link_ttl(&self) -> Duration {
+      return self
+        .magic_link_ttl_sec
+        .map_or(DEFAULT_MAGIC_LINK_TTL, Duration::seconds);
+    }
+
+    /// Returns the max failed login attempts per account and per IP, where 0 means unlimited,
+    /// and the lockout duration.
+    pub fn lockout_options(&self) -> (i64, i64, Duration) {
+      return (
+        self
+          .max_failed_login_attempts
+          .unwrap_or(DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS) as i64,
+        self
+          .max_failed_login_attempts_per_ip
+          .unwrap_or(DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP) as i64,
+        self
+          .lockout_duration_sec
+          .map_or(DEFAULT_LOCKOUT_DURATION, Duration::seconds),
+      );
+    }
   }
 }
 
@@ -273,7 +310,7 @@ fn recursively_merge_vault_and_env(
           Value::List(_child_list) => {
             // There isn't really a good way for us to support mapping env variables to repeated
             // fields. Hard-coding the index in the variable name sounds brittle. Instead, we just
-            // don't support it.
+            // don't support it. Webhook secrets are the exception, see `webhook_secret_var_name`.
             trace!("Skipping repeated field: {name}", name = field_descr.name());
             continue;
           }
@@ -321,13 +358,43 @@ fn recursively_merge_vault_and_env(
   return Ok(());
 }
 
+/// Variable name of a webhook's secret. Webhooks live in a repeated field and are thus keyed by
+/// their API's name and their stable id, e.g. `TRAIL_RECORD_APIS_WEBHOOKS_orders/billing_SECRET`.
+/// Unlike other variable names, the key is case-sensitive to avoid collisions.
+fn webhook_secret_var_name(api_name: &str, webhook_id: &str) -> String {
+  return format!("TRAIL_RECORD_APIS_WEBHOOKS_{api_name}/{webhook_id}_SECRET");
+}
+
+fn merge_webhook_secrets(config: &mut proto::Config, vault: &proto::Vault) {
+  for api in &mut config.record_apis {
+    let Some(ref api_name) = api.name else {
+      continue;
+    };
+
+    for webhook in &mut api.webhooks {
+      let Some(ref id) = webhook.id else {
+        continue;
+      };
+
+      let var_name = webhook_secret_var_name(api_name, id);
+      if let Ok(Some(value)) = parse_env_var::<String>(&var_name) {
+        webhook.secret = Some(value);
+      } else if let Some(stored_secret) = vault.secrets.get(&var_name) {
+        webhook.secret = Some(stored_secret.to_string());
+      }
+    }
+  }
+}
+
 fn merge_vault_and_env(
   config: proto::Config,
   vault: proto::Vault,
 ) -> Result<proto::Config, ConfigError> {
   let mut dyn_config = config.transcode_to_dynamic();
   recursively_merge_vault_and_env(&mut dyn_config, &vault, vec![])?;
-  return Ok(dyn_config.transcode_to::<proto::Config>()?);
+  let mut merged = dyn_config.transcode_to::<proto::Config>()?;
+  merge_webhook_secrets(&mut merged, &vault);
+  return Ok(merged);
 }
 
 fn recursively_strip_secrets(
@@ -388,7 +455,24 @@ pub(crate) fn strip_secrets(
   let mut secrets = HashMap::<String, String>::new();
   let mut dyn_config = config.transcode_to_dynamic();
   recursively_strip_secrets(&mut dyn_config, &mut secrets, vec![])?;
-  let stripped = dyn_config.transcode_to::<proto::Config>()?;
+  let mut stripped = dyn_config.transcode_to::<proto::Config>()?;
+
+  // Webhooks without an id cannot be mapped to the vault and thus keep their secret.
+  for api in &mut stripped.record_apis {
+    let Some(ref api_name) = api.name else {
+      continue;
+    };
+
+    for webhook in &mut api.webhooks {
+      let Some(ref id) = webhook.id else {
+        continue;
+      };
+
+      if let Some(secret) = webhook.secret.take() {
+        secrets.insert(webhook_secret_var_name(api_name, id), secret);
+      }
+    }
+  }
 
   return Ok((stripped, secrets));
 }
@@ -419,7 +503,19 @@ pub async fn load_or_init_config_textproto(
 
   let config: proto::Config =
     match fs::read_to_string(data_dir.config_path().join(CONFIG_FILENAME)).await {
-      Ok(contents) => proto::Config::from_text(&contents)?,
+      Ok(contents) => {
+        let mut config = proto::Config::from_text(&contents)?;
+        if migrate_config(&mut config) | assign_webhook_ids(&mut config) {
+          // NOTE: The config on disk doesn't contain secrets, thus we can write it back as is.
+          info!("Updated config to version {CONFIG_VERSION}");
+          fs::write(
+            data_dir.config_path().join(CONFIG_FILENAME),
+            config.to_text()?.as_bytes(),
+          )
+          .await?;
+        }
+        config
+      }
       Err(err) => match err.kind() {
         std::io::ErrorKind::NotFound => {
           warn!("Falling back to default config: {err}");
@@ -439,6 +535,45 @@ pub async fn load_or_init_config_textproto(
   return Ok(merged_config);
 }
 
+/// Migrates configs written by older versions of TrailBase. Returns true if the config changed.
+fn migrate_config(config: &mut proto::Config) -> bool {
+  let version = config.version.unwrap_or(0);
+  if version >= CONFIG_VERSION {
+    return false;
+  }
+
+  if version < 1 {
+    // Listing used to be covered by READ. Preserve existing behavior by granting LIST wherever
+    // READ was granted.
+    let read = proto::PermissionFlag::Read as i32;
+    let list = proto::PermissionFlag::List as i32;
+    for api in &mut config.record_apis {
+      for acl in [&mut api.acl_world, &mut api.acl_authenticated] {
+        if acl.contains(&read) && !acl.contains(&list) {
+          acl.push(list);
+        }
+      }
+    }
+  }
+
+  config.version = Some(CONFIG_VERSION);
+  return true;
+}
+
+/// Assigns ids to webhooks missing one. Returns true if the config changed.
+pub(crate) fn assign_webhook_ids(config: &mut proto::Config) -> bool {
+  let mut changed = false;
+  for api in &mut config.record_apis {
+    for webhook in &mut api.webhooks {
+      if webhook.id.as_ref().is_none_or(|id| id.is_empty()) {
+        webhook.id = Some(crate::rand::generate_random_string(10));
+        changed = true;
+      }
+    }
+  }
+  return changed;
+}
+
 fn split_config(config: &proto::Config) -> Result<(proto::Config, proto::Vault), ConfigError> {
   let mut new_vault = proto::Vault::default();
   let (stripped_config, secrets) = strip_secrets(config)?;
@@ -491,6 +626,47 @@ fn validate_application_name(name: &str) -> Result<(), ConfigError> {
   Ok(())
 }
 
+fn validate_custom_claim_name(name: &str) -> Result<(), ConfigError> {
+  // Registered JWT claims, claims set by TrailBase and columns of `_USER_` in access rules.
+  const RESERVED: &[&str] = &[
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "nbf",
+    "iat",
+    "jti",
+    "email",
+    "csrf_token",
+    "roles",
+    "id",
+    "claims",
+  ];
+
+  let mut chars = name.chars();
+  let valid_identifier = chars
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+  if !valid_identifier {
+    return Err(ConfigError::Invalid(format!(
+      "Custom claim: {name}. Must start with a letter or '_' followed by alphanumeric characters or '_'."
+    )));
+  }
+
+  // Claims are matched case-insensitively in access rules, e.g. `_USER_.ID` refers to `id`.
+  if RESERVED
+    .iter()
+    .any(|reserved| reserved.eq_ignore_ascii_case(name))
+  {
+    return Err(ConfigError::Invalid(format!(
+      "Custom claim: {name} collides with reserved claim"
+    )));
+  }
+
+  Ok(())
+}
+
 pub(crate) fn validate_config(
   tables: &TableMetadataCache,
   config: &proto::Config,
@@ -518,6 +694,25 @@ pub(crate) fn validate_config(
       ));
     }
   }
+  for api in &config.record_apis {
+    validate_record_api_children(tables, api, &config.record_apis)?;
+  }
+
+  // Check change data capture.
+  for table_name in &config.server.change_capture_tables {
+    if tables.get(table_name).is_none() {
+      return ierr(format!("Change capture table not found: '{table_name}'"));
+    }
+  }
+
+  // Check rate limits.
+  if let Some(ref rate_limits) = config.server.rate_limits {
+    for api_name in rate_limits.record_apis.keys() {
+      if !api_names.contains(api_name) {
+        return ierr(format!("Rate limit for unknown record API: '{api_name}'"));
+      }
+    }
+  }
 
   // Check auth.
   let mut providers = HashSet::<String>::new();
@@ -540,9 +735,46 @@ pub(crate) fn validate_config(
       return ierr(format!("Missing client id for: {name}"));
     }
 
+    if provider.provider_id == Some(proto::OAuthProviderId::Oidc as i32) {
+      // OIDC providers are identified by their key, which ends up in login and callback paths.
+      if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+      {
+        return ierr(format!("Invalid OIDC provider name: {name}"));
+      }
+
+      match provider.issuer_url {
+        Some(ref issuer_url) if issuer_url.validate_url() => {}
+        Some(_) => {
+          return ierr(format!("Invalid issuer url for: {name}"));
+        }
+        None => {
+          return ierr(format!("Missing issuer url for: {name}"));
+        }
+      };
+    }
+
+    if provider.provider_id == Some(proto::OAuthProviderId::Apple as i32) {
+      if provider.team_id.is_none() {
+        return ierr(format!("Missing team id for: {name}"));
+      }
+
+      if provider.key_id.is_none() {
+        return ierr(format!("Missing key id for: {name}"));
+      }
+    }
+
     // TODO: validate critical endpoint urls are present and valid.
   }
 
+  for (claim, key) in &config.auth.custom_claims {
+    validate_custom_claim_name(claim)?;
+    if key.is_empty() {
+      return ierr(format!("Missing metadata key for claim: {claim}"));
+    }
+  }
+
   // Check JSON Schema configs
   for schema in &config.schemas {
     if schema.name.is_none() {
@@ -640,11 +872,56 @@ mod test {
     test_default_config_is_valid().await;
     test_config_merging()?;
     test_config_stripping()?;
+    test_repeated_field_secrets()?;
     test_config_merging_from_env_and_vault()?;
 
     Ok(())
   }
 
+  #[test]
+  fn test_custom_claim_names() {
+    assert!(validate_custom_claim_name("tenant_id").is_ok());
+    assert!(validate_custom_claim_name("_plan2").is_ok());
+
+    assert!(validate_custom_claim_name("").is_err());
+    assert!(validate_custom_claim_name("2fa").is_err());
+    assert!(validate_custom_claim_name("tenant-id").is_err());
+    assert!(validate_custom_claim_name("sub").is_err());
+    assert!(validate_custom_claim_name("roles").is_err());
+    assert!(validate_custom_claim_name("Roles").is_err());
+    assert!(validate_custom_claim_name("ID").is_err());
+  }
+
+  #[test]
+  fn test_config_migration() {
+    use proto::{PermissionFlag, RecordApiConfig};
+
+    let mut config = Config {
+      record_apis: vec![RecordApiConfig {
+        name: Some("api".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32],
+        acl_authenticated: vec![PermissionFlag::Create as i32],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+
+    assert!(migrate_config(&mut config));
+    assert_eq!(config.version, Some(CONFIG_VERSION));
+    assert_eq!(
+      config.record_apis[0].acl_world,
+      vec![PermissionFlag::Read as i32, PermissionFlag::List as i32]
+    );
+    assert_eq!(
+      config.record_apis[0].acl_authenticated,
+      vec![PermissionFlag::Create as i32]
+    );
+
+    // Migrated configs are left alone.
+    assert!(!migrate_config(&mut config));
+    assert!(!migrate_config(&mut Config::new_with_custom_defaults()));
+  }
+
   async fn test_default_config_is_valid() {
     let state = test_state(None).await.unwrap();
     let table_metadata = TableMetadataCache::new(state.conn().clone()).await.unwrap();
@@ -770,7 +1047,87 @@ mod test {
 
     return Ok(());
   }
+
+  fn test_repeated_field_secrets() -> anyhow::Result<()> {
+    let webhook = |id: Option<&str>, secret: &str| proto::WebhookConfig {
+      id: id.map(|id| id.to_string()),
+      url: Some("https://example.com/hook".to_string()),
+      secret: Some(secret.to_string()),
+      ..Default::default()
+    };
+
+    let mut config = proto::Config {
+      record_apis: vec![proto::RecordApiConfig {
+        name: Some("api".to_string()),
+        webhooks: vec![
+          webhook(Some("hook"), "secret"),
+          webhook(Some("HOOK"), "other"),
+          webhook(None, "unkeyed"),
+        ],
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+
+    let (stripped, secrets) = strip_secrets(&config)?;
+    // Keys are case-sensitive, i.e. ids differing only in case don't collide.
+    assert_eq!(
+      HashMap::from([
+        (
+          "TRAIL_RECORD_APIS_WEBHOOKS_api/hook_SECRET".to_string(),
+          "secret".to_string()
+        ),
+        (
+          "TRAIL_RECORD_APIS_WEBHOOKS_api/HOOK_SECRET".to_string(),
+          "other".to_string()
+        ),
+      ]),
+      secrets
+    );
+
+    // Elements without a key cannot be mapped to the vault and are thus left as is.
+    config.record_apis[0].webhooks[0].secret = None;
+    config.record_apis[0].webhooks[1].secret = None;
+    assert_eq!(config, stripped);
+
+    let vault = proto::Vault {
+      secrets: secrets.into_iter().collect(),
+    };
+    let merged = merge_vault_and_env(stripped.clone(), vault.clone())?;
+    assert_eq!(
+      Some("secret"),
+      merged.record_apis[0].webhooks[0].secret.as_deref()
+    );
+    assert_eq!(
+      Some("other"),
+      merged.record_apis[0].webhooks[1].secret.as_deref()
+    );
+    assert_eq!(
+      Some("unkeyed"),
+      merged.record_apis[0].webhooks[2].secret.as_deref()
+    );
+
+    // Env variables take precedence over the vault.
+    test_env::set("TRAIL_RECORD_APIS_WEBHOOKS_api/hook_SECRET", Some("env"));
+    let merged = merge_vault_and_env(stripped, vault)?;
+    test_env::set("TRAIL_RECORD_APIS_WEBHOOKS_api/hook_SECRET", None);
+    assert_eq!(
+      Some("env"),
+      merged.record_apis[0].webhooks[0].secret.as_deref()
+    );
+
+    assert!(assign_webhook_ids(&mut config));
+    assert!(!assign_webhook_ids(&mut config));
+    assert_eq!(
+      Some("hook"),
+      config.record_apis[0].webhooks[0].id.as_deref()
+    );
+    assert!(config.record_apis[0].webhooks[2].id.is_some());
+
+    return Ok(());
+  }
 }
 
 const CONFIG_FILENAME: &str = "config.textproto";
+const CONFIG_VERSION: u32 = 1;
 const VAULT_FILENAME: &str = "secrets.textproto";
@@ -7,10 +7,27 @@ pub(crate) const USER_TABLE_ID_COLUMN: &str = "id";
 
 pub(crate) const SESSION_TABLE: &str = "_session";
 pub(crate) const AVATAR_TABLE: &str = "_user_avatar";
+pub(crate) const USER_MFA_TABLE: &str = "_user_mfa";
+pub(crate) const USER_MFA_RECOVERY_CODE_TABLE: &str = "_user_mfa_recovery_code";
+pub(crate) const USER_PASSKEY_TABLE: &str = "_user_passkey";
+pub(crate) const WEBAUTHN_CHALLENGE_TABLE: &str = "_webauthn_challenge";
+pub(crate) const LOGIN_FAILURE_TABLE: &str = "_login_failure";
+pub(crate) const ROLE_TABLE: &str = "_role";
+pub(crate) const GROUP_TABLE: &str = "_group";
+pub(crate) const USER_ROLE_TABLE: &str = "_user_role";
+pub(crate) const GROUP_MEMBER_TABLE: &str = "_group_member";
+pub(crate) const GROUP_ROLE_TABLE: &str = "_group_role";
 
 pub(crate) const LOGS_TABLE_ID_COLUMN: &str = "id";
 pub const LOGS_RETENTION_DEFAULT: Duration = Duration::days(7);
 
+pub(crate) const CHANGES_TABLE: &str = "_changes";
+pub const CHANGE_CAPTURE_RETENTION_DEFAULT: Duration = Duration::days(7);
+
+pub(crate) const WEBHOOK_DELIVERIES_TABLE: &str = "_webhook_deliveries";
+/// Completed, i.e. delivered or failed, deliveries are pruned after this long.
+pub(crate) const WEBHOOK_DELIVERIES_RETENTION: Duration = Duration::days(7);
+
 pub const COOKIE_AUTH_TOKEN: &str = "auth_token";
 pub const COOKIE_REFRESH_TOKEN: &str = "refresh_token";
 pub const COOKIE_OAUTH_STATE: &str = "oauth_state";
@@ -26,12 +43,20 @@ pub const DEFAULT_AUTH_TOKEN_TTL: Duration = Duration::minutes(2);
 pub const DEFAULT_AUTH_TOKEN_TTL: Duration = Duration::minutes(60);
 
 pub const DEFAULT_REFRESH_TOKEN_TTL: Duration = Duration::days(30);
+pub const DEFAULT_MAGIC_LINK_TTL: Duration = Duration::minutes(15);
+pub const DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS: i32 = 10;
+pub const DEFAULT_MAX_FAILED_LOGIN_ATTEMPTS_PER_IP: i32 = 50;
+pub const DEFAULT_LOCKOUT_DURATION: Duration = Duration::minutes(15);
 
 pub const SITE_URL_DEFAULT: &str = "http://localhost:4000";
 
 pub(crate) const PASSWORD_OPTIONS: PasswordOptions = PasswordOptions::default();
 pub(crate) const VERIFICATION_CODE_LENGTH: usize = 24;
 pub(crate) const REFRESH_TOKEN_LENGTH: usize = 32;
+pub(crate) const MFA_CHALLENGE_TTL: Duration = Duration::minutes(5);
+pub(crate) const MFA_CHALLENGE_MAX_ATTEMPTS: i64 = 5;
+pub(crate) const MFA_RECOVERY_CODE_COUNT: usize = 10;
+pub(crate) const WEBAUTHN_CHALLENGE_TTL: Duration = Duration::minutes(5);
 
 // Public APIs
 pub const RECORD_API_PATH: &str = "api/records/v1";
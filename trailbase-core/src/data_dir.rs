@@ -27,6 +27,10 @@ impl DataDir {
     return self.data_path().join("logs.db");
   }
 
+  pub fn changes_db_path(&self) -> PathBuf {
+    return self.data_path().join("changes.db");
+  }
+
   pub fn data_path(&self) -> PathBuf {
     return self.0.join("data/");
   }
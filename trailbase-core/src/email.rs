@@ -7,6 +7,8 @@ use thiserror::Error;
 
 use crate::auth::user::DbUser;
 use crate::config::proto::{Config, EmailTemplate};
+use crate::constants::AUTH_API_PATH;
+use crate::util::urlencode;
 use crate::AppState;
 
 #[derive(Debug, Error)]
@@ -208,6 +210,58 @@ impl Email {
 
     return Email::new(state, user.email.clone(), subject, body);
   }
+
+  pub(crate) fn magic_link_email(
+    state: &AppState,
+    user: &DbUser,
+    magic_link_code: &str,
+    one_time_code: &str,
+    redirect_to: Option<&str>,
+  ) -> Result<Self, EmailError> {
+    let (server_config, template) =
+      state.access_config(|c| (c.server.clone(), c.email.magic_link_template.clone()));
+
+    let Some(ref site_url) = server_config.site_url else {
+      return Err(EmailError::Missing("config.site_url"));
+    };
+
+    let (subject_template, body_template) = match template {
+      Some(EmailTemplate {
+        subject: Some(subject),
+        body: Some(body),
+      }) => (subject, body),
+      _ => {
+        log::debug!("Falling back to default magic link email");
+        let d = defaults::magic_link_email();
+        (d.subject.unwrap(), d.body.unwrap())
+      }
+    };
+
+    let mut verification_url =
+      format!("{site_url}/{AUTH_API_PATH}/login/magic_link/confirm/{magic_link_code}");
+    if let Some(redirect_to) = redirect_to {
+      verification_url.push_str(&format!("?redirect_to={}", urlencode(redirect_to)));
+    }
+
+    let env = Environment::new();
+    let subject = env
+      .template_from_named_str("subject", &subject_template)?
+      .render(context! {
+        APP_NAME => server_config.application_name,
+        EMAIL => user.email,
+      })?;
+    let body = env
+      .template_from_named_str("body", &body_template)?
+      .render(context! {
+        APP_NAME => server_config.application_name,
+        VERIFICATION_URL => verification_url,
+        SITE_URL => server_config.site_url,
+        CODE => one_time_code,
+        EMAIL => user.email,
+      })?;
+
+    return Email::new(state, user.email.clone(), subject, body);
+  }
 }
 
 fn get_sender(state: &AppState) -> Result<Mailbox, EmailError> {
@@ -348,6 +402,37 @@ pub(crate) mod defaults {
       body: Some(BODY.to_string()),
     };
   }
+
+  pub fn magic_link_email() -> EmailTemplate {
+    const SUBJECT: &str = "Log in to {{ APP_NAME }}";
+    const BODY: &str = indoc! {r#"
+        <html>
+          <body>
+            <h1>Log in to {{ APP_NAME }}</h1>
+
+            <p>
+              Click the link below to log in:
+            </p>
+
+            <a class="btn" href="{{ VERIFICATION_URL }}">
+              {{ VERIFICATION_URL }}
+            </a>
+
+            <p>
+              Alternatively, enter the following code: <b>{{ CODE }}</b>
+            </p>
+
+            <p>
+              If you didn't request this email, you can safely ignore it.
+            </p>
+          </body>
+        </html>"#};
+
+    return EmailTemplate {
+      subject: Some(SUBJECT.to_string()),
+      body: Some(BODY.to_string()),
+    };
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,32 @@
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum_client_ip::{SecureClientIp, SecureClientIpSource};
+use std::convert::Infallible;
+use std::net::IpAddr;
+
+/// Client IP address, e.g. for brute-force protection.
+///
+/// NOTE: Unlike the request logs, this only trusts the configured source, i.e. the connection's
+/// peer address by default or a header set by a trusted reverse proxy. The address is `None` if
+/// it cannot be determined.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+  S: Send + Sync,
+{
+  type Rejection = Infallible;
+
+  async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    let Some(source) = parts.extensions.get::<SecureClientIpSource>() else {
+      return Ok(ClientIp(None));
+    };
+
+    return Ok(ClientIp(
+      SecureClientIp::from(source, &parts.headers, &parts.extensions)
+        .ok()
+        .map(|ip| ip.0),
+    ));
+  }
+}
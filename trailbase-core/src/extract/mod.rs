@@ -1,4 +1,6 @@
+mod client_ip;
 mod either;
 mod multipart;
 
+pub use client_ip::ClientIp;
 pub use either::Either;
@@ -45,6 +45,8 @@ struct JsUser {
   id: String,
   email: String,
   csrf: String,
+  roles: Vec<String>,
+  claims: serde_json::Map<String, serde_json::Value>,
 }
 
 struct DispatchArgs {
@@ -591,6 +593,8 @@ fn add_route_to_router(
       id: u.id,
       email: u.email,
       csrf: u.csrf_token,
+      roles: u.roles,
+      claims: u.custom_claims,
     });
 
     let (sender, receiver) = tokio::sync::oneshot::channel::<Result<JsResponse, JsResponseError>>();
@@ -18,18 +18,21 @@ mod extract;
 mod js;
 mod listing;
 mod migrations;
+mod rate_limit;
 mod scheduler;
 mod schema;
 mod server;
 mod table_metadata;
 mod transaction;
 mod value_notifier;
+mod webhooks;
 
 #[cfg(test)]
 mod test;
 
 pub use app_state::AppState;
 pub use auth::User;
+pub use axum_client_ip::SecureClientIpSource;
 pub use data_dir::DataDir;
 pub use server::{InitError, Server, ServerOptions};
 
@@ -63,6 +66,7 @@ pub mod api {
 
   pub use crate::admin::user::{create_user_handler, CreateUserRequest};
   pub use crate::auth::api::login::login_with_password;
+  pub use crate::auth::jwt::rotate_keys;
   pub use crate::auth::{force_password_reset, JwtHelper, TokenClaims};
   pub use crate::email::{Email, EmailError};
   pub use crate::migrations::new_unique_migration_filename;
@@ -1,12 +1,15 @@
+use base64::prelude::*;
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 
-use crate::records::json_to_sql::json_string_to_value;
+use crate::records::json_to_sql::{json_string_to_value, ParamsError};
+use crate::schema::Column;
 use crate::table_metadata::TableOrViewMetadata;
-use crate::util::b64_to_id;
 
 #[derive(Debug, Error)]
 pub enum WhereClauseError {
@@ -21,7 +24,7 @@ pub enum WhereClauseError {
 }
 
 // Syntax: ?key[gte]=value&key[lte]=value
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct QueryParam {
   pub value: String,
   /// Qualifier or operation such as "greater-than";
@@ -39,11 +42,20 @@ pub enum Qualifier {
   LessThan,
   Like,
   Regexp,
+  In,
+  NotIn,
+  IsNull,
+  IsNotNull,
 }
 
 impl Qualifier {
-  fn from(qualifier: Option<&str>) -> Option<Self> {
+  fn from(qualifier: Option<&str>, value: &str) -> Option<Self> {
     return match qualifier {
+      Some("is") => match value {
+        v if v.eq_ignore_ascii_case("null") => Some(Self::IsNull),
+        v if v.eq_ignore_ascii_case("!null") => Some(Self::IsNotNull),
+        _ => None,
+      },
       Some("gte") => Some(Self::GreaterThanEqual),
       Some("gt") => Some(Self::GreaterThan),
       Some("lte") => Some(Self::LessThanEqual),
@@ -52,6 +64,8 @@ impl Qualifier {
       Some("ne") => Some(Self::NotEqual),
       Some("like") => Some(Self::Like),
       Some("re") => Some(Self::Regexp),
+      Some("in") => Some(Self::In),
+      Some("nin") => Some(Self::NotIn),
       None => Some(Self::Equal),
       _ => None,
     };
@@ -68,6 +82,34 @@ impl Qualifier {
       Self::Like => "LIKE",
       Self::Regexp => "REGEXP",
       Self::Equal => "=",
+      Self::In => "IN",
+      Self::NotIn => "NOT IN",
+      Self::IsNull => "IS NULL",
+      Self::IsNotNull => "IS NOT NULL",
+    };
+  }
+}
+
+/// Composite filter expression, e.g. built from nested query params:
+///
+///   ?filter[$or][0][status]=open&filter[$or][1][assignee]=<id>
+///
+/// Conditions on the same nesting level are implicitly AND-ed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+  Column { column: String, param: QueryParam },
+  And(Vec<Filter>),
+  Or(Vec<Filter>),
+}
+
+impl Filter {
+  /// Returns true if any column referenced by this filter matches the given predicate.
+  pub fn any_column(&self, f: &impl Fn(&str) -> bool) -> bool {
+    return match self {
+      Filter::Column { column, .. } => f(column),
+      Filter::And(filters) | Filter::Or(filters) => {
+        filters.iter().any(|filter| filter.any_column(f))
+      }
     };
   }
 }
@@ -78,21 +120,99 @@ pub enum Order {
   Descending,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum AggregateFunction {
+  Count,
+  Sum,
+  Avg,
+  Min,
+  Max,
+}
+
+/// An aggregate, e.g. "count" or "sum(amount)".
+#[derive(PartialEq, Debug, Clone)]
+pub struct Aggregate {
+  pub function: AggregateFunction,
+  /// Aggregated column. Only `count` may omit it, i.e. count all rows.
+  pub column: Option<String>,
+}
+
+impl Aggregate {
+  fn parse(value: &str) -> Option<Aggregate> {
+    let (name, column) = match value.split_once('(') {
+      Some((name, rest)) => (name, Some(rest.strip_suffix(')')?)),
+      None => (value, None),
+    };
+
+    let function = match name {
+      "count" => AggregateFunction::Count,
+      "sum" => AggregateFunction::Sum,
+      "avg" => AggregateFunction::Avg,
+      "min" => AggregateFunction::Min,
+      "max" => AggregateFunction::Max,
+      _ => {
+        return None;
+      }
+    };
+
+    return match column {
+      Some(column) if !column.is_empty() => Some(Aggregate {
+        function,
+        column: Some(column.to_string()),
+      }),
+      None if function == AggregateFunction::Count => Some(Aggregate {
+        function,
+        column: None,
+      }),
+      _ => None,
+    };
+  }
+
+  /// Name of the aggregate in responses, e.g. "count" or "sum(amount)".
+  pub fn name(&self) -> String {
+    let function = match self.function {
+      AggregateFunction::Count => "count",
+      AggregateFunction::Sum => "sum",
+      AggregateFunction::Avg => "avg",
+      AggregateFunction::Min => "min",
+      AggregateFunction::Max => "max",
+    };
+    return match self.column {
+      Some(ref column) => format!("{function}({column})"),
+      None => function.to_string(),
+    };
+  }
+}
+
 #[derive(Default, Debug)]
 pub struct QueryParseResult {
   // Pagination parameters.
   pub limit: Option<usize>,
-  pub cursor: Option<[u8; 16]>,
+  /// Raw cursor. Its format depends on the consumer, e.g. admin APIs expect base64 encoded ids
+  /// while record APIs use opaque keyset [`Cursor`]s.
+  pub cursor: Option<String>,
   pub offset: Option<usize>,
   pub count: Option<bool>,
   pub expand: Option<Vec<String>>,
+  // Projection, i.e. the subset of columns to be returned: &fields=col0,col1.
+  pub fields: Option<Vec<String>>,
 
   // Ordering. It's a vector for &order=-col0,+col1,col2
   pub order: Option<Vec<(String, Order)>>,
 
+  // Full-text search query, i.e. &q=some text.
+  pub search: Option<String>,
+
+  // Aggregation, i.e. &group_by=col0&agg=count,sum(col1).
+  pub group_by: Option<Vec<String>>,
+  pub aggregates: Option<Vec<Aggregate>>,
+
   // Map from filter params to filter value. It's a vector in cases like
   // "col0[gte]=2&col0[lte]=10".
   pub params: Option<HashMap<String, Vec<QueryParam>>>,
+
+  // Composite filter from "filter[...]" params, which allows for nested AND/OR groups.
+  pub filter: Option<Filter>,
 }
 
 pub fn limit_or_default(limit: Option<usize>) -> usize {
@@ -102,6 +222,70 @@ pub fn limit_or_default(limit: Option<usize>) -> usize {
   return std::cmp::min(limit.unwrap_or(DEFAULT_LIMIT), MAX_LIMIT);
 }
 
+/// Parses a single order column, e.g. "-col", "+col" or "col".
+fn parse_order_column(v: &str) -> (String, Order) {
+  return match v {
+    x if x.starts_with("-") => (v[1..].to_string(), Order::Descending),
+    x if x.starts_with("+") => (v[1..].to_string(), Order::Ascending),
+    x => (x.to_string(), Order::Ascending),
+  };
+}
+
+/// Options of a child expansion, e.g. `?expand=comments(limit:5,order:-created)`.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ExpandOptions {
+  pub limit: Option<usize>,
+  pub order: Vec<(String, Order)>,
+}
+
+/// Splits a comma-separated list of expansions, leaving commas within parenthesized options
+/// untouched, e.g. "author,comments(limit:5,order:-created)".
+pub fn split_expand(value: &str) -> Vec<String> {
+  let mut result = vec![];
+  let mut depth: usize = 0;
+  let mut start = 0;
+  for (idx, c) in value.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      ',' if depth == 0 => {
+        result.push(value[start..idx].to_string());
+        start = idx + 1;
+      }
+      _ => {}
+    }
+  }
+  result.push(value[start..].to_string());
+  return result;
+}
+
+/// Splits a single expansion into its name and options, e.g. "comments(limit:5,order:-created)".
+pub fn parse_expand_entry(entry: &str) -> Result<(&str, Option<ExpandOptions>), String> {
+  let Some((name, rest)) = entry.split_once('(') else {
+    return Ok((entry, None));
+  };
+  let Some(options) = rest.strip_suffix(')') else {
+    return Err(entry.to_string());
+  };
+
+  let mut result = ExpandOptions::default();
+  for option in options.split(',').filter(|o| !o.is_empty()) {
+    match option.split_once(':') {
+      Some(("limit", limit)) => {
+        result.limit = Some(limit.parse::<usize>().map_err(|_| entry.to_string())?);
+      }
+      Some(("order", order)) if !order.is_empty() => {
+        result.order.push(parse_order_column(order));
+      }
+      _ => {
+        return Err(entry.to_string());
+      }
+    }
+  }
+
+  return Ok((name, Some(result)));
+}
+
 fn parse_bool(s: &str) -> Option<bool> {
   return match s {
     "TRUE" | "true" | "1" => Some(true),
@@ -114,8 +298,18 @@ fn parse_bool(s: &str) -> Option<bool> {
 ///
 /// An example query may look like:
 ///  ?cursor=[0:16]&limit=50&order=price,-date&price[lte]=100&date[gte]=<timestamp>.
+///
+/// Composite filters can be expressed using nested "filter" params:
+///  ?filter[$or][0][price][lte]=100&filter[$or][1][category][in]=a,b.
+///
+/// Aggregates can be requested, optionally grouped, using:
+///  ?group_by=status&agg=count,sum(amount).
+///
+/// Records can be searched by text, if the API configures search columns, using:
+///  ?q=some+text.
 pub fn parse_query(query: Option<&str>) -> Result<QueryParseResult, String> {
   let mut result: QueryParseResult = Default::default();
+  let mut filter = FilterGroup::default();
   let Some(query) = query else {
     return Ok(result);
   };
@@ -127,23 +321,36 @@ pub fn parse_query(query: Option<&str>) -> Result<QueryParseResult, String> {
   for (key, value) in form_urlencoded::parse(query.as_bytes()) {
     match key.as_ref() {
       "limit" => result.limit = value.parse::<usize>().ok(),
-      "cursor" => result.cursor = b64_to_id(value.as_ref()).ok(),
+      "cursor" => result.cursor = Some(value.to_string()),
       "offset" => result.offset = value.parse::<usize>().ok(),
       "count" => result.count = parse_bool(&value),
-      "expand" => result.expand = Some(value.split(",").map(|s| s.to_owned()).collect()),
-      "order" => {
-        let order: Vec<(String, Order)> = value
+      "expand" => result.expand = Some(split_expand(&value)),
+      "fields" => result.fields = Some(value.split(",").map(|s| s.to_owned()).collect()),
+      "q" => result.search = Some(value.to_string()),
+      "group_by" => result.group_by = Some(value.split(",").map(|s| s.to_owned()).collect()),
+      "agg" => {
+        let aggregates = value
           .split(",")
-          .map(|v| {
-            return match v {
-              x if x.starts_with("-") => (v[1..].to_string(), Order::Descending),
-              x if x.starts_with("+") => (v[1..].to_string(), Order::Ascending),
-              x => (x.to_string(), Order::Ascending),
-            };
-          })
-          .collect();
+          .map(Aggregate::parse)
+          .collect::<Option<Vec<_>>>()
+          .ok_or_else(|| key.to_string())?;
+        result.aggregates = Some(aggregates);
+      }
+      "order" => {
+        result.order = Some(value.split(",").map(parse_order_column).collect());
+      }
+      key if key.starts_with("filter[") => {
+        let Some(path) = split_filter_path(key) else {
+          return Err(key.to_string());
+        };
+
+        if value.is_empty() {
+          return Err(key.to_string());
+        }
 
-        result.order = Some(order);
+        filter
+          .insert(&path, &value, 0)
+          .map_err(|_| key.to_string())?;
       }
       key => {
         // Key didn't match any of the predefined list operations (limit, cursor, order), we thus
@@ -165,8 +372,8 @@ pub fn parse_query(query: Option<&str>) -> Result<QueryParseResult, String> {
         }
 
         let query_param = QueryParam {
+          qualifier: Qualifier::from(maybe_op, &value),
           value: value.to_string(),
-          qualifier: Qualifier::from(maybe_op),
         };
 
         let params = result.params.get_or_insert_default();
@@ -179,9 +386,104 @@ pub fn parse_query(query: Option<&str>) -> Result<QueryParseResult, String> {
     }
   }
 
+  result.filter = filter.build();
+
   return Ok(result);
 }
 
+/// Max nesting depth of composite filters to bound the complexity of the resulting query.
+const MAX_FILTER_DEPTH: usize = 4;
+/// Max number of entries in an AND/OR group.
+const MAX_FILTER_GROUP_SIZE: usize = 32;
+
+/// Intermediate representation of one nesting level of "filter[...]" params. All conditions on a
+/// level are AND-ed, while the members of "$or" groups are OR-ed.
+#[derive(Default)]
+struct FilterGroup {
+  columns: Vec<Filter>,
+  and: BTreeMap<usize, FilterGroup>,
+  or: BTreeMap<usize, FilterGroup>,
+}
+
+impl FilterGroup {
+  fn insert(&mut self, path: &[&str], value: &str, depth: usize) -> Result<(), ()> {
+    if depth > MAX_FILTER_DEPTH {
+      return Err(());
+    }
+
+    return match path {
+      [combiner @ ("$and" | "$or"), index, rest @ ..] if !rest.is_empty() => {
+        let index = index.parse::<usize>().map_err(|_| ())?;
+        if index >= MAX_FILTER_GROUP_SIZE {
+          return Err(());
+        }
+
+        let groups = if *combiner == "$and" {
+          &mut self.and
+        } else {
+          &mut self.or
+        };
+        groups
+          .entry(index)
+          .or_default()
+          .insert(rest, value, depth + 1)
+      }
+      [column, maybe_op @ ..] if maybe_op.len() <= 1 => {
+        if column.is_empty() || !column.chars().all(|c| c.is_alphanumeric() || c == '_') {
+          return Err(());
+        }
+
+        self.columns.push(Filter::Column {
+          column: column.to_string(),
+          param: QueryParam {
+            qualifier: Qualifier::from(maybe_op.first().copied(), value),
+            value: value.to_string(),
+          },
+        });
+        Ok(())
+      }
+      _ => Err(()),
+    };
+  }
+
+  fn build(self) -> Option<Filter> {
+    let mut filters = self.columns;
+    if !self.and.is_empty() {
+      filters.push(Filter::And(
+        self.and.into_values().filter_map(|g| g.build()).collect(),
+      ));
+    }
+    if !self.or.is_empty() {
+      filters.push(Filter::Or(
+        self.or.into_values().filter_map(|g| g.build()).collect(),
+      ));
+    }
+
+    return match filters.len() {
+      0 => None,
+      1 => filters.pop(),
+      _ => Some(Filter::And(filters)),
+    };
+  }
+}
+
+/// Splits "filter[a][b][c]" into ["a", "b", "c"].
+fn split_filter_path(key: &str) -> Option<Vec<&str>> {
+  let mut rest = key.strip_prefix("filter")?;
+  let mut segments = vec![];
+  while !rest.is_empty() {
+    let inner = rest.strip_prefix('[')?;
+    let end = inner.find(']')?;
+    segments.push(&inner[..end]);
+    rest = &inner[end + 1..];
+  }
+
+  if segments.is_empty() {
+    return None;
+  }
+  return Some(segments);
+}
+
 #[derive(Debug, Clone)]
 pub struct WhereClause {
   pub clause: String,
@@ -191,43 +493,33 @@ pub struct WhereClause {
 pub fn build_filter_where_clause(
   table_metadata: &dyn TableOrViewMetadata,
   filter_params: Option<HashMap<String, Vec<QueryParam>>>,
+  filter: Option<Filter>,
 ) -> Result<WhereClause, WhereClauseError> {
   let mut where_clauses = Vec::<String>::with_capacity(16);
   let mut params = Vec::<(Cow<'static, str>, trailbase_sqlite::Value)>::with_capacity(16);
 
   if let Some(filter_params) = filter_params {
     for (column_name, query_params) in filter_params {
-      if column_name.starts_with("_") {
-        return Err(WhereClauseError::UnrecognizedParam(format!(
-          "Invalid parameter: {column_name}"
-        )));
-      }
-
-      // IMPORTANT: We only include parameters with known columns to avoid building an invalid
-      // query early and forbid injections.
-      let Some((col, _col_meta)) = table_metadata.column_by_name(&column_name) else {
-        return Err(WhereClauseError::UnrecognizedParam(format!(
-          "Unrecognized parameter: {column_name}"
-        )));
-      };
+      let col = lookup_filter_column(table_metadata, &column_name)?;
 
       for query_param in query_params {
-        let Some(op) = query_param.qualifier.map(|q| q.to_sql()) else {
+        let Some(qualifier) = query_param.qualifier else {
           info!("No op for: {column_name}={query_param:?}");
           continue;
         };
 
-        match json_string_to_value(col.data_type, query_param.value) {
-          Ok(value) => {
-            where_clauses.push(format!("{column_name} {op} :{column_name}"));
-            params.push((format!(":{column_name}").into(), value));
-          }
+        match build_column_clause(col, qualifier, query_param.value, &mut params) {
+          Ok(clause) => where_clauses.push(clause),
           Err(err) => debug!("Parameter conversion for {column_name} failed: {err}"),
         };
       }
     }
   }
 
+  if let Some(filter) = filter {
+    where_clauses.push(build_composite_clause(table_metadata, filter, &mut params)?);
+  }
+
   let clause = match where_clauses.len() {
     0 => "TRUE".to_string(),
     _ => where_clauses.join(" AND "),
@@ -236,6 +528,242 @@ pub fn build_filter_where_clause(
   return Ok(WhereClause { clause, params });
 }
 
+fn build_composite_clause(
+  table_metadata: &dyn TableOrViewMetadata,
+  filter: Filter,
+  params: &mut Vec<(Cow<'static, str>, trailbase_sqlite::Value)>,
+) -> Result<String, WhereClauseError> {
+  let (filters, separator, empty) = match filter {
+    Filter::Column { column, param } => {
+      let col = lookup_filter_column(table_metadata, &column)?;
+      let Some(qualifier) = param.qualifier else {
+        return Err(WhereClauseError::Parse(format!("Invalid op for: {column}")));
+      };
+
+      // NOTE: Unlike for flat filter params, we don't skip over invalid values. Silently dropping
+      // a condition from an OR group would change the meaning of the filter.
+      return build_column_clause(col, qualifier, param.value, params)
+        .map_err(|err| WhereClauseError::Parse(format!("Invalid value for {column}: {err}")));
+    }
+    Filter::And(filters) => (filters, " AND ", "TRUE"),
+    Filter::Or(filters) => (filters, " OR ", "FALSE"),
+  };
+
+  if filters.is_empty() {
+    return Ok(empty.to_string());
+  }
+
+  let clauses = filters
+    .into_iter()
+    .map(|f| build_composite_clause(table_metadata, f, params))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  return Ok(format!("({})", clauses.join(separator)));
+}
+
+fn lookup_filter_column<'a>(
+  table_metadata: &'a dyn TableOrViewMetadata,
+  column_name: &str,
+) -> Result<&'a Column, WhereClauseError> {
+  if column_name.starts_with("_") {
+    return Err(WhereClauseError::UnrecognizedParam(format!(
+      "Invalid parameter: {column_name}"
+    )));
+  }
+
+  // IMPORTANT: We only include parameters with known columns to avoid building an invalid
+  // query early and forbid injections.
+  let Some((col, _col_meta)) = table_metadata.column_by_name(column_name) else {
+    return Err(WhereClauseError::UnrecognizedParam(format!(
+      "Unrecognized parameter: {column_name}"
+    )));
+  };
+
+  return Ok(col);
+}
+
+/// Builds the clause for a single `column[op]=value` condition and appends the respective
+/// parameters.
+fn build_column_clause(
+  col: &Column,
+  qualifier: Qualifier,
+  value: String,
+  params: &mut Vec<(Cow<'static, str>, trailbase_sqlite::Value)>,
+) -> Result<String, ParamsError> {
+  let column_name = &col.name;
+  let op = qualifier.to_sql();
+
+  // NOTE: Placeholders are numbered rather than named after the column, since the same column may
+  // appear in multiple conditions, e.g. "col[gte]=0&col[lt]=10".
+  let mut push_param = |value: trailbase_sqlite::Value| -> String {
+    let placeholder = format!(":__filter{}", params.len());
+    params.push((placeholder.clone().into(), value));
+    return placeholder;
+  };
+
+  return match qualifier {
+    Qualifier::IsNull | Qualifier::IsNotNull => Ok(format!("{column_name} {op}")),
+    Qualifier::In | Qualifier::NotIn => {
+      let values = split_list_values(&value)
+        .into_iter()
+        .map(|v| json_string_to_value(col.data_type, v))
+        .collect::<Result<Vec<_>, _>>()?;
+
+      let placeholders = values.into_iter().map(&mut push_param).join(", ");
+      Ok(format!("{column_name} {op} ({placeholders})"))
+    }
+    _ => {
+      let value = json_string_to_value(col.data_type, value)?;
+      Ok(format!("{column_name} {op} {}", push_param(value)))
+    }
+  };
+}
+
+/// Splits the comma-separated values of `in` and `nin` conditions. Commas within values can be
+/// escaped as `\,` and backslashes as `\\`. Any other backslash is kept as is.
+fn split_list_values(value: &str) -> Vec<String> {
+  let mut values: Vec<String> = vec![];
+  let mut current = String::new();
+
+  let mut chars = value.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.peek() {
+        Some(next @ (',' | '\\')) => {
+          current.push(*next);
+          chars.next();
+        }
+        _ => current.push(c),
+      },
+      ',' => values.push(std::mem::take(&mut current)),
+      c => current.push(c),
+    }
+  }
+  values.push(current);
+
+  return values;
+}
+
+/// Opaque cursor for keyset pagination.
+///
+/// Captures the values of all ORDER BY columns, including the primary key tie-breaker, of the last
+/// record on a page. This lets us continue listing right after said record for arbitrary orderings
+/// rather than only for a descending primary key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor(pub Vec<trailbase_sqlite::Value>);
+
+/// Compact serialization format for cursor values.
+#[derive(Serialize, Deserialize)]
+enum CursorValue {
+  N,
+  I(i64),
+  R(f64),
+  T(String),
+  B(String),
+}
+
+impl Cursor {
+  pub fn encode(&self) -> String {
+    use trailbase_sqlite::Value;
+
+    let values: Vec<CursorValue> = self
+      .0
+      .iter()
+      .map(|v| match v {
+        Value::Null => CursorValue::N,
+        Value::Integer(i) => CursorValue::I(*i),
+        Value::Real(r) => CursorValue::R(*r),
+        Value::Text(t) => CursorValue::T(t.clone()),
+        Value::Blob(b) => CursorValue::B(BASE64_URL_SAFE_NO_PAD.encode(b)),
+      })
+      .collect();
+
+    // Serializing a list of plain values cannot fail.
+    let json = serde_json::to_vec(&values).unwrap_or_default();
+    return BASE64_URL_SAFE_NO_PAD.encode(json);
+  }
+
+  pub fn decode(cursor: &str) -> Result<Self, WhereClauseError> {
+    use trailbase_sqlite::Value;
+
+    let json = BASE64_URL_SAFE_NO_PAD.decode(cursor)?;
+    let values: Vec<CursorValue> = serde_json::from_slice(&json)
+      .map_err(|err| WhereClauseError::Parse(format!("Invalid cursor: {err}")))?;
+
+    return Ok(Cursor(
+      values
+        .into_iter()
+        .map(|v| -> Result<Value, WhereClauseError> {
+          Ok(match v {
+            CursorValue::N => Value::Null,
+            CursorValue::I(i) => Value::Integer(i),
+            CursorValue::R(r) => Value::Real(r),
+            CursorValue::T(t) => Value::Text(t),
+            CursorValue::B(b) => Value::Blob(BASE64_URL_SAFE_NO_PAD.decode(b)?),
+          })
+        })
+        .collect::<Result<Vec<_>, _>>()?,
+    ));
+  }
+}
+
+/// Builds a keyset predicate matching all rows strictly after the cursor given the ordering.
+///
+/// For an ordering (a ASC, b DESC) and cursor values (x, y) this yields:
+///
+///   a > x OR (a IS x AND b < y)
+///
+/// with additional care for NULLs, which SQLite considers smaller than any other value.
+///
+/// NOTE: The caller is expected to have validated the column names and to make sure that the
+/// ordering is total, e.g. by appending the primary key.
+pub fn build_keyset_clause(
+  prefix: &str,
+  order: &[(String, Order)],
+  cursor: Cursor,
+  params: &mut Vec<(Cow<'static, str>, trailbase_sqlite::Value)>,
+) -> Result<String, WhereClauseError> {
+  use trailbase_sqlite::Value;
+
+  if order.len() != cursor.0.len() {
+    return Err(WhereClauseError::Parse(
+      "Cursor doesn't match ordering".to_string(),
+    ));
+  }
+
+  let mut equalities: Vec<String> = vec![];
+  let mut terms: Vec<String> = vec![];
+
+  for (index, ((col, ord), value)) in std::iter::zip(order, cursor.0).enumerate() {
+    let placeholder = format!(":__cursor{index}");
+    let column = format!("{prefix}.{col}");
+
+    let after = match (ord, &value) {
+      (Order::Ascending, Value::Null) => Some(format!("{column} IS NOT NULL")),
+      (Order::Ascending, _) => Some(format!("{column} > {placeholder}")),
+      // Nothing comes after NULL in descending order.
+      (Order::Descending, Value::Null) => None,
+      (Order::Descending, _) => Some(format!("({column} < {placeholder} OR {column} IS NULL)")),
+    };
+
+    if let Some(after) = after {
+      terms.push(if equalities.is_empty() {
+        after
+      } else {
+        format!("({} AND {after})", equalities.join(" AND "))
+      });
+    }
+
+    equalities.push(format!("{column} IS {placeholder}"));
+    params.push((placeholder.into(), value));
+  }
+
+  if terms.is_empty() {
+    return Ok("FALSE".to_string());
+  }
+  return Ok(format!("({})", terms.join(" OR ")));
+}
+
 fn split_key_into_col_and_op(key: &str) -> Option<(&str, Option<&str>)> {
   let Some(captures) = QUALIFIER_REGEX.captures(key) else {
     // Regex didn't match, i.e. key has invalid format.
@@ -295,7 +823,7 @@ mod tests {
       let result = parse_query(query.as_deref()).unwrap();
 
       assert_eq!(result.limit, Some(10));
-      assert_eq!(result.cursor, Some(cursor));
+      assert_eq!(result.cursor, Some(id_to_b64(&cursor)));
       assert_eq!(
         result.order.unwrap(),
         vec![
@@ -396,4 +924,280 @@ mod tests {
       );
     }
   }
+
+  #[test]
+  fn test_aggregate_parsing() {
+    let result = parse_query(Some(
+      "group_by=status,day&agg=count,sum(amount),max(created)",
+    ))
+    .unwrap();
+    assert_eq!(
+      result.group_by,
+      Some(vec!["status".to_string(), "day".to_string()])
+    );
+
+    let aggregates = result.aggregates.unwrap();
+    assert_eq!(
+      aggregates,
+      vec![
+        Aggregate {
+          function: AggregateFunction::Count,
+          column: None,
+        },
+        Aggregate {
+          function: AggregateFunction::Sum,
+          column: Some("amount".to_string()),
+        },
+        Aggregate {
+          function: AggregateFunction::Max,
+          column: Some("created".to_string()),
+        },
+      ]
+    );
+    assert_eq!(
+      aggregates.iter().map(|a| a.name()).collect::<Vec<_>>(),
+      vec!["count", "sum(amount)", "max(created)"]
+    );
+
+    assert!(parse_query(Some("agg=sum")).is_err());
+    assert!(parse_query(Some("agg=sum()")).is_err());
+    assert!(parse_query(Some("agg=median(amount)")).is_err());
+    assert!(parse_query(Some("agg=count(amount")).is_err());
+  }
+
+  #[test]
+  fn test_expand_parsing() {
+    let result = parse_query(Some(
+      "expand=author,comments(limit:5,order:-created,order:id)",
+    ))
+    .unwrap();
+    let expand = result.expand.unwrap();
+    assert_eq!(
+      expand,
+      vec![
+        "author".to_string(),
+        "comments(limit:5,order:-created,order:id)".to_string()
+      ]
+    );
+
+    assert_eq!(parse_expand_entry(&expand[0]).unwrap(), ("author", None));
+    assert_eq!(
+      parse_expand_entry(&expand[1]).unwrap(),
+      (
+        "comments",
+        Some(ExpandOptions {
+          limit: Some(5),
+          order: vec![
+            ("created".to_string(), Order::Descending),
+            ("id".to_string(), Order::Ascending),
+          ],
+        })
+      )
+    );
+    assert_eq!(
+      parse_expand_entry("comments()").unwrap(),
+      ("comments", Some(ExpandOptions::default()))
+    );
+
+    assert!(parse_expand_entry("comments(limit:5").is_err());
+    assert!(parse_expand_entry("comments(limit:x)").is_err());
+    assert!(parse_expand_entry("comments(unknown:1)").is_err());
+  }
+
+  #[test]
+  fn test_composite_filter_parsing() {
+    {
+      let query = Some("filter[$or][0][status]=open&filter[$or][1][assignee][ne]=me&limit=5");
+      let result = parse_query(query).unwrap();
+
+      assert_eq!(result.limit, Some(5));
+      assert_eq!(result.params, None);
+      assert_eq!(
+        result.filter,
+        Some(Filter::Or(vec![
+          Filter::Column {
+            column: "status".to_string(),
+            param: QueryParam {
+              value: "open".to_string(),
+              qualifier: Some(Qualifier::Equal),
+            },
+          },
+          Filter::Column {
+            column: "assignee".to_string(),
+            param: QueryParam {
+              value: "me".to_string(),
+              qualifier: Some(Qualifier::NotEqual),
+            },
+          },
+        ]))
+      );
+    }
+
+    {
+      let query = Some("filter[id][in]=1,2,3&filter[name][is]=!NULL");
+      let result = parse_query(query).unwrap();
+
+      assert_eq!(
+        result.filter,
+        Some(Filter::And(vec![
+          Filter::Column {
+            column: "id".to_string(),
+            param: QueryParam {
+              value: "1,2,3".to_string(),
+              qualifier: Some(Qualifier::In),
+            },
+          },
+          Filter::Column {
+            column: "name".to_string(),
+            param: QueryParam {
+              value: "!NULL".to_string(),
+              qualifier: Some(Qualifier::IsNotNull),
+            },
+          },
+        ]))
+      );
+    }
+
+    {
+      // Invalid paths.
+      assert!(parse_query(Some("filter[$or][x][a]=1")).is_err());
+      assert!(parse_query(Some("filter[$or][0]=1")).is_err());
+      assert!(parse_query(Some("filter[a][gt][lt]=1")).is_err());
+      assert!(parse_query(Some("filter[a b]=1")).is_err());
+      assert!(parse_query(Some("filter[a]")).is_err());
+      assert!(parse_query(Some("filter[$or][0][$or][0][$or][0][$or][0][$or][0][a]=1")).is_err());
+    }
+  }
+
+  #[test]
+  fn test_build_filter_where_clause() {
+    let table: crate::schema::Table = crate::table_metadata::sqlite3_parse_into_statement(
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, status TEXT, rank INTEGER) STRICT",
+    )
+    .unwrap()
+    .unwrap()
+    .try_into()
+    .unwrap();
+    let metadata = crate::table_metadata::TableMetadata::new(table.clone(), &[table]);
+
+    let build = |query: &str| {
+      let QueryParseResult { params, filter, .. } = parse_query(Some(query)).unwrap();
+      return build_filter_where_clause(&metadata, params, filter);
+    };
+
+    {
+      let WhereClause { clause, params } =
+        build("rank[gte]=0&filter[$or][0][status]=open&filter[$or][1][rank][in]=1,2").unwrap();
+
+      assert_eq!(
+        clause,
+        "rank >= :__filter0 AND (status = :__filter1 OR rank IN (:__filter2, :__filter3))"
+      );
+      assert_eq!(params.len(), 4);
+      assert_eq!(params[3].1, trailbase_sqlite::Value::Integer(2));
+    }
+
+    {
+      let WhereClause { clause, params } =
+        build("filter[status][is]=NULL&filter[rank][nin]=5").unwrap();
+      assert_eq!(clause, "(status IS NULL AND rank NOT IN (:__filter0))");
+      assert_eq!(params.len(), 1);
+    }
+
+    {
+      // Escaped commas are part of the value.
+      let WhereClause { clause, params } = build(r"filter[status][in]=a\,b,c\\,d\x").unwrap();
+      assert_eq!(clause, "status IN (:__filter0, :__filter1, :__filter2)");
+      assert_eq!(
+        params.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+        vec![
+          trailbase_sqlite::Value::Text("a,b".to_string()),
+          trailbase_sqlite::Value::Text(r"c\".to_string()),
+          trailbase_sqlite::Value::Text(r"d\x".to_string()),
+        ]
+      );
+    }
+
+    // Unknown and hidden columns are rejected.
+    assert!(build("filter[$or][0][missing]=1").is_err());
+    assert!(build("filter[_hidden]=1").is_err());
+    // Unlike flat params, invalid values in composite filters are an error.
+    assert!(build("filter[rank]=not_a_number").is_err());
+  }
+
+  #[test]
+  fn test_cursor_encoding() {
+    use trailbase_sqlite::Value;
+
+    let cursor = Cursor(vec![
+      Value::Null,
+      Value::Integer(-5),
+      Value::Real(2.5),
+      Value::Text("a,b".to_string()),
+      Value::Blob(vec![0, 1, 255]),
+    ]);
+
+    let encoded = cursor.encode();
+    assert!(encoded
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    assert_eq!(Cursor::decode(&encoded).unwrap(), cursor);
+
+    assert!(Cursor::decode("not a cursor").is_err());
+    assert!(Cursor::decode(&BASE64_URL_SAFE_NO_PAD.encode("[{\"X\":1}]")).is_err());
+  }
+
+  #[test]
+  fn test_build_keyset_clause() {
+    use trailbase_sqlite::Value;
+
+    let order = vec![
+      ("a".to_string(), Order::Ascending),
+      ("b".to_string(), Order::Descending),
+      ("id".to_string(), Order::Descending),
+    ];
+
+    {
+      let mut params = vec![];
+      let clause = build_keyset_clause(
+        "_ROW_",
+        &order,
+        Cursor(vec![
+          Value::Integer(1),
+          Value::Integer(2),
+          Value::Integer(3),
+        ]),
+        &mut params,
+      )
+      .unwrap();
+
+      assert_eq!(
+        clause,
+        "(_ROW_.a > :__cursor0 \
+        OR (_ROW_.a IS :__cursor0 AND (_ROW_.b < :__cursor1 OR _ROW_.b IS NULL)) \
+        OR (_ROW_.a IS :__cursor0 AND _ROW_.b IS :__cursor1 AND (_ROW_.id < :__cursor2 OR _ROW_.id IS NULL)))"
+      );
+      assert_eq!(params.len(), 3);
+    }
+
+    {
+      let mut params = vec![];
+      let clause = build_keyset_clause(
+        "_ROW_",
+        &order,
+        Cursor(vec![Value::Null, Value::Null, Value::Integer(3)]),
+        &mut params,
+      )
+      .unwrap();
+
+      assert_eq!(
+        clause,
+        "(_ROW_.a IS NOT NULL \
+        OR (_ROW_.a IS :__cursor0 AND _ROW_.b IS :__cursor1 AND (_ROW_.id < :__cursor2 OR _ROW_.id IS NULL)))"
+      );
+    }
+
+    // Mismatching number of values.
+    assert!(build_keyset_clause("_ROW_", &order, Cursor(vec![Value::Null]), &mut vec![]).is_err());
+  }
 }
@@ -185,15 +185,21 @@ impl SqliteLogLayer {
     lazy_static::lazy_static! {
       static ref QUERY: String = indoc::formatdoc! {"
         INSERT INTO
-          _logs (type, level, status, method, url, latency, client_ip, referer, user_agent)
+          _logs (type, level, status, method, url, latency, client_ip, referer, user_agent, data)
         VALUES
-          ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+          ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
       "};
     }
 
+    // Extra fields, e.g. from events recorded during the request, are stored as JSON.
+    let data = if log.fields.is_empty() {
+      None
+    } else {
+      serde_json::to_vec(&log.fields).ok()
+    };
+
     let mut stmt = conn.prepare_cached(&QUERY)?;
     stmt.execute((
-      // TODO: we're yet not writing extra JSON data to the data field.
       log.r#type,
       log.level,
       log.status,
@@ -203,6 +209,7 @@ impl SqliteLogLayer {
       log.client_ip,
       log.referer,
       log.user_agent,
+      data,
     ))?;
 
     return Ok(());
@@ -10,6 +10,9 @@ mod main {
 mod logs {
   trailbase_refinery_macros::embed_migrations!("migrations/logs");
 }
+mod changes {
+  trailbase_refinery_macros::embed_migrations!("migrations/changes");
+}
 
 const MIGRATION_TABLE_NAME: &str = "_schema_history";
 
@@ -130,3 +133,23 @@ pub(crate) fn apply_logs_migrations(
 
   return Ok(());
 }
+
+pub(crate) fn apply_changes_migrations(
+  changes_conn: &mut rusqlite::Connection,
+) -> Result<(), trailbase_refinery_core::Error> {
+  let mut runner = changes::migrations::runner();
+  runner.set_migration_table_name(MIGRATION_TABLE_NAME);
+
+  let report = runner.run(changes_conn).map_err(|err| {
+    error!("Changes migrations: {err}");
+    return err;
+  })?;
+
+  if cfg!(test) {
+    debug!("Changes migrations: {report:?}");
+  } else {
+    info!("Changes migrations: {report:?}");
+  }
+
+  return Ok(());
+}
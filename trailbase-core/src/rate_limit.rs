@@ -0,0 +1,364 @@
+use axum::extract::{RawPathParams, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::RequestExt;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::app_state::AppState;
+use crate::auth::{AuthError, User};
+use crate::config::proto::{RateLimitConfig, RateLimitPolicy};
+use crate::extract::ClientIp;
+
+/// Buckets are spread across independently locked shards to reduce contention.
+const NUM_SHARDS: usize = 16;
+/// Maximum number of buckets per shard, bounding memory in the face of many distinct clients.
+const SHARD_CAPACITY: usize = 4096;
+
+/// Groups of routes, which share a rate limit policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RouteGroup {
+  Auth,
+  Records,
+  CustomRoutes,
+}
+
+impl RouteGroup {
+  fn name(&self) -> &'static str {
+    return match self {
+      Self::Auth => "auth",
+      Self::Records => "records",
+      Self::CustomRoutes => "custom",
+    };
+  }
+
+  /// Returns the configured policy for this group. For record APIs, per-API overrides take
+  /// precedence over the group's default.
+  fn policy<'a>(
+    &self,
+    config: &'a RateLimitConfig,
+    api_name: Option<&str>,
+  ) -> Option<&'a RateLimitPolicy> {
+    return match self {
+      Self::Auth => config.auth.as_ref(),
+      Self::Records => api_name
+        .and_then(|name| config.record_apis.get(name))
+        .or(config.records.as_ref()),
+      Self::CustomRoutes => config.custom_routes.as_ref(),
+    };
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Limit {
+  /// Tokens refilled per second.
+  rate: f64,
+  /// Bucket capacity.
+  burst: f64,
+  per_user: bool,
+}
+
+impl Limit {
+  fn from_policy(policy: &RateLimitPolicy) -> Option<Self> {
+    let requests_per_minute = policy.requests_per_minute.unwrap_or(0);
+    if requests_per_minute == 0 {
+      return None;
+    }
+
+    return Some(Limit {
+      rate: requests_per_minute as f64 / 60.0,
+      burst: policy.burst.unwrap_or(requests_per_minute).max(1) as f64,
+      per_user: policy.per_user.unwrap_or(false),
+    });
+  }
+}
+
+#[derive(Debug)]
+struct Bucket {
+  tokens: f64,
+  updated: Instant,
+}
+
+impl Bucket {
+  /// Takes a token from the bucket or returns the time until the next token becomes available.
+  fn acquire(&mut self, limit: &Limit, now: Instant) -> Result<(), Duration> {
+    let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * limit.rate).min(limit.burst);
+    self.updated = now;
+
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      return Ok(());
+    }
+
+    return Err(Duration::from_secs_f64((1.0 - self.tokens) / limit.rate));
+  }
+
+  /// A bucket that would have been refilled completely is indistinguishable from a new one.
+  fn is_full(&self, limit: &Limit, now: Instant) -> bool {
+    let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+    return self.tokens + elapsed * limit.rate >= limit.burst;
+  }
+}
+
+type Shard = Mutex<HashMap<String, (Bucket, Limit)>>;
+
+/// In-memory token-bucket rate limiter shared across all route groups.
+///
+/// Idle buckets are pruned periodically, see `prune`. Shards exceeding their capacity in between
+/// evict idle buckets and, if need be, the least recently used one.
+#[derive(Clone)]
+pub(crate) struct RateLimiter {
+  shards: Arc<[Shard]>,
+  hasher: RandomState,
+}
+
+impl Default for RateLimiter {
+  fn default() -> Self {
+    return Self {
+      shards: (0..NUM_SHARDS).map(|_| Shard::default()).collect(),
+      hasher: RandomState::new(),
+    };
+  }
+}
+
+impl RateLimiter {
+  fn shard(&self, key: &str) -> &Shard {
+    return &self.shards[self.hasher.hash_one(key) as usize % self.shards.len()];
+  }
+
+  fn acquire(&self, key: String, limit: Limit, now: Instant) -> Result<(), Duration> {
+    let mut buckets = self.shard(&key).lock();
+
+    if buckets.len() >= SHARD_CAPACITY && !buckets.contains_key(&key) {
+      buckets.retain(|_, (bucket, limit)| !bucket.is_full(limit, now));
+
+      if buckets.len() >= SHARD_CAPACITY {
+        let lru = buckets
+          .iter()
+          .min_by_key(|(_, (bucket, _))| bucket.updated)
+          .map(|(key, _)| key.clone());
+        if let Some(lru) = lru {
+          buckets.remove(&lru);
+        }
+      }
+    }
+
+    let (bucket, bucket_limit) = buckets.entry(key).or_insert_with(|| {
+      (
+        Bucket {
+          tokens: limit.burst,
+          updated: now,
+        },
+        limit,
+      )
+    });
+    // Pick up config changes.
+    *bucket_limit = limit;
+
+    return bucket.acquire(&limit, now);
+  }
+
+  /// Drops buckets, which have been refilled completely. Returns the number of remaining buckets.
+  pub(crate) fn prune(&self, now: Instant) -> usize {
+    return self
+      .shards
+      .iter()
+      .map(|shard| {
+        let mut buckets = shard.lock();
+        buckets.retain(|_, (bucket, limit)| !bucket.is_full(limit, now));
+        return buckets.len();
+      })
+      .sum();
+  }
+}
+
+fn bucket_key(
+  group: RouteGroup,
+  api_name: Option<&str>,
+  limit: &Limit,
+  user: Option<&User>,
+  ip: Option<IpAddr>,
+) -> String {
+  let subject = match (user, ip) {
+    (Some(user), _) if limit.per_user => format!("user:{}", user.id),
+    (_, Some(ip)) => format!("ip:{ip}"),
+    // Requests for which we cannot determine the client's IP share a single bucket.
+    (_, None) => "anonymous".to_string(),
+  };
+
+  return match api_name {
+    Some(api_name) => format!("{}:{api_name}:{subject}", group.name()),
+    None => format!("{}:{subject}", group.name()),
+  };
+}
+
+/// Middleware rejecting requests exceeding the configured limits for the given route group with
+/// "429 Too Many Requests".
+pub(crate) async fn rate_limit(
+  State((state, group)): State<(AppState, RouteGroup)>,
+  mut req: Request,
+  next: Next,
+) -> Result<Response, AuthError> {
+  let api_name = match group {
+    RouteGroup::Records => req
+      .extract_parts::<RawPathParams>()
+      .await
+      .ok()
+      .and_then(|params| {
+        params
+          .iter()
+          .find(|(key, _)| *key == "name")
+          .map(|(_, value)| value.to_string())
+      }),
+    _ => None,
+  };
+
+  let Some(limit) = state.access_config(|c| {
+    let rate_limits = c.server.rate_limits.as_ref()?;
+    return Limit::from_policy(group.policy(rate_limits, api_name.as_deref())?);
+  }) else {
+    return Ok(next.run(req).await);
+  };
+
+  let user = if limit.per_user {
+    req
+      .extract_parts_with_state::<Option<User>, _>(&state)
+      .await
+      .ok()
+      .flatten()
+  } else {
+    None
+  };
+  let ClientIp(ip) = req
+    .extract_parts::<ClientIp>()
+    .await
+    .unwrap_or(ClientIp(None));
+
+  let key = bucket_key(group, api_name.as_deref(), &limit, user.as_ref(), ip);
+  if let Err(retry_after) = state.rate_limiter().acquire(key, limit, Instant::now()) {
+    let retry_after_sec = retry_after.as_secs_f64().ceil() as i64;
+
+    // Fields of events within the request's span end up in the log entry's data.
+    tracing::event!(
+      tracing::Level::WARN,
+      rate_limited = group.name(),
+      retry_after_sec
+    );
+    return Err(AuthError::TooManyRequests(retry_after_sec));
+  }
+
+  return Ok(next.run(req).await);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::http::StatusCode;
+  use axum::routing::get;
+  use axum::{middleware, Router};
+  use axum_test::TestServer;
+
+  use crate::app_state::{test_state, TestStateOptions};
+  use crate::config::proto::Config;
+
+  #[test]
+  fn test_token_bucket() {
+    let limiter = RateLimiter::default();
+    let limit = Limit::from_policy(&RateLimitPolicy {
+      requests_per_minute: Some(60),
+      burst: Some(2),
+      per_user: None,
+    })
+    .unwrap();
+
+    let now = Instant::now();
+    assert!(limiter.acquire("a".to_string(), limit, now).is_ok());
+    assert!(limiter.acquire("a".to_string(), limit, now).is_ok());
+
+    let retry_after = limiter
+      .acquire("a".to_string(), limit, now)
+      .expect_err("burst exhausted");
+    assert_eq!(retry_after, Duration::from_secs(1));
+
+    // Other keys have their own bucket.
+    assert!(limiter.acquire("b".to_string(), limit, now).is_ok());
+
+    // Refilled at one token per second.
+    let later = now + Duration::from_millis(1500);
+    assert!(limiter.acquire("a".to_string(), limit, later).is_ok());
+    assert!(limiter.acquire("a".to_string(), limit, later).is_err());
+
+    // Disabled policies.
+    assert_eq!(Limit::from_policy(&RateLimitPolicy::default()), None);
+  }
+
+  #[test]
+  fn test_bucket_pruning() {
+    let limiter = RateLimiter::default();
+    let limit = Limit::from_policy(&RateLimitPolicy {
+      requests_per_minute: Some(60),
+      burst: Some(2),
+      per_user: None,
+    })
+    .unwrap();
+
+    let now = Instant::now();
+    assert!(limiter.acquire("a".to_string(), limit, now).is_ok());
+    assert_eq!(1, limiter.prune(now));
+    assert_eq!(0, limiter.prune(now + Duration::from_secs(1)));
+
+    // Shards are bounded, evicting the least recently used bucket if none is idle.
+    let limit = Limit {
+      burst: 100.0,
+      ..limit
+    };
+    for i in 0..(NUM_SHARDS * SHARD_CAPACITY + 100) {
+      assert!(limiter.acquire(format!("key{i}"), limit, now).is_ok());
+    }
+    assert!(limiter.prune(now) <= NUM_SHARDS * SHARD_CAPACITY);
+  }
+
+  #[tokio::test]
+  async fn test_rate_limit_middleware() {
+    let mut config = Config::new_with_custom_defaults();
+    config.server.rate_limits = Some(RateLimitConfig {
+      custom_routes: Some(RateLimitPolicy {
+        requests_per_minute: Some(1),
+        ..Default::default()
+      }),
+      ..Default::default()
+    });
+
+    let state = test_state(Some(TestStateOptions {
+      config: Some(config),
+      ..Default::default()
+    }))
+    .await
+    .unwrap();
+
+    let router = Router::new()
+      .route("/limited", get(|| async { "ok" }))
+      .route_layer(middleware::from_fn_with_state(
+        (state.clone(), RouteGroup::CustomRoutes),
+        rate_limit,
+      ))
+      .route("/unlimited", get(|| async { "ok" }))
+      .with_state(state.clone());
+
+    let server = TestServer::new(router).unwrap();
+
+    server.get("/limited").await.assert_status_ok();
+
+    let response = server.get("/limited").await;
+    response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.header("retry-after"), "60");
+
+    server.get("/unlimited").await.assert_status_ok();
+    server.get("/unlimited").await.assert_status_ok();
+  }
+}
@@ -0,0 +1,256 @@
+use log::*;
+use parking_lot::Mutex;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::hooks::PreUpdateCase;
+use rusqlite::types::Value;
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::sync::Arc;
+use trailbase_sqlite::connection::{extract_old_record_values, extract_record_values};
+
+use crate::config::proto::Config;
+use crate::constants::CHANGES_TABLE;
+use crate::records::sql_to_json::valueref_to_json;
+use crate::records::subscribe::RecordAction;
+use crate::table_metadata::TableMetadata;
+use crate::value_notifier::{Computed, ValueNotifier};
+
+/// Name under which the changes database is attached to the main connection.
+const CHANGES_SCHEMA: &str = "changes";
+const TRIGGER_PREFIX: &str = "__change_capture_";
+const VALUES_FUNCTION: &str = "_change_capture_values";
+
+struct CapturedChange {
+  table_metadata: Arc<TableMetadata>,
+  old_values: Option<Vec<Value>>,
+  new_values: Option<Vec<Value>>,
+}
+
+/// Records mutations of the configured tables to the `_changes` table in the separate changes
+/// database for consumption by external systems, e.g. ETL.
+///
+/// The changes database is attached to the main connection and changes are written by TEMP
+/// triggers as part of the mutating transaction, i.e. changes rolled back by a transaction or
+/// savepoint are never recorded. The triggers only reference the rowid, s.t. they don't get in
+/// the way of schema changes, and pick up record values captured by the preupdate hook.
+///
+/// NOTE: In WAL mode transactions are atomic per database, i.e. a crash mid-commit may persist
+/// the main database's changes without their log entries or vice versa.
+pub(crate) struct ChangeCapture {
+  tables: Computed<HashSet<String>, Config>,
+
+  /// Record values captured by the preupdate hook, keyed by table name and rowid, until picked
+  /// up by the triggers of the same change.
+  pending: Mutex<HashMap<(String, i64), CapturedChange>>,
+}
+
+impl ChangeCapture {
+  pub(crate) fn new(config: &ValueNotifier<Config>) -> Self {
+    return Self {
+      tables: Computed::new(config, |c| {
+        return c.server.change_capture_tables.iter().cloned().collect();
+      }),
+      pending: Mutex::new(HashMap::new()),
+    };
+  }
+
+  pub(crate) fn enabled(&self) -> bool {
+    return !self.tables.load().is_empty();
+  }
+
+  pub(crate) fn is_captured(&self, table_name: &str) -> bool {
+    return self.tables.load().contains(table_name);
+  }
+
+  /// Collects a change's record values from within the preupdate hook.
+  pub(crate) fn capture(
+    &self,
+    table_metadata: Option<Arc<TableMetadata>>,
+    action: RecordAction,
+    rowid: i64,
+    case: &PreUpdateCase,
+  ) {
+    let Some(table_metadata) = table_metadata else {
+      warn!("Missing metadata for captured table");
+      return;
+    };
+
+    let (old_values, new_values) = match action {
+      RecordAction::Insert => (None, extract_record_values(case)),
+      RecordAction::Update => (extract_old_record_values(case), extract_record_values(case)),
+      RecordAction::Delete => (extract_old_record_values(case), None),
+    };
+
+    self.pending.lock().insert(
+      (table_metadata.name().to_string(), rowid),
+      CapturedChange {
+        table_metadata,
+        old_values,
+        new_values,
+      },
+    );
+  }
+
+  /// Registers the function used by the triggers to look up captured values and installs hooks
+  /// to discard them once the transaction ends.
+  pub(crate) fn install(self: &Arc<Self>, conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    // NOTE: Not INNOCUOUS, since it's only meant to be used by our TEMP triggers, which are
+    // trusted independent of "trusted_schema".
+    let capture = AssertUnwindSafe(self.clone());
+    conn.create_scalar_function(VALUES_FUNCTION, 3, FunctionFlags::SQLITE_UTF8, move |ctx| {
+      let table_name: String = ctx.get(0)?;
+      let rowid: i64 = ctx.get(1)?;
+      let old: bool = ctx.get::<String>(2)? == "old";
+
+      let pending = capture.pending.lock();
+      let Some(change) = pending.get(&(table_name, rowid)) else {
+        warn!("Missing captured values");
+        return Ok(None);
+      };
+
+      let values = if old {
+        &change.old_values
+      } else {
+        &change.new_values
+      };
+      return Ok(
+        values
+          .as_ref()
+          .and_then(|values| record_to_json(&change.table_metadata, values)),
+      );
+    })?;
+
+    let capture = self.clone();
+    conn.commit_hook(Some(move || {
+      capture.pending.lock().clear();
+      // Don't turn the commit into a rollback.
+      return false;
+    }));
+
+    let capture = self.clone();
+    conn.rollback_hook(Some(move || {
+      capture.pending.lock().clear();
+    }));
+
+    return Ok(());
+  }
+
+  /// (Re-)creates the TEMP triggers for all captured tables.
+  ///
+  /// Needs to be called whenever the config or the schema changes, since triggers are dropped
+  /// together with their table, e.g. when a table is altered by re-creating it.
+  pub(crate) fn sync_triggers(&self, conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let existing: Vec<String> = {
+      let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_temp_master WHERE type = 'trigger' AND name LIKE $1 || '%'",
+      )?;
+      let rows = stmt.query_map([TRIGGER_PREFIX], |row| row.get(0))?;
+      rows.collect::<Result<_, _>>()?
+    };
+
+    for name in existing {
+      let name = name.replace('"', "\"\"");
+      conn.execute(&format!(r#"DROP TRIGGER IF EXISTS temp."{name}""#), ())?;
+    }
+
+    for table_name in self.tables.load().iter() {
+      // Only rowid tables can be captured.
+      let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_list WHERE schema = 'main' AND name = $1 AND type = 'table' AND wr = 0)",
+        [table_name],
+        |row| row.get(0),
+      )?;
+      if !exists {
+        warn!("Change capture table not found: '{table_name}'");
+        continue;
+      }
+
+      conn.execute_batch(&build_triggers(table_name))?;
+    }
+
+    return Ok(());
+  }
+}
+
+/// Attaches the changes database to the main connection, s.t. changes can be recorded as part of
+/// the mutating transaction.
+pub(crate) fn attach_changes_db(conn: &rusqlite::Connection, path: &Path) -> rusqlite::Result<()> {
+  conn.execute(
+    &format!("ATTACH DATABASE $1 AS {CHANGES_SCHEMA}"),
+    [path.to_string_lossy()],
+  )?;
+  return Ok(());
+}
+
+fn build_triggers(table_name: &str) -> String {
+  let quoted_name = table_name.replace('"', "\"\"");
+  let literal_name = table_name.replace('\'', "''");
+
+  // NOTE: Statements within triggers must not qualify table names. Since `_changes` only exists
+  // in the attached changes database, it resolves unambiguously.
+  let trigger = |action: &str, rowid: &str, old_values: &str, new_values: &str| {
+    return format!(
+      r#"
+        CREATE TEMP TRIGGER "{TRIGGER_PREFIX}{quoted_name}_{action}" AFTER {op} ON "{quoted_name}"
+        BEGIN
+          INSERT INTO {CHANGES_TABLE} (table_name, row_id, action, old_values, new_values)
+          VALUES ('{literal_name}', {rowid}, '{action}', {old_values}, {new_values});
+        END;
+      "#,
+      op = action.to_uppercase(),
+    );
+  };
+  let values = |rowid: &str, which: &str| {
+    return format!("{VALUES_FUNCTION}('{literal_name}', {rowid}, '{which}')");
+  };
+
+  return [
+    trigger(
+      "insert",
+      "NEW._rowid_",
+      "NULL",
+      &values("NEW._rowid_", "new"),
+    ),
+    trigger(
+      "update",
+      "NEW._rowid_",
+      &values("NEW._rowid_", "old"),
+      &values("NEW._rowid_", "new"),
+    ),
+    trigger(
+      "delete",
+      "OLD._rowid_",
+      &values("OLD._rowid_", "old"),
+      "NULL",
+    ),
+  ]
+  .join("\n");
+}
+
+fn record_to_json(table_metadata: &TableMetadata, values: &[Value]) -> Option<String> {
+  let columns = &table_metadata.schema.columns;
+  if columns.len() != values.len() {
+    warn!(
+      "Captured {} values for {} columns of '{}'",
+      values.len(),
+      columns.len(),
+      table_metadata.name()
+    );
+    return None;
+  }
+
+  let record = serde_json::Value::Object(
+    columns
+      .iter()
+      .zip(values)
+      .map(|(column, value)| {
+        return (
+          column.name.clone(),
+          valueref_to_json(value.into()).unwrap_or(serde_json::Value::Null),
+        );
+      })
+      .collect(),
+  );
+  return Some(record.to_string());
+}
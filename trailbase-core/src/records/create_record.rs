@@ -7,10 +7,12 @@ use utoipa::{IntoParams, ToSchema};
 
 use crate::app_state::AppState;
 use crate::auth::user::User;
+use crate::config::proto::WebhookEvent;
 use crate::extract::Either;
 use crate::records::json_to_sql::{InsertQueryBuilder, JsonRow, LazyParams, Params};
 use crate::records::{Permission, RecordError};
 use crate::schema::ColumnDataType;
+use crate::webhooks::WebhookDeliveries;
 
 #[derive(Clone, Debug, Default, Deserialize, IntoParams)]
 pub struct CreateRecordQuery {
@@ -124,6 +126,7 @@ pub async fn create_record_handler(
     let Ok(mut params) = lazy_params.consume() else {
       return Err(RecordError::BadRequest("Parameter conversion"));
     };
+    api.check_writable_columns(&params)?;
 
     if api.insert_autofill_missing_user_id_columns() {
       let column_names = params.column_names();
@@ -153,6 +156,9 @@ pub async fn create_record_handler(
 
   let pk_column = api.record_pk_column();
   let data_type = pk_column.data_type;
+  let webhooks = WebhookDeliveries::new(&api, WebhookEvent::Insert);
+  let notify_webhooks = webhooks.is_some();
+
   let record_ids = match params_list.len() {
     0 => {
       return Err(RecordError::Internal("".into()));
@@ -164,6 +170,7 @@ pub async fn create_record_handler(
         api.insert_conflict_resolution_strategy(),
         Some(&pk_column.name),
         move |row| extract_record_id(data_type, row),
+        webhooks.map(WebhookDeliveries::insert_hook),
       )
       .await
       .map_err(|err| RecordError::Internal(err.into()))?;
@@ -176,11 +183,16 @@ pub async fn create_record_handler(
       api.insert_conflict_resolution_strategy(),
       Some(&pk_column.name),
       move |row| extract_record_id(data_type, row),
+      webhooks.map(WebhookDeliveries::insert_hook),
     )
     .await
     .map_err(|err| RecordError::Internal(err.into()))?,
   };
 
+  if notify_webhooks {
+    state.webhooks().notify();
+  }
+
   if let Some(redirect_to) = create_record_query.redirect_to {
     return Ok(Redirect::to(&redirect_to).into_response());
   }
@@ -6,8 +6,10 @@ use axum::{
 
 use crate::app_state::AppState;
 use crate::auth::user::User;
+use crate::config::proto::WebhookEvent;
 use crate::records::json_to_sql::DeleteQueryBuilder;
 use crate::records::{Permission, RecordError};
+use crate::webhooks::WebhookDeliveries;
 
 /// Delete record.
 #[utoipa::path(
@@ -36,15 +38,23 @@ pub async fn delete_record_handler(
     .check_record_level_access(Permission::Delete, Some(&record_id), None, user.as_ref())
     .await?;
 
+  let webhooks = WebhookDeliveries::new(&api, WebhookEvent::Delete);
+  let notify_webhooks = webhooks.is_some();
+
   DeleteQueryBuilder::run(
     &state,
     table_metadata,
     &api.record_pk_column().name,
     record_id,
+    webhooks.map(|w| w.hook(record)),
   )
   .await
   .map_err(|err| RecordError::Internal(err.into()))?;
 
+  if notify_webhooks {
+    state.webhooks().notify();
+  }
+
   return Ok((StatusCode::OK, "deleted").into_response());
 }
 
@@ -3,7 +3,7 @@ use serde::Deserialize;
 
 use crate::auth::user::User;
 use crate::records::{Permission, RecordError};
-use crate::table_metadata::{build_json_schema, build_json_schema_recursive, Expand};
+use crate::table_metadata::{build_json_schema_recursive, Expand};
 use crate::{api::JsonSchemaMode, app_state::AppState};
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,6 +35,17 @@ pub async fn json_schema_handler(
 
   let mode = request.mode.unwrap_or(JsonSchemaMode::Insert);
 
+  // Hidden columns are neither readable nor writable, read-only columns only readable.
+  let omitted_columns: Vec<&str> = match mode {
+    JsonSchemaMode::Select => api.hidden_columns().iter().map(|c| c.as_str()).collect(),
+    JsonSchemaMode::Insert | JsonSchemaMode::Update => api
+      .hidden_columns()
+      .iter()
+      .chain(api.read_only_columns())
+      .map(|c| c.as_str())
+      .collect(),
+  };
+
   match (api.expand(), mode) {
     (Some(config_expand), JsonSchemaMode::Select) => {
       let foreign_key_columns = config_expand.keys().map(|k| k.as_str()).collect::<Vec<_>>();
@@ -42,14 +53,25 @@ pub async fn json_schema_handler(
         table_metadata: state.table_metadata(),
         foreign_key_columns,
       };
-      let (_schema, json) =
-        build_json_schema_recursive(api.table_name(), api.metadata(), mode, Some(expand))
-          .map_err(|err| RecordError::Internal(err.into()))?;
+      let (_schema, json) = build_json_schema_recursive(
+        api.table_name(),
+        api.metadata(),
+        mode,
+        Some(expand),
+        &omitted_columns,
+      )
+      .map_err(|err| RecordError::Internal(err.into()))?;
       return Ok(Json(json));
     }
     _ => {
-      let (_schema, json) = build_json_schema(api.table_name(), api.metadata(), mode)
-        .map_err(|err| RecordError::Internal(err.into()))?;
+      let (_schema, json) = build_json_schema_recursive(
+        api.table_name(),
+        api.metadata(),
+        mode,
+        None,
+        &omitted_columns,
+      )
+      .map_err(|err| RecordError::Internal(err.into()))?;
       return Ok(Json(json));
     }
   }
@@ -3,18 +3,20 @@ use itertools::Itertools;
 use log::*;
 use object_store::ObjectStore;
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use trailbase_sqlite::schema::{FileUpload, FileUploadInput, FileUploads};
-use trailbase_sqlite::{NamedParams, Params as _, Value};
+use trailbase_sqlite::{named_params, NamedParams, Params as _, Value};
 
+use crate::auth::user::User;
 use crate::config::proto::ConflictResolutionStrategy;
+use crate::listing::{limit_or_default, parse_expand_entry, ExpandOptions, Order};
 use crate::records::error::RecordError;
 use crate::records::files::delete_files_in_row;
+use crate::records::sql_to_json::{row_to_json_expand, JsonError};
+use crate::records::{user_claims_value, user_roles_value, Permission, RecordApi, USER_SUBQUERY};
 use crate::schema::{Column, ColumnDataType, ColumnOption};
-use crate::table_metadata::{
-  self, ColumnMetadata, JsonColumnMetadata, TableMetadata, TableMetadataCache,
-};
+use crate::table_metadata::{self, ColumnMetadata, JsonColumnMetadata, TableMetadata};
 use crate::AppState;
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -313,92 +315,503 @@ impl Params {
   }
 }
 
+/// Column projection, i.e. `?fields=col0,col1,fk.col2`.
+///
+/// Plain names select columns of the record itself, while dotted names select columns of the
+/// foreign records inlined via `expand`, e.g. `author.name` or `author.organization.name`.
+#[derive(Debug, Default)]
+pub(crate) struct Fields {
+  /// Selected columns of the record itself.
+  pub columns: Vec<String>,
+  /// Selected columns of expanded foreign records keyed by the expansion path, e.g. "author" or
+  /// "author.organization".
+  pub foreign: HashMap<String, Vec<String>>,
+}
+
+impl Fields {
+  /// Parses and validates the requested fields. Returns `None` if no fields were requested, i.e.
+  /// all columns should be returned.
+  pub(crate) fn parse<T: AsRef<str>, E: AsRef<str>>(
+    api: &RecordApi,
+    fields: &[T],
+    expand: &[E],
+  ) -> Result<Option<Fields>, RecordError> {
+    let invalid = || RecordError::BadRequest("Invalid fields");
+    let metadata = api.metadata();
+
+    let mut result = Fields::default();
+    for field in fields {
+      let field = field.as_ref();
+      if field.is_empty() {
+        continue;
+      }
+
+      match field.rsplit_once('.') {
+        Some((path, foreign_column_name)) => {
+          // Foreign columns are validated when building the expansions.
+          if foreign_column_name.is_empty()
+            || foreign_column_name.starts_with("_")
+            || !expand.iter().any(|e| e.as_ref() == path)
+          {
+            return Err(invalid());
+          }
+
+          let foreign = result.foreign.entry(path.to_string()).or_default();
+          if !foreign.iter().any(|c| c == foreign_column_name) {
+            foreign.push(foreign_column_name.to_string());
+          }
+        }
+        None => {
+          if !api.is_column_visible(field) || metadata.column_by_name(field).is_none() {
+            return Err(invalid());
+          }
+
+          if !result.columns.iter().any(|c| c == field) {
+            result.columns.push(field.to_string());
+          }
+        }
+      }
+    }
+
+    if result.columns.is_empty() && result.foreign.is_empty() {
+      return Ok(None);
+    }
+
+    // Expanded records are nested into their parent's foreign key column, thus projections need
+    // to include the foreign key columns along each expansion path.
+    for path in expand {
+      // Child expansions are fetched separately and cannot be projected.
+      let Ok((path, None)) = parse_expand_entry(path.as_ref()) else {
+        continue;
+      };
+      if api.expand_children().iter().any(|c| c == path) {
+        continue;
+      }
+
+      let mut segments = path.split('.');
+      let Some(column_name) = segments.next().filter(|c| !c.is_empty()) else {
+        continue;
+      };
+      if !api.is_column_visible(column_name) || metadata.column_by_name(column_name).is_none() {
+        return Err(RecordError::BadRequest("Invalid expansion"));
+      }
+      if !result.columns.iter().any(|c| c == column_name) {
+        result.columns.push(column_name.to_string());
+      }
+
+      let mut parent_path = column_name.to_string();
+      for column_name in segments {
+        if let Some(foreign) = result.foreign.get_mut(&parent_path) {
+          if !foreign.iter().any(|c| c == column_name) {
+            foreign.push(column_name.to_string());
+          }
+        }
+        parent_path = format!("{parent_path}.{column_name}");
+      }
+    }
+
+    return Ok(Some(result));
+  }
+
+  /// Select clause for the selected columns of the record, e.g. `R."col0", R."col1"`.
+  pub(crate) fn select(&self, prefix: &str) -> String {
+    return self
+      .columns
+      .iter()
+      .map(|col| format!(r#"{prefix}."{col}""#))
+      .join(", ");
+  }
+}
+
+/// A single expanded foreign record, i.e. one hop of an `expand=author.organization` path.
+pub(crate) struct Expansion {
+  /// Full path of the expansion, e.g. "author.organization".
+  pub path: String,
+  /// Index of the parent expansion or `None` if the foreign key belongs to the root record.
+  pub parent: Option<usize>,
+  /// Foreign key column of the parent record.
+  pub column_name: String,
+  /// Metadata of the referenced foreign table.
+  pub metadata: Arc<TableMetadata>,
+  /// Target Record API of the expansion, if any. Its access rules and hidden columns apply to the
+  /// expanded record and its `expand` list to nested expansions.
+  pub api: Option<RecordApi>,
+  /// Number of selected columns including the trailing primary key marker.
+  num_columns: usize,
+}
+
+/// Child records referencing the root record, e.g. `?expand=comments(limit:5,order:-created)`.
+///
+/// Unlike parent records, which are joined, children are fetched using a separate query.
+pub(crate) struct ChildExpansion {
+  /// Name of the child Record API, which is also the key of the inlined list.
+  pub name: String,
+  pub api: RecordApi,
+  /// Column of the child table referencing the root record.
+  pub column_name: String,
+  pub limit: usize,
+  /// Total ordering, i.e. with the primary key as tie-breaker.
+  pub order: Vec<(String, Order)>,
+}
+
+impl ChildExpansion {
+  fn build(
+    state: &AppState,
+    api: &RecordApi,
+    name: &str,
+    options: ExpandOptions,
+    user: Option<&User>,
+  ) -> Result<ChildExpansion, RecordError> {
+    let invalid = || RecordError::BadRequest("Invalid expansion");
+    if !api.expand_children().iter().any(|c| c == name) {
+      return Err(invalid());
+    }
+
+    let Some(child_api) = state.lookup_record_api(name) else {
+      return Err(invalid());
+    };
+    // Inlining children is akin to listing them.
+    child_api.check_table_level_access(Permission::List, user)?;
+
+    let Some(child_table) = child_api.table_metadata() else {
+      return Err(invalid());
+    };
+    let Some((column, _foreign_key)) = child_table.unique_foreign_key_to(api.table_name()) else {
+      return Err(invalid());
+    };
+
+    let pk_column_name = &child_api.record_pk_column().name;
+    let mut order = options.order;
+    for (col, _) in &order {
+      if !child_api.is_column_visible(col) || child_table.column_by_name(col).is_none() {
+        return Err(RecordError::BadRequest("Invalid order"));
+      }
+    }
+    if !order.iter().any(|(col, _)| col == pk_column_name) {
+      let tie_breaker = order
+        .last()
+        .map_or(Order::Descending, |(_, ord)| ord.clone());
+      order.push((pk_column_name.clone(), tie_breaker));
+    }
+
+    return Ok(ChildExpansion {
+      name: name.to_string(),
+      column_name: column.name.clone(),
+      limit: limit_or_default(options.limit),
+      order,
+      api: child_api,
+    });
+  }
+}
+
 pub(crate) struct Expansions {
-  /// Contains the indexes on where to cut the resulting Row.
+  /// Number of selected root record columns.
   ///
   /// The joins will lead to a row schema that looks something like:
   ///   (root_table..., foreign_table0..., foreign_table1...).
-  pub indexes: Vec<(usize, Arc<TableMetadata>)>,
+  root_columns: usize,
+  /// The expanded foreign records in join order. Parents always precede their children.
+  pub expansions: Vec<Expansion>,
   /// The actual join statements.
   pub joins: Vec<String>,
 
-  /// Select clauses in case the joins are aliased, i.e. a `prefix` is given.
-  pub selects: Option<Vec<String>>,
+  /// Select clauses for the joined foreign tables.
+  pub selects: Vec<String>,
+
+  /// Child records to be fetched separately.
+  pub children: Vec<ChildExpansion>,
 }
 
 impl Expansions {
+  /// Validates the requested expansion paths and builds the respective joins.
+  ///
+  /// The first hop of a path needs to be configured for expansion on the given API, subsequent
+  /// hops on the Record API exposing the respective parent table. Expanded records are subject to
+  /// the read access of the Record API exposing their table, i.e. records the user may not read
+  /// are not inlined.
+  ///
+  /// NOTE: the joins reference the `:__user_id` named parameter.
   pub(crate) fn build<T: AsRef<str>>(
-    table_metadata: &TableMetadataCache,
-    table_name: &str,
+    state: &AppState,
+    api: &RecordApi,
     expand: &[T],
-    prefix: Option<&str>,
+    prefix: &str,
+    fields: Option<&Fields>,
+    user: Option<&User>,
   ) -> Result<Expansions, RecordError> {
-    let Some(root_table) = table_metadata.get(table_name) else {
+    let invalid = || RecordError::BadRequest("Invalid expansion");
+    let table_metadata = state.table_metadata();
+    let Some(root_table) = table_metadata.get(api.table_name()) else {
       return Err(RecordError::ApiRequiresTable);
     };
 
-    let mut joins = vec![];
-    let mut indexes = vec![(root_table.schema.columns.len(), root_table.clone())];
+    let mut expansions: Vec<Expansion> = vec![];
+    let mut children: Vec<ChildExpansion> = vec![];
+    for path in expand {
+      let (path, options) = parse_expand_entry(path.as_ref()).map_err(|_| invalid())?;
+      if path.is_empty() {
+        continue;
+      }
 
-    for (idx, col_name) in expand.iter().enumerate() {
-      if col_name.as_ref().is_empty() {
+      if options.is_some() || api.expand_children().iter().any(|c| c == path) {
+        if children.iter().any(|c| c.name == path) {
+          return Err(invalid());
+        }
+        children.push(ChildExpansion::build(
+          state,
+          api,
+          path,
+          options.unwrap_or_default(),
+          user,
+        )?);
         continue;
       }
-      let Some((column, _col_metadata)) = root_table.column_by_name(col_name.as_ref()) else {
-        return Err(RecordError::ApiRequiresTable);
-      };
 
-      let Some(ColumnOption::ForeignKey {
-        foreign_table: foreign_table_name,
-        referred_columns: _,
-        ..
-      }) = column
-        .options
-        .iter()
-        .find_or_first(|o| matches!(o, ColumnOption::ForeignKey { .. }))
-      else {
-        return Err(RecordError::ApiRequiresTable);
-      };
+      let segments: Vec<&str> = path.split('.').collect();
+      if segments.len() > api.max_expand_depth() {
+        return Err(invalid());
+      }
 
-      let Some(foreign_table) = table_metadata.get(foreign_table_name) else {
-        return Err(RecordError::ApiRequiresTable);
-      };
+      let mut parent: Option<usize> = None;
+      for (depth, column_name) in segments.iter().enumerate() {
+        let sub_path = segments[..=depth].join(".");
+        if let Some(idx) = expansions.iter().position(|e| e.path == sub_path) {
+          parent = Some(idx);
+          continue;
+        }
+
+        let (parent_table, parent_api) = match parent {
+          None => (root_table.clone(), Some(api)),
+          Some(idx) => (
+            expansions[idx].metadata.clone(),
+            expansions[idx].api.as_ref(),
+          ),
+        };
+
+        let Some(parent_api) = parent_api.filter(|parent_api| {
+          parent_api
+            .expand()
+            .is_some_and(|expand| expand.contains_key(*column_name))
+        }) else {
+          return Err(invalid());
+        };
+
+        let Some((column, _col_metadata)) = parent_table.column_by_name(column_name) else {
+          return Err(invalid());
+        };
+
+        let Some(ColumnOption::ForeignKey {
+          foreign_table: foreign_table_name,
+          referred_columns: _,
+          ..
+        }) = column
+          .options
+          .iter()
+          .find_or_first(|o| matches!(o, ColumnOption::ForeignKey { .. }))
+        else {
+          return Err(invalid());
+        };
+
+        let Some(foreign_table) = table_metadata.get(foreign_table_name) else {
+          return Err(RecordError::ApiRequiresTable);
+        };
 
-      let Some(foreign_pk_column_idx) = foreign_table.record_pk_column else {
+        let foreign_api = match parent_api.expand_api(column_name) {
+          Some(foreign_api_name) => {
+            // Validated to exist as part of the config. Never fall back to an unrestricted
+            // expansion, in case it doesn't.
+            let Some(foreign_api) = state
+              .lookup_record_api(foreign_api_name)
+              .filter(|foreign_api| foreign_api.table_name() == foreign_table_name)
+            else {
+              return Err(RecordError::ApiNotFound);
+            };
+            Some(foreign_api)
+          }
+          None => state.lookup_record_api_for_table(foreign_table_name),
+        };
+        expansions.push(Expansion {
+          path: sub_path,
+          parent,
+          column_name: column_name.to_string(),
+          metadata: foreign_table,
+          api: foreign_api,
+          num_columns: 0,
+        });
+        parent = Some(expansions.len() - 1);
+      }
+    }
+
+    let mut joins = vec![];
+    let mut selects = vec![];
+    for (idx, expansion) in expansions.iter_mut().enumerate() {
+      let foreign_table_name = expansion.metadata.name();
+      let Some(foreign_pk_column_idx) = expansion.metadata.record_pk_column else {
         return Err(RecordError::ApiRequiresTable);
       };
 
-      let foreign_pk_column = &foreign_table.schema.columns[foreign_pk_column_idx].name;
+      let foreign_pk_column = &expansion.metadata.schema.columns[foreign_pk_column_idx].name;
 
       // TODO: Check that `referred_columns` and foreign_pk_column are the same. It's already
       // validated as part of config validation.
 
+      let access = match expansion.api {
+        Some(ref foreign_api) => {
+          if foreign_api
+            .check_table_level_access(Permission::Read, user)
+            .is_err()
+          {
+            Some("FALSE".to_string())
+          } else {
+            foreign_api
+              .access_rule(Permission::Read)
+              .as_ref()
+              .map(|rule| {
+                format!(
+                  r#"EXISTS (SELECT 1 FROM "{foreign_table_name}" AS _ROW_, {USER_SUBQUERY} WHERE _ROW_."{foreign_pk_column}" = F{idx}."{foreign_pk_column}" AND ({rule}))"#
+                )
+              })
+          }
+        }
+        None => None,
+      };
+
       joins.push(format!(
-        r#"LEFT JOIN "{foreign_table_name}" AS F{idx} ON {col_name} = F{idx}.{foreign_pk_column}"#,
-        col_name = prefix.map_or_else(
-          || col_name.as_ref().to_owned(),
-          |p| format!("{p}.{}", col_name.as_ref())
-        ),
+        r#"LEFT JOIN "{foreign_table_name}" AS F{idx} ON {parent}."{col_name}" = F{idx}."{foreign_pk_column}"{access}"#,
+        parent = expansion
+          .parent
+          .map_or_else(|| prefix.to_string(), |p| format!("F{p}")),
+        col_name = expansion.column_name,
+        access = access.map_or_else(String::new, |a| format!(" AND {a}")),
       ));
-      indexes.push((foreign_table.schema.columns.len(), foreign_table));
+
+      // The primary key is selected as trailing marker to tell apart missing or inaccessible
+      // foreign records.
+      let marker = format!(r#"F{idx}."{foreign_pk_column}" AS _expanded{idx}"#);
+      match fields.and_then(|f| f.foreign.get(&expansion.path)) {
+        Some(foreign_fields) => {
+          for field in foreign_fields {
+            let visible = match expansion.api {
+              Some(ref api) => api.is_column_visible(field),
+              None => true,
+            };
+            if !visible || expansion.metadata.column_by_name(field).is_none() {
+              return Err(RecordError::BadRequest("Invalid fields"));
+            }
+          }
+
+          selects.push(
+            foreign_fields
+              .iter()
+              .map(|field| format!(r#"F{idx}."{field}""#))
+              .chain(std::iter::once(marker))
+              .join(", "),
+          );
+          expansion.num_columns = foreign_fields.len() + 1;
+        }
+        None => {
+          selects.push(format!("F{idx}.*, {marker}"));
+          expansion.num_columns = expansion.metadata.schema.columns.len() + 1;
+        }
+      }
     }
 
-    let selects = if prefix.is_none() {
-      None
-    } else {
-      Some(
-        (0..joins.len())
-          .map(|idx| format!("F{idx}.*"))
-          .collect::<Vec<_>>(),
-      )
-    };
+    // Fields for expansions that weren't requested.
+    if let Some(fields) = fields {
+      if fields
+        .foreign
+        .keys()
+        .any(|path| !expansions.iter().any(|e| e.path == *path))
+      {
+        return Err(RecordError::BadRequest("Invalid fields"));
+      }
+    }
 
     return Ok(Expansions {
-      indexes,
+      root_columns: fields.map_or(root_table.schema.columns.len(), |f| f.columns.len()),
+      expansions,
       joins,
       selects,
+      children,
     });
   }
+
+  /// Serializes a row of an expanded query, i.e. (root..., foreign0..., foreign1..., ...), into
+  /// JSON with the foreign records nested into their parent's foreign key column.
+  ///
+  /// `expand` are the root record's expandable columns, which are rendered as `{ id }` objects even
+  /// if not expanded.
+  pub(crate) fn row_to_json(
+    &self,
+    columns: &[Column],
+    column_metadata: &[ColumnMetadata],
+    mut row: trailbase_sqlite::Row,
+    column_filter: &dyn Fn(&str) -> bool,
+    expand: Option<&HashMap<String, serde_json::Value>>,
+  ) -> Result<serde_json::Value, JsonError> {
+    let mut curr = row.split_off(self.root_columns);
+    let mut foreign_rows = Vec::with_capacity(self.expansions.len());
+    for expansion in &self.expansions {
+      let next = curr.split_off(expansion.num_columns);
+      foreign_rows.push(curr);
+      curr = next;
+    }
+
+    // Children always come after their parents, thus build the JSON values back to front.
+    let mut values: Vec<serde_json::Value> = vec![serde_json::Value::Null; self.expansions.len()];
+    for (idx, foreign_row) in foreign_rows.into_iter().enumerate().rev() {
+      let expansion = &self.expansions[idx];
+
+      let pk = foreign_row.get_value(expansion.num_columns - 1);
+      if matches!(pk, None | Some(Value::Null)) {
+        // Missing or inaccessible foreign record.
+        continue;
+      }
+
+      let children = self.take_children(Some(idx), &mut values);
+      let filter = |col_name: &str| {
+        return expansion
+          .api
+          .as_ref()
+          .map_or(!col_name.starts_with("_"), |api| {
+            api.is_column_visible(col_name)
+          });
+      };
+
+      values[idx] = row_to_json_expand(
+        &expansion.metadata.schema.columns,
+        expansion.metadata.column_metadata(),
+        &foreign_row,
+        &filter,
+        Some(&children),
+      )?;
+    }
+
+    let mut root_expand = expand.cloned().unwrap_or_default();
+    root_expand.extend(self.take_children(None, &mut values));
+
+    return row_to_json_expand(
+      columns,
+      column_metadata,
+      &row,
+      column_filter,
+      Some(&root_expand),
+    );
+  }
+
+  fn take_children(
+    &self,
+    parent: Option<usize>,
+    values: &mut [serde_json::Value],
+  ) -> HashMap<String, serde_json::Value> {
+    return self
+      .expansions
+      .iter()
+      .enumerate()
+      .filter(|(_, e)| e.parent == parent)
+      .map(|(idx, e)| (e.column_name.clone(), values[idx].take()))
+      .collect();
+  }
 }
 
 pub(crate) struct SelectQueryBuilder;
@@ -409,45 +822,102 @@ impl SelectQueryBuilder {
     table_name: &str,
     pk_column: &str,
     pk_value: Value,
+    fields: Option<&Fields>,
   ) -> Result<Option<trailbase_sqlite::Row>, trailbase_sqlite::Error> {
     return state
       .conn()
       .query_row(
-        &format!(r#"SELECT * FROM "{table_name}" WHERE "{pk_column}" = $1"#),
+        &format!(
+          r#"SELECT {selects} FROM "{table_name}" AS R WHERE R."{pk_column}" = $1"#,
+          selects = fields.map_or_else(|| "*".to_string(), |f| f.select("R")),
+        ),
         [pk_value],
       )
       .await;
   }
 
+  /// Reads a record joined with its expanded foreign records. The root table needs to be
+  /// aliased as "R" when building the expansions.
   pub(crate) async fn run_expanded(
     state: &AppState,
     table_name: &str,
     pk_column: &str,
     pk_value: Value,
-    expand: &[&str],
-  ) -> Result<Vec<(Arc<TableMetadata>, trailbase_sqlite::Row)>, RecordError> {
-    let table_metadata = state.table_metadata();
-    let Expansions { indexes, joins, .. } =
-      Expansions::build(table_metadata, table_name, expand, None)?;
-
+    expansions: &Expansions,
+    fields: Option<&Fields>,
+    user: Option<&User>,
+  ) -> Result<Option<trailbase_sqlite::Row>, trailbase_sqlite::Error> {
     let sql = format!(
-      r#"SELECT * FROM "{table_name}" AS R {} WHERE R.{pk_column} = $1"#,
-      joins.join(" ")
+      r#"SELECT {selects} FROM "{table_name}" AS R {joins} WHERE R."{pk_column}" = :__record_id"#,
+      selects = std::iter::once(fields.map_or_else(|| "R.*".to_string(), |f| f.select("R")))
+        .chain(expansions.selects.iter().cloned())
+        .join(", "),
+      joins = expansions.joins.join(" "),
     );
 
-    let Some(row) = state.conn().query_row(&sql, [pk_value]).await? else {
-      return Ok(vec![]);
-    };
+    return state
+      .conn()
+      .query_row(
+        &sql,
+        named_params! {
+          ":__record_id": pk_value,
+          ":__user_id": user.map_or(Value::Null, |u| Value::Blob(u.uuid.into())),
+          ":__user_roles": user_roles_value(user),
+          ":__user_claims": user_claims_value(user),
+        },
+      )
+      .await;
+  }
+}
 
-    let mut curr = row;
-    let mut result = Vec::with_capacity(indexes.len());
-    for (idx, metadata) in indexes {
-      let next = curr.split_off(idx);
-      result.push((metadata, curr));
-      curr = next;
+pub(crate) struct SelectChildrenQueryBuilder;
+
+impl SelectChildrenQueryBuilder {
+  /// Lists the child records referencing the given parent record subject to the child API's read
+  /// and list access rules.
+  pub(crate) async fn run(
+    state: &AppState,
+    child: &ChildExpansion,
+    parent_id: Value,
+    user: Option<&User>,
+  ) -> Result<trailbase_sqlite::Rows, trailbase_sqlite::Error> {
+    let mut clause = format!(r#"_ROW_."{}" = :__parent_id"#, child.column_name);
+    for permission in [Permission::Read, Permission::List] {
+      if let Some(rule) = child.api.access_rule(permission) {
+        clause = format!("({rule}) AND {clause}");
+      }
     }
 
-    return Ok(result);
+    let order_clause = child
+      .order
+      .iter()
+      .map(|(col, ord)| {
+        format!(
+          r#"_ROW_."{col}" {}"#,
+          match ord {
+            Order::Descending => "DESC",
+            Order::Ascending => "ASC",
+          }
+        )
+      })
+      .join(", ");
+
+    return state
+      .conn()
+      .query(
+        &format!(
+          r#"SELECT _ROW_.* FROM "{table_name}" AS _ROW_, {USER_SUBQUERY} WHERE {clause} ORDER BY {order_clause} LIMIT :__limit"#,
+          table_name = child.api.table_name(),
+        ),
+        named_params! {
+          ":__parent_id": parent_id,
+          ":__user_id": user.map_or(Value::Null, |u| Value::Blob(u.uuid.into())),
+          ":__user_roles": user_roles_value(user),
+          ":__user_claims": user_claims_value(user),
+          ":__limit": child.limit as i64,
+        },
+      )
+      .await;
   }
 }
 
@@ -519,6 +989,15 @@ impl GetFilesQueryBuilder {
   }
 }
 
+/// Callback run within the transaction of an insert, e.g. to enqueue follow-up work atomically
+/// with the inserted records. Receives the extracted results of the inserted rows.
+pub(crate) type InsertTransactionHook<T> =
+  Box<dyn FnOnce(&rusqlite::Connection, &[T]) -> Result<(), trailbase_sqlite::Error> + Send>;
+
+/// Callback run within the transaction of an update or delete.
+pub(crate) type TransactionHook =
+  Box<dyn FnOnce(&rusqlite::Connection) -> Result<(), trailbase_sqlite::Error> + Send>;
+
 pub(crate) struct InsertQueryBuilder;
 
 impl InsertQueryBuilder {
@@ -528,6 +1007,7 @@ impl InsertQueryBuilder {
     conflict_resolution: Option<ConflictResolutionStrategy>,
     return_column_name: Option<&str>,
     extractor: impl Fn(&rusqlite::Row) -> Result<T, trailbase_sqlite::Error> + Send + 'static,
+    hook: Option<InsertTransactionHook<T>>,
   ) -> Result<T, QueryError> {
     let (query, named_params, mut files) =
       Self::build_insert_query(params, conflict_resolution, return_column_name)?;
@@ -544,14 +1024,28 @@ impl InsertQueryBuilder {
     let result = state
       .conn()
       .call(move |conn| {
-        let mut stmt = conn.prepare(&query)?;
-        named_params.bind(&mut stmt)?;
-        let mut result = stmt.raw_query();
+        let tx = conn.transaction()?;
+
+        let value = {
+          let mut stmt = tx.prepare(&query)?;
+          named_params.bind(&mut stmt)?;
+          let mut result = stmt.raw_query();
 
-        return match result.next()? {
-          Some(row) => Ok(extractor(row)?),
-          _ => Err(rusqlite::Error::QueryReturnedNoRows.into()),
+          match result.next()? {
+            Some(row) => extractor(row)?,
+            _ => {
+              return Err(rusqlite::Error::QueryReturnedNoRows.into());
+            }
+          }
         };
+
+        if let Some(hook) = hook {
+          hook(&tx, std::slice::from_ref(&value))?;
+        }
+
+        tx.commit()?;
+
+        return Ok(value);
       })
       .await;
 
@@ -575,6 +1069,7 @@ impl InsertQueryBuilder {
     conflict_resolution: Option<ConflictResolutionStrategy>,
     return_column_name: Option<&str>,
     extractor: impl Fn(&rusqlite::Row) -> Result<T, trailbase_sqlite::Error> + Send + 'static,
+    hook: Option<InsertTransactionHook<T>>,
   ) -> Result<Vec<T>, QueryError> {
     let mut all_files: FileMetadataContents = vec![];
     let mut query_and_params: Vec<(String, NamedParams)> = vec![];
@@ -616,6 +1111,10 @@ impl InsertQueryBuilder {
           };
         }
 
+        if let Some(hook) = hook {
+          hook(&tx, &rows)?;
+        }
+
         tx.commit()?;
 
         return Ok(rows);
@@ -711,6 +1210,7 @@ impl UpdateQueryBuilder {
     mut params: Params,
     pk_column: &str,
     pk_value: Value,
+    hook: Option<TransactionHook>,
   ) -> Result<(), QueryError> {
     let table_name = metadata.name();
     assert_eq!(params.table_name, *table_name);
@@ -735,6 +1235,7 @@ impl UpdateQueryBuilder {
       params: Params,
       pk_column: &str,
       pk_value: Value,
+      hook: Option<TransactionHook>,
     ) -> Result<Option<trailbase_sqlite::Row>, QueryError> {
       let setters: String = {
         assert_eq!(params.col_names.len(), params.named_params.len());
@@ -783,6 +1284,10 @@ impl UpdateQueryBuilder {
             stmt.raw_execute()?;
           }
 
+          if let Some(hook) = hook {
+            hook(&tx)?;
+          }
+
           tx.commit()?;
 
           return Ok(files_row);
@@ -792,22 +1297,23 @@ impl UpdateQueryBuilder {
       return Ok(files_row);
     }
 
-    let files_row = match row_update(state.conn(), table_name, params, pk_column, pk_value).await {
-      Ok(files_row) => files_row,
-      Err(err) => {
-        if !files.is_empty() {
-          let store = state.objectstore();
-          for (metadata, _content) in &files {
-            let path = object_store::path::Path::from(metadata.path());
-            if let Err(err) = store.delete(&path).await {
-              warn!("Failed to cleanup file after failed insertion (leak): {err}");
+    let files_row =
+      match row_update(state.conn(), table_name, params, pk_column, pk_value, hook).await {
+        Ok(files_row) => files_row,
+        Err(err) => {
+          if !files.is_empty() {
+            let store = state.objectstore();
+            for (metadata, _content) in &files {
+              let path = object_store::path::Path::from(metadata.path());
+              if let Err(err) = store.delete(&path).await {
+                warn!("Failed to cleanup file after failed insertion (leak): {err}");
+              }
             }
           }
-        }
 
-        return Err(err);
-      }
-    };
+          return Err(err);
+        }
+      };
 
     // Finally, if everything else went well delete files from columns that were updated and are no
     // longer referenced.
@@ -827,17 +1333,39 @@ impl DeleteQueryBuilder {
     metadata: &TableMetadata,
     pk_column: &str,
     pk_value: Value,
+    hook: Option<TransactionHook>,
   ) -> Result<(), QueryError> {
     let table_name = metadata.name();
 
+    let query =
+      format!(r#"DELETE FROM "{table_name}" WHERE "{pk_column}" = :pk_value RETURNING *"#);
     let row = state
       .conn()
-      .query_row(
-        &format!(r#"DELETE FROM "{table_name}" WHERE "{pk_column}" = $1 RETURNING *"#),
-        [pk_value],
-      )
-      .await?
-      .ok_or_else(|| QueryError::Sql(rusqlite::Error::QueryReturnedNoRows.into()))?;
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+
+        let row = {
+          let mut stmt = tx.prepare(&query)?;
+          [(":pk_value", pk_value)].bind(&mut stmt)?;
+
+          let mut rows = stmt.raw_query();
+          match rows.next()? {
+            Some(row) => trailbase_sqlite::Row::from_row(row, None)?,
+            _ => {
+              return Err(rusqlite::Error::QueryReturnedNoRows.into());
+            }
+          }
+        };
+
+        if let Some(hook) = hook {
+          hook(&tx)?;
+        }
+
+        tx.commit()?;
+
+        return Ok(row);
+      })
+      .await?;
 
     // Finally, delete files.
     delete_files_in_row(state, metadata, row).await?;
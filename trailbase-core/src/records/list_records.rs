@@ -6,17 +6,22 @@ use indoc::formatdoc;
 use itertools::Itertools;
 use serde::Serialize;
 use std::borrow::Cow;
-use trailbase_sqlite::Value;
+use std::collections::HashMap;
+use trailbase_sqlite::{NamedParams, Value};
 
 use crate::app_state::AppState;
 use crate::auth::user::User;
 use crate::listing::{
-  build_filter_where_clause, limit_or_default, parse_query, Order, QueryParseResult, WhereClause,
+  build_filter_where_clause, build_keyset_clause, limit_or_default, parse_query, Aggregate,
+  AggregateFunction, Cursor, Filter, Order, QueryParam, QueryParseResult, WhereClause,
 };
-use crate::records::json_to_sql::Expansions;
-use crate::records::sql_to_json::{row_to_json, row_to_json_expand, rows_to_json_expand};
-use crate::records::{Permission, RecordError};
-use crate::util::uuid_to_b64;
+use crate::records::json_to_sql::{Expansions, Fields};
+use crate::records::search::{build_match_expression, search_table_name};
+use crate::records::sql_to_json::{row_to_json, row_to_json_array, rows_to_json_expand};
+use crate::records::{
+  user_claims_value, user_roles_value, Permission, RecordApi, RecordError, USER_SUBQUERY,
+};
+use crate::schema::ColumnDataType;
 
 /// JSON response containing the listed records.
 #[derive(Debug, Serialize)]
@@ -49,25 +54,28 @@ pub async fn list_records_handler(
     return Err(RecordError::ApiNotFound);
   };
 
-  // WARN: We do different access checking here because the access rule is used as a filter query
-  // on the table, i.e. no access -> empty results.
-  api.check_table_level_access(Permission::Read, user.as_ref())?;
+  // WARN: We do different access checking here because the access rules are used as a filter
+  // query on the table, i.e. no access -> empty results.
+  api.check_table_level_access(Permission::List, user.as_ref())?;
 
   let metadata = api.metadata();
   let Some(columns) = metadata.columns() else {
     return Err(RecordError::Internal("missing columns".into()));
   };
-  let Some((pk_index, _pk_column)) = metadata.record_pk_column() else {
-    return Err(RecordError::Internal("missing pk column".into()));
-  };
 
   let QueryParseResult {
     params: filter_params,
+    filter,
     cursor,
     limit,
+    offset,
     order,
     count,
     expand: query_expand,
+    fields,
+    search,
+    group_by,
+    aggregates,
     ..
   } = parse_query(raw_url_query.as_deref()).map_err(|_err| {
     return RecordError::BadRequest("Invalid query");
@@ -77,8 +85,12 @@ pub async fn list_records_handler(
   let WhereClause {
     mut clause,
     mut params,
-  } = build_filter_where_clause(metadata, filter_params)
-    .map_err(|_err| RecordError::BadRequest("Invalid filter params"))?;
+  } = build_api_filter_where_clause(&api, filter_params, filter)?;
+
+  let offset = offset.unwrap_or(0);
+  if offset > api.max_list_offset() {
+    return Err(RecordError::BadRequest("Offset exceeds maximum"));
+  }
 
   // User properties
   params.extend_from_slice(&[
@@ -86,33 +98,134 @@ pub async fn list_records_handler(
       Cow::Borrowed(":limit"),
       Value::Integer(limit_or_default(limit) as i64),
     ),
+    (Cow::Borrowed(":offset"), Value::Integer(offset as i64)),
     (
       Cow::Borrowed(":__user_id"),
-      user.map_or(Value::Null, |u| Value::Blob(u.uuid.into())),
+      user
+        .as_ref()
+        .map_or(Value::Null, |u| Value::Blob(u.uuid.into())),
+    ),
+    (
+      Cow::Borrowed(":__user_roles"),
+      user_roles_value(user.as_ref()),
+    ),
+    (
+      Cow::Borrowed(":__user_claims"),
+      user_claims_value(user.as_ref()),
     ),
   ]);
 
-  // NOTE: We're using the read access rule to filter the rows as opposed to yes/no early access
-  // blocking as for read-record.
-  //
-  // TODO: Should this be a separate access rule? Maybe one wants users to access a specific
-  // record but not list all the records.
+  // NOTE: We're using the read and list access rules to filter the rows as opposed to yes/no
+  // early access blocking as for read-record. Listed records are returned to the user, so they
+  // have to satisfy the read access rule as well.
+  if let Some(list_access) = api.access_rule(Permission::List) {
+    clause = format!("({list_access}) AND ({clause})");
+  }
   if let Some(read_access) = api.access_rule(Permission::Read) {
     clause = format!("({read_access}) AND ({clause})");
   }
 
-  let clause_with_cursor = match cursor {
+  // Full-text search restricts the records to matches in the table's FTS5 index. The index may
+  // cover more columns than searchable through this API, thus the expression is restricted to the
+  // API's search columns.
+  let search_table = match search {
+    Some(ref query) => {
+      let Some(expression) = build_match_expression(api.search_columns(), query) else {
+        return Err(RecordError::BadRequest("Invalid search"));
+      };
+
+      let fts = search_table_name(api.table_name());
+      clause = format!(
+        r#"({clause}) AND _ROW_._rowid_ IN (SELECT rowid FROM "{fts}" WHERE "{fts}" MATCH :__search)"#
+      );
+      params.push((Cow::Borrowed(":__search"), Value::Text(expression)));
+      Some(fts)
+    }
+    None => None,
+  };
+
+  if group_by.is_some() || aggregates.is_some() {
+    if cursor.is_some() || count.is_some() || query_expand.is_some() || fields.is_some() {
+      return Err(RecordError::BadRequest("Invalid aggregate query"));
+    }
+
+    return Ok(Json(ListResponse {
+      cursor: None,
+      total_count: None,
+      records: list_aggregates(
+        &state,
+        &api,
+        &clause,
+        params,
+        group_by.unwrap_or_default(),
+        aggregates.unwrap_or_else(|| {
+          vec![Aggregate {
+            function: AggregateFunction::Count,
+            column: None,
+          }]
+        }),
+        order,
+      )
+      .await?,
+    }));
+  }
+
+  // Without explicit ordering, search results are ranked by relevance, i.e. bm25. Ranks aren't
+  // stable across index updates, thus ranked results can only be paged using offsets.
+  let search_join = match search_table {
+    Some(ref fts) if order.is_none() => {
+      if cursor.is_some() {
+        return Err(RecordError::BadRequest("Invalid cursor"));
+      }
+      Some(format!(
+        r#"JOIN "{fts}" AS _SEARCH_ ON _SEARCH_.rowid = _ROW_._rowid_ AND _SEARCH_ MATCH :__search"#
+      ))
+    }
+    _ => None,
+  };
+
+  // Validate the ordering and make it total by appending the primary key as a tie-breaker. This
+  // way we can paginate using keyset cursors built from all the order columns.
+  let order = {
+    let pk_column_name = &api.record_pk_column().name;
+    let mut order = match order {
+      Some(order) => {
+        for (col, _) in &order {
+          if !api.is_column_visible(col) || metadata.column_by_name(col).is_none() {
+            return Err(RecordError::BadRequest("Invalid order"));
+          }
+        }
+        order
+      }
+      None => vec![(pk_column_name.clone(), Order::Descending)],
+    };
+
+    if !order.iter().any(|(col, _)| col == pk_column_name) {
+      let tie_breaker = order
+        .last()
+        .map_or(Order::Descending, |(_, ord)| ord.clone());
+      order.push((pk_column_name.clone(), tie_breaker));
+    }
+    order
+  };
+
+  let clause_with_cursor = match &cursor {
     Some(cursor) => {
-      params.push((Cow::Borrowed(":cursor"), Value::Blob(cursor.to_vec())));
-      format!("{clause} AND _ROW_.id < :cursor")
+      let cursor =
+        Cursor::decode(cursor).map_err(|_err| RecordError::BadRequest("Invalid cursor"))?;
+      let keyset_clause = build_keyset_clause("_ROW_", &order, cursor, &mut params)
+        .map_err(|_err| RecordError::BadRequest("Invalid cursor"))?;
+
+      format!("{clause} AND {keyset_clause}")
     }
     None => clause.clone(),
   };
 
-  let order_clause = order
-    .unwrap_or_else(|| vec![(api.record_pk_column().name.clone(), Order::Descending)])
-    .iter()
-    .map(|(col, ord)| {
+  let order_clause = search_join
+    .as_ref()
+    .map(|_| "_SEARCH_.rank".to_string())
+    .into_iter()
+    .chain(order.iter().map(|(col, ord)| {
       format!(
         "_ROW_.{col} {}",
         match ord {
@@ -120,33 +233,68 @@ pub async fn list_records_handler(
           Order::Ascending => "ASC",
         }
       )
-    })
+    }))
     .join(", ");
 
   let table_name = api.table_name();
 
-  let (indexes, joins, selects) = match query_expand {
+  let fields = match fields {
+    Some(ref fields) => Fields::parse(&api, fields, query_expand.as_deref().unwrap_or_default())?,
+    None => None,
+  };
+
+  let expansions = match query_expand {
     Some(ref expand) if !expand.is_empty() => {
-      let Some(config_expand) = api.expand() else {
+      let expansions = Expansions::build(
+        &state,
+        &api,
+        expand,
+        "_ROW_",
+        fields.as_ref(),
+        user.as_ref(),
+      )?;
+
+      // Child records are only inlined when reading individual records.
+      if !expansions.children.is_empty() {
         return Err(RecordError::BadRequest("Invalid expansion"));
-      };
+      }
+      Some(expansions)
+    }
+    _ => None,
+  };
 
-      for col_name in expand {
-        if !config_expand.contains_key(col_name) {
-          return Err(RecordError::BadRequest("Invalid expansion"));
-        }
+  // Order columns, which aren't part of the projection, are selected as hidden columns at the end
+  // in order to build the next cursor.
+  let mut cursor_selects = vec![];
+  let cursor_columns: Vec<String> = order
+    .iter()
+    .enumerate()
+    .map(|(i, (col, _))| match fields {
+      Some(ref fields) if !fields.columns.contains(col) => {
+        cursor_selects.push(format!(r#"_ROW_."{col}" AS __cursor{i}"#));
+        format!("__cursor{i}")
       }
+      _ => col.clone(),
+    })
+    .collect();
 
-      let Expansions {
-        indexes,
-        joins,
-        selects,
-      } = Expansions::build(state.table_metadata(), table_name, expand, Some("_ROW_"))?;
+  let selects = std::iter::once(
+    fields
+      .as_ref()
+      .map_or_else(|| "_ROW_.*".to_string(), |f| f.select("_ROW_")),
+  )
+  .chain(
+    expansions
+      .as_ref()
+      .map_or_else(Vec::new, |e| e.selects.clone()),
+  )
+  .chain(cursor_selects)
+  .join(", ");
 
-      (Some(indexes), Some(joins), selects)
-    }
-    _ => (None, None, None),
-  };
+  let joins = search_join
+    .iter()
+    .chain(expansions.iter().flat_map(|e| &e.joins))
+    .join(" ");
 
   let get_total_count = count.unwrap_or(false);
   let query = if get_total_count {
@@ -156,65 +304,95 @@ pub async fn list_records_handler(
         SELECT COUNT(*) AS _value_
         FROM
           '{table_name}' as _ROW_,
-          (SELECT :__user_id AS id) AS _USER_
+          {USER_SUBQUERY}
         WHERE
           {clause}
       )
 
       SELECT
-        _ROW_.*,
-        {selects}
+        {selects},
         total_count._value_
       FROM
         '{table_name}' as _ROW_ {joins},
-        (SELECT :__user_id AS id) AS _USER_,
+        {USER_SUBQUERY},
         total_count
       WHERE
         {clause_with_cursor}
       ORDER BY
         {order_clause}
       LIMIT :limit
-      "#,
-      selects = selects.map_or(EMPTY, |v| format!("{}, ", v.join(", "))),
-      joins = joins.map_or(EMPTY, |j| j.join(" ")),
+      OFFSET :offset
+      "#
     )
   } else {
     formatdoc!(
       r#"
       SELECT
-        _ROW_.*
         {selects}
       FROM
         '{table_name}' AS _ROW_ {joins},
-        (SELECT :__user_id AS id) AS _USER_
+        {USER_SUBQUERY}
       WHERE
         {clause_with_cursor}
       ORDER BY
         {order_clause}
       LIMIT :limit
-      "#,
-      selects = selects.map_or(EMPTY, |v| format!(", {}", v.join(", "))),
-      joins = joins.map_or(EMPTY, |j| j.join(" ")),
+      OFFSET :offset
+      "#
     )
   };
 
+  let count_params = get_total_count.then(|| params.clone());
   let rows = state.conn().query(&query, params).await?;
   let Some(last_row) = rows.last() else {
-    // Rows are empty:
+    // Rows are empty. However, if we paged past the end using an offset or cursor, there may still
+    // be matching records to count.
+    let total_count = match count_params {
+      Some(count_params) if offset > 0 || cursor.is_some() => {
+        let count: i64 = state
+          .conn()
+          .query_value(
+            &formatdoc!(
+              r#"
+              SELECT COUNT(*)
+              FROM
+                '{table_name}' AS _ROW_,
+                {USER_SUBQUERY}
+              WHERE
+                {clause}
+              "#
+            ),
+            count_params,
+          )
+          .await?
+          .unwrap_or(0);
+        Some(count as usize)
+      }
+      Some(_) => Some(0),
+      None => None,
+    };
+
     return Ok(Json(ListResponse {
       cursor: None,
-      total_count: Some(0),
+      total_count,
       records: vec![],
     }));
   };
 
-  assert!(pk_index < last_row.len());
-  let cursor = match &last_row[pk_index] {
-    rusqlite::types::Value::Blob(blob) => {
-      uuid::Uuid::from_slice(blob).as_ref().map(uuid_to_b64).ok()
-    }
-    rusqlite::types::Value::Integer(i) => Some(i.to_string()),
-    _ => None,
+  // Build the cursor for the next page from the last row's order column values. The root
+  // record's columns come first, thus the first match by name is the right one.
+  let column_names = last_row.column_names();
+  let cursor = if search_join.is_some() {
+    None
+  } else {
+    cursor_columns
+      .iter()
+      .map(|col| {
+        let index = column_names.iter().position(|name| *name == col.as_str())?;
+        return last_row.get_value(index).cloned();
+      })
+      .collect::<Option<Vec<_>>>()
+      .map(|values| Cursor(values).encode())
   };
 
   let total_count = if get_total_count {
@@ -228,50 +406,21 @@ pub async fn list_records_handler(
     None
   };
 
-  fn filter(col_name: &str) -> bool {
-    return !col_name.starts_with("_");
-  }
+  let filter = |col_name: &str| api.is_column_visible(col_name);
 
-  let records = if let (Some(query_expand), Some(indexes)) = (query_expand, indexes) {
+  let records = if let Some(expansions) = expansions {
     rows
       .into_iter()
       .map(|row| {
-        let Some(mut expand) = api.expand().cloned() else {
-          return Err(RecordError::Internal(
-            "Expansion config must be some".into(),
-          ));
-        };
-
-        let mut curr = row;
-        let mut result = Vec::with_capacity(indexes.len());
-        for (idx, metadata) in &indexes {
-          let next = curr.split_off(*idx);
-          result.push((metadata, curr));
-          curr = next;
-        }
-
-        let foreign_rows = result.split_off(1);
-        for (col_name, (metadata, row)) in std::iter::zip(&query_expand, foreign_rows) {
-          let foreign_value = row_to_json(
-            &metadata.schema.columns,
+        return expansions
+          .row_to_json(
+            columns,
             metadata.column_metadata(),
-            &row,
-            filter,
+            row,
+            &filter,
+            api.expand(),
           )
-          .map_err(|err| RecordError::Internal(err.into()))?;
-
-          let result = expand.insert(col_name.to_string(), foreign_value);
-          assert!(result.is_some());
-        }
-
-        return row_to_json_expand(
-          columns,
-          metadata.column_metadata(),
-          &result[0].1,
-          filter,
-          Some(&expand),
-        )
-        .map_err(|err| RecordError::Internal(err.into()));
+          .map_err(|err| RecordError::Internal(err.into()));
       })
       .collect::<Result<Vec<_>, RecordError>>()?
   } else {
@@ -279,7 +428,7 @@ pub async fn list_records_handler(
       columns,
       metadata.column_metadata(),
       rows,
-      filter,
+      &filter,
       api.expand(),
     )
     .map_err(|err| RecordError::Internal(err.into()))?
@@ -292,10 +441,188 @@ pub async fn list_records_handler(
   }));
 }
 
+/// Builds the where clause for the given filters, rejecting filters on columns hidden by the API.
+pub(crate) fn build_api_filter_where_clause(
+  api: &RecordApi,
+  filter_params: Option<HashMap<String, Vec<QueryParam>>>,
+  filter: Option<Filter>,
+) -> Result<WhereClause, RecordError> {
+  // Filtering on hidden columns would allow probing their values.
+  let is_hidden = |col: &str| api.hidden_columns().iter().any(|c| c == col);
+  if filter_params
+    .as_ref()
+    .is_some_and(|params| params.keys().any(|col| is_hidden(col)))
+    || filter.as_ref().is_some_and(|f| f.any_column(&is_hidden))
+  {
+    return Err(RecordError::BadRequest("Invalid filter params"));
+  }
+
+  return build_filter_where_clause(api.metadata(), filter_params, filter)
+    .map_err(|_err| RecordError::BadRequest("Invalid filter params"));
+}
+
+/// Aggregates the records matching the given where clause, e.g.
+/// `?group_by=status&agg=count,sum(amount)`, into one object per group.
+///
+/// The where clause includes the access rules, thus aggregates only ever cover records the user
+/// can read.
+async fn list_aggregates(
+  state: &AppState,
+  api: &RecordApi,
+  clause: &str,
+  params: NamedParams,
+  group_by: Vec<String>,
+  aggregates: Vec<Aggregate>,
+  order: Option<Vec<(String, Order)>>,
+) -> Result<Vec<serde_json::Value>, RecordError> {
+  let metadata = api.metadata();
+  let Some(columns) = metadata.columns() else {
+    return Err(RecordError::Internal("missing columns".into()));
+  };
+
+  for col in &group_by {
+    if !api.is_column_visible(col) || metadata.column_by_name(col).is_none() {
+      return Err(RecordError::BadRequest("Invalid group_by"));
+    }
+  }
+
+  let aggregate_selects = aggregates
+    .iter()
+    .map(|aggregate| {
+      let Some(ref col) = aggregate.column else {
+        return Ok("COUNT(*)".to_string());
+      };
+
+      let Some((column, _)) = metadata.column_by_name(col) else {
+        return Err(RecordError::BadRequest("Invalid aggregate"));
+      };
+      if !api.is_column_visible(col) {
+        return Err(RecordError::BadRequest("Invalid aggregate"));
+      }
+
+      return Ok(match aggregate.function {
+        AggregateFunction::Count => format!(r#"COUNT(_ROW_."{col}")"#),
+        AggregateFunction::Sum | AggregateFunction::Avg => {
+          if matches!(
+            column.data_type,
+            ColumnDataType::Blob
+              | ColumnDataType::Text
+              | ColumnDataType::JSON
+              | ColumnDataType::JSONB
+          ) {
+            return Err(RecordError::BadRequest("Invalid aggregate"));
+          }
+
+          match aggregate.function {
+            AggregateFunction::Sum => format!(r#"SUM(_ROW_."{col}")"#),
+            _ => format!(r#"AVG(_ROW_."{col}")"#),
+          }
+        }
+        AggregateFunction::Min => format!(r#"MIN(_ROW_."{col}")"#),
+        AggregateFunction::Max => format!(r#"MAX(_ROW_."{col}")"#),
+      });
+    })
+    .collect::<Result<Vec<_>, RecordError>>()?;
+
+  // Groups can only be ordered by the grouped columns. Defaults to ascending.
+  let order = match order {
+    Some(order) => {
+      if order.iter().any(|(col, _)| !group_by.contains(col)) {
+        return Err(RecordError::BadRequest("Invalid order"));
+      }
+      order
+    }
+    None => group_by
+      .iter()
+      .map(|col| (col.clone(), Order::Ascending))
+      .collect(),
+  };
+
+  let group_by_clause = if group_by.is_empty() {
+    EMPTY
+  } else {
+    format!(
+      "GROUP BY {}",
+      group_by
+        .iter()
+        .map(|col| format!(r#"_ROW_."{col}""#))
+        .join(", ")
+    )
+  };
+  let order_clause = if order.is_empty() {
+    EMPTY
+  } else {
+    format!(
+      "ORDER BY {}",
+      order
+        .iter()
+        .map(|(col, ord)| {
+          format!(
+            r#"_ROW_."{col}" {}"#,
+            match ord {
+              Order::Descending => "DESC",
+              Order::Ascending => "ASC",
+            }
+          )
+        })
+        .join(", ")
+    )
+  };
+
+  let rows = state
+    .conn()
+    .query(
+      &formatdoc!(
+        r#"
+        SELECT
+          {selects}
+        FROM
+          '{table_name}' AS _ROW_,
+          {USER_SUBQUERY}
+        WHERE
+          {clause}
+        {group_by_clause}
+        {order_clause}
+        LIMIT :limit
+        OFFSET :offset
+        "#,
+        selects = group_by
+          .iter()
+          .map(|col| format!(r#"_ROW_."{col}""#))
+          .chain(aggregate_selects)
+          .join(", "),
+        table_name = api.table_name(),
+      ),
+      params,
+    )
+    .await?;
+
+  return rows
+    .into_iter()
+    .map(|mut row| {
+      // The grouped columns come first followed by the aggregates.
+      let aggregate_row = row.split_off(group_by.len());
+
+      let mut record = row_to_json(columns, metadata.column_metadata(), &row, &|_| true)
+        .map_err(|err| RecordError::Internal(err.into()))?;
+      let values =
+        row_to_json_array(&aggregate_row).map_err(|err| RecordError::Internal(err.into()))?;
+
+      if let serde_json::Value::Object(ref mut map) = record {
+        for (aggregate, value) in std::iter::zip(&aggregates, values) {
+          map.insert(aggregate.name(), value);
+        }
+      }
+      return Ok(record);
+    })
+    .collect();
+}
+
 const EMPTY: String = String::new();
 
 #[cfg(test)]
 mod tests {
+  use axum::extract::Query;
   use serde::Deserialize;
 
   use super::*;
@@ -303,11 +630,16 @@ mod tests {
   use crate::app_state::*;
   use crate::auth::api::login::login_with_password;
   use crate::auth::user::User;
-  use crate::config::proto::PermissionFlag;
+  use crate::config::proto::{PermissionFlag, RecordApiConfig};
+  use crate::extract::Either;
+  use crate::records::create_record::{create_record_handler, CreateRecordQuery};
+  use crate::records::read_record::{read_record_handler, ReadRecordQuery};
   use crate::records::test_utils::*;
+  use crate::records::update_record::update_record_handler;
   use crate::records::Acls;
-  use crate::records::{add_record_api, AccessRules, RecordError};
+  use crate::records::{add_record_api, add_record_api_config, AccessRules, RecordError};
   use crate::util::id_to_b64;
+  use serde_json::json;
 
   fn is_auth_err(error: &RecordError) -> bool {
     return match error {
@@ -340,7 +672,7 @@ mod tests {
       "messages_api",
       "message",
       Acls {
-        authenticated: vec![PermissionFlag::Create, PermissionFlag::Read],
+        authenticated: vec![PermissionFlag::Create, PermissionFlag::Read, PermissionFlag::List],
         ..Default::default()
       },
       AccessRules {
@@ -583,6 +915,680 @@ mod tests {
     }
   }
 
+  #[tokio::test]
+  async fn test_record_api_list_keyset_pagination() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE item (
+            id           INTEGER PRIMARY KEY,
+            rank         INTEGER,
+            name         TEXT NOT NULL
+          ) STRICT;
+
+          INSERT INTO item (rank, name) VALUES
+            (3, 'a'), (NULL, 'b'), (1, 'c'), (3, 'd'), (2, 'e'), (NULL, 'f'), (1, 'g');
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    add_record_api(
+      &state,
+      "items_api",
+      "item",
+      Acls {
+        world: vec![PermissionFlag::Read, PermissionFlag::List],
+        ..Default::default()
+      },
+      AccessRules::default(),
+    )
+    .await
+    .unwrap();
+
+    async fn list_all_paginated(state: &AppState, order: &str) -> Vec<String> {
+      let mut names = vec![];
+      let mut cursor: Option<String> = None;
+      loop {
+        let query = match cursor {
+          Some(ref cursor) => format!("limit=2&order={order}&cursor={cursor}"),
+          None => format!("limit=2&order={order}"),
+        };
+        let response = list_records_handler(
+          State(state.clone()),
+          Path("items_api".to_string()),
+          RawQuery(Some(query)),
+          None,
+        )
+        .await
+        .unwrap()
+        .0;
+
+        if response.records.is_empty() {
+          return names;
+        }
+        assert!(response.records.len() <= 2);
+
+        names.extend(
+          response
+            .records
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string()),
+        );
+        cursor = response.cursor;
+      }
+    }
+
+    for (order, sql_order) in [
+      ("-rank", "rank DESC, id DESC"),
+      ("rank", "rank ASC, id ASC"),
+      ("rank,-id", "rank ASC, id DESC"),
+      ("-rank,name", "rank DESC, name ASC, id ASC"),
+      ("id", "id ASC"),
+    ] {
+      let expected: Vec<String> = state
+        .conn()
+        .query_values(&format!("SELECT name FROM item ORDER BY {sql_order}"), ())
+        .await
+        .unwrap();
+
+      assert_eq!(
+        list_all_paginated(&state, order).await,
+        expected,
+        "order: {order}"
+      );
+    }
+
+    // Ordering by unknown or hidden columns is rejected.
+    for query in ["order=missing", "order=_rowid_", "cursor=invalid"] {
+      let response = list_records_handler(
+        State(state.clone()),
+        Path("items_api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+      .await;
+      assert!(
+        matches!(response, Err(RecordError::BadRequest(_))),
+        "{query}"
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn test_record_api_list_offset() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE item (
+            id           INTEGER PRIMARY KEY,
+            name         TEXT NOT NULL
+          ) STRICT;
+
+          INSERT INTO item (name) VALUES ('a'), ('b'), ('c'), ('d'), ('e');
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("items_api".to_string()),
+        table_name: Some("item".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32, PermissionFlag::List as i32],
+        max_list_offset: Some(4),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let list = |query: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path("items_api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+    };
+
+    let names = |response: &ListResponse| -> Vec<String> {
+      return response
+        .records
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect();
+    };
+
+    let response = list("order=name&limit=2&offset=2&count=true")
+      .await
+      .unwrap()
+      .0;
+    assert_eq!(names(&response), vec!["c", "d"]);
+    assert_eq!(response.total_count, Some(5));
+
+    // Offsets past the end still yield the total count.
+    let response = list("filter[name]=e&offset=1&count=true").await.unwrap().0;
+    assert!(response.records.is_empty());
+    assert_eq!(response.total_count, Some(1));
+
+    // Offsets can be combined with cursors.
+    let cursor = list("order=name&limit=1").await.unwrap().0.cursor.unwrap();
+    let response = list(&format!("order=name&limit=1&offset=2&cursor={cursor}"))
+      .await
+      .unwrap()
+      .0;
+    assert_eq!(names(&response), vec!["d"]);
+
+    // Offsets above the configured max are rejected.
+    assert!(matches!(
+      list("offset=5").await,
+      Err(RecordError::BadRequest(_))
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_record_api_list_permission() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE item (
+            id           INTEGER PRIMARY KEY,
+            name         TEXT NOT NULL,
+            public       INTEGER NOT NULL
+          ) STRICT;
+
+          INSERT INTO item (name, public) VALUES ('a', 1), ('b', 0), ('c', 1);
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    // LIST without READ is rejected.
+    assert!(add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("invalid_api".to_string()),
+        table_name: Some("item".to_string()),
+        acl_world: vec![PermissionFlag::List as i32],
+        ..Default::default()
+      },
+    )
+    .await
+    .is_err());
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("read_api".to_string()),
+        table_name: Some("item".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("list_api".to_string()),
+        table_name: Some("item".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32, PermissionFlag::List as i32],
+        read_access_rule: Some("_ROW_.name != 'c'".to_string()),
+        list_access_rule: Some("_ROW_.public = 1".to_string()),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let list = |api: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path(api.to_string()),
+        RawQuery(None),
+        None,
+      )
+    };
+
+    // Records can be read by id but not listed.
+    assert!(matches!(
+      list("read_api").await,
+      Err(RecordError::Forbidden)
+    ));
+    assert!(read_record_handler(
+      State(state.clone()),
+      Path(("read_api".to_string(), "2".to_string())),
+      Query(ReadRecordQuery::default()),
+      None,
+    )
+    .await
+    .is_ok());
+
+    // Listed records have to satisfy both the read and the list access rules.
+    let response = list("list_api").await.unwrap().0;
+    let names: Vec<_> = response
+      .records
+      .iter()
+      .map(|r| r["name"].as_str().unwrap())
+      .collect();
+    assert_eq!(names, vec!["a"]);
+  }
+
+  #[tokio::test]
+  async fn test_record_api_hidden_and_read_only_columns() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE item (
+            id           INTEGER PRIMARY KEY,
+            name         TEXT NOT NULL,
+            secret       TEXT NOT NULL DEFAULT 'hush',
+            created      INTEGER NOT NULL DEFAULT 0
+          ) STRICT;
+
+          INSERT INTO item (name, secret) VALUES ('a', 'x'), ('b', 'y');
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    // Unknown and primary key columns cannot be restricted.
+    for hidden_columns in [vec!["missing".to_string()], vec!["id".to_string()]] {
+      assert!(add_record_api_config(
+        &state,
+        RecordApiConfig {
+          name: Some("invalid_api".to_string()),
+          table_name: Some("item".to_string()),
+          hidden_columns,
+          ..Default::default()
+        },
+      )
+      .await
+      .is_err());
+    }
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("api".to_string()),
+        table_name: Some("item".to_string()),
+        acl_world: vec![
+          PermissionFlag::Create as i32,
+          PermissionFlag::Read as i32,
+          PermissionFlag::Update as i32,
+          PermissionFlag::List as i32,
+        ],
+        hidden_columns: vec!["secret".to_string()],
+        read_only_columns: vec!["created".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let list = |query: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path("api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+    };
+
+    // Hidden columns are omitted from read and listed records.
+    let response = list("order=name").await.unwrap().0;
+    assert_eq!(response.records.len(), 2);
+    for record in &response.records {
+      assert!(record.get("secret").is_none());
+      assert!(record.get("created").is_some());
+    }
+
+    let Json(record) = read_record_handler(
+      State(state.clone()),
+      Path(("api".to_string(), "1".to_string())),
+      Query(ReadRecordQuery::default()),
+      None,
+    )
+    .await
+    .unwrap();
+    assert!(record.get("secret").is_none());
+
+    // Hidden columns can neither be filtered, ordered by nor projected.
+    for query in ["filter[secret]=x", "order=secret", "fields=name,secret"] {
+      assert!(
+        matches!(list(query).await, Err(RecordError::BadRequest(_))),
+        "{query}"
+      );
+    }
+
+    // Neither hidden nor read-only columns can be written.
+    for (column, value) in [("secret", json!("z")), ("created", json!(5))] {
+      assert!(matches!(
+        create_record_handler(
+          State(state.clone()),
+          Path("api".to_string()),
+          Query(CreateRecordQuery::default()),
+          None,
+          Either::Json(json!({ "name": "c", column: value.clone() })),
+        )
+        .await,
+        Err(RecordError::BadRequest(_))
+      ));
+
+      assert!(matches!(
+        update_record_handler(
+          State(state.clone()),
+          Path(("api".to_string(), "1".to_string())),
+          None,
+          Either::Json(json_row_from_value(json!({ column: value })).unwrap()),
+        )
+        .await,
+        Err(RecordError::BadRequest(_))
+      ));
+    }
+
+    assert!(create_record_handler(
+      State(state.clone()),
+      Path("api".to_string()),
+      Query(CreateRecordQuery::default()),
+      None,
+      Either::Json(json!({ "name": "c" })),
+    )
+    .await
+    .is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_record_api_aggregates() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE sale (
+            id           INTEGER PRIMARY KEY,
+            status       TEXT NOT NULL,
+            amount       INTEGER NOT NULL,
+            secret       TEXT NOT NULL DEFAULT 'hush'
+          ) STRICT;
+
+          INSERT INTO sale (status, amount) VALUES
+            ('open', 10), ('open', 20), ('closed', 5), ('void', 1000);
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("api".to_string()),
+        table_name: Some("sale".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32, PermissionFlag::List as i32],
+        read_access_rule: Some("_ROW_.status != 'void'".to_string()),
+        hidden_columns: vec!["secret".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let list = |query: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path("api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+    };
+
+    // Records the user cannot read are not aggregated.
+    let response = list("agg=count,sum(amount)").await.unwrap().0;
+    assert_eq!(
+      response.records,
+      vec![json!({"count": 3, "sum(amount)": 35})]
+    );
+
+    let response = list("group_by=status&agg=count,sum(amount),max(amount)")
+      .await
+      .unwrap()
+      .0;
+    assert_eq!(
+      response.records,
+      vec![
+        json!({"status": "closed", "count": 1, "sum(amount)": 5, "max(amount)": 5}),
+        json!({"status": "open", "count": 2, "sum(amount)": 30, "max(amount)": 20}),
+      ]
+    );
+
+    // Groups count by default and can be ordered and filtered.
+    let response = list("group_by=status&order=-status&amount[gt]=5")
+      .await
+      .unwrap()
+      .0;
+    assert_eq!(
+      response.records,
+      vec![json!({"status": "open", "count": 2})]
+    );
+
+    for query in [
+      "group_by=secret",
+      "group_by=missing",
+      "agg=min(secret)",
+      "agg=sum(status)",
+      "group_by=status&order=amount",
+      "group_by=status&count=true",
+      "group_by=status&fields=status",
+    ] {
+      assert!(
+        matches!(list(query).await, Err(RecordError::BadRequest(_))),
+        "{query}"
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn test_record_api_search() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE article (
+            id           INTEGER PRIMARY KEY,
+            title        TEXT NOT NULL,
+            body         TEXT NOT NULL,
+            draft        INTEGER NOT NULL DEFAULT FALSE
+          ) STRICT;
+
+          INSERT INTO article (title, body) VALUES
+            ('Ferris', 'A long text about many things including a little rust.'),
+            ('Rust rust', 'Rust'),
+            ('Gophers', 'Go go go');
+          INSERT INTO article (title, body, draft) VALUES ('Rusty draft', 'rust', TRUE);
+
+          CREATE TABLE tag (name TEXT PRIMARY KEY) STRICT, WITHOUT ROWID;
+          -- Not managed by TrailBase despite the prefix.
+          CREATE VIRTUAL TABLE _fts_notes USING fts5(text);
+        "#,
+      )
+      .await
+      .unwrap();
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    // Only text columns can be searched.
+    assert!(add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("invalid_api".to_string()),
+        table_name: Some("article".to_string()),
+        search_columns: vec!["draft".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .is_err());
+
+    // FTS5 indexes require rowids.
+    assert!(add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("tag_api".to_string()),
+        table_name: Some("tag".to_string()),
+        search_columns: vec!["name".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .is_err());
+
+    let index_count = || {
+      let conn = state.conn().clone();
+      return async move {
+        return conn
+          .query_value::<i64>(
+            "SELECT COUNT(*) FROM sqlite_schema WHERE name LIKE '_fts_article%'",
+            (),
+          )
+          .await
+          .unwrap()
+          .unwrap();
+      };
+    };
+
+    // Rejected config updates don't touch the schema.
+    let mut config = state.get_config();
+    config.record_apis.push(RecordApiConfig {
+      name: Some("stale_api".to_string()),
+      table_name: Some("article".to_string()),
+      search_columns: vec!["title".to_string()],
+      ..Default::default()
+    });
+    assert!(state
+      .validate_and_update_config(config, Some(0))
+      .await
+      .is_err());
+    assert_eq!(index_count().await, 0);
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("api".to_string()),
+        table_name: Some("article".to_string()),
+        acl_world: vec![PermissionFlag::Read as i32, PermissionFlag::List as i32],
+        read_access_rule: Some("_ROW_.draft = FALSE".to_string()),
+        search_columns: vec!["title".to_string(), "body".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let list = |query: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path("api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+    };
+    let titles = |response: &ListResponse| {
+      response
+        .records
+        .iter()
+        .map(|r| r["title"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>()
+    };
+
+    // Pre-existing records are indexed, matches are ranked and drafts filtered by the read rule.
+    let response = list("q=rust&count=true").await.unwrap().0;
+    assert_eq!(titles(&response), vec!["Rust rust", "Ferris"]);
+    assert_eq!(response.total_count, Some(2));
+    assert!(response.cursor.is_none());
+
+    // Explicit ordering takes precedence over ranking.
+    let response = list("q=rust&order=title").await.unwrap().0;
+    assert_eq!(titles(&response), vec!["Ferris", "Rust rust"]);
+
+    // User input doesn't inject FTS5 syntax.
+    assert_eq!(titles(&list("q=rust%20OR%20go").await.unwrap().0).len(), 0);
+    assert_eq!(
+      list("agg=count&q=go").await.unwrap().0.records,
+      vec![json!({"count": 1})]
+    );
+
+    // The index is kept in sync with the table.
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          UPDATE article SET body = 'Only gophers' WHERE title = 'Ferris';
+          INSERT INTO article (title, body) VALUES ('Crab', 'Rust again');
+          DELETE FROM article WHERE title = 'Rust rust';
+        "#,
+      )
+      .await
+      .unwrap();
+    assert_eq!(titles(&list("q=rust").await.unwrap().0), vec!["Crab"]);
+
+    for query in ["q=", "q=rust&cursor=abc"] {
+      assert!(
+        matches!(list(query).await, Err(RecordError::BadRequest(_))),
+        "{query}"
+      );
+    }
+
+    // Removing the search columns drops the index.
+    let mut config = state.get_config();
+    for api in &mut config.record_apis {
+      api.search_columns.clear();
+    }
+    state
+      .validate_and_update_config(config, None)
+      .await
+      .unwrap();
+    assert!(matches!(
+      list("q=rust").await,
+      Err(RecordError::BadRequest(_))
+    ));
+
+    assert_eq!(index_count().await, 0);
+
+    // Foreign FTS5 tables are left alone.
+    let count: i64 = state
+      .conn()
+      .query_value(
+        "SELECT COUNT(*) FROM sqlite_schema WHERE name = '_fts_notes'",
+        (),
+      )
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(count, 1);
+  }
+
   async fn list_records(
     state: &AppState,
     auth_token: Option<&str>,
@@ -4,6 +4,7 @@ use axum::{
 };
 use utoipa::OpenApi;
 
+pub(crate) mod change_capture;
 pub(crate) mod create_record;
 pub(crate) mod delete_record;
 mod error;
@@ -13,6 +14,7 @@ pub mod json_to_sql;
 pub(crate) mod list_records;
 pub(crate) mod read_record;
 mod record_api;
+mod search;
 pub mod sql_to_json;
 pub(crate) mod subscribe;
 pub mod test_utils;
@@ -21,7 +23,9 @@ mod validate;
 
 pub(crate) use error::RecordError;
 pub use record_api::RecordApi;
-pub(crate) use validate::validate_record_api_config;
+pub(crate) use record_api::{user_claims_value, user_roles_value, USER_SUBQUERY};
+pub(crate) use search::{sync_search_indexes, validate_search_indexes};
+pub(crate) use validate::{validate_record_api_children, validate_record_api_config};
 
 use crate::config::proto::PermissionFlag;
 use crate::constants::RECORD_API_PATH;
@@ -80,6 +84,12 @@ pub(crate) fn router() -> Router<AppState> {
     .route(
       &format!("/{RECORD_API_PATH}/{{name}}/subscribe/{{record}}"),
       get(subscribe::add_subscription_sse_handler),
+    )
+    // Multiplexes subscriptions across APIs over a single WebSocket. The "_" prefix makes
+    // collisions with list endpoints of APIs unlikely.
+    .route(
+      &format!("/{RECORD_API_PATH}/_subscribe"),
+      get(subscribe::subscription_ws_handler),
     );
 }
 
@@ -88,12 +98,12 @@ pub(crate) fn router() -> Router<AppState> {
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Permission {
-  // TODO: Should there be a separate "list records" permission or is "read" enough?
   Create = 1,  // ~ DB insert
-  Read = 2,    // ~ DB select
+  Read = 2,    // ~ DB select by id
   Update = 4,  // ~ DB update
   Delete = 8,  // ~ DB delete
   Schema = 16, // Lookup json schema for the given record api .
+  List = 32,   // ~ DB select with filters
 }
 
 #[derive(Default)]
@@ -109,6 +119,7 @@ pub struct AccessRules {
   pub update: Option<String>,
   pub delete: Option<String>,
   pub schema: Option<String>,
+  pub list: Option<String>,
 }
 
 // NOTE: Prefer add_record_api_config below.
@@ -138,7 +149,15 @@ pub(crate) async fn add_record_api(
       update_access_rule: access_rules.update,
       delete_access_rule: access_rules.delete,
       schema_access_rule: access_rules.schema,
+      list_access_rule: access_rules.list,
       expand: vec![],
+      max_list_offset: None,
+      hidden_columns: vec![],
+      read_only_columns: vec![],
+      max_expand_depth: None,
+      expand_children: vec![],
+      search_columns: vec![],
+      webhooks: vec![],
     });
 
   return state.validate_and_update_config(config, None).await;
@@ -5,16 +5,22 @@ use axum::{
 };
 use serde::Deserialize;
 
+use crate::app_state::AppState;
 use crate::auth::user::User;
+use crate::listing::split_expand;
 use crate::records::files::read_file_into_response;
-use crate::records::json_to_sql::{GetFileQueryBuilder, GetFilesQueryBuilder, SelectQueryBuilder};
-use crate::records::sql_to_json::row_to_json;
+use crate::records::json_to_sql::{
+  Expansions, Fields, GetFileQueryBuilder, GetFilesQueryBuilder, SelectChildrenQueryBuilder,
+  SelectQueryBuilder,
+};
+use crate::records::sql_to_json::{row_to_json_expand, rows_to_json_expand};
 use crate::records::{Permission, RecordError};
-use crate::{app_state::AppState, records::sql_to_json::row_to_json_expand};
 
 #[derive(Debug, Default, Deserialize)]
 pub struct ReadRecordQuery {
   pub expand: Option<String>,
+  /// Comma-separated list of columns to return, e.g. `fields=a,b,fk.c`.
+  pub fields: Option<String>,
 }
 
 /// Read record.
@@ -45,65 +51,89 @@ pub async fn read_record_handler(
     .check_record_level_access(Permission::Read, Some(&record_id), None, user.as_ref())
     .await?;
 
-  fn filter(col_name: &str) -> bool {
-    return !col_name.starts_with("_");
-  }
+  let filter = |col_name: &str| api.is_column_visible(col_name);
 
   return Ok(Json(match query.expand {
     Some(query_expand) if !query_expand.is_empty() => {
-      let Some(mut expand) = api.expand().cloned() else {
-        return Err(RecordError::BadRequest("Invalid expansion"));
-      };
+      let query_expand = split_expand(&query_expand);
 
-      let query_expand: Vec<_> = query_expand.split(",").collect();
-      for col_name in &query_expand {
-        if !query_expand.contains(col_name) {
-          return Err(RecordError::BadRequest("Invalid expansion"));
+      let fields = match query.fields {
+        Some(ref fields) => {
+          Fields::parse(&api, &fields.split(",").collect::<Vec<_>>(), &query_expand)?
         }
-      }
+        None => None,
+      };
+
+      let expansions = Expansions::build(
+        &state,
+        &api,
+        &query_expand,
+        "R",
+        fields.as_ref(),
+        user.as_ref(),
+      )?;
 
-      let mut rows = SelectQueryBuilder::run_expanded(
+      let Some(row) = SelectQueryBuilder::run_expanded(
         &state,
         api.table_name(),
         &api.record_pk_column().name,
-        record_id,
-        &query_expand,
+        record_id.clone(),
+        &expansions,
+        fields.as_ref(),
+        user.as_ref(),
       )
-      .await?;
-
-      if rows.is_empty() {
+      .await?
+      else {
         return Err(RecordError::RecordNotFound);
-      }
+      };
 
-      let foreign_rows = rows.split_off(1);
-      for (col_name, (metadata, row)) in std::iter::zip(query_expand, foreign_rows) {
-        let foreign_value = row_to_json(
-          &metadata.schema.columns,
+      let mut value = expansions
+        .row_to_json(
+          columns,
           metadata.column_metadata(),
-          &row,
-          filter,
+          row,
+          &filter,
+          api.expand(),
+        )
+        .map_err(|err| RecordError::Internal(err.into()))?;
+
+      for child in &expansions.children {
+        let rows =
+          SelectChildrenQueryBuilder::run(&state, child, record_id.clone(), user.as_ref()).await?;
+        let Some(child_columns) = child.api.metadata().columns() else {
+          return Err(RecordError::Internal("missing columns".into()));
+        };
+
+        let records = rows_to_json_expand(
+          child_columns,
+          child.api.metadata().column_metadata(),
+          rows,
+          &|col_name: &str| child.api.is_column_visible(col_name),
+          child.api.expand(),
         )
         .map_err(|err| RecordError::Internal(err.into()))?;
 
-        let result = expand.insert(col_name.to_string(), foreign_value);
-        assert!(result.is_some());
+        if let serde_json::Value::Object(ref mut map) = value {
+          map.insert(child.name.clone(), serde_json::Value::Array(records));
+        }
       }
 
-      row_to_json_expand(
-        columns,
-        metadata.column_metadata(),
-        &rows[0].1,
-        filter,
-        Some(&expand),
-      )
-      .map_err(|err| RecordError::Internal(err.into()))?
+      value
     }
     _ => {
+      let fields = match query.fields {
+        Some(ref fields) => {
+          Fields::parse(&api, &fields.split(",").collect::<Vec<_>>(), &[] as &[&str])?
+        }
+        None => None,
+      };
+
       let Some(row) = SelectQueryBuilder::run(
         &state,
         api.table_name(),
         &api.record_pk_column().name,
         record_id,
+        fields.as_ref(),
       )
       .await?
       else {
@@ -114,7 +144,7 @@ pub async fn read_record_handler(
         columns,
         metadata.column_metadata(),
         &row,
-        filter,
+        &filter,
         api.expand(),
       )
       .map_err(|err| RecordError::Internal(err.into()))?
@@ -387,6 +417,158 @@ mod test {
     return Ok(());
   }
 
+  #[tokio::test]
+  async fn test_record_api_read_with_roles() -> Result<(), anyhow::Error> {
+    let state = test_state(None).await?;
+    let conn = state.conn();
+
+    create_chat_message_app_tables(&state).await?;
+    let room = add_room(conn, "room0").await?;
+    let password = "Secret!1!!";
+
+    add_record_api(
+      &state,
+      "messages_api",
+      "message",
+      Acls {
+        authenticated: vec![PermissionFlag::Read],
+        ..Default::default()
+      },
+      AccessRules {
+        read: Some("'moderator' IN _USER_.roles".to_string()),
+        ..Default::default()
+      },
+    )
+    .await?;
+
+    let moderator_email = "moderator@test.com";
+    let moderator = create_user_for_test(&state, moderator_email, password).await?;
+    conn
+      .execute_batch(&format!(
+        r#"
+          INSERT INTO "_role" (name) VALUES ('moderator');
+          INSERT INTO "_user_role" (user, role) VALUES (X'{}', 'moderator');
+        "#,
+        moderator.simple()
+      ))
+      .await?;
+    let moderator_token = login_with_password(&state, moderator_email, password).await?;
+
+    let user_email = "user@test.com";
+    let user = create_user_for_test(&state, user_email, password).await?;
+    let user_token = login_with_password(&state, user_email, password).await?;
+
+    let message_id = send_message(conn, user.into_bytes(), room, "message").await?;
+
+    let response = read_record_handler(
+      State(state.clone()),
+      Path(("messages_api".to_string(), id_to_b64(&message_id))),
+      Query(ReadRecordQuery::default()),
+      User::from_auth_token(&state, &moderator_token.auth_token),
+    )
+    .await;
+    assert!(response.is_ok(), "{response:?}");
+
+    // Without the role, not even the author can read the message.
+    let response = read_record_handler(
+      State(state.clone()),
+      Path(("messages_api".to_string(), id_to_b64(&message_id))),
+      Query(ReadRecordQuery::default()),
+      User::from_auth_token(&state, &user_token.auth_token),
+    )
+    .await;
+    assert!(response.is_err(), "{response:?}");
+
+    return Ok(());
+  }
+
+  #[tokio::test]
+  async fn test_record_api_read_with_custom_claims() -> Result<(), anyhow::Error> {
+    let state = test_state(None).await?;
+    let conn = state.conn();
+
+    let mut config = state.get_config();
+    config
+      .auth
+      .custom_claims
+      .insert("tenant_id".to_string(), "tenant".to_string());
+    state.validate_and_update_config(config, None).await?;
+
+    create_chat_message_app_tables(&state).await?;
+    let room = add_room(conn, "room0").await?;
+    let password = "Secret!1!!";
+
+    add_record_api(
+      &state,
+      "messages_api",
+      "message",
+      Acls {
+        authenticated: vec![PermissionFlag::Read],
+        ..Default::default()
+      },
+      AccessRules {
+        read: Some("_USER_.tenant_id = 'acme'".to_string()),
+        ..Default::default()
+      },
+    )
+    .await?;
+
+    let mut tokens = vec![];
+    for (email, tenant) in [("acme@test.com", "acme"), ("other@test.com", "other")] {
+      let user = create_user_for_test(&state, email, password).await?;
+      conn
+        .execute(
+          &format!(r#"UPDATE "{USER_TABLE}" SET metadata = $1 WHERE id = $2"#),
+          trailbase_sqlite::params!(
+            format!(r#"{{"tenant": "{tenant}", "plan": "pro"}}"#),
+            user.into_bytes()
+          ),
+        )
+        .await?;
+      tokens.push(login_with_password(&state, email, password).await?);
+    }
+
+    let acme_user = User::from_auth_token(&state, &tokens[0].auth_token).unwrap();
+    // Only mapped metadata keys end up in the claims.
+    assert_eq!(
+      serde_json::json!({"tenant_id": "acme"}),
+      serde_json::Value::Object(acme_user.custom_claims.clone())
+    );
+
+    // Refreshed tokens carry the same claims.
+    let (auth_token_ttl, refresh_token_ttl) = state.access_config(|c| c.auth.token_ttls());
+    let refreshed = crate::auth::tokens::reauth_with_refresh_token(
+      &state,
+      tokens[0].refresh_token.clone(),
+      refresh_token_ttl,
+      auth_token_ttl,
+    )
+    .await?;
+    assert_eq!(acme_user.custom_claims, refreshed.custom_claims);
+
+    let message_id = send_message(conn, acme_user.uuid.into_bytes(), room, "message").await?;
+
+    let response = read_record_handler(
+      State(state.clone()),
+      Path(("messages_api".to_string(), id_to_b64(&message_id))),
+      Query(ReadRecordQuery::default()),
+      Some(acme_user),
+    )
+    .await;
+    assert!(response.is_ok(), "{response:?}");
+
+    let response = read_record_handler(
+      State(state.clone()),
+      Path(("messages_api".to_string(), id_to_b64(&message_id))),
+      Query(ReadRecordQuery::default()),
+      User::from_auth_token(&state, &tokens[1].auth_token),
+    )
+    .await;
+    assert!(response.is_err(), "{response:?}");
+
+    return Ok(());
+  }
+
   async fn create_test_record_api(state: &AppState, api_name: &str) -> Result<(), anyhow::Error> {
     let conn = state.conn();
     conn
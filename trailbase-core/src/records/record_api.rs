@@ -6,13 +6,16 @@ use std::sync::Arc;
 use trailbase_sqlite::{NamedParamRef, NamedParams, NamedParamsRef, Params as _, Value};
 
 use crate::auth::user::User;
-use crate::config::proto::{ConflictResolutionStrategy, RecordApiConfig};
+use crate::config::proto::{ConflictResolutionStrategy, RecordApiConfig, WebhookConfig};
 use crate::records::json_to_sql::{LazyParams, Params};
 use crate::records::{Permission, RecordError};
 use crate::schema::{Column, ColumnDataType};
 use crate::table_metadata::{TableMetadata, TableOrViewMetadata, ViewMetadata};
 use crate::util::{assert_uuidv7, b64_to_id};
 
+const DEFAULT_MAX_LIST_OFFSET: usize = 10000;
+const DEFAULT_MAX_EXPAND_DEPTH: usize = 3;
+
 enum RecordApiMetadata {
   Table(TableMetadata),
   View(ViewMetadata),
@@ -38,11 +41,10 @@ impl RecordApiMetadata {
 
 /// FILTER CONTROL.
 ///
-/// Open question: right now we use the read_access rule also for listing. It could be nice to
-/// allow different access rules. On the other hand, this could also lead to setups where you can
-/// list records you cannot read (the other way round might be more sensible).
-/// On the other hand, different permissions could also be modeled as multiple apis on the same
-/// table.
+/// Listing is controlled separately from reading via the LIST permission and the list access
+/// rule. Since listed records are returned to the user, the list access rule is applied in
+/// addition to the read access rule, i.e. one can read records one cannot list but not the other
+/// way round.
 ///
 /// Independently, listing a user's own items might be a common task. Should we support a magic
 /// filter "mine" or is "owner_col=<my_user_id>" good enough?
@@ -64,6 +66,16 @@ struct RecordApiState {
   enable_subscriptions: bool,
 
   expand: Option<HashMap<String, serde_json::Value>>,
+  expand_apis: HashMap<String, String>,
+  max_list_offset: usize,
+  max_expand_depth: usize,
+  expand_children: Vec<String>,
+
+  hidden_columns: Vec<String>,
+  read_only_columns: Vec<String>,
+  search_columns: Vec<String>,
+
+  webhooks: Vec<WebhookConfig>,
 
   create_access_rule: Option<String>,
   create_access_query: Option<String>,
@@ -79,6 +91,10 @@ struct RecordApiState {
 
   schema_access_rule: Option<String>,
   schema_access_query: Option<String>,
+
+  // NOTE: There's no list access query. Listing applies the rule as part of the list query's
+  // WHERE clause and table subscriptions evaluate it against each changed record.
+  list_access_rule: Option<String>,
 }
 
 impl RecordApi {
@@ -160,6 +176,21 @@ impl RecordApi {
       return Err(format!("RecordApi misses name: {config:?}"));
     };
 
+    let mut config = config;
+    for rule in [
+      &mut config.create_access_rule,
+      &mut config.read_access_rule,
+      &mut config.update_access_rule,
+      &mut config.delete_access_rule,
+      &mut config.schema_access_rule,
+      &mut config.list_access_rule,
+    ]
+    .into_iter()
+    .flatten()
+    {
+      *rule = expand_custom_claims(&expand_user_roles(rule));
+    }
+
     let read_access_query = config.read_access_rule.as_ref().map(|rule| {
       build_read_delete_schema_query(metadata.table_name(), &record_pk_column.name, rule)
     });
@@ -214,6 +245,21 @@ impl RecordApi {
               .collect(),
           )
         },
+        expand_apis: config.expand_apis,
+
+        max_list_offset: config
+          .max_list_offset
+          .map_or(DEFAULT_MAX_LIST_OFFSET, |offset| offset as usize),
+        max_expand_depth: config
+          .max_expand_depth
+          .map_or(DEFAULT_MAX_EXPAND_DEPTH, |depth| depth as usize),
+        expand_children: config.expand_children,
+
+        hidden_columns: config.hidden_columns,
+        read_only_columns: config.read_only_columns,
+        search_columns: config.search_columns,
+
+        webhooks: config.webhooks,
 
         // Access control lists.
         acl: [
@@ -237,6 +283,8 @@ impl RecordApi {
 
         schema_access_rule: config.schema_access_rule,
         schema_access_query,
+
+        list_access_rule: config.list_access_rule,
       }),
     });
   }
@@ -261,6 +309,83 @@ impl RecordApi {
     return self.state.expand.as_ref();
   }
 
+  /// Name of the Record API explicitly configured as target for expanding the given column, if
+  /// any.
+  #[inline]
+  pub(crate) fn expand_api(&self, column_name: &str) -> Option<&str> {
+    return self
+      .state
+      .expand_apis
+      .get(column_name)
+      .map(|name| name.as_str());
+  }
+
+  #[inline]
+  pub fn max_list_offset(&self) -> usize {
+    return self.state.max_list_offset;
+  }
+
+  #[inline]
+  pub fn max_expand_depth(&self) -> usize {
+    return self.state.max_expand_depth;
+  }
+
+  #[inline]
+  pub(crate) fn expand_children(&self) -> &[String] {
+    return &self.state.expand_children;
+  }
+
+  #[inline]
+  pub fn hidden_columns(&self) -> &[String] {
+    return &self.state.hidden_columns;
+  }
+
+  #[inline]
+  pub fn read_only_columns(&self) -> &[String] {
+    return &self.state.read_only_columns;
+  }
+
+  #[inline]
+  pub(crate) fn search_columns(&self) -> &[String] {
+    return &self.state.search_columns;
+  }
+
+  #[inline]
+  pub(crate) fn webhooks(&self) -> &[WebhookConfig] {
+    return &self.state.webhooks;
+  }
+
+  /// Whether the given column may be exposed to clients, i.e. it's neither an internal "_" column
+  /// nor hidden by configuration.
+  #[inline]
+  pub fn is_column_visible(&self, column_name: &str) -> bool {
+    return !column_name.starts_with("_")
+      && !self.state.hidden_columns.iter().any(|c| c == column_name);
+  }
+
+  /// Whether clients may set the given column on create or update.
+  #[inline]
+  pub fn is_column_writable(&self, column_name: &str) -> bool {
+    return !self.state.hidden_columns.iter().any(|c| c == column_name)
+      && !self
+        .state
+        .read_only_columns
+        .iter()
+        .any(|c| c == column_name);
+  }
+
+  /// Rejects client-provided params targeting hidden or read-only columns.
+  pub(crate) fn check_writable_columns(&self, params: &Params) -> Result<(), RecordError> {
+    if params
+      .column_names()
+      .iter()
+      .any(|col| !self.is_column_writable(col))
+    {
+      return Err(RecordError::BadRequest("Column not writable"));
+    }
+    return Ok(());
+  }
+
   pub fn table_metadata(&self) -> Option<&TableMetadata> {
     match &self.state.metadata {
       RecordApiMetadata::Table(table) => Some(table),
@@ -304,18 +429,24 @@ impl RecordApi {
       Permission::Update => &self.state.update_access_rule,
       Permission::Delete => &self.state.delete_access_rule,
       Permission::Schema => &self.state.schema_access_rule,
+      Permission::List => &self.state.list_access_rule,
     };
   }
 
   #[inline]
-  fn access_query(&self, p: Permission) -> &Option<String> {
-    return match p {
-      Permission::Create => &self.state.create_access_query,
-      Permission::Read => &self.state.read_access_query,
-      Permission::Update => &self.state.update_access_query,
-      Permission::Delete => &self.state.delete_access_query,
-      Permission::Schema => &self.state.schema_access_query,
-    };
+  fn access_query(&self, p: Permission) -> Result<Option<&String>, RecordError> {
+    return Ok(match p {
+      Permission::Create => self.state.create_access_query.as_ref(),
+      Permission::Read => self.state.read_access_query.as_ref(),
+      Permission::Update => self.state.update_access_query.as_ref(),
+      Permission::Delete => self.state.delete_access_query.as_ref(),
+      Permission::Schema => self.state.schema_access_query.as_ref(),
+      Permission::List => {
+        return Err(RecordError::Internal(
+          "LIST has no record-level access query".into(),
+        ));
+      }
+    });
   }
 
   #[inline]
@@ -344,7 +475,7 @@ impl RecordApi {
     // First check table level access and if present check row-level access based on access rule.
     self.check_table_level_access(p, user)?;
 
-    let Some(access_query) = self.access_query(p) else {
+    let Some(access_query) = self.access_query(p)? else {
       return Ok(());
     };
     let access_query = access_query.clone();
@@ -487,7 +618,9 @@ impl RecordApi {
             .map_err(|err| RecordError::Internal(err.into()))?,
         )
       }
-      Permission::Read | Permission::Delete | Permission::Schema => NamedParams::with_capacity(2),
+      Permission::Read | Permission::Delete | Permission::Schema | Permission::List => {
+        NamedParams::with_capacity(2)
+      }
     };
 
     params.extend_from_slice(&[
@@ -495,6 +628,8 @@ impl RecordApi {
         Cow::Borrowed(":__user_id"),
         user.map_or(Value::Null, |u| Value::Blob(u.uuid.into())),
       ),
+      (Cow::Borrowed(":__user_roles"), user_roles_value(user)),
+      (Cow::Borrowed(":__user_claims"), user_claims_value(user)),
       (
         Cow::Borrowed(":__record_id"),
         record_id.map_or(Value::Null, |id| id.clone()),
@@ -505,6 +640,69 @@ impl RecordApi {
   }
 }
 
+/// Sub-select providing the `_USER_` table to access rules. Expects the `:__user_id`,
+/// `:__user_roles` and `:__user_claims` parameters to be bound.
+pub(crate) const USER_SUBQUERY: &str =
+  "(SELECT :__user_id AS id, :__user_roles AS roles, :__user_claims AS claims) AS _USER_";
+
+/// Matches SQL string literals and quoted identifiers, which access rule rewrites must skip.
+const QUOTED_PATTERN: &str = r#"(?P<quoted>'(?:[^']|'')*'|"(?:[^"]|"")*")"#;
+
+/// Rewrites `<expr> IN _USER_.roles` into a membership test against the JSON array of roles.
+///
+/// NOTE: Without rewrite, SQLite would interpret `_USER_.roles` as table "roles" in schema
+/// "_USER_", i.e. this doesn't change the meaning of otherwise valid rules.
+fn expand_user_roles(rule: &str) -> String {
+  lazy_static::lazy_static! {
+    static ref IN_ROLES: regex::Regex =
+      regex::Regex::new(&format!(r"{QUOTED_PATTERN}|(?i:\bIN\s+_USER_\.roles\b)")).unwrap();
+  }
+
+  return IN_ROLES
+    .replace_all(rule, |captures: &regex::Captures| {
+      if captures.name("quoted").is_some() {
+        return captures[0].to_string();
+      }
+      return "IN (SELECT value FROM json_each(_USER_.roles))".to_string();
+    })
+    .to_string();
+}
+
+/// Binds the user's roles as JSON array to be used as `_USER_.roles` in access rules.
+pub(crate) fn user_roles_value(user: Option<&User>) -> Value {
+  return user.map_or(Value::Null, |u| {
+    Value::Text(serde_json::to_string(&u.roles).unwrap_or_else(|_| "[]".to_string()))
+  });
+}
+
+/// Rewrites references to custom claims, i.e. `_USER_.<claim>` other than the built-in `id`,
+/// `roles` and `claims` columns, into lookups in the JSON object of the user's custom claims.
+fn expand_custom_claims(rule: &str) -> String {
+  lazy_static::lazy_static! {
+    static ref USER_FIELD: regex::Regex =
+      regex::Regex::new(&format!(r"{QUOTED_PATTERN}|\b_USER_\.(?P<field>\w+)\b")).unwrap();
+  }
+
+  return USER_FIELD
+    .replace_all(rule, |captures: &regex::Captures| {
+      let Some(field) = captures.name("field").map(|m| m.as_str()) else {
+        return captures[0].to_string();
+      };
+      return match field.to_ascii_lowercase().as_str() {
+        "id" | "roles" | "claims" => captures[0].to_string(),
+        _ => format!("json_extract(_USER_.claims, '$.{field}')"),
+      };
+    })
+    .to_string();
+}
+
+/// Binds the user's custom claims as JSON object to be used as `_USER_.<claim>` in access rules.
+pub(crate) fn user_claims_value(user: Option<&User>) -> Value {
+  return user.map_or(Value::Null, |u| {
+    Value::Text(serde_json::to_string(&u.custom_claims).unwrap_or_else(|_| "{}".to_string()))
+  });
+}
+
 fn build_query_and_params_for_record_read<'a>(
   access_rule: &str,
   user: Option<&User>,
@@ -536,6 +734,14 @@ fn build_query_and_params_for_record_read<'a>(
       rusqlite::types::ToSqlOutput::Owned(Value::Blob(u.uuid.into()))
     }),
   ));
+  params.push((
+    Cow::Borrowed(":__user_roles"),
+    rusqlite::types::ToSqlOutput::Owned(user_roles_value(user)),
+  ));
+  params.push((
+    Cow::Borrowed(":__user_claims"),
+    rusqlite::types::ToSqlOutput::Owned(user_claims_value(user)),
+  ));
 
   // Assumes access_rule is an expression: https://www.sqlite.org/syntax/expr.html
   let query = indoc::formatdoc!(
@@ -543,7 +749,7 @@ fn build_query_and_params_for_record_read<'a>(
         SELECT
           ({access_rule})
         FROM
-          (SELECT :__user_id AS id) AS _USER_,
+          {USER_SUBQUERY},
           (SELECT {row}) AS _ROW_
       "#
   );
@@ -564,7 +770,7 @@ fn build_read_delete_schema_query(
       SELECT
         ({access_rule})
       FROM
-        (SELECT :__user_id AS id) AS _USER_,
+        {USER_SUBQUERY},
         (SELECT * FROM "{table_name}" WHERE "{pk_column_name}" = :__record_id) AS _ROW_
     "#
   );
@@ -589,7 +795,7 @@ fn build_create_access_query(table_metadata: &TableMetadata, create_access_rule:
       SELECT
         ({create_access_rule})
       FROM
-        (SELECT :__user_id AS id) AS _USER_,
+        {USER_SUBQUERY},
         ({column_sub_select}) AS _REQ_
     "#,
   );
@@ -620,7 +826,7 @@ fn build_update_access_query(
       SELECT
         ({update_access_rule})
       FROM
-        (SELECT :__user_id AS id) AS _USER_,
+        {USER_SUBQUERY},
         ({column_sub_select}) AS _REQ_,
         (SELECT * FROM "{table_name}" WHERE "{pk_column_name}" = :__record_id) AS _ROW_
     "#,
@@ -673,7 +879,7 @@ enum Entity {
 
 #[cfg(test)]
 mod tests {
-  use super::convert_acl;
+  use super::{convert_acl, expand_user_roles};
   use crate::{config::proto::PermissionFlag, records::Permission};
 
   fn has_access(flags: u8, p: Permission) -> bool {
@@ -705,4 +911,48 @@ mod tests {
       assert!(has_access(acl, Permission::Update), "ACL: {acl}");
     }
   }
+
+  #[test]
+  fn test_expand_user_roles() {
+    assert_eq!(
+      "'editor' IN (SELECT value FROM json_each(_USER_.roles)) OR _ROW_.owner = _USER_.id",
+      expand_user_roles("'editor' IN _USER_.roles OR _ROW_.owner = _USER_.id")
+    );
+    assert_eq!(
+      "'a' NOT IN (SELECT value FROM json_each(_USER_.roles))",
+      expand_user_roles("'a' NOT in  _USER_.roles")
+    );
+    // Other uses are left alone.
+    assert_eq!(
+      "json_array_length(_USER_.roles) > 0",
+      expand_user_roles("json_array_length(_USER_.roles) > 0")
+    );
+    // String literals and quoted identifiers are left alone.
+    assert_eq!(
+      "_ROW_.note = 'x IN _USER_.roles' AND 'it''s' IN (SELECT value FROM json_each(_USER_.roles))",
+      expand_user_roles("_ROW_.note = 'x IN _USER_.roles' AND 'it''s' IN _USER_.roles")
+    );
+  }
+
+  #[test]
+  fn test_expand_custom_claims() {
+    assert_eq!(
+      "json_extract(_USER_.claims, '$.tenant_id') = _ROW_.tenant AND _ROW_.owner = _USER_.id",
+      expand_custom_claims("_USER_.tenant_id = _ROW_.tenant AND _ROW_.owner = _USER_.id")
+    );
+    assert_eq!(
+      "json_array_length(_USER_.roles) > 0 AND _USER_.claims IS NOT NULL",
+      expand_custom_claims("json_array_length(_USER_.roles) > 0 AND _USER_.claims IS NOT NULL")
+    );
+    // Only references to the _USER_ table are rewritten.
+    assert_eq!(
+      "_ROW_.tenant_id = 'foo' AND MY_USER_.plan = 'pro'",
+      expand_custom_claims("_ROW_.tenant_id = 'foo' AND MY_USER_.plan = 'pro'")
+    );
+    // String literals and quoted identifiers are left alone.
+    assert_eq!(
+      r#"_ROW_.label = '_USER_.plan' AND "_USER_.plan" = json_extract(_USER_.claims, '$.plan')"#,
+      expand_custom_claims(r#"_ROW_.label = '_USER_.plan' AND "_USER_.plan" = _USER_.plan"#)
+    );
+  }
 }
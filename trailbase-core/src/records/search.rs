@@ -0,0 +1,257 @@
+use itertools::Itertools;
+use log::*;
+use rusqlite::OptionalExtension;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::proto::RecordApiConfig;
+use crate::config::ConfigError;
+use crate::constants::SQLITE_SCHEMA_TABLE;
+use crate::transaction::{TransactionError, TransactionRecorder};
+
+const SEARCH_TABLE_PREFIX: &str = "_fts_";
+
+/// Name of the FTS5 index backing `search_columns` of Record APIs over the given table.
+pub(crate) fn search_table_name(table_name: &str) -> String {
+  return format!("{SEARCH_TABLE_PREFIX}{table_name}");
+}
+
+/// Builds an FTS5 match expression for the given user query restricted to the given columns.
+///
+/// Terms are quoted, i.e. user input cannot inject FTS5 query syntax, and implicitly AND-ed.
+/// Returns None for empty queries.
+pub(crate) fn build_match_expression(columns: &[String], query: &str) -> Option<String> {
+  let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+
+  let terms = query.split_whitespace().map(quote).join(" ");
+  if terms.is_empty() || columns.is_empty() {
+    return None;
+  }
+
+  let columns = columns.iter().map(|c| quote(c)).join(" ");
+  return Some(format!("{{{columns}}} : ({terms})"));
+}
+
+fn create_search_index_statements(table_name: &str, columns: &[String]) -> Vec<String> {
+  let fts = search_table_name(table_name);
+  let cols = columns.iter().map(|c| format!(r#""{c}""#)).join(", ");
+  let new_values = columns.iter().map(|c| format!(r#"new."{c}""#)).join(", ");
+  let old_values = columns.iter().map(|c| format!(r#"old."{c}""#)).join(", ");
+
+  // NOTE: The index uses the table as external content, i.e. it doesn't duplicate the text.
+  return vec![
+    format!(r#"CREATE VIRTUAL TABLE "{fts}" USING fts5({cols}, content='{table_name}')"#),
+    format!(
+      r#"CREATE TRIGGER "{fts}_insert" AFTER INSERT ON "{table_name}" BEGIN INSERT INTO "{fts}"(rowid, {cols}) VALUES (new.rowid, {new_values}); END"#
+    ),
+    format!(
+      r#"CREATE TRIGGER "{fts}_delete" AFTER DELETE ON "{table_name}" BEGIN INSERT INTO "{fts}"("{fts}", rowid, {cols}) VALUES ('delete', old.rowid, {old_values}); END"#
+    ),
+    format!(
+      r#"CREATE TRIGGER "{fts}_update" AFTER UPDATE ON "{table_name}" BEGIN INSERT INTO "{fts}"("{fts}", rowid, {cols}) VALUES ('delete', old.rowid, {old_values}); INSERT INTO "{fts}"(rowid, {cols}) VALUES (new.rowid, {new_values}); END"#
+    ),
+    // Index pre-existing records.
+    format!(r#"INSERT INTO "{fts}"("{fts}") VALUES ('rebuild')"#),
+  ];
+}
+
+fn drop_search_index_statements(table_name: &str) -> Vec<String> {
+  let fts = search_table_name(table_name);
+  return vec![
+    format!(r#"DROP TRIGGER IF EXISTS "{fts}_insert""#),
+    format!(r#"DROP TRIGGER IF EXISTS "{fts}_delete""#),
+    format!(r#"DROP TRIGGER IF EXISTS "{fts}_update""#),
+    format!(r#"DROP TABLE IF EXISTS "{fts}""#),
+  ];
+}
+
+/// Union of the searched columns of all APIs, keyed by table. Multiple APIs may search the same
+/// table, thus an index covers all their columns.
+fn wanted_search_indexes(record_apis: &[RecordApiConfig]) -> BTreeMap<String, Vec<String>> {
+  let mut wanted = BTreeMap::<String, Vec<String>>::new();
+  for api in record_apis {
+    let Some(ref table_name) = api.table_name else {
+      continue;
+    };
+    if api.search_columns.is_empty() {
+      continue;
+    }
+
+    let columns = wanted.entry(table_name.clone()).or_default();
+    columns.extend(api.search_columns.iter().cloned());
+    columns.sort();
+    columns.dedup();
+  }
+  return wanted;
+}
+
+/// Search indexes previously created by `sync_search_indexes` keyed by table. Other FTS5 tables,
+/// which merely share the prefix, are left alone.
+fn existing_search_indexes(
+  conn: &rusqlite::Connection,
+) -> rusqlite::Result<BTreeMap<String, Vec<String>>> {
+  let candidates: Vec<(String, String)> = {
+    let mut stmt = conn.prepare(&format!(
+      "SELECT name, sql FROM {SQLITE_SCHEMA_TABLE} WHERE type = 'table' AND sql LIKE 'CREATE VIRTUAL TABLE%fts5%'"
+    ))?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect::<Result<_, _>>()?
+  };
+
+  let mut existing = BTreeMap::<String, Vec<String>>::new();
+  for (name, sql) in candidates {
+    let Some(table_name) = name.strip_prefix(SEARCH_TABLE_PREFIX) else {
+      continue;
+    };
+
+    let columns: Vec<String> = {
+      let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+      let rows = stmt.query_map([&name], |row| row.get(0))?;
+      rows.collect::<Result<_, _>>()?
+    };
+
+    // SQLite keeps the original statement, thus ours are recognizable by their definition.
+    if create_search_index_statements(table_name, &columns).first() != Some(&sql) {
+      debug!("Skipping foreign FTS5 table: {name}");
+      continue;
+    }
+    existing.insert(table_name.to_string(), columns);
+  }
+
+  return Ok(existing);
+}
+
+/// Checks that the wanted indexes can be created, i.e. that the searched tables have rowids and
+/// that the index names aren't taken.
+fn check_search_indexes(
+  conn: &rusqlite::Connection,
+  wanted: &BTreeMap<String, Vec<String>>,
+  existing: &BTreeMap<String, Vec<String>>,
+) -> rusqlite::Result<Result<(), String>> {
+  for table_name in wanted.keys() {
+    let without_rowid: Option<bool> = conn
+      .query_row(
+        "SELECT wr FROM pragma_table_list WHERE schema = 'main' AND type = 'table' AND name = ?1",
+        [table_name],
+        |row| row.get(0),
+      )
+      .optional()?;
+    if without_rowid == Some(true) {
+      return Ok(Err(format!(
+        "Searching requires a rowid table, got WITHOUT ROWID: {table_name}"
+      )));
+    }
+
+    let fts = search_table_name(table_name);
+    if !existing.contains_key(table_name) {
+      let taken: bool = conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM {SQLITE_SCHEMA_TABLE} WHERE name = ?1)"),
+        [&fts],
+        |row| row.get(0),
+      )?;
+      if taken {
+        return Ok(Err(format!("Search index name already taken: {fts}")));
+      }
+    }
+  }
+
+  return Ok(Ok(()));
+}
+
+/// Validates that the search indexes of the given Record APIs can be created without changing
+/// anything, e.g. before applying a new config.
+pub(crate) async fn validate_search_indexes(
+  conn: &trailbase_sqlite::Connection,
+  record_apis: &[RecordApiConfig],
+) -> Result<(), ConfigError> {
+  let wanted = wanted_search_indexes(record_apis);
+  if wanted.is_empty() {
+    return Ok(());
+  }
+
+  return conn
+    .call(move |conn| {
+      let existing = existing_search_indexes(conn)?;
+      return Ok(check_search_indexes(conn, &wanted, &existing)?);
+    })
+    .await
+    .map_err(|err| ConfigError::Update(err.to_string()))?
+    .map_err(ConfigError::Invalid);
+}
+
+/// Creates, re-creates or drops FTS5 indexes and their triggers to match the `search_columns` of
+/// the given Record APIs. Changes are applied and recorded as a migration.
+///
+/// Returns whether any index was changed, in which case the table metadata should be refreshed.
+pub(crate) async fn sync_search_indexes(
+  conn: &trailbase_sqlite::Connection,
+  migration_path: PathBuf,
+  record_apis: &[RecordApiConfig],
+) -> Result<bool, TransactionError> {
+  let wanted = wanted_search_indexes(record_apis);
+
+  let writer = conn
+    .call(move |conn| {
+      let existing = existing_search_indexes(conn)?;
+      if let Err(err) = check_search_indexes(conn, &wanted, &existing)? {
+        return Err(trailbase_sqlite::Error::Other(err.into()));
+      }
+
+      let mut statements: Vec<String> = vec![];
+      for (table_name, columns) in &existing {
+        if wanted.get(table_name) != Some(columns) {
+          debug!("Dropping search index: {}", search_table_name(table_name));
+          statements.extend(drop_search_index_statements(table_name));
+        }
+      }
+      for (table_name, columns) in &wanted {
+        if existing.get(table_name) != Some(columns) {
+          debug!("Creating search index: {}", search_table_name(table_name));
+          statements.extend(create_search_index_statements(table_name, columns));
+        }
+      }
+
+      if statements.is_empty() {
+        return Ok(None);
+      }
+
+      let mut tx =
+        TransactionRecorder::new(conn, migration_path, "update_search_indexes".to_string())?;
+      for statement in &statements {
+        tx.execute(statement)?;
+      }
+
+      return tx
+        .rollback_and_create_migration()
+        .map_err(|err| trailbase_sqlite::Error::Other(err.into()));
+    })
+    .await?;
+
+  let Some(writer) = writer else {
+    return Ok(false);
+  };
+  writer.write(conn).await?;
+
+  return Ok(true);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_build_match_expression() {
+    let columns = vec!["title".to_string(), "body".to_string()];
+
+    assert_eq!(
+      build_match_expression(&columns, "  hello world "),
+      Some(r#"{"title" "body"} : ("hello" "world")"#.to_string())
+    );
+    assert_eq!(
+      build_match_expression(&columns, r#"a"b OR NEAR(c)"#),
+      Some(r#"{"title" "body"} : ("a""b" "OR" "NEAR(c)")"#.to_string())
+    );
+    assert_eq!(build_match_expression(&columns, "   "), None);
+    assert_eq!(build_match_expression(&[], "hello"), None);
+  }
+}
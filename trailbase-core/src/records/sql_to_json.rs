@@ -22,6 +22,8 @@ pub enum JsonError {
   ValueNotFound,
   #[error("Missing col name")]
   MissingColumnName,
+  #[error("Unknown column: {0}")]
+  UnknownColumn(String),
 }
 
 pub(crate) fn valueref_to_json(
@@ -48,7 +50,7 @@ pub fn row_to_json(
   columns: &[Column],
   column_metadata: &[ColumnMetadata],
   row: &trailbase_sqlite::Row,
-  column_filter: fn(&str) -> bool,
+  column_filter: &dyn Fn(&str) -> bool,
 ) -> Result<serde_json::Value, JsonError> {
   return row_to_json_expand(columns, column_metadata, row, column_filter, None);
 }
@@ -61,11 +63,13 @@ fn is_foreign_key(options: &[ColumnOption]) -> bool {
 }
 
 /// Serialize SQL row to json.
+///
+/// The row may only contain a subset of the given columns, e.g. when a projection was requested.
 pub fn row_to_json_expand(
   columns: &[Column],
   column_metadata: &[ColumnMetadata],
   row: &trailbase_sqlite::Row,
-  column_filter: fn(&str) -> bool,
+  column_filter: &dyn Fn(&str) -> bool,
   expand: Option<&HashMap<String, serde_json::Value>>,
 ) -> Result<serde_json::Value, JsonError> {
   let map = (0..row.column_count())
@@ -77,10 +81,17 @@ pub fn row_to_json_expand(
         return None;
       }
 
-      assert!(i < columns.len());
-      assert!(i < column_metadata.len());
-      let column = &columns[i];
-      assert_eq!(column_name, column.name);
+      // Fast path: the row contains all columns in order. Otherwise, look the column up by name.
+      let index = if columns.get(i).is_some_and(|c| c.name == column_name) {
+        i
+      } else {
+        let Some(index) = columns.iter().position(|c| c.name == column_name) else {
+          return Some(Err(JsonError::UnknownColumn(column_name.to_string())));
+        };
+        index
+      };
+      assert!(index < column_metadata.len());
+      let column = &columns[index];
 
       let Some(value) = row.get_value(i) else {
         return Some(Err(JsonError::ValueNotFound));
@@ -118,7 +129,7 @@ pub fn row_to_json_expand(
       }
 
       if let rusqlite::types::Value::Text(str) = &value {
-        let metadata = &column_metadata[i];
+        let metadata = &column_metadata[index];
         if metadata.json.is_some() {
           return match serde_json::from_str(str) {
             Ok(json) => Some(Ok((column_name.to_string(), json))),
@@ -142,7 +153,7 @@ pub fn rows_to_json(
   columns: &[Column],
   column_metadata: &[ColumnMetadata],
   rows: trailbase_sqlite::Rows,
-  column_filter: fn(&str) -> bool,
+  column_filter: &dyn Fn(&str) -> bool,
 ) -> Result<Vec<serde_json::Value>, JsonError> {
   return rows
     .iter()
@@ -155,7 +166,7 @@ pub fn rows_to_json_expand(
   columns: &[Column],
   column_metadata: &[ColumnMetadata],
   rows: trailbase_sqlite::Rows,
-  column_filter: fn(&str) -> bool,
+  column_filter: &dyn Fn(&str) -> bool,
   expand: Option<&HashMap<String, serde_json::Value>>,
 ) -> Result<Vec<serde_json::Value>, JsonError> {
   return rows
@@ -300,7 +311,7 @@ mod tests {
       metadata.columns().unwrap(),
       metadata.column_metadata(),
       rows,
-      |_| true,
+      &|_| true,
     )
     .unwrap();
 
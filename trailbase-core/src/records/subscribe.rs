@@ -1,34 +1,47 @@
 use async_channel::WeakReceiver;
 use axum::{
-  extract::{Path, State},
+  extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+  extract::{Path, RawQuery, State},
+  http::{header::ORIGIN, HeaderMap},
   response::sse::{Event, KeepAlive, Sse},
+  response::Response,
 };
-use futures_util::Stream;
+use futures_util::{Stream, StreamExt};
+use itertools::Itertools;
 use parking_lot::RwLock;
 use pin_project_lite::pin_project;
 use rusqlite::hooks::{Action, PreUpdateCase};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::{
-  atomic::{AtomicI64, Ordering},
+  atomic::{AtomicI64, AtomicU64, Ordering},
   Arc,
 };
 use std::task::{Context, Poll};
-use trailbase_sqlite::connection::{extract_record_values, extract_row_id};
+use std::time::{Duration, Instant};
+use trailbase_sqlite::connection::{
+  extract_old_record_values, extract_record_values, extract_row_id,
+};
 
+use crate::auth::tokens::Tokens;
 use crate::auth::user::User;
+use crate::listing::{parse_query, QueryParseResult, WhereClause};
+use crate::records::change_capture::ChangeCapture;
+use crate::records::list_records::build_api_filter_where_clause;
 use crate::records::sql_to_json::valueref_to_json;
 use crate::records::RecordApi;
 use crate::records::{Permission, RecordError};
 use crate::table_metadata::{TableMetadata, TableMetadataCache};
-use crate::value_notifier::Computed;
+use crate::value_notifier::{Computed, ValueNotifier};
 use crate::AppState;
 
 static SUBSCRIPTION_COUNTER: AtomicI64 = AtomicI64::new(0);
 
 type SseEvent = Result<axum::response::sse::Event, axum::Error>;
 
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
 /// Composite id uniquely identifying a subscription.
 ///
 /// If row_id is Some, this is considered to reference a subscription to a specific record.
@@ -42,7 +55,7 @@ struct SubscriptionId {
 /// RAII type for automatically cleaning up subscriptions when the receiving side gets dropped,
 /// e.g. client disconnects.
 struct CleanupSubscription {
-  receiver: WeakReceiver<Event>,
+  receiver: WeakReceiver<SequencedEvent>,
   state: AppState,
   id: SubscriptionId,
 }
@@ -69,22 +82,33 @@ pin_project! {
   struct AutoCleanupEventStream {
     cleanup: CleanupSubscription,
 
+    /// Missed events replayed to resuming subscribers ahead of live events.
+    replay: VecDeque<SubscriptionEvent>,
+
     #[pin]
-    receiver: async_channel::Receiver<Event>,
+    receiver: async_channel::Receiver<SequencedEvent>,
   }
 }
 
 impl Stream for AutoCleanupEventStream {
-  type Item = Result<Event, axum::Error>;
+  type Item = SubscriptionEvent;
 
   fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
     let mut this = self.project();
-    let res = futures_util::ready!(this.receiver.as_mut().poll_next(cx));
-    Poll::Ready(res.map(Ok))
+    if let Some(event) = this.replay.pop_front() {
+      return Poll::Ready(Some(event));
+    }
+    this
+      .receiver
+      .as_mut()
+      .poll_next(cx)
+      .map(|event| event.map(SubscriptionEvent::Change))
   }
 
   fn size_hint(&self) -> (usize, Option<usize>) {
-    self.receiver.size_hint()
+    let (lower, upper) = self.receiver.size_hint();
+    let replay = self.replay.len();
+    (lower + replay, upper.map(|upper| upper + replay))
   }
 }
 
@@ -114,6 +138,95 @@ pub enum DbEvent {
   Error(String),
 }
 
+/// A change event tagged with its sequence number, which is sent as the SSE event id.
+///
+/// Sequence numbers are unique and increasing across all tables.
+#[derive(Debug, Clone, PartialEq)]
+struct SequencedEvent {
+  seq: u64,
+  event: DbEvent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SubscriptionEvent {
+  Change(SequencedEvent),
+  /// Sent to resuming clients, when missed changes can no longer be replayed. Clients should
+  /// re-fetch their state. All changes after `seq` will be delivered.
+  Resync {
+    seq: u64,
+  },
+}
+
+/// Max number of changes retained per table for replaying to resuming subscribers.
+const CHANGE_LOG_CAPACITY: usize = 1024;
+
+/// How long change logs of tables without subscribers are retained for clients to resume.
+const CHANGE_LOG_RETENTION: Duration = Duration::from_secs(300);
+
+struct ChangeLogEntry {
+  seq: u64,
+  action: RecordAction,
+  rowid: i64,
+  table_metadata: Arc<TableMetadata>,
+  record_values: Vec<rusqlite::types::Value>,
+  /// Values prior to the change for updates.
+  old_record_values: Option<Vec<rusqlite::types::Value>>,
+}
+
+impl ChangeLogEntry {
+  fn record(&self) -> Vec<(&str, rusqlite::types::ValueRef<'_>)> {
+    return named_record(&self.table_metadata, &self.record_values);
+  }
+
+  fn old_record(&self) -> Option<Vec<(&str, rusqlite::types::ValueRef<'_>)>> {
+    return self
+      .old_record_values
+      .as_ref()
+      .map(|values| named_record(&self.table_metadata, values));
+  }
+}
+
+/// Joins record values with their column names.
+fn named_record<'a>(
+  table_metadata: &'a TableMetadata,
+  values: &'a [rusqlite::types::Value],
+) -> Vec<(&'a str, rusqlite::types::ValueRef<'a>)> {
+  return values
+    .iter()
+    .enumerate()
+    .map(|(idx, v)| (table_metadata.schema.columns[idx].name.as_str(), v.into()))
+    .collect();
+}
+
+/// Bounded log of the most recent changes to a table.
+struct ChangeLog {
+  /// All changes with a greater sequence number are in `entries`.
+  retained_after: u64,
+  entries: VecDeque<ChangeLogEntry>,
+  /// Set while the table has no subscribers. The log is dropped after `CHANGE_LOG_RETENTION`.
+  idle_since: Option<Instant>,
+}
+
+impl ChangeLog {
+  fn push(&mut self, entry: ChangeLogEntry) {
+    self.entries.push_back(entry);
+    if self.entries.len() > CHANGE_LOG_CAPACITY {
+      if let Some(evicted) = self.entries.pop_front() {
+        self.retained_after = evicted.seq;
+      }
+    }
+  }
+}
+
+/// Decision on what to send to a subscription for a given change.
+enum Delivery {
+  Send(DbEvent),
+  Skip,
+  /// The subscription is no longer valid, e.g. its API was removed or access got revoked, and
+  /// should be ended after sending the optional event.
+  Cancel(Option<DbEvent>),
+}
+
 pub struct Subscription {
   /// Id uniquely identifying this subscription.
   subscription_id: i64,
@@ -124,8 +237,11 @@ pub struct Subscription {
   /// Record id present for subscriptions to specific records.
   // record_id: Option<trailbase_sqlite::Value>,
   user: Option<User>,
-  /// Channel for sending events to the SSE handler.
-  sender: async_channel::Sender<Event>,
+  /// Optional filter for table subscriptions, e.g. `?room=42`, using the same syntax as listing
+  /// records. Only events for matching records are sent.
+  filter: Option<WhereClause>,
+  /// Channel for sending events to the SSE or WebSocket handler.
+  sender: async_channel::Sender<SequencedEvent>,
 }
 
 /// Internal, shareable state of the cloneable SubscriptionManager.
@@ -143,6 +259,15 @@ struct ManagerState {
 
   /// Map from table name to table subscriptions.
   table_subscriptions: RwLock<HashMap<String, Vec<Subscription>>>,
+
+  /// Sequence number of the most recent change.
+  last_seq: AtomicU64,
+  /// Map from table name to recent changes for resuming subscriptions. Logs are created on
+  /// subscribe and outlive their subscriptions for `CHANGE_LOG_RETENTION`.
+  change_logs: RwLock<HashMap<String, ChangeLog>>,
+
+  /// Durable change data capture piggybacking on the preupdate hook.
+  change_capture: Arc<ChangeCapture>,
 }
 
 impl ManagerState {
@@ -173,7 +298,11 @@ impl ManagerState {
           if table.is_empty() {
             lock.remove(&id.table_name);
 
-            if lock.is_empty() && self.table_subscriptions.read().is_empty() {
+            if lock.is_empty()
+              && self.table_subscriptions.read().is_empty()
+              && self.change_logs.read().is_empty()
+              && !self.change_capture.enabled()
+            {
               conn.preupdate_hook(NO_HOOK);
             }
           }
@@ -189,7 +318,11 @@ impl ManagerState {
 
         if subs.is_empty() {
           lock.remove(&id.table_name);
-          if lock.is_empty() && self.record_subscriptions.read().is_empty() {
+          if lock.is_empty()
+            && self.record_subscriptions.read().is_empty()
+            && self.change_logs.read().is_empty()
+            && !self.change_capture.enabled()
+          {
             conn.preupdate_hook(NO_HOOK);
           }
         }
@@ -200,6 +333,69 @@ impl ManagerState {
   }
 }
 
+/// Builds a SQLite event (insert, update, delete) with a JSON-encoded record from the columns
+/// accepted by `filter`.
+fn build_event(
+  action: RecordAction,
+  record: &[(&str, rusqlite::types::ValueRef<'_>)],
+  filter: impl Fn(&str) -> bool,
+) -> DbEvent {
+  let json_value = serde_json::Value::Object(
+    record
+      .iter()
+      .filter_map(|(name, value)| {
+        if !filter(name) {
+          return None;
+        }
+        if let Ok(v) = valueref_to_json(*value) {
+          return Some(((*name).to_string(), v));
+        };
+        return None;
+      })
+      .collect(),
+  );
+
+  return match action {
+    RecordAction::Delete => DbEvent::Delete(Some(json_value)),
+    RecordAction::Insert => DbEvent::Insert(Some(json_value)),
+    RecordAction::Update => DbEvent::Update(Some(json_value)),
+  };
+}
+
+/// Evaluates a subscription filter against the given values of a changed record.
+fn record_matches_filter(
+  conn: &rusqlite::Connection,
+  filter: &WhereClause,
+  record: &[(&str, rusqlite::types::ValueRef<'_>)],
+) -> Result<bool, rusqlite::Error> {
+  let row = record
+    .iter()
+    .map(|(name, _value)| format!(r#":__v_{name} AS "{name}""#))
+    .join(", ");
+
+  let mut stmt = conn.prepare_cached(&format!(
+    "SELECT ({clause}) FROM (SELECT {row}) AS _ROW_",
+    clause = filter.clause
+  ))?;
+
+  for (name, value) in record {
+    if let Some(idx) = stmt.parameter_index(&format!(":__v_{name}"))? {
+      stmt.raw_bind_parameter(idx, value)?;
+    }
+  }
+  for (name, value) in &filter.params {
+    if let Some(idx) = stmt.parameter_index(name)? {
+      stmt.raw_bind_parameter(idx, value)?;
+    }
+  }
+
+  let mut rows = stmt.raw_query();
+  return match rows.next()? {
+    Some(row) => Ok(row.get::<_, Option<bool>>(0)?.unwrap_or(false)),
+    None => Ok(false),
+  };
+}
+
 #[derive(Clone)]
 pub struct SubscriptionManager {
   state: Arc<ManagerState>,
@@ -212,6 +408,7 @@ struct ContinuationState {
   table_name: String,
   rowid: i64,
   record_values: Vec<rusqlite::types::Value>,
+  old_record_values: Option<Vec<rusqlite::types::Value>>,
 }
 
 impl SubscriptionManager {
@@ -219,17 +416,73 @@ impl SubscriptionManager {
     conn: trailbase_sqlite::Connection,
     table_metadata: TableMetadataCache,
     record_apis: Computed<Vec<(String, RecordApi)>, crate::config::proto::Config>,
+    change_capture: ChangeCapture,
+    config: &ValueNotifier<crate::config::proto::Config>,
   ) -> Self {
-    return Self {
-      state: Arc::new(ManagerState {
-        conn,
-        table_metadata,
-        record_apis,
+    let state = Arc::new(ManagerState {
+      conn,
+      table_metadata,
+      record_apis,
+
+      record_subscriptions: RwLock::new(HashMap::new()),
+      table_subscriptions: RwLock::new(HashMap::new()),
+
+      // Seeded with the current time, s.t. sequence numbers keep increasing across restarts
+      // and stale ids from before a restart lead to a resync rather than a bogus replay.
+      last_seq: AtomicU64::new(
+        std::time::SystemTime::now()
+          .duration_since(std::time::UNIX_EPOCH)
+          .map_or(0, |d| d.as_micros() as u64),
+      ),
+      change_logs: RwLock::new(HashMap::new()),
+      change_capture: Arc::new(change_capture),
+    });
 
-        record_subscriptions: RwLock::new(HashMap::new()),
-        table_subscriptions: RwLock::new(HashMap::new()),
-      }),
-    };
+    {
+      let state = state.clone();
+      state.conn.clone().call_and_forget(move |db| {
+        if let Err(err) = state.change_capture.install(db) {
+          log::error!("Failed to install change capture: {err}");
+        }
+        Self::sync_change_capture_triggers(&state, db);
+      });
+    }
+
+    // Change capture may be enabled at runtime, in which case the preupdate hook needs to be
+    // installed independent of any subscriptions.
+    let weak = Arc::downgrade(&state);
+    config.listen(move |_c| {
+      let Some(state) = weak.upgrade() else {
+        return;
+      };
+      state.conn.clone().call_and_forget(move |db| {
+        Self::sync_change_capture_triggers(&state, db);
+      });
+    });
+
+    return Self { state };
+  }
+
+  /// Brings change capture triggers in sync after schema changes.
+  pub(crate) async fn sync_change_capture(&self) -> Result<(), trailbase_sqlite::Error> {
+    let state = self.state.clone();
+    return self
+      .state
+      .conn
+      .call(move |db| {
+        Self::sync_change_capture_triggers(&state, db);
+        return Ok(());
+      })
+      .await;
+  }
+
+  fn sync_change_capture_triggers(state: &Arc<ManagerState>, db: &rusqlite::Connection) {
+    if let Err(err) = state.change_capture.sync_triggers(db) {
+      log::error!("Failed to sync change capture triggers: {err}");
+    }
+    if state.change_capture.enabled() {
+      Self::install_hook(state, db);
+    }
   }
 
   #[cfg(test)]
@@ -252,38 +505,136 @@ impl SubscriptionManager {
     return count;
   }
 
+  /// Decides what to send to the given subscription for a change, applying access rules, filters
+  /// and hidden columns.
+  ///
+  /// For filtered table subscriptions, updates are also evaluated against the prior values
+  /// `old_record`: records no longer matching are sent as deletions and records starting to match
+  /// as insertions.
+  #[allow(clippy::too_many_arguments)]
+  fn deliver(
+    s: &ManagerState,
+    conn: &rusqlite::Connection,
+    sub: &Subscription,
+    record_subscription: bool,
+    action: RecordAction,
+    record: &[(&str, rusqlite::types::ValueRef<'_>)],
+    old_record: Option<&[(&str, rusqlite::types::ValueRef<'_>)]>,
+    event: &DbEvent,
+  ) -> Delivery {
+    let Some(api) = s.lookup_record_api(&sub.record_api_name) else {
+      return Delivery::Cancel(None);
+    };
+
+    if record_subscription {
+      if let Err(_err) =
+        api.check_record_level_read_access(conn, Permission::Read, record, sub.user.as_ref())
+      {
+        // This can happen if the record api configuration has changed since originally
+        // subscribed. In this case we just send and error and cancel the subscription.
+        return Delivery::Cancel(Some(DbEvent::Error("Access denied".into())));
+      }
+    } else {
+      let matches = Self::table_subscription_matches(conn, &api, sub, record);
+
+      if let (Some(_), Some(old_record)) = (&sub.filter, old_record) {
+        let matched = Self::table_subscription_matches(conn, &api, sub, old_record);
+        match (matched, matches) {
+          (true, false) => {
+            return Delivery::Send(build_event(RecordAction::Delete, old_record, |name| {
+              api.is_column_visible(name)
+            }));
+          }
+          (false, true) => {
+            return Delivery::Send(build_event(RecordAction::Insert, record, |name| {
+              api.is_column_visible(name)
+            }));
+          }
+          _ => {}
+        }
+      }
+
+      if !matches {
+        return Delivery::Skip;
+      }
+    }
+
+    // APIs with hidden columns get their own, filtered event.
+    if api.hidden_columns().is_empty() {
+      return Delivery::Send(event.clone());
+    }
+    return Delivery::Send(build_event(action, record, |name| {
+      api.is_column_visible(name)
+    }));
+  }
+
+  /// Whether the given record is visible to a table subscription, i.e. it passes the read and
+  /// list access rules as well as the subscription's filter.
+  fn table_subscription_matches(
+    conn: &rusqlite::Connection,
+    api: &RecordApi,
+    sub: &Subscription,
+    record: &[(&str, rusqlite::types::ValueRef<'_>)],
+  ) -> bool {
+    // Table subscriptions are a form of listing and thus also subject to the list access rule.
+    for permission in [Permission::Read, Permission::List] {
+      if api
+        .check_record_level_read_access(conn, permission, record, sub.user.as_ref())
+        .is_err()
+      {
+        return false;
+      }
+    }
+
+    let Some(ref filter) = sub.filter else {
+      return true;
+    };
+    return match record_matches_filter(conn, filter, record) {
+      Ok(matches) => matches,
+      Err(err) => {
+        log::warn!("Subscription filter failed: {err}");
+        false
+      }
+    };
+  }
+
+  #[allow(clippy::too_many_arguments)]
   fn broker_subscriptions(
     s: &ManagerState,
     conn: &rusqlite::Connection,
     subs: &[Subscription],
     record_subscriptions: bool,
+    action: RecordAction,
     record: &[(&str, rusqlite::types::ValueRef<'_>)],
-    event: &Event,
+    old_record: Option<&[(&str, rusqlite::types::ValueRef<'_>)]>,
+    seq: u64,
+    event: &DbEvent,
   ) -> Vec<usize> {
     let mut dead_subscriptions: Vec<usize> = vec![];
     for (idx, sub) in subs.iter().enumerate() {
-      let Some(api) = s.lookup_record_api(&sub.record_api_name) else {
-        dead_subscriptions.push(idx);
-        sub.sender.close();
-        continue;
-      };
-
-      if let Err(_err) =
-        api.check_record_level_read_access(conn, Permission::Read, record, sub.user.as_ref())
-      {
-        if record_subscriptions {
-          // This can happen if the record api configuration has changed since originally
-          // subscribed. In this case we just send and error and cancel the subscription.
-          if let Ok(ev) = Event::default().json_data(DbEvent::Error("Access denied".into())) {
-            let _ = sub.sender.try_send(ev);
+      let event = match Self::deliver(
+        s,
+        conn,
+        sub,
+        record_subscriptions,
+        action,
+        record,
+        old_record,
+        event,
+      ) {
+        Delivery::Send(event) => event,
+        Delivery::Skip => continue,
+        Delivery::Cancel(event) => {
+          if let Some(event) = event {
+            let _ = sub.sender.try_send(SequencedEvent { seq, event });
           }
           dead_subscriptions.push(idx);
           sub.sender.close();
+          continue;
         }
-        continue;
-      }
+      };
 
-      match sub.sender.try_send(event.clone()) {
+      match sub.sender.try_send(SequencedEvent { seq, event }) {
         Ok(_) => {}
         Err(async_channel::TrySendError::Full(ev)) => {
           log::warn!("Channel full, dropping event: {ev:?}");
@@ -307,6 +658,7 @@ impl SubscriptionManager {
       action,
       rowid,
       record_values,
+      old_record_values,
     } = state;
     let s = &state;
     let table_name = table_name.as_str();
@@ -322,7 +674,14 @@ impl SubscriptionManager {
       let mut table_subs = s.table_subscriptions.write();
       table_subs.remove(table_name);
 
-      if record_subs.is_empty() && table_subs.is_empty() {
+      let mut change_logs = s.change_logs.write();
+      change_logs.remove(table_name);
+
+      if record_subs.is_empty()
+        && table_subs.is_empty()
+        && change_logs.is_empty()
+        && !s.change_capture.enabled()
+      {
         conn.preupdate_hook(NO_HOOK);
       }
 
@@ -330,38 +689,53 @@ impl SubscriptionManager {
     };
 
     // Join values with column names.
-    let record: Vec<(&str, rusqlite::types::ValueRef<'_>)> = record_values
-      .iter()
-      .enumerate()
-      .map(|(idx, v)| (table_metadata.schema.columns[idx].name.as_str(), v.into()))
-      .collect();
+    let record = named_record(&table_metadata, &record_values);
+    let old_record = old_record_values
+      .as_ref()
+      .map(|values| named_record(&table_metadata, values));
 
     // Build a JSON-encoded SQLite event (insert, update, delete).
-    let event = {
-      let json_value = serde_json::Value::Object(
-        record
-          .iter()
-          .filter_map(|(name, value)| {
-            if let Ok(v) = valueref_to_json(*value) {
-              return Some(((*name).to_string(), v));
-            };
-            return None;
-          })
-          .collect(),
-      );
+    let event = build_event(action, &record, |_| true);
+    let seq = s.last_seq.fetch_add(1, Ordering::SeqCst) + 1;
 
-      let db_event = match action {
-        RecordAction::Delete => DbEvent::Delete(Some(json_value)),
-        RecordAction::Insert => DbEvent::Insert(Some(json_value)),
-        RecordAction::Update => DbEvent::Update(Some(json_value)),
-      };
+    'change_log: {
+      let has_subscribers = s.record_subscriptions.read().contains_key(table_name)
+        || s.table_subscriptions.read().contains_key(table_name);
 
-      let Ok(event) = Event::default().json_data(db_event) else {
-        return;
+      let mut change_logs = s.change_logs.write();
+      let Some(change_log) = change_logs.get_mut(table_name) else {
+        break 'change_log;
       };
 
-      event
-    };
+      if has_subscribers {
+        change_log.idle_since = None;
+      } else if change_log
+        .idle_since
+        .get_or_insert_with(Instant::now)
+        .elapsed()
+        > CHANGE_LOG_RETENTION
+      {
+        change_logs.remove(table_name);
+
+        if change_logs.is_empty()
+          && s.record_subscriptions.read().is_empty()
+          && s.table_subscriptions.read().is_empty()
+          && !s.change_capture.enabled()
+        {
+          conn.preupdate_hook(NO_HOOK);
+        }
+        break 'change_log;
+      }
+
+      change_log.push(ChangeLogEntry {
+        seq,
+        action,
+        rowid,
+        table_metadata: table_metadata.clone(),
+        record_values: record_values.clone(),
+        old_record_values: old_record_values.clone(),
+      });
+    }
 
     'record_subs: {
       let mut read_lock = s.record_subscriptions.upgradable_read();
@@ -369,7 +743,8 @@ impl SubscriptionManager {
         break 'record_subs;
       };
 
-      let dead_subscriptions = Self::broker_subscriptions(s, conn, subs, true, &record, &event);
+      let dead_subscriptions =
+        Self::broker_subscriptions(s, conn, subs, true, action, &record, None, seq, &event);
       if dead_subscriptions.is_empty() && action != RecordAction::Delete {
         // No cleanup needed.
         break 'record_subs;
@@ -386,7 +761,11 @@ impl SubscriptionManager {
 
           if table_subscriptions.is_empty() {
             subscriptions.remove(table_name);
-            if subscriptions.is_empty() && s.table_subscriptions.read().is_empty() {
+            if subscriptions.is_empty()
+              && s.table_subscriptions.read().is_empty()
+              && s.change_logs.read().is_empty()
+              && !s.change_capture.enabled()
+            {
               conn.preupdate_hook(NO_HOOK);
             }
           }
@@ -404,7 +783,11 @@ impl SubscriptionManager {
 
             if table_subscriptions.is_empty() {
               subscriptions.remove(table_name);
-              if subscriptions.is_empty() && s.table_subscriptions.read().is_empty() {
+              if subscriptions.is_empty()
+                && s.table_subscriptions.read().is_empty()
+                && s.change_logs.read().is_empty()
+                && !s.change_capture.enabled()
+              {
                 conn.preupdate_hook(NO_HOOK);
               }
             }
@@ -419,7 +802,17 @@ impl SubscriptionManager {
         break 'table_subs;
       };
 
-      let dead_subscriptions = Self::broker_subscriptions(s, conn, subs, false, &record, &event);
+      let dead_subscriptions = Self::broker_subscriptions(
+        s,
+        conn,
+        subs,
+        false,
+        action,
+        &record,
+        old_record.as_deref(),
+        seq,
+        &event,
+      );
       if dead_subscriptions.is_empty() && action != RecordAction::Delete {
         // No cleanup needed.
         break 'table_subs;
@@ -437,7 +830,11 @@ impl SubscriptionManager {
         if table_subscriptions.is_empty() {
           subscriptions.remove(table_name);
 
-          if subscriptions.is_empty() && s.record_subscriptions.read().is_empty() {
+          if subscriptions.is_empty()
+            && s.record_subscriptions.read().is_empty()
+            && s.change_logs.read().is_empty()
+            && !s.change_capture.enabled()
+          {
             conn.preupdate_hook(NO_HOOK);
           }
         }
@@ -445,65 +842,189 @@ impl SubscriptionManager {
     }
   }
 
-  async fn add_hook(&self) -> trailbase_sqlite::connection::Result<()> {
-    let state = &self.state;
+  /// Installs the preupdate hook. Must be called on the SQLite executor, i.e. from within
+  /// `Connection::call`, to not miss changes racing with new subscriptions.
+  fn install_hook(state: &Arc<ManagerState>, db: &rusqlite::Connection) {
     let conn = state.conn.clone();
     let s = state.clone();
 
-    return state
-      .conn
-      .add_preupdate_hook(Some(
-        move |action: Action, db: &str, table_name: &str, case: &PreUpdateCase| {
-          assert_eq!(db, "main");
-
-          let action: RecordAction = match action {
-            Action::SQLITE_UPDATE | Action::SQLITE_INSERT | Action::SQLITE_DELETE => action.into(),
-            a => {
-              log::error!("Unknown action: {a:?}");
-              return;
-            }
-          };
+    db.preupdate_hook(Some(
+      move |action: Action, db: &str, table_name: &str, case: &PreUpdateCase| {
+        // Skip attached databases, e.g. captured changes being written.
+        if db != "main" {
+          return;
+        }
 
-          let Some(rowid) = extract_row_id(case) else {
-            log::error!("Failed to extract row id");
-            return;
-          };
-
-          // If there are no subscriptions, do nothing.
-          let record_subs_candidate = s
-            .record_subscriptions
-            .read()
-            .get(table_name)
-            .and_then(|m| m.get(&rowid))
-            .is_some();
-          let table_subs_candidate = s.table_subscriptions.read().get(table_name).is_some();
-          if !record_subs_candidate && !table_subs_candidate {
+        let action: RecordAction = match action {
+          Action::SQLITE_UPDATE | Action::SQLITE_INSERT | Action::SQLITE_DELETE => action.into(),
+          a => {
+            log::error!("Unknown action: {a:?}");
             return;
           }
+        };
 
-          let Some(record_values) = extract_record_values(case) else {
-            log::error!("Failed to extract values");
-            return;
-          };
-
-          let state = ContinuationState {
-            state: s.clone(),
-            table_metadata: s.table_metadata.get(table_name),
-            action,
-            table_name: table_name.to_string(),
-            rowid,
-            record_values,
-          };
-
-          // TODO: Optimization: in cases where there's only table-level access restrictions, we
-          // could avoid the continuation and even dispatch the subscription handling to a
-          // different thread entirely to take more work off the SQLite thread.
-          conn.call_and_forget(move |conn| {
-            Self::hook_continuation(conn, state);
-          });
-        },
-      ))
-      .await;
+        let Some(rowid) = extract_row_id(case) else {
+          log::error!("Failed to extract row id");
+          return;
+        };
+
+        if s.change_capture.is_captured(table_name) {
+          s.change_capture
+            .capture(s.table_metadata.get(table_name), action, rowid, case);
+        }
+
+        // If there are no subscriptions, do nothing.
+        let record_subs_candidate = s
+          .record_subscriptions
+          .read()
+          .get(table_name)
+          .and_then(|m| m.get(&rowid))
+          .is_some();
+        let table_subs_candidate = s.table_subscriptions.read().get(table_name).is_some();
+        let change_log_candidate = s.change_logs.read().contains_key(table_name);
+        if !record_subs_candidate && !table_subs_candidate && !change_log_candidate {
+          return;
+        }
+
+        let Some(record_values) = extract_record_values(case) else {
+          log::error!("Failed to extract values");
+          return;
+        };
+        // Prior values let filtered table subscriptions tell when records enter or leave.
+        let old_record_values = match action {
+          RecordAction::Update => extract_old_record_values(case),
+          _ => None,
+        };
+
+        let state = ContinuationState {
+          state: s.clone(),
+          table_metadata: s.table_metadata.get(table_name),
+          action,
+          table_name: table_name.to_string(),
+          rowid,
+          record_values,
+          old_record_values,
+        };
+
+        // TODO: Optimization: in cases where there's only table-level access restrictions, we
+        // could avoid the continuation and even dispatch the subscription handling to a
+        // different thread entirely to take more work off the SQLite thread.
+        conn.call_and_forget(move |conn| {
+          Self::hook_continuation(conn, state);
+        });
+      },
+    ));
+  }
+
+  /// Registers a new subscription and builds the replay of changes missed since `last_event_id`
+  /// for resuming subscribers.
+  ///
+  /// Runs on the SQLite executor, thus no changes can slip in between the replay and
+  /// registration. Subscriptions ending during replay, e.g. the record was deleted, aren't
+  /// registered.
+  fn register_subscription(
+    state: &Arc<ManagerState>,
+    conn: &rusqlite::Connection,
+    table_name: &str,
+    row_id: Option<i64>,
+    subscription: Subscription,
+    last_event_id: Option<u64>,
+  ) -> VecDeque<SubscriptionEvent> {
+    let s = state;
+    let hook_installed = !s.record_subscriptions.read().is_empty()
+      || !s.table_subscriptions.read().is_empty()
+      || !s.change_logs.read().is_empty()
+      || s.change_capture.enabled();
+
+    let mut replay = VecDeque::<SubscriptionEvent>::new();
+    let mut ended = false;
+    {
+      let mut change_logs = s.change_logs.write();
+      let change_log = change_logs
+        .entry(table_name.to_string())
+        .or_insert_with(|| ChangeLog {
+          // Reserve a sequence number to tell apart ids from before the log existed.
+          retained_after: s.last_seq.fetch_add(1, Ordering::SeqCst) + 1,
+          entries: VecDeque::new(),
+          idle_since: None,
+        });
+      change_log.idle_since = None;
+
+      if let Some(last_event_id) = last_event_id {
+        let last_seq = s.last_seq.load(Ordering::SeqCst);
+        if last_event_id < change_log.retained_after || last_event_id > last_seq {
+          replay.push_back(SubscriptionEvent::Resync { seq: last_seq });
+        } else {
+          for entry in change_log.entries.iter().filter(|entry| {
+            return entry.seq > last_event_id && row_id.is_none_or(|row_id| row_id == entry.rowid);
+          }) {
+            let record = entry.record();
+            let old_record = entry.old_record();
+            let event = build_event(entry.action, &record, |_| true);
+
+            match Self::deliver(
+              s,
+              conn,
+              &subscription,
+              row_id.is_some(),
+              entry.action,
+              &record,
+              old_record.as_deref(),
+              &event,
+            ) {
+              Delivery::Send(event) => {
+                replay.push_back(SubscriptionEvent::Change(SequencedEvent {
+                  seq: entry.seq,
+                  event,
+                }));
+                if row_id.is_some() && entry.action == RecordAction::Delete {
+                  ended = true;
+                  break;
+                }
+              }
+              Delivery::Skip => {}
+              Delivery::Cancel(event) => {
+                if let Some(event) = event {
+                  replay.push_back(SubscriptionEvent::Change(SequencedEvent {
+                    seq: entry.seq,
+                    event,
+                  }));
+                }
+                ended = true;
+                break;
+              }
+            }
+          }
+        }
+      }
+    }
+
+    if !ended {
+      match row_id {
+        Some(row_id) => {
+          s.record_subscriptions
+            .write()
+            .entry(table_name.to_string())
+            .or_default()
+            .entry(row_id)
+            .or_default()
+            .push(subscription);
+        }
+        None => {
+          s.table_subscriptions
+            .write()
+            .entry(table_name.to_string())
+            .or_default()
+            .push(subscription);
+        }
+      }
+    }
+
+    if !hook_installed {
+      Self::install_hook(s, conn);
+    }
+
+    return replay;
   }
 
   async fn add_record_subscription(
@@ -512,6 +1033,7 @@ impl SubscriptionManager {
     api: RecordApi,
     record: trailbase_sqlite::Value,
     user: Option<User>,
+    last_event_id: Option<u64>,
   ) -> Result<AutoCleanupEventStream, RecordError> {
     let table_name = api.table_name().to_string();
     let pk_column = &api.record_pk_column().name;
@@ -531,28 +1053,36 @@ impl SubscriptionManager {
       .get(0)
       .map_err(|err| RecordError::Internal(err.into()))?;
 
-    let (sender, receiver) = async_channel::bounded::<Event>(16);
+    let (sender, receiver) = async_channel::bounded::<SequencedEvent>(16);
 
     let subscription_id = SUBSCRIPTION_COUNTER.fetch_add(1, Ordering::SeqCst);
-    let empty = {
-      let mut lock = self.state.record_subscriptions.write();
-      let empty = lock.is_empty();
-      let m: &mut HashMap<i64, Vec<Subscription>> = lock.entry(table_name.clone()).or_default();
-
-      m.entry(row_id).or_default().push(Subscription {
-        subscription_id,
-        record_api_name: api.api_name().to_string(),
-        // record_id: Some(record),
-        user,
-        sender,
-      });
-
-      empty
+    let subscription = Subscription {
+      subscription_id,
+      record_api_name: api.api_name().to_string(),
+      // record_id: Some(record),
+      user,
+      filter: None,
+      sender,
     };
 
-    if empty {
-      self.add_hook().await.unwrap();
-    }
+    let state = self.state.clone();
+    let replay = {
+      let table_name = table_name.clone();
+      self
+        .state
+        .conn
+        .call(move |conn| {
+          return Ok(Self::register_subscription(
+            &state,
+            conn,
+            &table_name,
+            Some(row_id),
+            subscription,
+            last_event_id,
+          ));
+        })
+        .await?
+    };
 
     return Ok(AutoCleanupEventStream {
       cleanup: CleanupSubscription {
@@ -564,6 +1094,7 @@ impl SubscriptionManager {
           sub_id: subscription_id,
         },
       },
+      replay,
       receiver,
     });
   }
@@ -573,30 +1104,39 @@ impl SubscriptionManager {
     app_state: AppState,
     api: RecordApi,
     user: Option<User>,
+    filter: Option<WhereClause>,
+    last_event_id: Option<u64>,
   ) -> Result<AutoCleanupEventStream, RecordError> {
-    let state = &self.state;
     let table_name = api.table_name().to_string();
 
-    let (sender, receiver) = async_channel::bounded::<Event>(16);
+    let (sender, receiver) = async_channel::bounded::<SequencedEvent>(16);
     let subscription_id = SUBSCRIPTION_COUNTER.fetch_add(1, Ordering::SeqCst);
-    let empty = {
-      let mut lock = state.table_subscriptions.write();
-      let empty = lock.is_empty() && state.record_subscriptions.read().is_empty();
-      let m: &mut Vec<Subscription> = lock.entry(table_name.clone()).or_default();
-
-      m.push(Subscription {
-        subscription_id,
-        record_api_name: api.api_name().to_string(),
-        user,
-        sender,
-      });
-
-      empty
+    let subscription = Subscription {
+      subscription_id,
+      record_api_name: api.api_name().to_string(),
+      user,
+      filter,
+      sender,
     };
 
-    if empty {
-      self.add_hook().await.unwrap();
-    }
+    let state = self.state.clone();
+    let replay = {
+      let table_name = table_name.clone();
+      self
+        .state
+        .conn
+        .call(move |conn| {
+          return Ok(Self::register_subscription(
+            &state,
+            conn,
+            &table_name,
+            None,
+            subscription,
+            last_event_id,
+          ));
+        })
+        .await?
+    };
 
     return Ok(AutoCleanupEventStream {
       cleanup: CleanupSubscription {
@@ -608,17 +1148,26 @@ impl SubscriptionManager {
           sub_id: subscription_id,
         },
       },
+      replay,
       receiver,
     });
   }
 }
 
-pub async fn add_subscription_sse_handler(
-  State(state): State<AppState>,
-  Path((api_name, record)): Path<(String, String)>,
+/// Subscribes to changes of a specific record or, if `record` is "*", to the entire table
+/// optionally narrowed down by filters in `query`.
+///
+/// If `last_event_id` is given, changes missed since are replayed first or, if no longer
+/// available, a resync is requested.
+async fn subscribe(
+  state: &AppState,
+  api_name: &str,
+  record: &str,
+  query: Option<&str>,
   user: Option<User>,
-) -> Result<Sse<impl Stream<Item = SseEvent>>, RecordError> {
-  let Some(api) = state.lookup_record_api(&api_name) else {
+  last_event_id: Option<u64>,
+) -> Result<AutoCleanupEventStream, RecordError> {
+  let Some(api) = state.lookup_record_api(api_name) else {
     return Err(RecordError::ApiNotFound);
   };
 
@@ -626,32 +1175,333 @@ pub async fn add_subscription_sse_handler(
     return Err(RecordError::Forbidden);
   }
 
+  let QueryParseResult {
+    params: filter_params,
+    filter,
+    ..
+  } = parse_query(query).map_err(|_err| {
+    return RecordError::BadRequest("Invalid query");
+  })?;
+  let filter = match (filter_params, filter) {
+    (None, None) => None,
+    (filter_params, filter) => Some(build_api_filter_where_clause(&api, filter_params, filter)?),
+  };
+
   if record == "*" {
     api.check_table_level_access(Permission::Read, user.as_ref())?;
+    api.check_table_level_access(Permission::List, user.as_ref())?;
 
-    let receiver = state
+    return state
       .subscription_manager()
-      .add_table_subscription(state.clone(), api, user)
-      .await?;
+      .add_table_subscription(state.clone(), api, user, filter, last_event_id)
+      .await;
+  }
 
-    return Ok(Sse::new(receiver).keep_alive(KeepAlive::default()));
-  } else {
-    let record_id = api.id_to_sql(&record)?;
-    api
-      .check_record_level_access(Permission::Read, Some(&record_id), None, user.as_ref())
-      .await?;
+  // Filters only apply to table subscriptions.
+  if filter.is_some() {
+    return Err(RecordError::BadRequest("Invalid query"));
+  }
 
-    let receiver = state
-      .subscription_manager()
-      .add_record_subscription(state.clone(), api, record_id, user)
-      .await?;
+  let record_id = api.id_to_sql(record)?;
+  api
+    .check_record_level_access(Permission::Read, Some(&record_id), None, user.as_ref())
+    .await?;
+
+  return state
+    .subscription_manager()
+    .add_record_subscription(state.clone(), api, record_id, user, last_event_id)
+    .await;
+}
+
+pub async fn add_subscription_sse_handler(
+  State(state): State<AppState>,
+  Path((api_name, record)): Path<(String, String)>,
+  RawQuery(raw_url_query): RawQuery,
+  headers: HeaderMap,
+  user: Option<User>,
+) -> Result<Sse<impl Stream<Item = SseEvent>>, RecordError> {
+  // Set by reconnecting EventSources. Unparsable ids lead to a resync.
+  let last_event_id = headers.get(LAST_EVENT_ID_HEADER).map(|id| {
+    id.to_str()
+      .ok()
+      .and_then(|id| id.parse::<u64>().ok())
+      .unwrap_or(0)
+  });
+
+  let receiver = subscribe(
+    &state,
+    &api_name,
+    &record,
+    raw_url_query.as_deref(),
+    user,
+    last_event_id,
+  )
+  .await?;
+
+  return Ok(Sse::new(receiver.map(build_sse_event)).keep_alive(KeepAlive::default()));
+}
+
+fn build_sse_event(event: SubscriptionEvent) -> SseEvent {
+  return match event {
+    SubscriptionEvent::Change(SequencedEvent { seq, event }) => {
+      Event::default().id(seq.to_string()).json_data(event)
+    }
+    // NOTE: EventSources don't dispatch events without data.
+    SubscriptionEvent::Resync { seq } => Ok(
+      Event::default()
+        .event("resync")
+        .id(seq.to_string())
+        .data("{}"),
+    ),
+  };
+}
+
+/// Messages sent by clients over the WebSocket subscription transport.
+///
+/// Clients pick the ids, which are then used to tag events and to unsubscribe.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsClientMessage {
+  Subscribe {
+    id: u64,
+    api: String,
+    /// Record id or "*" for table subscriptions.
+    record: String,
+    /// Optional filter for table subscriptions using the list query syntax, e.g. "room=42".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+  },
+  Unsubscribe {
+    id: u64,
+  },
+}
+
+/// Messages sent by the server over the WebSocket subscription transport.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+  Subscribed {
+    id: u64,
+  },
+  /// Sent on unsubscribe or when the server ends the subscription, e.g. the record was deleted.
+  Unsubscribed {
+    id: u64,
+  },
+  Event {
+    id: u64,
+    event: DbEvent,
+  },
+  Error {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    error: String,
+  },
+}
+
+/// Upper bound of concurrent subscriptions multiplexed over a single WebSocket.
+const MAX_WS_SUBSCRIPTIONS: usize = 64;
+
+/// Multiplexes many record and table subscriptions over a single WebSocket.
+pub async fn subscription_ws_handler(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  tokens: Option<Tokens>,
+  ws: WebSocketUpgrade,
+) -> Result<Response, RecordError> {
+  // WebSockets aren't subject to CORS, while browsers still attach cookies. Thus, unless
+  // checked, any website could stream a logged-in user's records (cross-site WebSocket
+  // hijacking). Non-browser clients don't send an origin.
+  if let Some(origin) = headers.get(ORIGIN) {
+    let allowed = state.dev_mode()
+      || origin.to_str().is_ok_and(|origin| {
+        is_allowed_origin(origin, &state.site_url(), state.cors_allowed_origins())
+      });
+    if !allowed {
+      return Err(RecordError::Forbidden);
+    }
+  }
+
+  // Unlike SSE, sockets outlive the request authorizing them, since they keep adding
+  // subscriptions. They're thus closed once the auth token expires and clients need to reconnect
+  // with a fresh token.
+  let (user, expires_at) = match tokens {
+    Some(tokens) => {
+      let expires_at = ws_token_expiry(tokens.auth_token_claims.exp);
+      let user =
+        User::from_token_claims(tokens.auth_token_claims).map_err(|_| RecordError::Forbidden)?;
+      (Some(user), Some(expires_at))
+    }
+    None => (None, None),
+  };
+
+  return Ok(ws.on_upgrade(move |socket| handle_ws_subscriptions(state, user, expires_at, socket)));
+}
+
+/// Converts a token's `exp` claim, i.e. seconds since epoch, into a monotonic deadline.
+fn ws_token_expiry(exp: i64) -> tokio::time::Instant {
+  let now = chrono::Utc::now().timestamp();
+  let left = Duration::from_secs(exp.saturating_sub(now).max(0) as u64);
+  return tokio::time::Instant::now() + left;
+}
+
+fn is_allowed_origin(origin: &str, site_url: &str, cors_allowed_origins: &[String]) -> bool {
+  let serialize = |url: &str| -> Option<String> {
+    return url::Url::parse(url)
+      .ok()
+      .map(|url| url.origin().ascii_serialization());
+  };
+
+  let Some(origin) = serialize(origin) else {
+    return false;
+  };
+
+  // NOTE: A CORS wildcard doesn't count, since it doesn't allow credentialed requests either.
+  return serialize(site_url).is_some_and(|site| site == origin)
+    || cors_allowed_origins
+      .iter()
+      .any(|allowed| serialize(allowed).is_some_and(|allowed| allowed == origin));
+}
+
+async fn handle_ws_subscriptions(
+  state: AppState,
+  user: Option<User>,
+  expires_at: Option<tokio::time::Instant>,
+  mut socket: WebSocket,
+) {
+  // Events of all subscriptions are funneled into a single channel feeding the socket.
+  let (sender, receiver) = async_channel::bounded::<WsServerMessage>(64);
+  // Forwarding tasks by subscription id. Aborting a task drops its stream and thus cleans up the
+  // subscription.
+  let mut subscriptions = HashMap::<u64, tokio::task::JoinHandle<()>>::new();
+
+  let expiry = tokio::time::sleep_until(expires_at.unwrap_or_else(tokio::time::Instant::now));
+  tokio::pin!(expiry);
+
+  loop {
+    let message = tokio::select! {
+      _ = &mut expiry, if expires_at.is_some() => {
+        let message = WsServerMessage::Error {
+          id: None,
+          error: "Token expired".to_string(),
+        };
+        if let Ok(text) = serde_json::to_string(&message) {
+          let _ = socket.send(Message::Text(text.into())).await;
+        }
+        let _ = socket
+          .send(Message::Close(Some(CloseFrame {
+            code: close_code::POLICY,
+            reason: "Token expired".into(),
+          })))
+          .await;
+        break;
+      }
+      msg = socket.recv() => {
+        let text = match msg {
+          Some(Ok(Message::Text(text))) => text,
+          Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+          Some(Ok(_)) => continue,
+        };
 
-    return Ok(Sse::new(receiver).keep_alive(KeepAlive::default()));
+        match serde_json::from_str::<WsClientMessage>(&text) {
+          Ok(WsClientMessage::Subscribe { id, api, record, filter }) => {
+            // Subscriptions ended by the server don't count towards the limit.
+            subscriptions.retain(|_id, task| !task.is_finished());
+
+            if expires_at.is_some_and(|expires_at| expires_at <= tokio::time::Instant::now()) {
+              // Racing the socket getting closed.
+              WsServerMessage::Error {
+                id: Some(id),
+                error: "Token expired".to_string(),
+              }
+            } else if subscriptions.contains_key(&id) {
+              WsServerMessage::Error {
+                id: Some(id),
+                error: "Duplicate subscription id".to_string(),
+              }
+            } else if subscriptions.len() >= MAX_WS_SUBSCRIPTIONS {
+              WsServerMessage::Error {
+                id: Some(id),
+                error: "Too many subscriptions".to_string(),
+              }
+            } else {
+              match subscribe(&state, &api, &record, filter.as_deref(), user.clone(), None).await {
+                Ok(stream) => {
+                  subscriptions.insert(id, tokio::spawn(forward_ws_events(id, stream, sender.clone())));
+                  WsServerMessage::Subscribed { id }
+                }
+                Err(err) => WsServerMessage::Error {
+                  id: Some(id),
+                  error: ws_error_message(err),
+                },
+              }
+            }
+          }
+          Ok(WsClientMessage::Unsubscribe { id }) => {
+            if let Some(task) = subscriptions.remove(&id) {
+              task.abort();
+            }
+            WsServerMessage::Unsubscribed { id }
+          }
+          Err(_err) => WsServerMessage::Error {
+            id: None,
+            error: "Invalid message".to_string(),
+          },
+        }
+      }
+      msg = receiver.recv() => {
+        let Ok(msg) = msg else {
+          break;
+        };
+        msg
+      }
+    };
+
+    let Ok(text) = serde_json::to_string(&message) else {
+      continue;
+    };
+    if socket.send(Message::Text(text.into())).await.is_err() {
+      break;
+    }
+  }
+
+  for task in subscriptions.values() {
+    task.abort();
   }
 }
 
+async fn forward_ws_events(
+  id: u64,
+  stream: AutoCleanupEventStream,
+  sender: async_channel::Sender<WsServerMessage>,
+) {
+  let mut stream = std::pin::pin!(stream);
+  while let Some(event) = stream.next().await {
+    // There are no resyncs, since WebSocket subscriptions don't resume.
+    let SubscriptionEvent::Change(SequencedEvent { event, .. }) = event else {
+      continue;
+    };
+    if sender
+      .send(WsServerMessage::Event { id, event })
+      .await
+      .is_err()
+    {
+      return;
+    }
+  }
+
+  // The subscription was ended by the server.
+  let _ = sender.send(WsServerMessage::Unsubscribed { id }).await;
+}
+
+fn ws_error_message(err: RecordError) -> String {
+  return match err {
+    RecordError::Internal(_) => "Internal".to_string(),
+    err => err.to_string(),
+  };
+}
+
 #[cfg(test)]
-async fn decode_sse_json_event(event: Event) -> serde_json::Value {
+async fn encode_sse_event(event: Event) -> String {
   use axum::response::IntoResponse;
   use futures_util::stream::StreamExt;
 
@@ -666,7 +1516,12 @@ async fn decode_sse_json_event(event: Event) -> serde_json::Value {
     .await
     .unwrap();
 
-  let str = String::from_utf8_lossy(&bytes);
+  return String::from_utf8_lossy(&bytes).to_string();
+}
+
+#[cfg(test)]
+async fn decode_sse_json_event(event: Event) -> serde_json::Value {
+  let str = encode_sse_event(event).await;
   let x = str
     .strip_prefix("data: ")
     .unwrap()
@@ -691,8 +1546,8 @@ mod tests {
   use crate::records::{add_record_api_config, PermissionFlag};
   use crate::util::uuid_to_b64;
 
-  async fn decode_db_event(event: Event) -> DbEvent {
-    let json = decode_sse_json_event(event).await;
+  async fn decode_db_event(event: DbEvent) -> DbEvent {
+    let json = decode_sse_json_event(Event::default().json_data(event).unwrap()).await;
     return serde_json::from_value(json).unwrap();
   }
 
@@ -703,9 +1558,8 @@ mod tests {
       "b": "text",
     });
     let db_event = DbEvent::Delete(Some(json));
-    let event = Event::default().json_data(db_event.clone()).unwrap();
 
-    assert_eq!(decode_db_event(event).await, db_event);
+    assert_eq!(decode_db_event(db_event.clone()).await, db_event);
   }
 
   async fn setup_world_readable() -> AppState {
@@ -729,7 +1583,12 @@ mod tests {
         name: Some("api_name".to_string()),
         table_name: Some("test".to_string()),
         enable_subscriptions: Some(true),
-        acl_world: [PermissionFlag::Create as i32, PermissionFlag::Read as i32].into(),
+        acl_world: [
+          PermissionFlag::Create as i32,
+          PermissionFlag::Read as i32,
+          PermissionFlag::List as i32,
+        ]
+        .into(),
         ..Default::default()
       },
     )
@@ -767,6 +1626,7 @@ mod tests {
         api,
         trailbase_sqlite::Value::Integer(0),
         None,
+        None,
       )
       .await
       .unwrap();
@@ -794,7 +1654,7 @@ mod tests {
       "id": record_id_raw,
       "text": "bar",
     });
-    match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+    match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
       DbEvent::Update(Some(value)) => {
         assert_eq!(value, expected);
       }
@@ -808,7 +1668,7 @@ mod tests {
       .await
       .unwrap();
 
-    match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+    match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
       DbEvent::Delete(Some(value)) => {
         assert_eq!(value, expected);
       }
@@ -823,6 +1683,240 @@ mod tests {
     assert_eq!(0, manager.num_record_subscriptions());
   }
 
+  #[tokio::test]
+  async fn subscribe_to_table_with_filter_test() {
+    let state = setup_world_readable().await;
+    let conn = state.conn().clone();
+
+    let manager = state.subscription_manager();
+    let api = state.lookup_record_api("api_name").unwrap();
+
+    let QueryParseResult {
+      params: filter_params,
+      filter,
+      ..
+    } = parse_query(Some("text[like]=room42%25")).unwrap();
+    let filter = build_api_filter_where_clause(&api, filter_params, filter).unwrap();
+
+    let stream = manager
+      .add_table_subscription(state.clone(), api, None, Some(filter), None)
+      .await
+      .unwrap();
+
+    conn
+      .execute_batch(
+        r#"
+          INSERT INTO test (id, text) VALUES (1, 'room1: hi'), (2, 'room42: hello');
+          UPDATE test SET text = 'room1: bye' WHERE id = 1;
+          DELETE FROM test WHERE id = 2;
+        "#,
+      )
+      .await
+      .unwrap();
+
+    // Only events for records matching the filter are sent.
+    let expected = serde_json::json!({
+      "id": 2,
+      "text": "room42: hello",
+    });
+    assert_eq!(
+      decode_db_event(stream.receiver.recv().await.unwrap().event).await,
+      DbEvent::Insert(Some(expected.clone()))
+    );
+    assert_eq!(
+      decode_db_event(stream.receiver.recv().await.unwrap().event).await,
+      DbEvent::Delete(Some(expected))
+    );
+    assert!(matches!(
+      stream.receiver.try_recv(),
+      Err(TryRecvError::Empty)
+    ));
+
+    // Updates moving records into the filter are sent as insertions and ones moving records out of
+    // it as deletions of the prior values.
+    conn
+      .execute_batch(
+        r#"
+          UPDATE test SET text = 'room42: moved' WHERE id = 1;
+          UPDATE test SET text = 'room42: edited' WHERE id = 1;
+          UPDATE test SET text = 'room7: moved' WHERE id = 1;
+          UPDATE test SET text = 'room8: moved' WHERE id = 1;
+        "#,
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      decode_db_event(stream.receiver.recv().await.unwrap().event).await,
+      DbEvent::Insert(Some(serde_json::json!({
+        "id": 1,
+        "text": "room42: moved",
+      })))
+    );
+    assert_eq!(
+      decode_db_event(stream.receiver.recv().await.unwrap().event).await,
+      DbEvent::Update(Some(serde_json::json!({
+        "id": 1,
+        "text": "room42: edited",
+      })))
+    );
+    assert_eq!(
+      decode_db_event(stream.receiver.recv().await.unwrap().event).await,
+      DbEvent::Delete(Some(serde_json::json!({
+        "id": 1,
+        "text": "room42: edited",
+      })))
+    );
+    assert!(matches!(
+      stream.receiver.try_recv(),
+      Err(TryRecvError::Empty)
+    ));
+
+    // Filters must reference existing columns and only apply to table subscriptions.
+    for (record, query) in [("*", "missing=1"), ("1", "text=foo")] {
+      let sse_or = add_subscription_sse_handler(
+        State(state.clone()),
+        Path(("api_name".to_string(), record.to_string())),
+        RawQuery(Some(query.to_string())),
+        HeaderMap::new(),
+        None,
+      )
+      .await;
+      assert!(matches!(sse_or, Err(RecordError::BadRequest(_))), "{query}");
+    }
+  }
+
+  #[tokio::test]
+  async fn ws_subscriptions_test() {
+    assert_eq!(
+      serde_json::from_str::<WsClientMessage>(
+        r#"{"type": "subscribe", "id": 1, "api": "api_name", "record": "*", "filter": "id=1"}"#
+      )
+      .unwrap(),
+      WsClientMessage::Subscribe {
+        id: 1,
+        api: "api_name".to_string(),
+        record: "*".to_string(),
+        filter: Some("id=1".to_string()),
+      }
+    );
+    assert_eq!(
+      serde_json::to_value(WsServerMessage::Event {
+        id: 1,
+        event: DbEvent::Delete(None),
+      })
+      .unwrap(),
+      serde_json::json!({"type": "event", "id": 1, "event": {"Delete": null}}),
+    );
+
+    let state = setup_world_readable().await;
+    let conn = state.conn().clone();
+    let manager = state.subscription_manager();
+
+    assert!(matches!(
+      subscribe(&state, "missing", "*", None, None, None).await,
+      Err(RecordError::ApiNotFound)
+    ));
+
+    // Events of multiple subscriptions are tagged with their subscription's id.
+    let (sender, receiver) = async_channel::unbounded::<WsServerMessage>();
+    let table_task = tokio::spawn(forward_ws_events(
+      1,
+      subscribe(&state, "api_name", "*", Some("text=foo"), None, None)
+        .await
+        .unwrap(),
+      sender.clone(),
+    ));
+
+    conn
+      .execute("INSERT INTO test (id, text) VALUES (5, 'foo')", ())
+      .await
+      .unwrap();
+    let record_task = tokio::spawn(forward_ws_events(
+      2,
+      subscribe(&state, "api_name", "5", None, None, None)
+        .await
+        .unwrap(),
+      sender.clone(),
+    ));
+    assert_eq!(1, manager.num_table_subscriptions());
+    assert_eq!(1, manager.num_record_subscriptions());
+
+    let inserted = serde_json::json!({"id": 5, "text": "foo"});
+    assert_eq!(
+      receiver.recv().await.unwrap(),
+      WsServerMessage::Event {
+        id: 1,
+        event: DbEvent::Insert(Some(inserted.clone())),
+      }
+    );
+
+    conn
+      .execute("DELETE FROM test WHERE id = 5", ())
+      .await
+      .unwrap();
+
+    let mut messages = vec![
+      receiver.recv().await.unwrap(),
+      receiver.recv().await.unwrap(),
+      receiver.recv().await.unwrap(),
+    ];
+    messages.sort_by_key(|m| serde_json::to_string(m).unwrap());
+    assert_eq!(
+      messages,
+      vec![
+        WsServerMessage::Event {
+          id: 1,
+          event: DbEvent::Delete(Some(inserted.clone())),
+        },
+        WsServerMessage::Event {
+          id: 2,
+          event: DbEvent::Delete(Some(inserted)),
+        },
+        // Record subscriptions end with the deletion of the record.
+        WsServerMessage::Unsubscribed { id: 2 },
+      ]
+    );
+    record_task.await.unwrap();
+
+    // Aborting the forwarding task, i.e. unsubscribing, cleans up the subscription.
+    table_task.abort();
+    let _ = table_task.await;
+    conn.query("SELECT 1", ()).await.unwrap();
+    assert_eq!(0, manager.num_table_subscriptions());
+    assert_eq!(0, manager.num_record_subscriptions());
+  }
+
+  #[tokio::test]
+  async fn ws_token_expiry_test() {
+    let now = chrono::Utc::now().timestamp();
+    assert!(ws_token_expiry(now - 60) <= tokio::time::Instant::now());
+    assert!(ws_token_expiry(now + 60) > tokio::time::Instant::now() + Duration::from_secs(30));
+  }
+
+  #[test]
+  fn ws_origin_test() {
+    let site = "https://example.com";
+    assert!(is_allowed_origin("https://example.com", site, &[]));
+    assert!(!is_allowed_origin("https://evil.com", site, &[]));
+    assert!(!is_allowed_origin(
+      "https://example.com.evil.com",
+      site,
+      &[]
+    ));
+    assert!(!is_allowed_origin("http://example.com", site, &[]));
+    assert!(!is_allowed_origin("null", site, &[]));
+
+    let cors = ["https://app.example.com".to_string()];
+    assert!(is_allowed_origin("https://app.example.com", site, &cors));
+    assert!(!is_allowed_origin("https://evil.com", site, &cors));
+    assert!(!is_allowed_origin(
+      "https://evil.com",
+      site,
+      &["*".to_string()]
+    ));
+  }
+
   #[tokio::test]
   async fn subscribe_to_table_test() {
     let state = setup_world_readable().await;
@@ -833,7 +1927,7 @@ mod tests {
 
     {
       let stream = manager
-        .add_table_subscription(state.clone(), api, None)
+        .add_table_subscription(state.clone(), api, None, None, None)
         .await
         .unwrap();
 
@@ -856,7 +1950,7 @@ mod tests {
         .await
         .unwrap();
 
-      match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+      match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
         DbEvent::Insert(Some(value)) => {
           let expected = serde_json::json!({
             "id": record_id_raw,
@@ -873,7 +1967,7 @@ mod tests {
         "id": record_id_raw,
         "text": "bar",
       });
-      match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+      match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
         DbEvent::Update(Some(value)) => {
           assert_eq!(value, expected);
         }
@@ -887,7 +1981,7 @@ mod tests {
         .await
         .unwrap();
 
-      match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+      match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
         DbEvent::Delete(Some(value)) => {
           assert_eq!(value, expected);
         }
@@ -903,6 +1997,71 @@ mod tests {
     assert_eq!(0, manager.num_table_subscriptions());
   }
 
+  #[tokio::test]
+  async fn subscribe_to_table_list_access_test() {
+    let state = setup_world_readable().await;
+    let conn = state.conn().clone();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("read_only".to_string()),
+        table_name: Some("test".to_string()),
+        enable_subscriptions: Some(true),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("public_only".to_string()),
+        table_name: Some("test".to_string()),
+        enable_subscriptions: Some(true),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        list_access_rule: Some("_ROW_.text = 'public'".to_string()),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    // Subscribing to a table requires LIST.
+    assert!(matches!(
+      subscribe(&state, "read_only", "*", None, None, None).await,
+      Err(RecordError::Forbidden)
+    ));
+
+    let stream = subscribe(&state, "public_only", "*", None, None, None)
+      .await
+      .unwrap();
+
+    conn
+      .execute(
+        "INSERT INTO test (id, text) VALUES (1, 'private'), (2, 'public')",
+        (),
+      )
+      .await
+      .unwrap();
+
+    // Only records matching the list access rule are delivered.
+    match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
+      DbEvent::Insert(Some(value)) => {
+        assert_eq!(value, serde_json::json!({"id": 2, "text": "public"}));
+      }
+      x => {
+        assert!(false, "Expected insert, got: {x:?}");
+      }
+    };
+    assert_eq!(
+      stream.receiver.try_recv().err().unwrap(),
+      TryRecvError::Empty
+    );
+  }
+
   #[tokio::test]
   async fn subscription_lifecycle_test() {
     let state = setup_world_readable().await;
@@ -926,6 +2085,8 @@ mod tests {
     let sse = add_subscription_sse_handler(
       State(state.clone()),
       Path(("api_name".to_string(), record_id_raw.to_string())),
+      RawQuery(None),
+      HeaderMap::new(),
       None,
     )
     .await;
@@ -966,7 +2127,7 @@ mod tests {
         name: Some("api_name".to_string()),
         table_name: Some("test".to_string()),
         enable_subscriptions: Some(true),
-        acl_authenticated: [PermissionFlag::Read as i32].into(),
+        acl_authenticated: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
         read_access_rule: Some(
           "EXISTS(SELECT 1 FROM test AS m WHERE _USER_.id = _ROW_.user)".to_string(),
         ),
@@ -990,6 +2151,8 @@ mod tests {
     let sse_or = add_subscription_sse_handler(
       State(state.clone()),
       Path(("api_name".to_string(), "*".to_string())),
+      RawQuery(None),
+      HeaderMap::new(),
       None,
     )
     .await;
@@ -1009,6 +2172,8 @@ mod tests {
       let _ = add_subscription_sse_handler(
         State(state.clone()),
         Path(("api_name".to_string(), "*".to_string())),
+        RawQuery(None),
+        HeaderMap::new(),
         User::from_auth_token(&state, &user_x_token.auth_token),
       )
       .await
@@ -1036,6 +2201,8 @@ mod tests {
       let _ = add_subscription_sse_handler(
         State(state.clone()),
         Path(("api_name".to_string(), record_id_raw.to_string())),
+        RawQuery(None),
+        HeaderMap::new(),
         User::from_auth_token(&state, &user_x_token.auth_token),
       )
       .await
@@ -1056,6 +2223,8 @@ mod tests {
       let sse_or = add_subscription_sse_handler(
         State(state.clone()),
         Path(("api_name".to_string(), record_id_raw.to_string())),
+        RawQuery(None),
+        HeaderMap::new(),
         User::from_auth_token(&state, &user_y_token.auth_token),
       )
       .await;
@@ -1097,6 +2266,8 @@ mod tests {
           state.clone(),
           api.clone(),
           User::from_auth_token(&state, &user_x_token.auth_token),
+          None,
+          None,
         )
         .await
         .unwrap();
@@ -1106,6 +2277,8 @@ mod tests {
           state.clone(),
           api.clone(),
           User::from_auth_token(&state, &user_y_token.auth_token),
+          None,
+          None,
         )
         .await
         .unwrap();
@@ -1124,7 +2297,7 @@ mod tests {
         .await
         .unwrap();
 
-      match decode_db_event(user_x_subscription.receiver.recv().await.unwrap()).await {
+      match decode_db_event(user_x_subscription.receiver.recv().await.unwrap().event).await {
         DbEvent::Insert(Some(value)) => {
           let expected = serde_json::json!({
             "id": record_id_raw,
@@ -1193,6 +2366,7 @@ mod tests {
         api,
         trailbase_sqlite::Value::Integer(record_id),
         user_x,
+        None,
       )
       .await
       .unwrap();
@@ -1216,7 +2390,7 @@ mod tests {
       .await
       .unwrap();
 
-    match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+    match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
       DbEvent::Update(Some(value)) => {
         let expected = serde_json::json!({
           "id": record_id,
@@ -1230,7 +2404,7 @@ mod tests {
       }
     }
 
-    match decode_db_event(stream.receiver.recv().await.unwrap()).await {
+    match decode_db_event(stream.receiver.recv().await.unwrap().event).await {
       DbEvent::Error(_msg) => {}
       x => {
         assert!(false, "Expected error, got: {x:?}");
@@ -1243,6 +2417,124 @@ mod tests {
     assert!(stream.receiver.is_closed());
     assert_eq!(0, manager.num_record_subscriptions());
   }
+
+  #[tokio::test]
+  async fn resume_subscription_test() {
+    let state = setup_world_readable().await;
+    let conn = state.conn().clone();
+    let manager = state.subscription_manager();
+    let api = state.lookup_record_api("api_name").unwrap();
+
+    async fn next_change(mut stream: Pin<&mut AutoCleanupEventStream>) -> SequencedEvent {
+      match stream.next().await {
+        Some(SubscriptionEvent::Change(change)) => change,
+        x => panic!("Expected change, got: {x:?}"),
+      }
+    }
+
+    let mut stream = Box::pin(
+      manager
+        .add_table_subscription(state.clone(), api.clone(), None, None, None)
+        .await
+        .unwrap(),
+    );
+
+    conn
+      .execute("INSERT INTO test (id, text) VALUES (1, 'foo')", ())
+      .await
+      .unwrap();
+    let last_seen = next_change(stream.as_mut()).await.seq;
+
+    // Disconnect and miss a few changes.
+    drop(stream);
+    conn.query("SELECT 1", ()).await.unwrap();
+    assert_eq!(0, manager.num_table_subscriptions());
+
+    conn
+      .execute("INSERT INTO test (id, text) VALUES (2, 'bar')", ())
+      .await
+      .unwrap();
+    conn
+      .execute("UPDATE test SET text = 'baz' WHERE id = 1", ())
+      .await
+      .unwrap();
+
+    // Resume and get the missed changes replayed ahead of live ones.
+    let mut stream = Box::pin(
+      manager
+        .add_table_subscription(state.clone(), api.clone(), None, None, Some(last_seen))
+        .await
+        .unwrap(),
+    );
+
+    let insert = next_change(stream.as_mut()).await;
+    assert!(insert.seq > last_seen);
+    assert_eq!(
+      insert.event,
+      DbEvent::Insert(Some(serde_json::json!({"id": 2, "text": "bar"})))
+    );
+    let update = next_change(stream.as_mut()).await;
+    assert!(update.seq > insert.seq);
+    assert_eq!(
+      update.event,
+      DbEvent::Update(Some(serde_json::json!({"id": 1, "text": "baz"})))
+    );
+
+    conn
+      .execute("DELETE FROM test WHERE id = 2", ())
+      .await
+      .unwrap();
+    let delete = next_change(stream.as_mut()).await;
+    assert!(delete.seq > update.seq);
+    assert!(matches!(delete.event, DbEvent::Delete(_)));
+
+    // Record subscriptions only replay changes to their record.
+    {
+      let mut record_stream = Box::pin(
+        manager
+          .add_record_subscription(
+            state.clone(),
+            api.clone(),
+            trailbase_sqlite::Value::Integer(1),
+            None,
+            Some(last_seen),
+          )
+          .await
+          .unwrap(),
+      );
+
+      let change = next_change(record_stream.as_mut()).await;
+      assert_eq!(change, update);
+      assert!(matches!(
+        record_stream.receiver.try_recv(),
+        Err(TryRecvError::Empty)
+      ));
+    }
+
+    // Ids from before the change log's retention or the future require a resync.
+    for last_event_id in [0, u64::MAX] {
+      let mut stream = Box::pin(
+        manager
+          .add_table_subscription(state.clone(), api.clone(), None, None, Some(last_event_id))
+          .await
+          .unwrap(),
+      );
+
+      match stream.next().await {
+        Some(SubscriptionEvent::Resync { seq }) => assert_eq!(seq, delete.seq),
+        x => panic!("Expected resync, got: {x:?}"),
+      };
+    }
+
+    let sse =
+      encode_sse_event(build_sse_event(SubscriptionEvent::Resync { seq: 42 }).unwrap()).await;
+    assert!(sse.contains("event: resync\n"), "{sse}");
+    assert!(sse.contains("id: 42\n"), "{sse}");
+
+    let sse =
+      encode_sse_event(build_sse_event(SubscriptionEvent::Change(update.clone())).unwrap()).await;
+    assert!(sse.contains(&format!("id: {}\n", update.seq)), "{sse}");
+  }
 }
 
 const NO_HOOK: Option<fn(Action, &str, &str, &PreUpdateCase)> = None;
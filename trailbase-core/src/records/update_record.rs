@@ -2,9 +2,11 @@ use axum::extract::{Path, State};
 
 use crate::app_state::AppState;
 use crate::auth::user::User;
+use crate::config::proto::WebhookEvent;
 use crate::extract::Either;
 use crate::records::json_to_sql::{JsonRow, LazyParams, UpdateQueryBuilder};
 use crate::records::{Permission, RecordError};
+use crate::webhooks::WebhookDeliveries;
 
 /// Update existing record.
 #[utoipa::path(
@@ -47,18 +49,29 @@ pub async fn update_record_handler(
     )
     .await?;
 
+  let params = lazy_params
+    .consume()
+    .map_err(|err| RecordError::Internal(err.into()))?;
+  api.check_writable_columns(&params)?;
+
+  let webhooks = WebhookDeliveries::new(&api, WebhookEvent::Update);
+  let notify_webhooks = webhooks.is_some();
+
   UpdateQueryBuilder::run(
     &state,
     table_metadata,
-    lazy_params
-      .consume()
-      .map_err(|err| RecordError::Internal(err.into()))?,
+    params,
     &api.record_pk_column().name,
     record_id,
+    webhooks.map(|w| w.hook(record)),
   )
   .await
   .map_err(|err| RecordError::Internal(err.into()))?;
 
+  if notify_webhooks {
+    state.webhooks().notify();
+  }
+
   return Ok(());
 }
 
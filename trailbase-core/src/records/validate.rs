@@ -1,7 +1,8 @@
 use itertools::Itertools;
+use std::collections::HashSet;
 
 use crate::config::{proto, ConfigError};
-use crate::schema::ColumnOption;
+use crate::schema::{ColumnDataType, ColumnOption};
 use crate::table_metadata::{
   sqlite3_parse_into_statements, TableMetadataCache, TableOrViewMetadata,
 };
@@ -22,6 +23,105 @@ fn validate_record_api_name(name: &str) -> Result<(), ConfigError> {
   Ok(())
 }
 
+/// Validates `expand_children` and `expand_apis`, which reference other Record APIs and can thus
+/// only be checked against the entire list of APIs.
+pub(crate) fn validate_record_api_children(
+  tables: &TableMetadataCache,
+  api_config: &proto::RecordApiConfig,
+  record_apis: &[proto::RecordApiConfig],
+) -> Result<(), ConfigError> {
+  let ierr = |msg: &str| Err(ConfigError::Invalid(msg.to_string()));
+
+  let (Some(name), Some(table_name)) = (&api_config.name, &api_config.table_name) else {
+    return ierr("RecordApi config misses name or table name.");
+  };
+
+  for (column_name, target) in &api_config.expand_apis {
+    if !api_config.expand.contains(column_name) {
+      return ierr(&format!(
+        "{name} sets expand target for non-expanded column: {column_name}"
+      ));
+    }
+
+    let Some(target_table_name) = record_apis
+      .iter()
+      .find(|c| c.name.as_ref() == Some(target))
+      .and_then(|c| c.table_name.as_ref())
+    else {
+      return ierr(&format!(
+        "{name} expands {column_name} to missing API: {target}"
+      ));
+    };
+
+    let metadata: Option<std::sync::Arc<dyn TableOrViewMetadata>> = match tables.get(table_name) {
+      Some(metadata) => Some(metadata as std::sync::Arc<dyn TableOrViewMetadata>),
+      None => tables
+        .get_view(table_name)
+        .map(|metadata| metadata as std::sync::Arc<dyn TableOrViewMetadata>),
+    };
+    let foreign_table_name = metadata
+      .as_ref()
+      .and_then(|metadata| metadata.columns())
+      .and_then(|columns| columns.iter().find(|c| c.name == *column_name))
+      .and_then(|column| {
+        column.options.iter().find_map(|o| match o {
+          ColumnOption::ForeignKey { foreign_table, .. } => Some(foreign_table),
+          _ => None,
+        })
+      });
+    if foreign_table_name != Some(target_table_name) {
+      return ierr(&format!(
+        "{name} expands {column_name} to API of another table: {target}"
+      ));
+    }
+  }
+
+  let Some(table) = tables.get(table_name) else {
+    if api_config.expand_children.is_empty() {
+      return Ok(());
+    }
+    return ierr(&format!(
+      "{name} expands children of non-table: {table_name}"
+    ));
+  };
+
+  for child in &api_config.expand_children {
+    if table.column_by_name(child).is_some() {
+      return ierr(&format!(
+        "{name} child expansion collides with column: {child}"
+      ));
+    }
+
+    let Some(child_table) = record_apis
+      .iter()
+      .find(|c| c.name.as_ref() == Some(child))
+      .and_then(|c| c.table_name.as_ref())
+      .and_then(|child_table_name| tables.get(child_table_name))
+    else {
+      return ierr(&format!("{name} expands missing child API: {child}"));
+    };
+
+    let Some((_column, foreign_key)) = child_table.unique_foreign_key_to(table_name) else {
+      return ierr(&format!(
+        "{name} expands child API '{child}' without exactly one reference to {table_name}"
+      ));
+    };
+
+    let pk_column_name = table.record_pk_column().map(|(_idx, c)| c.name.as_str());
+    match foreign_key.referred_columns.as_slice() {
+      [] => {}
+      [referred_column] if Some(referred_column.as_str()) == pk_column_name => {}
+      _ => {
+        return ierr(&format!(
+          "{name} expands child API '{child}' with non-primary-key reference"
+        ));
+      }
+    }
+  }
+
+  return Ok(());
+}
+
 pub(crate) fn validate_record_api_config(
   tables: &TableMetadataCache,
   api_config: &proto::RecordApiConfig,
@@ -114,12 +214,94 @@ pub(crate) fn validate_record_api_config(
     };
   }
 
+  let pk_column_name = metadata.record_pk_column().map(|(_idx, c)| c.name.as_str());
+  for column_name in api_config
+    .hidden_columns
+    .iter()
+    .chain(&api_config.read_only_columns)
+  {
+    if !columns.iter().any(|c| c.name == *column_name) {
+      return ierr(&format!("{name} restricts missing column: {column_name}"));
+    }
+  }
+  for column_name in &api_config.hidden_columns {
+    if Some(column_name.as_str()) == pk_column_name {
+      return ierr(&format!("{name} hides primary key column: {column_name}"));
+    }
+    if api_config.expand.contains(column_name) {
+      return ierr(&format!("{name} hides expanded column: {column_name}"));
+    }
+  }
+
+  if !api_config.search_columns.is_empty() {
+    if tables.get(table_name).is_none() {
+      return ierr(&format!("{name} searches non-table: {table_name}"));
+    }
+
+    for column_name in &api_config.search_columns {
+      let Some(column) = columns.iter().find(|c| c.name == *column_name) else {
+        return ierr(&format!("{name} searches missing column: {column_name}"));
+      };
+      if column.data_type != ColumnDataType::Text {
+        return ierr(&format!("{name} searches non-text column: {column_name}"));
+      }
+      if column_name.starts_with("_") || api_config.hidden_columns.contains(column_name) {
+        return ierr(&format!("{name} searches hidden column: {column_name}"));
+      }
+    }
+  }
+
+  let mut webhook_ids = HashSet::<&str>::new();
+  for webhook in &api_config.webhooks {
+    let Some(ref url) = webhook.url else {
+      return ierr(&format!("{name} webhook misses url"));
+    };
+    match webhook.id {
+      Some(ref id) if !id.is_empty() => {
+        if !webhook_ids.insert(id) {
+          return ierr(&format!("{name} has conflicting webhook id: {id}"));
+        }
+      }
+      _ => {
+        return ierr(&format!("{name} webhook misses id: {url}"));
+      }
+    };
+    match url::Url::parse(url) {
+      Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+      _ => {
+        return ierr(&format!("{name} webhook has invalid url: {url}"));
+      }
+    };
+    for event in &webhook.events {
+      match proto::WebhookEvent::try_from(*event) {
+        Ok(e) if e != proto::WebhookEvent::Undefined => {}
+        _ => {
+          return ierr(&format!("{name} webhook has invalid event: {event}"));
+        }
+      };
+    }
+  }
+
+  // Listing returns records, thus LIST must only be granted alongside READ.
+  for (entity, acl) in [
+    ("world", &api_config.acl_world),
+    ("authenticated", &api_config.acl_authenticated),
+  ] {
+    let has = |flag: proto::PermissionFlag| acl.contains(&(flag as i32));
+    if has(proto::PermissionFlag::List) && !has(proto::PermissionFlag::Read) {
+      return ierr(&format!(
+        "{name} grants LIST without READ to {entity} users"
+      ));
+    }
+  }
+
   let rules = [
     &api_config.create_access_rule,
     &api_config.read_access_rule,
     &api_config.update_access_rule,
     &api_config.delete_access_rule,
     &api_config.schema_access_rule,
+    &api_config.list_access_rule,
   ];
   for rule in rules.into_iter().flatten() {
     let _stmt = sqlite3_parse_into_statements(&format!("SELECT ({rule})"))
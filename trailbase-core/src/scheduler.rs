@@ -4,7 +4,11 @@ use std::future::Future;
 use trailbase_sqlite::params;
 
 use crate::app_state::AppState;
-use crate::constants::{DEFAULT_REFRESH_TOKEN_TTL, LOGS_RETENTION_DEFAULT, SESSION_TABLE};
+use crate::constants::{
+  CHANGES_TABLE, CHANGE_CAPTURE_RETENTION_DEFAULT, DEFAULT_REFRESH_TOKEN_TTL,
+  LOGS_RETENTION_DEFAULT, SESSION_TABLE,
+};
+use crate::webhooks::{prune_webhook_deliveries, run_delivery_worker};
 
 #[derive(Default)]
 pub struct AbortOnDrop {
@@ -45,6 +49,34 @@ pub(super) fn start_periodic_tasks(app_state: &AppState) -> AbortOnDrop {
     info!("alive");
   });
 
+  // Webhook delivery worker.
+  let handle = tokio::spawn(run_delivery_worker(app_state.clone()));
+  tasks.handles.push(handle.abort_handle());
+
+  // Webhook delivery log cleaner.
+  let state = app_state.clone();
+  tasks.add_periodic_task(Duration::hours(2), move || {
+    let state = state.clone();
+
+    tokio::spawn(async move {
+      match prune_webhook_deliveries(&state).await {
+        Ok(count) => info!("Successfully pruned {count} webhook deliveries"),
+        Err(err) => warn!("Failed to clean up webhook deliveries: {err}"),
+      };
+    })
+  });
+
+  // Rate limiter cleaner.
+  let rate_limiter = app_state.rate_limiter().clone();
+  tasks.add_periodic_task(Duration::seconds(60), move || {
+    let rate_limiter = rate_limiter.clone();
+
+    async move {
+      let remaining = rate_limiter.prune(std::time::Instant::now());
+      debug!("Pruned rate limit buckets, {remaining} remaining");
+    }
+  });
+
   // Backup job.
   let conn = app_state.conn().clone();
   let backup_file = app_state.data_dir().backup_path().join("backup.db");
@@ -98,6 +130,35 @@ pub(super) fn start_periodic_tasks(app_state: &AppState) -> AbortOnDrop {
     });
   }
 
+  // Change data capture cleaner. The retention is looked up on every run to pick up config
+  // changes.
+  let state = app_state.clone();
+  tasks.add_periodic_task(Duration::hours(2), move || {
+    let state = state.clone();
+
+    tokio::spawn(async move {
+      let retention = state
+        .access_config(|c| c.server.change_capture_retention_sec)
+        .map_or(CHANGE_CAPTURE_RETENTION_DEFAULT, Duration::seconds);
+      if retention.is_zero() {
+        return;
+      }
+
+      let timestamp = (Utc::now() - retention).timestamp();
+      match state
+        .changes_conn()
+        .execute(
+          &format!("DELETE FROM {CHANGES_TABLE} WHERE created < $1"),
+          params!(timestamp),
+        )
+        .await
+      {
+        Ok(count) => info!("Successfully pruned {count} captured changes"),
+        Err(err) => warn!("Failed to clean up captured changes: {err}"),
+      };
+    })
+  });
+
   // Refresh token cleaner.
   let state = app_state.clone();
   tasks.add_periodic_task(Duration::hours(12), move || {
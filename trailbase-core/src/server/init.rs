@@ -6,8 +6,10 @@ use crate::app_state::{build_objectstore, AppState, AppStateArgs};
 use crate::auth::jwt::{JwtHelper, JwtHelperError};
 use crate::config::load_or_init_config_textproto;
 use crate::constants::USER_TABLE;
-use crate::migrations::{apply_logs_migrations, apply_main_migrations};
+use crate::migrations::{apply_changes_migrations, apply_logs_migrations, apply_main_migrations};
 use crate::rand::generate_random_string;
+use crate::records::change_capture::attach_changes_db;
+use crate::records::sync_search_indexes;
 use crate::server::DataDir;
 use crate::table_metadata::TableMetadataCache;
 
@@ -39,11 +41,14 @@ pub enum InitError {
   ScriptError(String),
   #[error("ObjectStore error: {0}")]
   ObjectStore(#[from] object_store::Error),
+  #[error("Transaction error: {0}")]
+  Transaction(#[from] crate::transaction::TransactionError),
 }
 
 #[derive(Default)]
 pub struct InitArgs {
   pub dev: bool,
+  pub cors_allowed_origins: Vec<String>,
   pub js_runtime_threads: Option<usize>,
 }
 
@@ -62,11 +67,18 @@ pub async fn init_app_state(
     trailbase_sqlite::Connection::from_conn(conn)?
   };
 
+  let changes_conn = {
+    let mut conn = trailbase_sqlite::connect_sqlite(data_dir.changes_db_path().into(), None)?;
+    apply_changes_migrations(&mut conn)?;
+    trailbase_sqlite::Connection::from_conn(conn)?
+  };
+
   // Open or init the main db. Note that we derive whether a new DB was initialized based on
   // whether the V1 migration had to be applied. Should be fairly robust.
   let (conn, new_db) = {
     let mut conn = trailbase_sqlite::connect_sqlite(Some(data_dir.main_db_path()), None)?;
     let new_db = apply_main_migrations(&mut conn, Some(data_dir.migrations_path()))?;
+    attach_changes_db(&conn, &data_dir.changes_db_path())?;
 
     (trailbase_sqlite::Connection::from_conn(conn)?, new_db)
   };
@@ -76,6 +88,11 @@ pub async fn init_app_state(
   // Read config or write default one.
   let config = load_or_init_config_textproto(&data_dir, &table_metadata).await?;
 
+  // The config may have been edited by hand, thus bring search indexes in sync.
+  if sync_search_indexes(&conn, data_dir.migrations_path(), &config.record_apis).await? {
+    table_metadata.invalidate_all().await?;
+  }
+
   debug!("Initializing JSON schemas from config");
   trailbase_sqlite::schema::set_user_schemas(
     config
@@ -123,10 +140,12 @@ pub async fn init_app_state(
     data_dir: data_dir.clone(),
     public_dir,
     dev: args.dev,
+    cors_allowed_origins: args.cors_allowed_origins,
     table_metadata,
     config,
     conn,
     logs_conn,
+    changes_conn,
     jwt,
     object_store,
     js_runtime_threads: args.js_runtime_threads,
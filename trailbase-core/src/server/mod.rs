@@ -8,7 +8,9 @@ use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::{RequestExt, Router};
+use axum_client_ip::SecureClientIpSource;
 use rust_embed::RustEmbed;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::signal;
@@ -29,6 +31,7 @@ use crate::auth::{self, AuthError, User};
 use crate::constants::{ADMIN_API_PATH, HEADER_CSRF_TOKEN};
 use crate::data_dir::DataDir;
 use crate::logging;
+use crate::rate_limit::{self, RouteGroup};
 use crate::records;
 use crate::scheduler;
 
@@ -64,6 +67,10 @@ pub struct ServerOptions {
   /// Limit the set of allowed origins the HTTP server will answer to.
   pub cors_allowed_origins: Vec<String>,
 
+  /// Trusted source of client IP addresses, e.g. a header set by a reverse proxy. Defaults to the
+  /// connection's peer address.
+  pub client_ip_source: Option<SecureClientIpSource>,
+
   /// Number of V8 worker threads. If set to None, default of num available cores will be used.
   pub js_runtime_threads: Option<usize>,
 
@@ -121,6 +128,7 @@ impl Server {
       opts.public_dir.clone(),
       InitArgs {
         dev: opts.dev,
+        cors_allowed_origins: opts.cors_allowed_origins.clone(),
         js_runtime_threads: opts.js_runtime_threads,
       },
     )
@@ -239,9 +247,14 @@ impl Server {
           acceptor: TlsAcceptor::from(Arc::new(server_config)),
         };
 
-        if let Err(err) = serve::serve(listener, router.clone())
-          .with_graceful_shutdown(shutdown_signal())
-          .await
+        if let Err(err) = serve::serve(
+          listener,
+          router
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal())
+        .await
         {
           log::error!("Failed to start server: {err}");
           std::process::exit(1);
@@ -256,9 +269,14 @@ impl Server {
           }
         };
 
-        if let Err(err) = serve::serve(listener, router.clone())
-          .with_graceful_shutdown(shutdown_signal())
-          .await
+        if let Err(err) = serve::serve(
+          listener,
+          router
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal())
+        .await
         {
           log::error!("Failed to start server: {err}");
           std::process::exit(1);
@@ -296,7 +314,12 @@ impl Server {
     }
 
     let router = Router::new()
-      .merge(auth::admin_auth_router())
+      .merge(
+        auth::admin_auth_router().layer(middleware::from_fn_with_state(
+          (state.clone(), RouteGroup::Auth),
+          rate_limit::rate_limit,
+        )),
+      )
       .merge(Self::build_admin_router(state));
 
     return Some((
@@ -312,8 +335,14 @@ impl Server {
   ) -> (String, Router<()>) {
     let mut router = Router::new()
       // Public, stable and versioned APIs.
-      .merge(records::router())
-      .merge(auth::router())
+      .merge(records::router().layer(middleware::from_fn_with_state(
+        (state.clone(), RouteGroup::Records),
+        rate_limit::rate_limit,
+      )))
+      .merge(auth::router().layer(middleware::from_fn_with_state(
+        (state.clone(), RouteGroup::Auth),
+        rate_limit::rate_limit,
+      )))
       .route("/api/healthcheck", get(healthcheck_handler));
 
     if !has_indepenedent_admin_router(opts) {
@@ -325,7 +354,10 @@ impl Server {
     }
 
     if let Some(custom_router) = custom_router {
-      router = router.merge(custom_router);
+      router = router.merge(custom_router.layer(middleware::from_fn_with_state(
+        (state.clone(), RouteGroup::CustomRoutes),
+        rate_limit::rate_limit,
+      )));
     }
 
     if let Some(public_dir) = &opts.public_dir {
@@ -352,7 +384,13 @@ impl Server {
     opts: &ServerOptions,
     router: Router<AppState>,
   ) -> Router<()> {
+    let client_ip_source = opts
+      .client_ip_source
+      .clone()
+      .unwrap_or(SecureClientIpSource::ConnectInfo);
+
     return router
+      .layer(client_ip_source.into_extension())
       .layer(CookieManagerLayer::new())
       .layer(build_cors(opts))
       .layer(
@@ -312,6 +312,12 @@ impl axum::extract::connect_info::Connected<IncomingStream<'_, TcpListener>> for
   }
 }
 
+impl axum::extract::connect_info::Connected<IncomingStream<'_, TlsListener>> for SocketAddr {
+  fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+    *stream.remote_addr()
+  }
+}
+
 /// Serve future with graceful shutdown enabled.
 #[must_use = "futures must be awaited or polled"]
 pub struct WithGracefulShutdown<L, M, S, F> {
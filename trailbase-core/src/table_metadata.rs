@@ -79,7 +79,6 @@ pub struct TableMetadata {
   pub file_uploads_columns: Vec<usize>,
 
   // Only non-composite keys.
-  #[allow(unused)]
   foreign_ids: Vec<(usize, ForeignKey)>,
   // TODO: Add triggers once sqlparser supports a sqlite "CREATE TRIGGER" statements.
 }
@@ -163,6 +162,22 @@ impl TableMetadata {
     &self.schema.name
   }
 
+  /// Returns the column referencing the given table, if there's exactly one such foreign key.
+  pub(crate) fn unique_foreign_key_to(
+    &self,
+    foreign_table: &str,
+  ) -> Option<(&Column, &ForeignKey)> {
+    let mut foreign_keys = self
+      .foreign_ids
+      .iter()
+      .filter(|(_, fk)| fk.foreign_table == foreign_table);
+    let (index, foreign_key) = foreign_keys.next()?;
+    if foreign_keys.next().is_some() {
+      return None;
+    }
+    return Some((&self.schema.columns[*index], foreign_key));
+  }
+
   #[inline]
   pub fn column_index_by_name(&self, key: &str) -> Option<usize> {
     self.name_to_index.get(key).copied()
@@ -770,7 +785,7 @@ pub fn build_json_schema(
   metadata: &(dyn TableOrViewMetadata + Send + Sync),
   mode: JsonSchemaMode,
 ) -> Result<(Validator, serde_json::Value), JsonSchemaError> {
-  return build_json_schema_recursive(table_or_view_name, metadata, mode, None);
+  return build_json_schema_recursive(table_or_view_name, metadata, mode, None, &[]);
 }
 
 pub(crate) struct Expand<'a> {
@@ -778,6 +793,9 @@ pub(crate) struct Expand<'a> {
   pub(crate) foreign_key_columns: Vec<&'a str>,
 }
 
+/// Columns listed in `omitted_columns` are left out of the schema entirely, e.g. columns hidden by
+/// a record API.
+///
 /// NOTE: Foreign keys can only reference tables not view, so the inline schemas don't need to be
 /// able to reference views.
 pub(crate) fn build_json_schema_recursive(
@@ -785,6 +803,7 @@ pub(crate) fn build_json_schema_recursive(
   metadata: &(dyn TableOrViewMetadata + Send + Sync),
   mode: JsonSchemaMode,
   expand: Option<Expand<'_>>,
+  omitted_columns: &[&str],
 ) -> Result<(Validator, serde_json::Value), JsonSchemaError> {
   let mut properties = serde_json::Map::new();
   let mut defs = serde_json::Map::new();
@@ -797,6 +816,10 @@ pub(crate) fn build_json_schema_recursive(
   };
 
   for col in columns {
+    if omitted_columns.contains(&col.name.as_str()) {
+      continue;
+    }
+
     let mut found_def = false;
     let mut not_null = false;
     let mut default = false;
@@ -1138,7 +1161,12 @@ mod tests {
       RecordApiConfig {
         name: Some("test_table_api".to_string()),
         table_name: Some(table_name.to_string()),
-        acl_world: [PermissionFlag::Create as i32, PermissionFlag::Read as i32].into(),
+        acl_world: [
+          PermissionFlag::Create as i32,
+          PermissionFlag::Read as i32,
+          PermissionFlag::List as i32,
+        ]
+        .into(),
         expand: vec!["fk".to_string()],
         ..Default::default()
       },
@@ -1156,6 +1184,7 @@ mod tests {
         table_metadata: state.table_metadata(),
         foreign_key_columns: vec!["foreign_table"],
       }),
+      &[],
     )
     .unwrap();
 
@@ -1209,6 +1238,7 @@ mod tests {
         Path(("test_table_api".to_string(), "1".to_string())),
         Query(ReadRecordQuery {
           expand: Some("UNKNOWN".to_string()),
+          ..Default::default()
         }),
         None,
       )
@@ -1276,6 +1306,7 @@ mod tests {
         Path(("test_table_api".to_string(), "1".to_string())),
         Query(ReadRecordQuery {
           expand: Some("fk".to_string()),
+          ..Default::default()
         }),
         None,
       )
@@ -1317,6 +1348,533 @@ mod tests {
     }
   }
 
+  #[tokio::test]
+  async fn test_expanded_foreign_key_fields() {
+    let state = test_state(None).await.unwrap();
+    let conn = state.conn();
+
+    conn
+      .execute_batch(
+        r#"
+          CREATE TABLE foreign_table (
+            id         INTEGER PRIMARY KEY,
+            name       TEXT NOT NULL,
+            body       TEXT NOT NULL
+          ) STRICT;
+
+          CREATE TABLE test_table (
+            id         INTEGER PRIMARY KEY,
+            title      TEXT NOT NULL,
+            body       TEXT NOT NULL,
+            fk         INTEGER REFERENCES foreign_table(id)
+          ) STRICT;
+
+          INSERT INTO foreign_table (id, name, body) VALUES (1, 'foreign', 'large foreign body');
+          INSERT INTO test_table (id, title, body, fk) VALUES
+            (1, 'first', 'large body', 1),
+            (2, 'second', 'large body', 1);
+        "#,
+      )
+      .await
+      .unwrap();
+
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("test_table_api".to_string()),
+        table_name: Some("test_table".to_string()),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        expand: vec!["fk".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+    let read = |expand: Option<&str>, fields: &str| {
+      read_record_handler(
+        State(state.clone()),
+        Path(("test_table_api".to_string(), "1".to_string())),
+        Query(ReadRecordQuery {
+          expand: expand.map(|e| e.to_string()),
+          fields: Some(fields.to_string()),
+        }),
+        None,
+      )
+    };
+
+    let list = |query: &str| {
+      list_records_handler(
+        State(state.clone()),
+        Path("test_table_api".to_string()),
+        RawQuery(Some(query.to_string())),
+        None,
+      )
+    };
+
+    {
+      let Json(value) = read(None, "title").await.unwrap();
+      assert_eq!(json!({ "title": "first" }), value);
+
+      let Json(list_response) = list("fields=title").await.unwrap();
+      assert_eq!(
+        vec![json!({ "title": "second" }), json!({ "title": "first" })],
+        list_response.records
+      );
+    }
+
+    {
+      let expected = json!({
+        "title": "first",
+        "fk": {
+          "id": 1,
+          "data": {
+            "name": "foreign",
+          },
+        },
+      });
+
+      let Json(value) = read(Some("fk"), "title,fk.name").await.unwrap();
+      assert_eq!(expected, value);
+
+      let Json(list_response) = list("fields=title,fk.name&expand=fk&order=id&count=1")
+        .await
+        .unwrap();
+      assert_eq!(Some(2), list_response.total_count);
+      assert_eq!(expected, list_response.records[0]);
+    }
+
+    {
+      // Paginate using cursors on columns that aren't part of the projection.
+      let Json(first) = list("fields=title&limit=1").await.unwrap();
+      assert_eq!(vec![json!({ "title": "second" })], first.records);
+
+      let Json(second) = list(&format!(
+        "fields=title&limit=1&cursor={}",
+        first.cursor.unwrap()
+      ))
+      .await
+      .unwrap();
+      assert_eq!(vec![json!({ "title": "first" })], second.records);
+    }
+
+    // Invalid fields.
+    assert!(read(None, "missing").await.is_err());
+    assert!(read(None, "_rowid_").await.is_err());
+    assert!(read(None, "fk.name").await.is_err());
+    assert!(read(Some("fk"), "fk.missing").await.is_err());
+    assert!(list("fields=missing").await.is_err());
+    assert!(list("fields=fk.name").await.is_err());
+    assert!(list("fields=fk.missing&expand=fk").await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_nested_expansion() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE organization (
+            id         INTEGER PRIMARY KEY,
+            name       TEXT NOT NULL,
+            secret     TEXT NOT NULL DEFAULT ''
+          ) STRICT;
+
+          CREATE TABLE author (
+            id            INTEGER PRIMARY KEY,
+            name          TEXT NOT NULL,
+            organization  INTEGER REFERENCES organization(id)
+          ) STRICT;
+
+          CREATE TABLE post (
+            id         INTEGER PRIMARY KEY,
+            title      TEXT NOT NULL,
+            author     INTEGER REFERENCES author(id)
+          ) STRICT;
+
+          INSERT INTO organization (id, name) VALUES (1, 'public'), (2, 'private');
+          INSERT INTO author (id, name, organization) VALUES (1, 'alice', 1), (2, 'bob', 2);
+          INSERT INTO post (id, title, author) VALUES (1, 'first', 1), (2, 'second', 2);
+        "#,
+      )
+      .await
+      .unwrap();
+
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    for config in [
+      RecordApiConfig {
+        name: Some("post_api".to_string()),
+        table_name: Some("post".to_string()),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        expand: vec!["author".to_string()],
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("shallow_post_api".to_string()),
+        table_name: Some("post".to_string()),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        expand: vec!["author".to_string()],
+        max_expand_depth: Some(1),
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("author_api".to_string()),
+        table_name: Some("author".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        expand: vec!["organization".to_string()],
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("organization_api".to_string()),
+        table_name: Some("organization".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        read_access_rule: Some("_ROW_.name = 'public'".to_string()),
+        hidden_columns: vec!["secret".to_string()],
+        ..Default::default()
+      },
+    ] {
+      add_record_api_config(&state, config).await.unwrap();
+    }
+
+    let read = |api: &str, id: &str, expand: &str, fields: Option<&str>| {
+      read_record_handler(
+        State(state.clone()),
+        Path((api.to_string(), id.to_string())),
+        Query(ReadRecordQuery {
+          expand: Some(expand.to_string()),
+          fields: fields.map(|f| f.to_string()),
+        }),
+        None,
+      )
+    };
+
+    let expected = |id: i64| {
+      let (title, name, organization) = match id {
+        1 => (
+          "first",
+          "alice",
+          json!({
+            "id": 1,
+            "data": { "id": 1, "name": "public" },
+          }),
+        ),
+        // Records of the private organization cannot be read, thus aren't expanded.
+        _ => ("second", "bob", json!({ "id": 2 })),
+      };
+
+      return json!({
+        "id": id,
+        "title": title,
+        "author": {
+          "id": id,
+          "data": {
+            "id": id,
+            "name": name,
+            "organization": organization,
+          },
+        },
+      });
+    };
+
+    let Json(value) = read("post_api", "1", "author.organization", None)
+      .await
+      .unwrap();
+    assert_eq!(expected(1), value);
+
+    let Json(value) = read("post_api", "2", "author,author.organization", None)
+      .await
+      .unwrap();
+    assert_eq!(expected(2), value);
+
+    let Json(list_response) = list_records_handler(
+      State(state.clone()),
+      Path("post_api".to_string()),
+      RawQuery(Some("expand=author.organization&order=id".to_string())),
+      None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(vec![expected(1), expected(2)], list_response.records);
+
+    // Projections along the expansion path.
+    let Json(value) = read(
+      "post_api",
+      "1",
+      "author.organization",
+      Some("title,author.name,author.organization.name"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+      json!({
+        "title": "first",
+        "author": {
+          "id": 1,
+          "data": {
+            "name": "alice",
+            "organization": {
+              "id": 1,
+              "data": { "name": "public" },
+            },
+          },
+        },
+      }),
+      value
+    );
+
+    // Hidden columns of the expanded record's API cannot be projected.
+    assert!(read(
+      "post_api",
+      "1",
+      "author.organization",
+      Some("author.organization.secret")
+    )
+    .await
+    .is_err());
+
+    // Exceeding the max depth.
+    assert!(read("shallow_post_api", "1", "author.organization", None)
+      .await
+      .is_err());
+
+    // Hops not configured for expansion.
+    assert!(read("post_api", "1", "author.name", None).await.is_err());
+    assert!(read("post_api", "1", "author.organization.id", None)
+      .await
+      .is_err());
+
+    // Other APIs exposing the same tables don't affect expansions, which default to the first API
+    // in config order.
+    for config in [
+      RecordApiConfig {
+        name: Some("closed_organization_api".to_string()),
+        table_name: Some("organization".to_string()),
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("flat_author_api".to_string()),
+        table_name: Some("author".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        ..Default::default()
+      },
+    ] {
+      add_record_api_config(&state, config).await.unwrap();
+    }
+
+    let Json(value) = read("post_api", "1", "author.organization", None)
+      .await
+      .unwrap();
+    assert_eq!(expected(1), value);
+
+    // Explicit targets apply their access rules and `expand` instead.
+    for config in [
+      RecordApiConfig {
+        name: Some("restricted_author_api".to_string()),
+        table_name: Some("author".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        expand: vec!["organization".to_string()],
+        expand_apis: [(
+          "organization".to_string(),
+          "closed_organization_api".to_string(),
+        )]
+        .into(),
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("restricted_post_api".to_string()),
+        table_name: Some("post".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        expand: vec!["author".to_string()],
+        expand_apis: [("author".to_string(), "restricted_author_api".to_string())].into(),
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("flat_post_api".to_string()),
+        table_name: Some("post".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        expand: vec!["author".to_string()],
+        expand_apis: [("author".to_string(), "flat_author_api".to_string())].into(),
+        ..Default::default()
+      },
+    ] {
+      add_record_api_config(&state, config).await.unwrap();
+    }
+
+    let Json(value) = read("restricted_post_api", "1", "author.organization", None)
+      .await
+      .unwrap();
+    assert_eq!(json!({ "id": 1 }), value["author"]["data"]["organization"]);
+
+    assert!(read("flat_post_api", "1", "author", None).await.is_ok());
+    assert!(read("flat_post_api", "1", "author.organization", None)
+      .await
+      .is_err());
+
+    // Targets must be APIs exposing the foreign table.
+    for (column, target) in [
+      ("author", "organization_api"),
+      ("author", "missing_api"),
+      ("title", "author_api"),
+    ] {
+      assert!(
+        add_record_api_config(
+          &state,
+          RecordApiConfig {
+            name: Some("invalid_post_api".to_string()),
+            table_name: Some("post".to_string()),
+            expand: vec!["author".to_string()],
+            expand_apis: [(column.to_string(), target.to_string())].into(),
+            ..Default::default()
+          },
+        )
+        .await
+        .is_err(),
+        "{column}: {target}"
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn test_child_expansion() {
+    let state = test_state(None).await.unwrap();
+
+    state
+      .conn()
+      .execute_batch(
+        r#"
+          CREATE TABLE post (
+            id         INTEGER PRIMARY KEY,
+            title      TEXT NOT NULL
+          ) STRICT;
+
+          CREATE TABLE comment (
+            id         INTEGER PRIMARY KEY,
+            post       INTEGER NOT NULL REFERENCES post(id),
+            body       TEXT NOT NULL,
+            created    INTEGER NOT NULL,
+            secret     TEXT NOT NULL DEFAULT ''
+          ) STRICT;
+
+          INSERT INTO post (id, title) VALUES (1, 'first'), (2, 'second');
+          INSERT INTO comment (id, post, body, created) VALUES
+            (1, 1, 'a', 3),
+            (2, 1, 'b', 1),
+            (3, 1, 'hidden', 2),
+            (4, 2, 'c', 0);
+        "#,
+      )
+      .await
+      .unwrap();
+
+    state.table_metadata().invalidate_all().await.unwrap();
+
+    for config in [
+      RecordApiConfig {
+        name: Some("comments".to_string()),
+        table_name: Some("comment".to_string()),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        read_access_rule: Some("_ROW_.body != 'hidden'".to_string()),
+        hidden_columns: vec!["secret".to_string()],
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("unlisted_comments".to_string()),
+        table_name: Some("comment".to_string()),
+        acl_world: [PermissionFlag::Read as i32].into(),
+        ..Default::default()
+      },
+      RecordApiConfig {
+        name: Some("post_api".to_string()),
+        table_name: Some("post".to_string()),
+        acl_world: [PermissionFlag::Read as i32, PermissionFlag::List as i32].into(),
+        expand_children: vec!["comments".to_string(), "unlisted_comments".to_string()],
+        ..Default::default()
+      },
+    ] {
+      add_record_api_config(&state, config).await.unwrap();
+    }
+
+    // Children need to reference the parent.
+    assert!(add_record_api_config(
+      &state,
+      RecordApiConfig {
+        name: Some("invalid_api".to_string()),
+        table_name: Some("comment".to_string()),
+        expand_children: vec!["post_api".to_string()],
+        ..Default::default()
+      },
+    )
+    .await
+    .is_err());
+
+    let read = |expand: &str| {
+      read_record_handler(
+        State(state.clone()),
+        Path(("post_api".to_string(), "1".to_string())),
+        Query(ReadRecordQuery {
+          expand: Some(expand.to_string()),
+          ..Default::default()
+        }),
+        None,
+      )
+    };
+
+    let comment = |id: i64, body: &str, created: i64| {
+      json!({
+        "id": id,
+        "post": 1,
+        "body": body,
+        "created": created,
+      })
+    };
+
+    let Json(value) = read("comments(limit:5,order:-created)").await.unwrap();
+    assert_eq!(
+      json!({
+        "id": 1,
+        "title": "first",
+        "comments": [comment(1, "a", 3), comment(2, "b", 1)],
+      }),
+      value
+    );
+
+    let Json(value) = read("comments(limit:1,order:created)").await.unwrap();
+    assert_eq!(json!([comment(2, "b", 1)]), value["comments"]);
+
+    // Ordered by primary key by default.
+    let Json(value) = read("comments").await.unwrap();
+    assert_eq!(
+      json!([comment(2, "b", 1), comment(1, "a", 3)]),
+      value["comments"]
+    );
+
+    // Children of APIs that cannot be listed.
+    assert!(matches!(
+      read("unlisted_comments").await,
+      Err(RecordError::Forbidden)
+    ));
+
+    // Invalid options.
+    assert!(read("comments(order:secret)").await.is_err());
+    assert!(read("comments(limit:1").await.is_err());
+    assert!(read("missing(limit:1)").await.is_err());
+
+    // Children are only inlined when reading individual records.
+    assert!(list_records_handler(
+      State(state.clone()),
+      Path("post_api".to_string()),
+      RawQuery(Some("expand=comments".to_string())),
+      None,
+    )
+    .await
+    .is_err());
+  }
+
   #[tokio::test]
   async fn test_expanded_with_multiple_foreign_keys() {
     let state = test_state(None).await.unwrap();
@@ -1356,7 +1914,12 @@ mod tests {
       RecordApiConfig {
         name: Some("test_table_api".to_string()),
         table_name: Some(table_name.to_string()),
-        acl_world: [PermissionFlag::Create as i32, PermissionFlag::Read as i32].into(),
+        acl_world: [
+          PermissionFlag::Create as i32,
+          PermissionFlag::Read as i32,
+          PermissionFlag::List as i32,
+        ]
+        .into(),
         expand: vec!["fk0".to_string(), "fk1".to_string()],
         ..Default::default()
       },
@@ -1382,7 +1945,7 @@ mod tests {
       let Json(value) = read_record_handler(
         State(state.clone()),
         Path(("test_table_api".to_string(), "1".to_string())),
-        Query(ReadRecordQuery { expand: None }),
+        Query(ReadRecordQuery::default()),
         None,
       )
       .await
@@ -1428,6 +1991,7 @@ mod tests {
         Path(("test_table_api".to_string(), "1".to_string())),
         Query(ReadRecordQuery {
           expand: Some("fk1".to_string()),
+          ..Default::default()
         }),
         None,
       )
@@ -1472,6 +2036,7 @@ mod tests {
         Path(("test_table_api".to_string(), "1".to_string())),
         Query(ReadRecordQuery {
           expand: Some("fk0,fk1".to_string()),
+          ..Default::default()
         }),
         None,
       )
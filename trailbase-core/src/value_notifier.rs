@@ -54,12 +54,17 @@ impl<T> ValueNotifier<T> {
   }
 
   pub fn store(&self, v: T) {
-    let ptr = Arc::new(v);
-    self.value.store(ptr.clone());
+    self.swap(Arc::new(v));
+  }
+
+  /// Stores the given value and returns the previous one.
+  pub fn swap(&self, new: Arc<T>) -> Arc<T> {
+    let prev = self.value.swap(new.clone());
 
     for callback in self.listeners.lock().iter() {
-      callback(&*ptr);
+      callback(&*new);
     }
+    return prev;
   }
 }
 
@@ -103,6 +108,13 @@ mod test {
     assert_eq!(**v.load(), 42);
     v.store(23);
     assert_eq!(**v.load(), 23);
+
+    let new = Arc::new(5);
+    let prev = v.swap(new.clone());
+    assert_eq!(*prev, 23);
+    assert!(!v.compare_and_swap(&prev, Arc::new(7)));
+    assert!(v.compare_and_swap(&new, prev));
+    assert_eq!(**v.load(), 23);
   }
 
   #[test]
@@ -0,0 +1,638 @@
+use chrono::{Duration, Utc};
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use log::*;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use trailbase_sqlite::params;
+
+use crate::app_state::AppState;
+use crate::config::proto::WebhookEvent;
+use crate::constants::{WEBHOOK_DELIVERIES_RETENTION, WEBHOOK_DELIVERIES_TABLE};
+use crate::records::json_to_sql::{InsertTransactionHook, TransactionHook};
+use crate::records::RecordApi;
+
+pub(crate) const WEBHOOK_SIGNATURE_HEADER: &str = "X-TrailBase-Signature";
+pub(crate) const WEBHOOK_EVENT_HEADER: &str = "X-TrailBase-Event";
+pub(crate) const WEBHOOK_DELIVERY_HEADER: &str = "X-TrailBase-Delivery";
+
+/// Deliveries are given up after this many failed attempts.
+const MAX_DELIVERY_ATTEMPTS: i64 = 8;
+/// Backoff after the first failed attempt, doubling with every subsequent one.
+const INITIAL_RETRY_BACKOFF: Duration = Duration::seconds(30);
+const MAX_RETRY_BACKOFF: Duration = Duration::hours(6);
+
+const DELIVERY_BATCH_SIZE: usize = 64;
+/// Upper bound on concurrent in-flight deliveries to any single webhook.
+const MAX_CONCURRENT_DELIVERIES_PER_WEBHOOK: usize = 4;
+const DELIVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// Interval at which the worker checks for due retries in the absence of new deliveries.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Shared state for enqueueing and delivering webhooks.
+#[derive(Clone)]
+pub(crate) struct Webhooks {
+  notify: Arc<Notify>,
+  client: reqwest::Client,
+}
+
+impl Default for Webhooks {
+  fn default() -> Self {
+    return Self {
+      notify: Arc::new(Notify::new()),
+      client: reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("startup"),
+    };
+  }
+}
+
+fn event_name(event: WebhookEvent) -> &'static str {
+  return match event {
+    WebhookEvent::Undefined => "undefined",
+    WebhookEvent::Insert => "insert",
+    WebhookEvent::Update => "update",
+    WebhookEvent::Delete => "delete",
+  };
+}
+
+/// Computes the value of the `X-TrailBase-Signature` header, i.e. the hex-encoded HMAC-SHA256 of
+/// the body.
+pub(crate) fn sign_payload(secret: &str, payload: &[u8]) -> String {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+  mac.update(payload);
+
+  let digest = mac.finalize().into_bytes();
+  return format!(
+    "sha256={}",
+    digest
+      .iter()
+      .map(|b| format!("{b:02x}"))
+      .collect::<String>()
+  );
+}
+
+fn retry_backoff(attempts: i64) -> Duration {
+  let exponent = (attempts - 1).clamp(0, 20) as u32;
+  return std::cmp::min(
+    INITIAL_RETRY_BACKOFF * 2_i32.pow(exponent),
+    MAX_RETRY_BACKOFF,
+  );
+}
+
+/// Deliveries to all of an API's webhooks subscribed to a given event, enqueued within the
+/// transaction of the triggering mutation, s.t. deliveries are only ever recorded for committed
+/// changes and never lost for them.
+pub(crate) struct WebhookDeliveries {
+  api_name: String,
+  table_name: String,
+  event: WebhookEvent,
+  /// Pairs of webhook id and URL.
+  webhooks: Vec<(String, String)>,
+}
+
+impl WebhookDeliveries {
+  /// Returns None if no webhook is subscribed to the event.
+  pub(crate) fn new(api: &RecordApi, event: WebhookEvent) -> Option<Self> {
+    let webhooks: Vec<(String, String)> = api
+      .webhooks()
+      .iter()
+      .filter(|w| w.events.is_empty() || w.events.contains(&(event as i32)))
+      .filter_map(|w| Some((w.id.clone()?, w.url.clone()?)))
+      .collect();
+    if webhooks.is_empty() {
+      return None;
+    }
+
+    return Some(Self {
+      api_name: api.api_name().to_string(),
+      table_name: api.table_name().to_string(),
+      event,
+      webhooks,
+    });
+  }
+
+  /// Hook enqueueing deliveries for inserted records given their ids.
+  pub(crate) fn insert_hook(self) -> InsertTransactionHook<String> {
+    return Box::new(move |conn, record_ids| self.enqueue(conn, record_ids));
+  }
+
+  /// Hook enqueueing deliveries for the updated or deleted record.
+  pub(crate) fn hook(self, record_id: String) -> TransactionHook {
+    return Box::new(move |conn| self.enqueue(conn, &[record_id]));
+  }
+
+  fn enqueue(
+    &self,
+    conn: &rusqlite::Connection,
+    record_ids: &[String],
+  ) -> Result<(), trailbase_sqlite::Error> {
+    let timestamp = Utc::now().timestamp();
+    let mut stmt = conn.prepare_cached(&format!(
+      "INSERT INTO {WEBHOOK_DELIVERIES_TABLE} (api_name, webhook_id, url, event, payload) VALUES ($1, $2, $3, $4, $5)"
+    ))?;
+
+    for record_id in record_ids {
+      let payload = serde_json::json!({
+        "event": event_name(self.event),
+        "api_name": self.api_name,
+        "table_name": self.table_name,
+        "record_id": record_id,
+        "timestamp": timestamp,
+      })
+      .to_string();
+
+      for (webhook_id, url) in &self.webhooks {
+        stmt.execute((
+          &self.api_name,
+          webhook_id,
+          url,
+          event_name(self.event),
+          &payload,
+        ))?;
+      }
+    }
+
+    return Ok(());
+  }
+}
+
+impl Webhooks {
+  /// Wakes up the delivery worker, e.g. after deliveries have been enqueued.
+  pub(crate) fn notify(&self) {
+    self.notify.notify_one();
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingDelivery {
+  id: i64,
+  api_name: String,
+  webhook_id: Option<String>,
+  url: String,
+  event: String,
+  payload: String,
+  attempts: i64,
+}
+
+enum Outcome {
+  Delivered {
+    status: u16,
+  },
+  Failed {
+    status: Option<u16>,
+    error: String,
+  },
+  /// The webhook was removed from the config since the delivery was enqueued.
+  Unconfigured,
+}
+
+async fn attempt_delivery(state: &AppState, delivery: &PendingDelivery) -> Outcome {
+  let Some(webhook) = state.lookup_record_api(&delivery.api_name).and_then(|api| {
+    return api
+      .webhooks()
+      .iter()
+      .find(|w| match delivery.webhook_id {
+        Some(ref id) => w.id.as_ref() == Some(id),
+        // Deliveries enqueued before webhooks had ids.
+        None => w.url.as_ref() == Some(&delivery.url),
+      })
+      .cloned();
+  }) else {
+    return Outcome::Unconfigured;
+  };
+  let Some(ref url) = webhook.url else {
+    return Outcome::Unconfigured;
+  };
+
+  let mut request = state
+    .webhooks()
+    .client
+    .post(url)
+    .header(reqwest::header::CONTENT_TYPE, "application/json")
+    .header(WEBHOOK_EVENT_HEADER, &delivery.event)
+    .header(WEBHOOK_DELIVERY_HEADER, delivery.id.to_string());
+  if let Some(ref secret) = webhook.secret {
+    request = request.header(
+      WEBHOOK_SIGNATURE_HEADER,
+      sign_payload(secret, delivery.payload.as_bytes()),
+    );
+  }
+
+  return match request.body(delivery.payload.clone()).send().await {
+    Ok(response) if response.status().is_success() => Outcome::Delivered {
+      status: response.status().as_u16(),
+    },
+    Ok(response) => Outcome::Failed {
+      status: Some(response.status().as_u16()),
+      error: format!("Unexpected status: {}", response.status()),
+    },
+    Err(err) => Outcome::Failed {
+      status: None,
+      error: err.to_string(),
+    },
+  };
+}
+
+/// Attempts a single delivery and records its outcome.
+async fn process_delivery(
+  state: &AppState,
+  delivery: &PendingDelivery,
+) -> Result<(), trailbase_sqlite::Error> {
+  let outcome = attempt_delivery(state, delivery).await;
+
+  let attempts = delivery.attempts + 1;
+  let now = Utc::now().timestamp();
+  let (status, response_status, error, next_attempt) = match outcome {
+    Outcome::Delivered { status } => ("delivered", Some(status), None, now),
+    Outcome::Unconfigured => (
+      "failed",
+      None,
+      Some("Webhook no longer configured".to_string()),
+      now,
+    ),
+    Outcome::Failed { status, error } => {
+      if attempts >= MAX_DELIVERY_ATTEMPTS {
+        ("failed", status, Some(error), now)
+      } else {
+        (
+          "pending",
+          status,
+          Some(error),
+          (Utc::now() + retry_backoff(attempts)).timestamp(),
+        )
+      }
+    }
+  };
+
+  if let Some(ref error) = error {
+    debug!(
+      "Webhook delivery {} to {}: {error}",
+      delivery.id, delivery.url
+    );
+  }
+
+  state
+    .conn()
+    .execute(
+      &format!(
+        r#"
+          UPDATE {WEBHOOK_DELIVERIES_TABLE}
+          SET status = $1, attempts = $2, last_attempt = $3, next_attempt = $4,
+              response_status = $5, error = $6
+          WHERE id = $7
+        "#
+      ),
+      params!(
+        status,
+        attempts,
+        now,
+        next_attempt,
+        response_status.map(|s| s as i64),
+        error,
+        delivery.id,
+      ),
+    )
+    .await?;
+
+  return Ok(());
+}
+
+/// Attempts all deliveries that are due and records the outcomes. Returns the number of
+/// attempted deliveries.
+///
+/// Deliveries are attempted concurrently, bounded per webhook, s.t. a slow endpoint neither
+/// holds up the others nor gets flooded.
+pub(crate) async fn process_pending_deliveries(
+  state: &AppState,
+) -> Result<usize, trailbase_sqlite::Error> {
+  let now = Utc::now().timestamp();
+  let deliveries = state
+    .conn()
+    .query_values::<PendingDelivery>(
+      &format!(
+        r#"
+          SELECT id, api_name, webhook_id, url, event, payload, attempts FROM {WEBHOOK_DELIVERIES_TABLE}
+          WHERE status = 'pending' AND next_attempt <= $1
+          ORDER BY next_attempt ASC, id ASC
+          LIMIT $2
+        "#
+      ),
+      params!(now, DELIVERY_BATCH_SIZE as i64),
+    )
+    .await?;
+
+  let mut by_webhook: HashMap<(&str, Option<&str>, &str), Vec<&PendingDelivery>> = HashMap::new();
+  for delivery in &deliveries {
+    by_webhook
+      .entry((
+        &delivery.api_name,
+        delivery.webhook_id.as_deref(),
+        &delivery.url,
+      ))
+      .or_default()
+      .push(delivery);
+  }
+
+  let results = futures_util::future::join_all(by_webhook.into_values().map(|group| {
+    return futures_util::stream::iter(group)
+      .map(|delivery| process_delivery(state, delivery))
+      .buffer_unordered(MAX_CONCURRENT_DELIVERIES_PER_WEBHOOK)
+      .collect::<Vec<_>>();
+  }))
+  .await;
+
+  for result in results.into_iter().flatten() {
+    result?;
+  }
+
+  return Ok(deliveries.len());
+}
+
+/// Deletes delivered and failed deliveries older than the retention period.
+pub(crate) async fn prune_webhook_deliveries(
+  state: &AppState,
+) -> Result<usize, trailbase_sqlite::Error> {
+  let timestamp = (Utc::now() - WEBHOOK_DELIVERIES_RETENTION).timestamp();
+  return state
+    .conn()
+    .execute(
+      &format!("DELETE FROM {WEBHOOK_DELIVERIES_TABLE} WHERE status != 'pending' AND created < $1"),
+      params!(timestamp),
+    )
+    .await;
+}
+
+/// Background worker delivering webhooks as they're enqueued as well as retrying failed ones
+/// once due.
+pub(crate) async fn run_delivery_worker(state: AppState) {
+  let notify = state.webhooks().notify.clone();
+  loop {
+    match process_pending_deliveries(&state).await {
+      // There may be more due deliveries.
+      Ok(n) if n >= DELIVERY_BATCH_SIZE => continue,
+      Ok(_) => {}
+      Err(err) => warn!("Failed to process webhook deliveries: {err}"),
+    };
+
+    tokio::select! {
+      _ = notify.notified() => {},
+      _ = tokio::time::sleep(POLL_INTERVAL) => {},
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::extract::State;
+  use axum::http::{HeaderMap, StatusCode, Uri};
+  use axum::routing::post;
+  use axum::Router;
+  use parking_lot::Mutex;
+  use std::future::IntoFuture;
+
+  use super::*;
+  use crate::app_state::test_state;
+  use crate::config::proto::{PermissionFlag, RecordApiConfig, WebhookConfig};
+  use crate::records::create_record::{
+    create_record_handler, CreateRecordQuery, CreateRecordResponse,
+  };
+  use crate::records::delete_record::delete_record_handler;
+  use crate::test::unpack_json_response;
+
+  #[derive(Default)]
+  struct Receiver {
+    requests: Mutex<Vec<(String, HeaderMap, String)>>,
+    fail_next: Mutex<bool>,
+  }
+
+  async fn receive(
+    State(receiver): State<Arc<Receiver>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: String,
+  ) -> StatusCode {
+    receiver
+      .requests
+      .lock()
+      .push((uri.path().to_string(), headers, body));
+
+    let mut fail_next = receiver.fail_next.lock();
+    if *fail_next {
+      *fail_next = false;
+      return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    return StatusCode::OK;
+  }
+
+  #[test]
+  fn test_retry_backoff() {
+    assert_eq!(INITIAL_RETRY_BACKOFF, retry_backoff(1));
+    assert_eq!(INITIAL_RETRY_BACKOFF * 4, retry_backoff(3));
+    assert_eq!(
+      MAX_RETRY_BACKOFF,
+      retry_backoff(MAX_DELIVERY_ATTEMPTS + 100)
+    );
+  }
+
+  #[tokio::test]
+  async fn test_webhook_delivery() {
+    // Local stand-in for the receiving service.
+    let receiver = Arc::new(Receiver {
+      fail_next: Mutex::new(true),
+      ..Default::default()
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let url = format!("http://{address}/hook");
+    tokio::spawn(
+      axum::serve(
+        listener,
+        Router::new()
+          .route("/hook", post(receive))
+          .route("/moved", post(receive))
+          .with_state(receiver.clone()),
+      )
+      .into_future(),
+    );
+
+    let state = test_state(None).await.unwrap();
+    state
+      .conn()
+      .execute(
+        "CREATE TABLE hooked (id INTEGER PRIMARY KEY, text TEXT NOT NULL) STRICT",
+        (),
+      )
+      .await
+      .unwrap();
+    state.refresh_table_cache().await.unwrap();
+
+    let secret = "hook_secret";
+    let mut config = state.get_config();
+    config.record_apis.push(RecordApiConfig {
+      name: Some("hooked_api".to_string()),
+      table_name: Some("hooked".to_string()),
+      acl_world: vec![PermissionFlag::Create as i32, PermissionFlag::Delete as i32],
+      webhooks: vec![
+        WebhookConfig {
+          id: Some("hook".to_string()),
+          url: Some(url.clone()),
+          events: vec![WebhookEvent::Insert as i32],
+          secret: Some(secret.to_string()),
+        },
+        WebhookConfig {
+          id: None,
+          url: Some("http://127.0.0.1:1/unsubscribed".to_string()),
+          events: vec![WebhookEvent::Update as i32],
+          secret: None,
+        },
+      ],
+      ..Default::default()
+    });
+    state
+      .validate_and_update_config(config, None)
+      .await
+      .unwrap();
+
+    // Missing ids get assigned.
+    let webhooks = state
+      .get_config()
+      .record_apis
+      .last()
+      .unwrap()
+      .webhooks
+      .clone();
+    assert_eq!(Some("hook"), webhooks[0].id.as_deref());
+    assert!(webhooks[1].id.as_ref().is_some_and(|id| !id.is_empty()));
+
+    let response = create_record_handler(
+      State(state.clone()),
+      axum::extract::Path("hooked_api".to_string()),
+      axum::extract::Query(CreateRecordQuery::default()),
+      None,
+      crate::extract::Either::Json(serde_json::json!({"id": 5, "text": "hello"})),
+    )
+    .await
+    .unwrap();
+    let ids = unpack_json_response::<CreateRecordResponse>(response)
+      .await
+      .unwrap()
+      .ids;
+    assert_eq!(vec!["5".to_string()], ids);
+
+    // Failed mutations mustn't enqueue anything.
+    assert!(create_record_handler(
+      State(state.clone()),
+      axum::extract::Path("hooked_api".to_string()),
+      axum::extract::Query(CreateRecordQuery::default()),
+      None,
+      crate::extract::Either::Json(serde_json::json!({"id": 5, "text": "conflict"})),
+    )
+    .await
+    .is_err());
+
+    // Deleting isn't subscribed to and thus mustn't enqueue anything.
+    delete_record_handler(
+      State(state.clone()),
+      axum::extract::Path(("hooked_api".to_string(), "5".to_string())),
+      None,
+    )
+    .await
+    .unwrap();
+
+    // First attempt fails and gets scheduled for retry.
+    assert_eq!(1, process_pending_deliveries(&state).await.unwrap());
+    let (status, attempts, response_status, next_attempt): (String, i64, i64, i64) = {
+      let row = crate::util::query_one_row(
+        state.conn(),
+        &format!(
+          "SELECT status, attempts, response_status, next_attempt FROM {WEBHOOK_DELIVERIES_TABLE}"
+        ),
+        (),
+      )
+      .await
+      .unwrap();
+      (
+        row.get(0).unwrap(),
+        row.get(1).unwrap(),
+        row.get(2).unwrap(),
+        row.get(3).unwrap(),
+      )
+    };
+    assert_eq!("pending", status);
+    assert_eq!(1, attempts);
+    assert_eq!(500, response_status);
+    assert!(next_attempt > Utc::now().timestamp());
+
+    // Not yet due.
+    assert_eq!(0, process_pending_deliveries(&state).await.unwrap());
+
+    // Deliveries refer to the webhook by id and thus follow changes to its URL.
+    let mut config = state.get_config();
+    config.record_apis.last_mut().unwrap().webhooks[0].url =
+      Some(format!("http://{address}/moved"));
+    state
+      .validate_and_update_config(config, None)
+      .await
+      .unwrap();
+
+    state
+      .conn()
+      .execute(
+        &format!("UPDATE {WEBHOOK_DELIVERIES_TABLE} SET next_attempt = 0"),
+        (),
+      )
+      .await
+      .unwrap();
+    assert_eq!(1, process_pending_deliveries(&state).await.unwrap());
+
+    let status: String = crate::util::query_one_row(
+      state.conn(),
+      &format!("SELECT status FROM {WEBHOOK_DELIVERIES_TABLE}"),
+      (),
+    )
+    .await
+    .unwrap()
+    .get(0)
+    .unwrap();
+    assert_eq!("delivered", status);
+
+    let requests = receiver.requests.lock().clone();
+    assert_eq!(2, requests.len());
+    assert_eq!("/hook", requests[0].0);
+    let (path, headers, body) = &requests[1];
+    assert_eq!("/moved", path);
+    assert_eq!(
+      sign_payload(secret, body.as_bytes()),
+      headers
+        .get(WEBHOOK_SIGNATURE_HEADER)
+        .unwrap()
+        .to_str()
+        .unwrap()
+    );
+    assert_eq!("insert", headers.get(WEBHOOK_EVENT_HEADER).unwrap());
+
+    let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(serde_json::json!("insert"), payload["event"]);
+    assert_eq!(serde_json::json!("hooked_api"), payload["api_name"]);
+    assert_eq!(serde_json::json!("5"), payload["record_id"]);
+
+    // Only completed deliveries past the retention period get pruned.
+    assert_eq!(0, prune_webhook_deliveries(&state).await.unwrap());
+    state
+      .conn()
+      .execute(
+        &format!("UPDATE {WEBHOOK_DELIVERIES_TABLE} SET created = 0"),
+        (),
+      )
+      .await
+      .unwrap();
+    assert_eq!(1, prune_webhook_deliveries(&state).await.unwrap());
+  }
+}
@@ -328,6 +328,30 @@ pub fn extract_record_values(case: &PreUpdateCase) -> Option<Vec<Value>> {
   });
 }
 
+/// Extracts the values of a row prior to an update or delete. Returns None for inserts.
+pub fn extract_old_record_values(case: &PreUpdateCase) -> Option<Vec<Value>> {
+  let accessor = match case {
+    PreUpdateCase::Delete(accessor) => accessor,
+    PreUpdateCase::Update {
+      old_value_accessor: accessor,
+      ..
+    } => accessor,
+    PreUpdateCase::Insert(_) | PreUpdateCase::Unknown => {
+      return None;
+    }
+  };
+
+  return Some(
+    (0..accessor.get_column_count())
+      .map(|idx| -> Value {
+        accessor
+          .get_old_column_value(idx)
+          .map_or(rusqlite::types::Value::Null, |v| v.into())
+      })
+      .collect(),
+  );
+}
+
 #[cfg(test)]
 #[path = "tests.rs"]
 mod tests;